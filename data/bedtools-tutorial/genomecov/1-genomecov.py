@@ -6,12 +6,12 @@ This is equivalent to bedtools command:
     bedtools genomecov -g ../data/genome.txt -i ../data/exons.bed | head -5
 """
 
-from pygrass import parse_args, IntervalFile, load_genome_file, score
-import grass_ext
+from pygrass import parse_args, IntervalFile, load_genome_file
 
 parse_args()
 
 load_genome_file("../data/genome.txt")
 file = IntervalFile("../data/exons.bed")
 
-grass_ext.print_genomecov(file)
+# The histogram of each chromosome is followed by the genome-wide histogram
+file.genome_coverage(histogram = True).print_to_stdout()
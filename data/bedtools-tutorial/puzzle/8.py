@@ -5,7 +5,6 @@
 # Does the result make sense?
 
 from pygrass import load_genome_file, IntervalFile, name, parse_args
-from grass_ext import jaccard
 
 parse_args()
 
@@ -16,7 +15,7 @@ def run_jaccard(kind):
     # Thus we need to open the file for each time we run the query.
     hesc = IntervalFile("../data/hesc.chromHmm.bed").filter(name.matches(kind))
     cpg = IntervalFile("../data/cpg.bed")
-    jaccard(cpg, hesc)
+    cpg.jaccard(hesc).print_to_stdout()
 
 run_jaccard("Enhancer")
 run_jaccard("Promoter")
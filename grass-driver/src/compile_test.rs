@@ -0,0 +1,45 @@
+//! The code generation tests, which expand the IRs into queries the same way the job does. The
+//! queries are only type checked, a failed expansion shows up as a compile error of the tests.
+
+/// Each case is `name, IR file` followed by the constant bag values the IR refers to, which are
+/// written as `static name: type = key`. The path of the IR file is relative to the grass-macro
+/// crate.
+macro_rules! compile_test {
+    ($name:ident, $path:literal $(, $bag_value:ident : $ty:ty = $key:literal)*) => {
+        mod $name {
+            #[allow(unused_imports)]
+            use grass_runtime::const_bag::{ConstBagRef, ConstBagType};
+
+            $(static $bag_value: ConstBagRef<$ty> = ConstBagRef::<$ty>::new($key);)*
+
+            // Not every IR reads the command line arguments
+            #[allow(dead_code, unused_variables)]
+            fn query(cmd_args: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+                grass_macro::import_grass_ir_from_file!($path);
+                Ok(())
+            }
+
+            #[test]
+            fn expand() {
+                let _ = query as fn(&[&str]) -> Result<(), Box<dyn std::error::Error>>;
+            }
+        }
+    };
+}
+
+compile_test!(sorted_intersect, "../data/ir/sorted-intersect.py.json");
+compile_test!(tee, "../data/ir/tee.json");
+compile_test!(vcf, "../data/ir/vcf.json");
+compile_test!(regex, "../data/ir/regex.json");
+compile_test!(filter, "../data/ir/filter.py.json", __CONST_BAG_VALUE_0: f64 = 0);
+compile_test!(
+    sorted_intersect_group,
+    "../data/ir/sorted-intersect-group.py.json",
+    __CONST_BAG_VALUE_0: String = 0,
+    __CONST_BAG_VALUE_1: String = 1
+);
+compile_test!(aggregate, "../data/ir/aggregate.py.json");
+compile_test!(merge_aggregate, "../data/ir/merge-aggregate.py.json");
+compile_test!(closest, "../data/ir/closest.py.json", __CONST_BAG_VALUE_0: f64 = 0);
+compile_test!(pair_intersect, "../data/ir/pair-intersect.py.json");
+compile_test!(getfasta, "../data/ir/getfasta.py.json");
@@ -0,0 +1,340 @@
+//! The conformance suite of the field expressions. Each case is evaluated by the closure the macro
+//! compiles the expression to and by the interpreter, and both of them should produce the expected
+//! value of the expected type.
+
+use std::{io::Cursor, rc::Rc};
+
+use grass_ir::FieldExpression;
+use grass_runtime::{
+    algorithm::{AssumeSorted, Groups},
+    record::{Bed3, Bed6},
+    Itertools, LineRecordStreamExt,
+};
+
+use super::{
+    record::{BedRecord, DynRecord},
+    value::{Evaluator, Value},
+};
+
+fn bed6() -> Bed6<'static> {
+    Cursor::new("chr1\t100\t250\tgene-42\t3.5\t-\n")
+        .into_record_iter::<Bed6>()
+        .next()
+        .unwrap()
+}
+
+fn pair() -> (Bed3, Bed6<'static>) {
+    let bed3 = Cursor::new("chr1\t50\t150\n")
+        .into_record_iter::<Bed3>()
+        .next()
+        .unwrap();
+    (bed3, bed6())
+}
+
+/// The pair an outer intersection produces when the BED3 record doesn't overlap anything
+fn outer() -> (Bed3, Option<Bed6<'static>>) {
+    (pair().0, None)
+}
+
+/// The records of a group with two members
+const GROUP: &str = "chr1\t50\t150\nchr1\t100\t250\n";
+
+fn describe(value: &Value) -> String {
+    let ty = match value {
+        Value::Int(_) => "int",
+        Value::Float(_) => "float",
+        Value::Str(_) => "str",
+        Value::Bool(_) => "bool",
+        Value::Record(_) => "record",
+    };
+    format!("{:?} ({})", value.to_string(), ty)
+}
+
+fn check(expr: &str, record: &DynRecord, compiled: Value, expected: Value) {
+    assert!(
+        compiled == expected,
+        "Compiled {}: expect {}, got {}",
+        expr,
+        describe(&expected),
+        describe(&compiled)
+    );
+    let expr: FieldExpression = serde_json::from_str(expr).unwrap();
+    match Evaluator::new(vec![]).eval(&expr, record) {
+        Ok(value) => assert!(
+            value == expected,
+            "Interpreted {:?}: expect {}, got {}",
+            expr,
+            describe(&expected),
+            describe(&value)
+        ),
+        Err(err) => panic!("Interpreted {:?}: {}", expr, err),
+    }
+}
+
+/// Each case is `name: record, expression => expected value`, the record is either a BED6
+/// record, a pair of a BED3 and a BED6 record, an outer pair whose BED6 record is missing or a
+/// group of two BED3 records
+macro_rules! conformance {
+    ($($name:ident : $record:ident, $expr:literal => $expected:expr;)*) => {
+        $(
+            #[test]
+            fn $name() {
+                conformance!(@run $record, $expr, $expected);
+            }
+        )*
+    };
+    (@run bed6, $expr:literal, $expected:expr) => {
+        let record = bed6();
+        let compiled = Some(&record).map(grass_macro::field_expr!($expr)).unwrap();
+        let dyn_record = DynRecord::Bed(BedRecord::new(&record, 6));
+        check($expr, &dyn_record, Value::from(compiled), Value::from($expected));
+    };
+    (@run pair, $expr:literal, $expected:expr) => {
+        let record = pair();
+        let compiled = Some(&record).map(grass_macro::field_expr!($expr)).unwrap();
+        let dyn_record = DynRecord::pair(
+            DynRecord::Bed(BedRecord::new(&record.0, 3)),
+            DynRecord::Bed(BedRecord::new(&record.1, 6)),
+        );
+        check($expr, &dyn_record, Value::from(compiled), Value::from($expected));
+    };
+    (@run outer, $expr:literal, $expected:expr) => {
+        let record = outer();
+        let compiled = Some(&record).map(grass_macro::field_expr!($expr)).unwrap();
+        let dyn_record = DynRecord::pair(
+            DynRecord::Bed(BedRecord::new(&record.0, 3)),
+            DynRecord::Missing(6),
+        );
+        check($expr, &dyn_record, Value::from(compiled), Value::from($expected));
+    };
+    (@run group, $expr:literal, $expected:expr) => {
+        let group_by = Cursor::new(GROUP)
+            .into_record_iter::<Bed3>()
+            .assume_sorted()
+            .group_by(|_| true);
+        let record = Groups::new(&group_by).next().unwrap();
+        let compiled = Some(&record).map(grass_macro::field_expr!($expr)).unwrap();
+        let members = Cursor::new(GROUP)
+            .into_record_iter::<Bed3>()
+            .map(|bed| DynRecord::Bed(BedRecord::new(&bed, 3)))
+            .collect();
+        let dyn_record = DynRecord::Group(Rc::new(members));
+        check($expr, &dyn_record, Value::from(compiled), Value::from($expected));
+    };
+}
+
+conformance! {
+    field_start: bed6, r#"{"opcode": "FieldRef", "field": "start"}"# => 100i64;
+    field_length: bed6, r#"{"opcode": "FieldRef", "field": "length"}"# => 150i64;
+    field_score: bed6, r#"{"opcode": "FieldRef", "field": "score"}"# => 3.5;
+    field_chrom: bed6, r#"{"opcode": "FieldRef", "field": "chrom"}"# => "chr1";
+    field_name: bed6, r#"{"opcode": "FieldRef", "field": "name"}"# => "gene-42";
+    field_strand: bed6, r#"{"opcode": "FieldRef", "field": "strand"}"# => "-";
+
+    const_int: bed6, r#"{"opcode": "ConstValue", "value": -7}"# => -7i64;
+    const_float: bed6, r#"{"opcode": "ConstValue", "value": 2.5}"# => 2.5;
+    const_str: bed6, r#"{"opcode": "ConstValue", "value": "x"}"# => "x";
+
+    and_bool: bed6, r#"{"opcode": "And",
+        "lhs": {"opcode": "GreaterThan", "lhs": {"opcode": "FieldRef", "field": "start"},
+            "rhs": {"opcode": "ConstValue", "value": 50}},
+        "rhs": {"opcode": "LessThan", "lhs": {"opcode": "FieldRef", "field": "end"},
+            "rhs": {"opcode": "ConstValue", "value": 200}}}"# => false;
+    and_int: bed6, r#"{"opcode": "And", "lhs": {"opcode": "ConstValue", "value": 12},
+        "rhs": {"opcode": "ConstValue", "value": 10}}"# => 8i64;
+    or_bool: bed6, r#"{"opcode": "Or",
+        "lhs": {"opcode": "Eq", "lhs": {"opcode": "FieldRef", "field": "strand"},
+            "rhs": {"opcode": "ConstValue", "value": "+"}},
+        "rhs": {"opcode": "Eq", "lhs": {"opcode": "FieldRef", "field": "chrom"},
+            "rhs": {"opcode": "ConstValue", "value": "chr1"}}}"# => true;
+    or_int: bed6, r#"{"opcode": "Or", "lhs": {"opcode": "ConstValue", "value": 12},
+        "rhs": {"opcode": "ConstValue", "value": 3}}"# => 15i64;
+    xor_bool: bed6, r#"{"opcode": "Xor",
+        "lhs": {"opcode": "Ne", "lhs": {"opcode": "FieldRef", "field": "strand"},
+            "rhs": {"opcode": "ConstValue", "value": "+"}},
+        "rhs": {"opcode": "Eq", "lhs": {"opcode": "FieldRef", "field": "chrom"},
+            "rhs": {"opcode": "ConstValue", "value": "chr1"}}}"# => false;
+    xor_int: bed6, r#"{"opcode": "Xor", "lhs": {"opcode": "ConstValue", "value": 12},
+        "rhs": {"opcode": "ConstValue", "value": 10}}"# => 6i64;
+    not_bool: bed6, r#"{"opcode": "Not", "operand": {"opcode": "Eq",
+        "lhs": {"opcode": "FieldRef", "field": "strand"},
+        "rhs": {"opcode": "ConstValue", "value": "+"}}}"# => true;
+    not_int: bed6, r#"{"opcode": "Not", "operand": {"opcode": "ConstValue", "value": 0}}"# => -1i64;
+    neg_int: bed6, r#"{"opcode": "Neg", "operand": {"opcode": "FieldRef", "field": "start"}}"# => -100i64;
+    neg_float: bed6, r#"{"opcode": "Neg", "operand": {"opcode": "FieldRef", "field": "score"}}"# => -3.5;
+
+    add_int: bed6, r#"{"opcode": "Add", "lhs": {"opcode": "FieldRef", "field": "start"},
+        "rhs": {"opcode": "ConstValue", "value": 1}}"# => 101i64;
+    add_widen: bed6, r#"{"opcode": "Add", "lhs": {"opcode": "FieldRef", "field": "start"},
+        "rhs": {"opcode": "ConstValue", "value": 0.5}}"# => 100.5;
+    add_str: bed6, r#"{"opcode": "Add", "lhs": {"opcode": "FieldRef", "field": "name"},
+        "rhs": {"opcode": "FieldRef", "field": "strand"}}"# => "gene-42-";
+    sub_int: bed6, r#"{"opcode": "Sub", "lhs": {"opcode": "FieldRef", "field": "start"},
+        "rhs": {"opcode": "FieldRef", "field": "end"}}"# => -150i64;
+    mul_widen: bed6, r#"{"opcode": "Mul", "lhs": {"opcode": "FieldRef", "field": "score"},
+        "rhs": {"opcode": "ConstValue", "value": 2}}"# => 7.0;
+    div_int: bed6, r#"{"opcode": "Div", "lhs": {"opcode": "FieldRef", "field": "end"},
+        "rhs": {"opcode": "ConstValue", "value": 100}}"# => 2.5;
+    mod_int: bed6, r#"{"opcode": "Mod", "lhs": {"opcode": "FieldRef", "field": "end"},
+        "rhs": {"opcode": "ConstValue", "value": 100}}"# => 50i64;
+    mod_float: bed6, r#"{"opcode": "Mod", "lhs": {"opcode": "FieldRef", "field": "score"},
+        "rhs": {"opcode": "ConstValue", "value": 2}}"# => 1.5;
+    left_shift: bed6, r#"{"opcode": "LeftShift", "lhs": {"opcode": "ConstValue", "value": 1},
+        "rhs": {"opcode": "ConstValue", "value": 4}}"# => 16i64;
+    right_shift: bed6, r#"{"opcode": "RightShift", "lhs": {"opcode": "FieldRef", "field": "start"},
+        "rhs": {"opcode": "ConstValue", "value": 2}}"# => 25i64;
+
+    eq_widen: bed6, r#"{"opcode": "Eq", "lhs": {"opcode": "FieldRef", "field": "start"},
+        "rhs": {"opcode": "ConstValue", "value": 100.0}}"# => true;
+    ne_str: bed6, r#"{"opcode": "Ne", "lhs": {"opcode": "FieldRef", "field": "chrom"},
+        "rhs": {"opcode": "ConstValue", "value": "chr2"}}"# => true;
+    less_than: bed6, r#"{"opcode": "LessThan", "lhs": {"opcode": "FieldRef", "field": "start"},
+        "rhs": {"opcode": "ConstValue", "value": 100}}"# => false;
+    greater_than: bed6, r#"{"opcode": "GreaterThan", "lhs": {"opcode": "FieldRef", "field": "end"},
+        "rhs": {"opcode": "ConstValue", "value": 249.5}}"# => true;
+    less_equal_than: bed6, r#"{"opcode": "LessEqualThan",
+        "lhs": {"opcode": "FieldRef", "field": "start"},
+        "rhs": {"opcode": "ConstValue", "value": 99}}"# => false;
+    greater_equal_than: bed6, r#"{"opcode": "GreaterEqualThan",
+        "lhs": {"opcode": "FieldRef", "field": "score"},
+        "rhs": {"opcode": "ConstValue", "value": 3}}"# => true;
+    compare_str: bed6, r#"{"opcode": "LessThan", "lhs": {"opcode": "FieldRef", "field": "name"},
+        "rhs": {"opcode": "ConstValue", "value": "gene-5"}}"# => true;
+
+    cond: bed6, r#"{"opcode": "Cond",
+        "cond": {"opcode": "Eq", "lhs": {"opcode": "FieldRef", "field": "strand"},
+            "rhs": {"opcode": "ConstValue", "value": "+"}},
+        "then": {"opcode": "FieldRef", "field": "start"},
+        "elze": {"opcode": "FieldRef", "field": "end"}}"# => 250i64;
+    cond_widen: bed6, r#"{"opcode": "Cond",
+        "cond": {"opcode": "LessThan", "lhs": {"opcode": "FieldRef", "field": "start"},
+            "rhs": {"opcode": "FieldRef", "field": "end"}},
+        "then": {"opcode": "FieldRef", "field": "start"},
+        "elze": {"opcode": "FieldRef", "field": "score"}}"# => 100.0;
+    cond_str: bed6, r#"{"opcode": "Cond",
+        "cond": {"opcode": "Eq", "lhs": {"opcode": "FieldRef", "field": "strand"},
+            "rhs": {"opcode": "ConstValue", "value": "-"}},
+        "then": {"opcode": "FieldRef", "field": "name"},
+        "elze": {"opcode": "ConstValue", "value": "other"}}"# => "gene-42";
+
+    regex_const: bed6, r#"{"opcode": "RegexMatch", "lhs": {"opcode": "FieldRef", "field": "name"},
+        "rhs": {"opcode": "ConstValue", "value": "^gene-[0-9]+$"}}"# => true;
+    regex_dynamic: bed6, r#"{"opcode": "RegexMatch",
+        "lhs": {"opcode": "FieldRef", "field": "chrom"},
+        "rhs": {"opcode": "Add", "lhs": {"opcode": "ConstValue", "value": "^chr"},
+            "rhs": {"opcode": "ConstValue", "value": "[XY]$"}}}"# => false;
+
+    string_repr: bed6, r#"{"opcode": "StringRepr",
+        "value": {"opcode": "FieldRef", "field": "score"}}"# => "3.5";
+    full_record_ref: bed6, r#"{"opcode": "StringRepr",
+        "value": {"opcode": "FullRecordRef"}}"# => "chr1\t100\t250\tgene-42\t3.5\t-";
+
+    cast_float_to_int: bed6, r#"{"opcode": "Cast",
+        "value": {"opcode": "FieldRef", "field": "score"}, "to": "Int"}"# => 3i64;
+    cast_int_to_float: bed6, r#"{"opcode": "Cast",
+        "value": {"opcode": "FieldRef", "field": "start"}, "to": "Float"}"# => 100.0;
+    cast_int_to_bool: bed6, r#"{"opcode": "Cast",
+        "value": {"opcode": "FieldRef", "field": "start"}, "to": "Bool"}"# => true;
+    cast_bool_to_int: bed6, r#"{"opcode": "Cast", "value": {"opcode": "Eq",
+        "lhs": {"opcode": "FieldRef", "field": "strand"},
+        "rhs": {"opcode": "ConstValue", "value": "-"}}, "to": "Int"}"# => 1i64;
+    cast_str_to_int: bed6, r#"{"opcode": "Cast",
+        "value": {"opcode": "ConstValue", "value": "42"}, "to": "Int"}"# => 42i64;
+    cast_str_to_float: bed6, r#"{"opcode": "Cast",
+        "value": {"opcode": "ConstValue", "value": "0.25"}, "to": "Float"}"# => 0.25;
+    cast_int_to_str: bed6, r#"{"opcode": "Cast",
+        "value": {"opcode": "FieldRef", "field": "end"}, "to": "Str"}"# => "250";
+
+    number_of_components: pair, r#"{"opcode": "NumberOfComponents"}"# => 2i64;
+    component_field_ref: pair, r#"{"opcode": "ComponentFieldRef", "target": 1,
+        "field_name": "name"}"# => "gene-42";
+    component_start: pair, r#"{"opcode": "Sub",
+        "lhs": {"opcode": "ComponentFieldRef", "target": 1, "field_name": "start"},
+        "rhs": {"opcode": "ComponentFieldRef", "target": 0, "field_name": "start"}}"# => 50i64;
+    record_ref: pair, r#"{"opcode": "StringRepr",
+        "value": {"opcode": "RecordRef", "id": 0}}"# => "chr1\t50\t150";
+
+    call_substr: bed6, r#"{"opcode": "Call", "func": "substr", "args": [
+        {"opcode": "FieldRef", "field": "chrom"}, {"opcode": "ConstValue", "value": 3}]}"# => "1";
+    call_substr_len: bed6, r#"{"opcode": "Call", "func": "substr", "args": [
+        {"opcode": "FieldRef", "field": "name"}, {"opcode": "ConstValue", "value": -2},
+        {"opcode": "ConstValue", "value": 1}]}"# => "4";
+    call_concat: bed6, r#"{"opcode": "Call", "func": "concat", "args": [
+        {"opcode": "FieldRef", "field": "chrom"}, {"opcode": "ConstValue", "value": ":"},
+        {"opcode": "StringRepr", "value": {"opcode": "FieldRef", "field": "start"}}]}"#
+        => "chr1:100";
+    call_split_nth: bed6, r#"{"opcode": "Call", "func": "split_nth", "args": [
+        {"opcode": "FieldRef", "field": "name"}, {"opcode": "ConstValue", "value": "-"},
+        {"opcode": "ConstValue", "value": -1}]}"# => "42";
+    call_replace: bed6, r#"{"opcode": "Call", "func": "replace", "args": [
+        {"opcode": "FieldRef", "field": "chrom"}, {"opcode": "ConstValue", "value": "chr"},
+        {"opcode": "ConstValue", "value": ""}]}"# => "1";
+    call_upper: bed6, r#"{"opcode": "Call", "func": "upper", "args": [
+        {"opcode": "FieldRef", "field": "name"}]}"# => "GENE-42";
+    call_lower: bed6, r#"{"opcode": "Call", "func": "lower", "args": [
+        {"opcode": "ConstValue", "value": "ChrX"}]}"# => "chrx";
+    call_starts_with: bed6, r#"{"opcode": "Call", "func": "starts_with", "args": [
+        {"opcode": "FieldRef", "field": "chrom"}, {"opcode": "ConstValue", "value": "chr"}]}"#
+        => true;
+    call_parse_int: bed6, r#"{"opcode": "Call", "func": "parse_int", "args": [
+        {"opcode": "Call", "func": "split_nth", "args": [{"opcode": "FieldRef", "field": "name"},
+            {"opcode": "ConstValue", "value": "-"}, {"opcode": "ConstValue", "value": 1}]}]}"#
+        => 42i64;
+    call_parse_int_default: bed6, r#"{"opcode": "Call", "func": "parse_int", "args": [
+        {"opcode": "FieldRef", "field": "name"}, {"opcode": "ConstValue", "value": -1}]}"#
+        => -1i64;
+    call_parse_float: bed6, r#"{"opcode": "Call", "func": "parse_float", "args": [
+        {"opcode": "ConstValue", "value": "0.5"}, {"opcode": "ConstValue", "value": 0}]}"# => 0.5;
+    call_abs_int: bed6, r#"{"opcode": "Call", "func": "abs", "args": [{"opcode": "Sub",
+        "lhs": {"opcode": "FieldRef", "field": "start"},
+        "rhs": {"opcode": "FieldRef", "field": "end"}}]}"# => 150i64;
+    call_abs_float: bed6, r#"{"opcode": "Call", "func": "abs", "args": [
+        {"opcode": "Neg", "operand": {"opcode": "FieldRef", "field": "score"}}]}"# => 3.5;
+    call_min_int: bed6, r#"{"opcode": "Call", "func": "min", "args": [
+        {"opcode": "FieldRef", "field": "end"}, {"opcode": "FieldRef", "field": "start"},
+        {"opcode": "ConstValue", "value": 120}]}"# => 100i64;
+    call_max_widen: bed6, r#"{"opcode": "Call", "func": "max", "args": [
+        {"opcode": "FieldRef", "field": "score"}, {"opcode": "ConstValue", "value": 2}]}"# => 3.5;
+    call_round: bed6, r#"{"opcode": "Call", "func": "round", "args": [
+        {"opcode": "FieldRef", "field": "score"}]}"# => 4i64;
+    call_floor: bed6, r#"{"opcode": "Call", "func": "floor", "args": [
+        {"opcode": "Div", "lhs": {"opcode": "FieldRef", "field": "end"},
+            "rhs": {"opcode": "ConstValue", "value": 100}}]}"# => 2i64;
+    call_log: bed6, r#"{"opcode": "Call", "func": "log", "args": [
+        {"opcode": "Call", "func": "exp", "args": [{"opcode": "ConstValue", "value": 2}]}]}"#
+        => 2.0;
+    call_log_base: bed6, r#"{"opcode": "Call", "func": "log", "args": [
+        {"opcode": "ConstValue", "value": 8}, {"opcode": "ConstValue", "value": 2}]}"# => 3.0;
+    call_pow: bed6, r#"{"opcode": "Call", "func": "pow", "args": [
+        {"opcode": "ConstValue", "value": 2}, {"opcode": "ConstValue", "value": 10}]}"# => 1024.0;
+
+    group_member_end: group, r#"{"opcode": "ComponentFieldRef", "target": 1,
+        "field_name": "end"}"# => 250i64;
+    group_short_start: group, r#"{"opcode": "ComponentFieldRef", "target": 2,
+        "field_name": "start"}"# => 0i64;
+    group_short_strand: group, r#"{"opcode": "ComponentFieldRef", "target": 5,
+        "field_name": "strand"}"# => ".";
+
+    missing_name: outer, r#"{"opcode": "ComponentFieldRef", "target": 1,
+        "field_name": "name"}"# => ".";
+    missing_score: outer, r#"{"opcode": "ComponentFieldRef", "target": 1,
+        "field_name": "score"}"# => 0.0;
+    missing_substr: outer, r#"{"opcode": "Call", "func": "substr", "args": [
+        {"opcode": "ComponentFieldRef", "target": 1, "field_name": "chrom"},
+        {"opcode": "ConstValue", "value": 3}]}"# => ".";
+    missing_concat: outer, r#"{"opcode": "Call", "func": "concat", "args": [
+        {"opcode": "ComponentFieldRef", "target": 0, "field_name": "chrom"},
+        {"opcode": "ComponentFieldRef", "target": 1, "field_name": "name"}]}"# => ".";
+    missing_split_nth: outer, r#"{"opcode": "Call", "func": "split_nth", "args": [
+        {"opcode": "ComponentFieldRef", "target": 1, "field_name": "name"},
+        {"opcode": "ConstValue", "value": "-"}, {"opcode": "ConstValue", "value": 0}]}"# => ".";
+    missing_starts_with: outer, r#"{"opcode": "Call", "func": "starts_with", "args": [
+        {"opcode": "ComponentFieldRef", "target": 1, "field_name": "strand"},
+        {"opcode": "ConstValue", "value": ""}]}"# => false;
+    missing_parse_int: outer, r#"{"opcode": "Call", "func": "parse_int", "args": [
+        {"opcode": "ComponentFieldRef", "target": 1, "field_name": "name"},
+        {"opcode": "ConstValue", "value": -1}]}"# => -1i64;
+    missing_max: outer, r#"{"opcode": "Call", "func": "max", "args": [
+        {"opcode": "ComponentFieldRef", "target": 0, "field_name": "end"},
+        {"opcode": "ComponentFieldRef", "target": 1, "field_name": "end"}]}"# => 150i64;
+}
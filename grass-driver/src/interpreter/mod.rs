@@ -0,0 +1,1019 @@
+//! The interpreter evaluates GRASS IR directly on dynamically typed records, so that a query can
+//! run without waiting for the artifact to be built. It covers the BED based operators, any other
+//! IR is reported as unsupported and should be compiled instead.
+
+#[cfg(test)]
+mod conformance;
+mod record;
+mod value;
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    fs::File,
+    io::{BufWriter, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use grass_ir::{
+    AggregateFunction, AggregateGroup, AggregateParam, ClosestParam, ConstOrEnv, CoverageParam,
+    CoverageStrand, GenomeCoverageParam, GenomeCoverageReport, GrassIR, InputFormat,
+    IntersectFlavor, IntersectParam, OpenParam, OpenTarget, ResizeAmount, SubtractParam, TagValue,
+    WindowParam, WriteFileParam, WriteTarget,
+};
+use grass_runtime::{
+    algorithm::{
+        AggregateExt, AggregateFunc, Aggregator, Amount, AssumeSorted, ClosestDirection,
+        ClosestOptions, ClosestTies, Components, CountExt, CoverageOptions, GenomeCoverageExt,
+        GenomeCoverageOptions, IndexedIntersect, IntervalIndex, OverlapStatsExt, ResizeAnchor,
+        ResizeExt, ResizeOp, SortedClosest, SortedCoverage, SortedIntersect, SortedInversionExt,
+        SortedRandomInterval, SortedSubtract, SortedWindow, SubtractMode, SubtractOptions, Tee,
+        TwoWayMergeExt, WindowOptions,
+    },
+    const_bag::split_const_bag,
+    property::{RegionCore, Serializable, Strand},
+    record::{Bed3, Bed4, Bed5, Bed6, CastTo},
+    Genome, GzipReader, LineRecordStreamExt,
+};
+
+pub use record::{BedRecord, DynRecord};
+pub use value::Value;
+
+use value::Evaluator;
+
+type RecordStream = Box<dyn Iterator<Item = DynRecord>>;
+
+#[derive(Debug)]
+pub enum InterpretError {
+    /// The IR uses something the interpreter can't run, which should be compiled instead
+    Unsupported(String),
+    /// The query failed while it's running, for example a field expression has a type error
+    Runtime(String),
+    Io(std::io::Error),
+}
+
+impl Display for InterpretError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterpretError::Unsupported(what) => {
+                write!(f, "Unsupported by the interpreter: {}", what)
+            }
+            InterpretError::Runtime(what) => write!(f, "{}", what),
+            InterpretError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for InterpretError {}
+
+impl From<std::io::Error> for InterpretError {
+    fn from(err: std::io::Error) -> Self {
+        InterpretError::Io(err)
+    }
+}
+
+type Result<T> = std::result::Result<T, InterpretError>;
+
+impl CastTo<DynRecord> for DynRecord {
+    fn make_record(&self) -> DynRecord {
+        self.clone()
+    }
+}
+
+/// Keeps the first error raised inside the iterators, since they have no way to return it. The
+/// writers check it after each record.
+#[derive(Clone, Default)]
+struct ErrorSlot(Rc<RefCell<Option<String>>>);
+
+impl ErrorSlot {
+    fn check<T>(&self, result: std::result::Result<T, String>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.0.borrow_mut().get_or_insert(err);
+                None
+            }
+        }
+    }
+    fn take(&self) -> Result<()> {
+        match self.0.borrow_mut().take() {
+            Some(err) => Err(InterpretError::Runtime(err)),
+            None => Ok(()),
+        }
+    }
+}
+
+enum Output {
+    Stdout,
+    Stderr,
+    File(PathBuf),
+}
+
+/// The output of a query, which is written after all the queries are planned
+enum Action {
+    Write(RecordStream, Output),
+}
+
+/// Parse the constant bag of a job, the values are typed by `const_bag_types`
+pub fn parse_const_bag(raw: Option<&str>, types: &[String]) -> Result<Vec<Value>> {
+    let raw_values = match raw {
+        Some(raw) => split_const_bag(raw)
+            .ok_or_else(|| InterpretError::Runtime(format!("Invalid constant bag: {}", raw)))?,
+        None => vec![],
+    };
+    types
+        .iter()
+        .zip(raw_values)
+        .map(|(ty, raw)| match ty.as_str() {
+            "str" => Ok(Value::Str(raw.into())),
+            "i64" => raw
+                .parse()
+                .map(Value::Int)
+                .map_err(|_| InterpretError::Runtime(format!("Unable to parse the value {}", raw))),
+            "f64" => raw
+                .parse()
+                .map(Value::Float)
+                .map_err(|_| InterpretError::Runtime(format!("Unable to parse the value {}", raw))),
+            _ => Err(InterpretError::Unsupported(format!(
+                "Const bag type {}",
+                ty
+            ))),
+        })
+        .collect()
+}
+
+fn resize_amount(amount: &ResizeAmount) -> Amount {
+    match amount {
+        ResizeAmount::Bases(bases) => Amount::Bases(*bases),
+        ResizeAmount::Fraction(fraction) => Amount::Fraction(*fraction),
+    }
+}
+
+pub struct Interpreter {
+    working_dir: PathBuf,
+    cmdline_args: Vec<String>,
+    evaluator: Evaluator,
+    ref_counts: HashMap<String, usize>,
+    symbols: HashMap<String, Tee<RecordStream>>,
+    /// The number of fields of the BED records bound to each symbol
+    widths: HashMap<String, Option<u32>>,
+    error: ErrorSlot,
+}
+
+impl Interpreter {
+    /// Create an interpreter, `cmdline_args` are the arguments of the job without the program name
+    pub fn new(working_dir: &Path, cmdline_args: &[String], const_bag: Vec<Value>) -> Self {
+        Self {
+            working_dir: working_dir.to_path_buf(),
+            cmdline_args: cmdline_args.to_vec(),
+            evaluator: Evaluator::new(const_bag),
+            ref_counts: HashMap::new(),
+            symbols: HashMap::new(),
+            widths: HashMap::new(),
+            error: ErrorSlot::default(),
+        }
+    }
+
+    fn const_str(&self, value: &ConstOrEnv<String>) -> Result<String> {
+        Ok(self
+            .evaluator
+            .const_value(value)
+            .map_err(InterpretError::Runtime)?
+            .to_string())
+    }
+
+    fn const_num<T: Clone + Into<Value>>(&self, value: &ConstOrEnv<T>) -> Result<f64> {
+        self.evaluator
+            .const_value(value)
+            .map_err(InterpretError::Runtime)?
+            .as_float()
+            .ok_or_else(|| InterpretError::Runtime("Expect a number".to_string()))
+    }
+
+    /// The path of the file an open operator reads, `None` for the file descriptors
+    fn input_path(&self, target: &OpenTarget) -> Result<Option<PathBuf>> {
+        let path = match target {
+            OpenTarget::Path(path) => self.const_str(path)?,
+            // The index is taken from the argument list of the artifact, which starts with the
+            // program name
+            OpenTarget::CmdArg(idx) => (*idx as usize)
+                .checked_sub(1)
+                .and_then(|idx| self.cmdline_args.get(idx))
+                .cloned()
+                .ok_or_else(|| {
+                    InterpretError::Runtime(format!("Missing command line argument #{}", idx))
+                })?,
+            OpenTarget::FileNo(_) => return Ok(None),
+        };
+        Ok(Some(self.working_dir.join(path)))
+    }
+
+    /// The total size of the files the queries read, `None` if any of the input can't be sized,
+    /// for example the standard input
+    pub fn input_size(&self, irs: &[GrassIR]) -> Option<u64> {
+        fn visit(interp: &Interpreter, ir: &GrassIR, total: &mut Option<u64>) {
+            if let GrassIR::Open(param) = ir {
+                let size = match interp.input_path(&param.target) {
+                    Ok(Some(path)) => std::fs::metadata(path).ok().map(|meta| meta.len()),
+                    _ => None,
+                };
+                *total = total.zip(size).map(|(a, b)| a + b);
+            }
+            for child in ir.children() {
+                visit(interp, child, total);
+            }
+        }
+        let mut total = Some(0);
+        for ir in irs {
+            visit(self, ir, &mut total);
+        }
+        total
+    }
+
+    fn count_refs(&mut self, ir: &GrassIR) {
+        if let GrassIR::Ref(param) = ir {
+            *self.ref_counts.entry(param.id.clone()).or_default() += 1;
+        }
+        for child in ir.children() {
+            self.count_refs(child);
+        }
+    }
+
+    /// The number of fields of the BED records an expression produces, `None` if they are not
+    /// BED records
+    fn width(&self, ir: &GrassIR) -> Option<u32> {
+        match ir {
+            GrassIR::Open(param) if matches!(param.format, InputFormat::Bed) => {
+                Some(param.num_of_fields as u32)
+            }
+            GrassIR::CastToBed(param) => Some(param.num_of_fields),
+            GrassIR::MergeOverlap(_) | GrassIR::Invert(_) | GrassIR::SortedRandom(_) => Some(3),
+            GrassIR::Ref(param) => self.widths.get(&param.id).copied().flatten(),
+            GrassIR::Let(_)
+            | GrassIR::Filter(_)
+            | GrassIR::Alter(_)
+            | GrassIR::AssumeSorted(_)
+            | GrassIR::InternalSort(_)
+            | GrassIR::Nop(_)
+            | GrassIR::Limit(_)
+            | GrassIR::AssignTag(_)
+            | GrassIR::TwoWayMerge(_)
+            | GrassIR::Slop(_)
+            | GrassIR::Flank(_)
+            | GrassIR::Resize(_)
+            | GrassIR::Subtract(_) => self.width(ir.children()[0]),
+            _ => None,
+        }
+    }
+
+    /// Run the queries, nothing is written until all of them are planned, so an unsupported query
+    /// doesn't leave any partial output
+    pub fn run(&mut self, irs: &[GrassIR]) -> Result<()> {
+        for ir in irs {
+            self.count_refs(ir);
+        }
+        let mut actions = Vec::new();
+        for ir in irs {
+            match ir {
+                GrassIR::WriteFile(param) => actions.push(self.plan_write(param)?),
+                // A bare count is printed, the same as the compiled query does
+                GrassIR::Count(_) => actions.push(self.plan_write(&WriteFileParam {
+                    what: Box::new(ir.clone()),
+                    target: WriteTarget::FileNo(1),
+                })?),
+                // The genome is loaded right away, since the input read by the later queries
+                // while they are planned should see the chromosomes in the genome file order
+                GrassIR::LoadGenomeFile(grass_ir::LoadGenomeFileParam::File(path)) => {
+                    let path = self.working_dir.join(self.const_str(path)?);
+                    Genome::load_genome_file(File::open(path)?)
+                        .map_err(|err| InterpretError::Runtime(err.to_string()))?;
+                }
+                // A query without any output does nothing, the same as an unused iterator
+                _ => drop(self.plan(ir)?),
+            }
+        }
+        for action in actions {
+            match action {
+                Action::Write(stream, Output::Stdout) => {
+                    self.write_records(stream, std::io::stdout().lock())?
+                }
+                Action::Write(stream, Output::Stderr) => {
+                    self.write_records(stream, std::io::stderr().lock())?
+                }
+                Action::Write(stream, Output::File(path)) => {
+                    self.write_records(stream, File::create(path)?)?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_records<W: Write>(&self, stream: RecordStream, writer: W) -> Result<()> {
+        let mut writer = BufWriter::new(writer);
+        let result = (|| {
+            for item in stream {
+                self.error.take()?;
+                item.dump(&mut writer)?;
+                writer.write_all(b"\n")?;
+            }
+            self.error.take()?;
+            writer.flush()?;
+            Ok(())
+        })();
+        match result {
+            Err(InterpretError::Io(err)) if err.kind() == ErrorKind::BrokenPipe => Ok(()),
+            other => other,
+        }
+    }
+
+    fn plan_write(&mut self, param: &WriteFileParam) -> Result<Action> {
+        let stream = self.plan(&param.what)?;
+        let output = match &param.target {
+            WriteTarget::FileNo(1) => Output::Stdout,
+            WriteTarget::FileNo(2) => Output::Stderr,
+            WriteTarget::FileNo(fd) => {
+                return Err(InterpretError::Unsupported(format!(
+                    "Writing to file #{}",
+                    fd
+                )))
+            }
+            WriteTarget::Path(path) => Output::File(self.working_dir.join(self.const_str(path)?)),
+        };
+        Ok(Action::Write(stream, output))
+    }
+
+    fn plan_open(&self, param: &OpenParam) -> Result<RecordStream> {
+        let num_of_fields = param.num_of_fields as u32;
+        if !matches!(param.format, InputFormat::Bed) || !(3..=6).contains(&num_of_fields) {
+            return Err(InterpretError::Unsupported(format!(
+                "Reading {:?} input with {} fields",
+                param.format, param.num_of_fields
+            )));
+        }
+        let reader: Box<dyn Read> = match self.input_path(&param.target)? {
+            Some(path) => Box::new(File::open(path)?),
+            None if matches!(param.target, OpenTarget::FileNo(0)) => Box::new(std::io::stdin()),
+            None => {
+                return Err(InterpretError::Unsupported(format!(
+                    "Reading from {:?}",
+                    param.target
+                )))
+            }
+        };
+        let reader: Box<dyn Read> = if param.compression {
+            Box::new(GzipReader::new(reader))
+        } else {
+            reader
+        };
+        let to_dyn = move |bed| DynRecord::Bed(bed);
+        let stream: RecordStream = match num_of_fields {
+            3 => Box::new(
+                reader
+                    .into_record_iter::<Bed3>()
+                    .map(move |r| to_dyn(BedRecord::new(&r, 3))),
+            ),
+            4 => Box::new(
+                reader
+                    .into_record_iter::<Bed4>()
+                    .map(move |r| to_dyn(BedRecord::new(&r, 4))),
+            ),
+            5 => Box::new(
+                reader
+                    .into_record_iter::<Bed5>()
+                    .map(move |r| to_dyn(BedRecord::new(&r, 5))),
+            ),
+            _ => Box::new(
+                reader
+                    .into_record_iter::<Bed6>()
+                    .map(move |r| to_dyn(BedRecord::new(&r, 6))),
+            ),
+        };
+        Ok(stream)
+    }
+
+    fn plan_closest(&mut self, param: &ClosestParam) -> Result<RecordStream> {
+        let options = ClosestOptions {
+            ties: match param.ties {
+                grass_ir::ClosestTies::All => ClosestTies::All,
+                grass_ir::ClosestTies::First => ClosestTies::First,
+                grass_ir::ClosestTies::Last => ClosestTies::Last,
+            },
+            direction: match param.direction {
+                grass_ir::ClosestDirection::Both => ClosestDirection::Both,
+                grass_ir::ClosestDirection::Upstream => ClosestDirection::Upstream,
+                grass_ir::ClosestDirection::Downstream => ClosestDirection::Downstream,
+            },
+            ignore_overlaps: param.ignore_overlaps,
+            k: param.k as usize,
+        };
+        // A record without any closest record is printed along with dots
+        let missing = match self.width(&param.rhs) {
+            Some(width) => DynRecord::Missing(width),
+            None => {
+                return Err(InterpretError::Unsupported(
+                    "Closest records of non-BED records".to_string(),
+                ))
+            }
+        };
+        let lhs = self.plan(&param.lhs)?.assume_sorted();
+        let rhs = self.plan(&param.rhs)?.assume_sorted();
+        Ok(Box::new(lhs.sorted_closest(rhs, options).map(
+            move |pair| {
+                let distance = pair.distance();
+                let b = pair.b.unwrap_or_else(|| missing.clone());
+                DynRecord::Closest(Rc::new((pair.a, b)), distance)
+            },
+        )))
+    }
+
+    fn plan_window(&mut self, param: &WindowParam) -> Result<RecordStream> {
+        let options = WindowOptions {
+            left: param.left,
+            right: param.right,
+            strand_relative: param.strand_relative,
+        };
+        let lhs = self.plan(&param.lhs)?.assume_sorted();
+        let rhs = self.plan(&param.rhs)?.assume_sorted();
+        if param.count_only {
+            Ok(Box::new(lhs.sorted_window_count(rhs, options).map(
+                |record| {
+                    let count = record.count();
+                    DynRecord::WindowCount(Rc::new(record.record), count)
+                },
+            )))
+        } else {
+            Ok(Box::new(
+                lhs.sorted_window(rhs, options)
+                    .map(|(a, b)| DynRecord::pair(a, b)),
+            ))
+        }
+    }
+
+    fn plan_coverage(&mut self, param: &CoverageParam) -> Result<RecordStream> {
+        let lhs = self.plan(&param.lhs)?.assume_sorted();
+        let rhs = self.plan(&param.rhs)?.assume_sorted();
+        if param.histogram {
+            Ok(Box::new(
+                lhs.sorted_depth_histogram(rhs)
+                    .map(|bin| DynRecord::DepthBin(Rc::new(bin))),
+            ))
+        } else {
+            let options = CoverageOptions {
+                by_strand: param.by_strand,
+            };
+            Ok(Box::new(
+                lhs.sorted_coverage(rhs, options)
+                    .map(|coverage| DynRecord::Coverage(Rc::new(coverage))),
+            ))
+        }
+    }
+
+    fn plan_genome_coverage(&mut self, param: &GenomeCoverageParam) -> Result<RecordStream> {
+        let options = GenomeCoverageOptions {
+            include_zero: param.include_zero,
+            strand: param.strand.map(|strand| match strand {
+                CoverageStrand::Positive => Strand::Positive,
+                CoverageStrand::Negative => Strand::Negative,
+            }),
+            scale: param.scale.unwrap_or(1.0),
+        };
+        let input = self.plan(&param.inner)?.assume_sorted();
+        match param.report {
+            GenomeCoverageReport::BedGraph => Ok(Box::new(
+                input.genome_coverage(options).map(DynRecord::BedGraph),
+            )),
+            GenomeCoverageReport::Histogram => Ok(Box::new(
+                input
+                    .genome_histogram(options)
+                    .map(DynRecord::GenomeHistogram),
+            )),
+        }
+    }
+
+    fn plan_overlap_stats(&mut self, ir: &GrassIR) -> Result<RecordStream> {
+        let children = ir.children();
+        let lhs = self.plan(children[0])?.assume_sorted();
+        let rhs = self.plan(children[1])?.assume_sorted();
+        Ok(match ir {
+            GrassIR::Jaccard(_) => Box::new(lhs.sorted_jaccard(rhs).map(DynRecord::Jaccard)),
+            GrassIR::Fisher(param) => Box::new(
+                lhs.sorted_fisher(rhs, param.genome_size)
+                    .map(DynRecord::Fisher),
+            ),
+            _ => Box::new(
+                lhs.sorted_relative_distance(rhs)
+                    .map(DynRecord::RelativeDistance),
+            ),
+        })
+    }
+
+    fn plan_resize(
+        &mut self,
+        ir: &GrassIR,
+        op: ResizeOp,
+        strand_relative: bool,
+        sorted: bool,
+    ) -> Result<RecordStream> {
+        if self.width(ir).is_none() {
+            return Err(InterpretError::Unsupported(format!(
+                "{} of non-BED records",
+                ir.opcode()
+            )));
+        }
+        let input = self.plan(ir.children()[0])?;
+        if sorted {
+            Ok(Box::new(
+                input
+                    .assume_sorted()
+                    .sorted_resize_regions(op, strand_relative),
+            ))
+        } else {
+            Ok(Box::new(input.resize_regions(op, strand_relative)))
+        }
+    }
+
+    fn plan_subtract(&mut self, param: &SubtractParam) -> Result<RecordStream> {
+        if self.width(&param.lhs).is_none() {
+            return Err(InterpretError::Unsupported(
+                "Subtract from non-BED records".to_string(),
+            ));
+        }
+        let options = SubtractOptions {
+            mode: match param.mode {
+                grass_ir::SubtractMode::Fragments => SubtractMode::Fragments,
+                grass_ir::SubtractMode::RemoveOverlapping => SubtractMode::RemoveOverlapping,
+            },
+            min_fraction: param.min_fraction.unwrap_or(0.0),
+            same_strand: param.same_strand,
+        };
+        let lhs = self.plan(&param.lhs)?.assume_sorted();
+        let rhs = self.plan(&param.rhs)?.assume_sorted();
+        Ok(Box::new(lhs.sorted_subtract(rhs, options)))
+    }
+
+    fn plan_aggregate(&mut self, param: &AggregateParam) -> Result<RecordStream> {
+        let funcs: Vec<_> = param
+            .columns
+            .iter()
+            .map(|column| match column.func {
+                AggregateFunction::Count => AggregateFunc::Count,
+                AggregateFunction::CountDistinct => AggregateFunc::CountDistinct,
+                AggregateFunction::Sum => AggregateFunc::Sum,
+                AggregateFunction::Mean => AggregateFunc::Mean,
+                AggregateFunction::Median => AggregateFunc::Median,
+                AggregateFunction::Min => AggregateFunc::Min,
+                AggregateFunction::Max => AggregateFunc::Max,
+                AggregateFunction::Mode => AggregateFunc::Mode,
+                AggregateFunction::Stdev => AggregateFunc::Stdev,
+                AggregateFunction::Collapse => AggregateFunc::Collapse,
+                AggregateFunction::Distinct => AggregateFunc::Distinct,
+                AggregateFunction::First => AggregateFunc::First,
+                AggregateFunction::Last => AggregateFunc::Last,
+            })
+            .collect();
+        let (evaluator, error) = (self.evaluator.clone(), self.error.clone());
+        let values: Vec<_> = param.columns.iter().map(|col| col.value.clone()).collect();
+        let push = move |item: &DynRecord, aggs: &mut [Aggregator]| {
+            for (agg, value) in aggs.iter_mut().zip(values.iter()) {
+                if let Some(value) = error.check(evaluator.eval(value, item)) {
+                    agg.push(&value);
+                }
+            }
+        };
+        let inner = self.plan(&param.expr)?;
+        let stream: RecordStream = match &param.group {
+            AggregateGroup::Keys(keys) => {
+                let (evaluator, error, keys) =
+                    (self.evaluator.clone(), self.error.clone(), keys.clone());
+                let key_of = move |item: &DynRecord| -> Vec<Value> {
+                    keys.iter()
+                        .filter_map(|key| error.check(evaluator.eval(key, item)))
+                        .collect()
+                };
+                Box::new(inner.aggregate_by(key_of, funcs, push).map(|record| {
+                    let columns = record.key.iter().map(Value::to_string);
+                    let columns = columns.chain(record.columns).collect();
+                    DynRecord::Aggregate(None, columns)
+                }))
+            }
+            AggregateGroup::Overlap => Box::new(
+                inner
+                    .assume_sorted()
+                    .aggregate_overlaps(funcs, push)
+                    .map(|record| DynRecord::Aggregate(Some(record.key), record.columns.into())),
+            ),
+        };
+        Ok(stream)
+    }
+
+    fn plan_intersect(&mut self, param: &IntersectParam) -> Result<RecordStream> {
+        if param.blocks_only {
+            return Err(InterpretError::Unsupported(
+                "Block-only intersection".to_string(),
+            ));
+        }
+        let lhs = self.plan(&param.lhs)?;
+        let rhs = self.plan(&param.rhs)?;
+        // The unmatched side of an outer intersection is printed as dots, one for each field
+        let missing = |ir: &GrassIR| match (&param.flavor, self.width(ir)) {
+            (IntersectFlavor::Inner, _) => Ok(DynRecord::Missing(0)),
+            (_, Some(width)) => Ok(DynRecord::Missing(width)),
+            (_, None) => Err(InterpretError::Unsupported(
+                "Outer intersection of non-BED records".to_string(),
+            )),
+        };
+        let (lhs_missing, rhs_missing) = (missing(&param.lhs)?, missing(&param.rhs)?);
+        let or_missing =
+            |missing: DynRecord| move |r: Option<DynRecord>| r.unwrap_or_else(|| missing.clone());
+        let (lhs_or, rhs_or) = (or_missing(lhs_missing), or_missing(rhs_missing));
+
+        let stream: RecordStream = match (param.sorted, &param.flavor) {
+            (true, IntersectFlavor::Inner) => Box::new(
+                lhs.assume_sorted()
+                    .sorted_intersect(rhs.assume_sorted())
+                    .map(|(a, b)| DynRecord::pair(a, b)),
+            ),
+            (true, IntersectFlavor::LeftOuter) => Box::new(
+                lhs.assume_sorted()
+                    .sorted_left_outer_intersect(rhs.assume_sorted())
+                    .map(move |(a, b)| DynRecord::pair(a, rhs_or(b))),
+            ),
+            (true, IntersectFlavor::RightOuter) => Box::new(
+                rhs.assume_sorted()
+                    .sorted_left_outer_intersect(lhs.assume_sorted())
+                    .map(move |(b, a)| DynRecord::pair(lhs_or(a), b)),
+            ),
+            (true, IntersectFlavor::Outer) => Box::new(
+                lhs.assume_sorted()
+                    .sorted_full_outer_intersect(rhs.assume_sorted())
+                    .map(move |(a, b)| DynRecord::pair(lhs_or(a), rhs_or(b))),
+            ),
+            (false, IntersectFlavor::Inner) => Box::new(
+                lhs.indexed_intersect(rhs.collect::<IntervalIndex<_>>())
+                    .map(|(a, b)| DynRecord::pair(a, b)),
+            ),
+            (false, IntersectFlavor::LeftOuter) => Box::new(
+                lhs.indexed_left_outer_intersect(rhs.collect::<IntervalIndex<_>>())
+                    .map(move |(a, b)| DynRecord::pair(a, rhs_or(b))),
+            ),
+            (false, IntersectFlavor::RightOuter) => Box::new(
+                rhs.indexed_left_outer_intersect(lhs.collect::<IntervalIndex<_>>())
+                    .map(move |(b, a)| DynRecord::pair(lhs_or(a), b)),
+            ),
+            (false, IntersectFlavor::Outer) => Box::new(
+                lhs.indexed_full_outer_intersect(rhs.collect::<IntervalIndex<_>>())
+                    .map(move |(a, b)| DynRecord::pair(lhs_or(a), rhs_or(b))),
+            ),
+        };
+        Ok(stream)
+    }
+
+    /// Build the record stream of an expression
+    fn plan(&mut self, ir: &GrassIR) -> Result<RecordStream> {
+        let unsupported = || {
+            Err(InterpretError::Unsupported(format!(
+                "{} operator",
+                ir.opcode()
+            )))
+        };
+        let stream: RecordStream = match ir {
+            GrassIR::Open(param) => self.plan_open(param)?,
+            GrassIR::Let(param) => {
+                let value = self.plan(&param.value)?;
+                self.widths
+                    .insert(param.id.clone(), self.width(&param.value));
+                let ref_count = self.ref_counts.get(&param.id).copied().unwrap_or(0);
+                if ref_count == 0 {
+                    return Ok(value);
+                }
+                // Like the compiled query, the binding itself and each reference read from their
+                // own branch of a tee
+                let tee = Tee::new(value, ref_count + 1);
+                let branch = tee.branch();
+                self.symbols.insert(param.id.clone(), tee);
+                Box::new(branch)
+            }
+            GrassIR::Ref(param) => match self.symbols.get(&param.id) {
+                Some(tee) => Box::new(tee.branch()),
+                None => {
+                    return Err(InterpretError::Runtime(format!(
+                        "Reference to undefined symbol {}",
+                        param.id
+                    )))
+                }
+            },
+            GrassIR::Filter(param) => {
+                let (evaluator, error, cond) = (
+                    self.evaluator.clone(),
+                    self.error.clone(),
+                    param.cond.clone(),
+                );
+                Box::new(self.plan(&param.input_expr)?.filter(move |item| {
+                    match error.check(evaluator.eval(&cond, item)) {
+                        Some(Value::Bool(keep)) => keep,
+                        Some(_) => error
+                            .check::<()>(Err("The filter condition isn't a boolean".to_string()))
+                            .is_some(),
+                        None => false,
+                    }
+                }))
+            }
+            GrassIR::Alter(param) => {
+                let (evaluator, error) = (self.evaluator.clone(), self.error.clone());
+                let (field, value) = (param.field.clone(), param.value.clone());
+                Box::new(self.plan(&param.original_expr)?.map(move |mut item| {
+                    if let Some(value) = error.check(evaluator.eval(&value, &item)) {
+                        error.check(item.set_field(&field, &value));
+                    }
+                    item
+                }))
+            }
+            GrassIR::Format(param) => {
+                let (evaluator, error) = (self.evaluator.clone(), self.error.clone());
+                let (fmt_str, values) = (param.fmt_str.clone(), param.values.clone());
+                Box::new(self.plan(&param.expr)?.map(move |item| {
+                    let text = error
+                        .check(evaluator.format(&fmt_str, &values, &item))
+                        .unwrap_or_default();
+                    DynRecord::Text(text.into())
+                }))
+            }
+            GrassIR::CastToBed(param) if (3..=6).contains(&param.num_of_fields) => {
+                let num_of_fields = param.num_of_fields;
+                Box::new(
+                    self.plan(&param.inner)?
+                        .map(move |item| DynRecord::Bed(item.to_bed(num_of_fields))),
+                )
+            }
+            GrassIR::AssumeSorted(param) => self.plan(&param.inner)?,
+            GrassIR::Nop(param) => self.plan(&param.inner)?,
+            GrassIR::InternalSort(param) => {
+                let mut buffer: Vec<_> = self.plan(&param.inner)?.collect();
+                buffer.sort_by(DynRecord::cmp_position);
+                Box::new(buffer.into_iter())
+            }
+            GrassIR::Limit(param) => {
+                let count = self.const_num(&param.count)? as usize;
+                Box::new(self.plan(&param.what)?.take(count))
+            }
+            GrassIR::Intersection(param) => self.plan_intersect(param)?,
+            GrassIR::Closest(param) => self.plan_closest(param)?,
+            GrassIR::Window(param) => self.plan_window(param)?,
+            GrassIR::Coverage(param) => self.plan_coverage(param)?,
+            GrassIR::GenomeCoverage(param) => self.plan_genome_coverage(param)?,
+            GrassIR::Jaccard(_) | GrassIR::Fisher(_) | GrassIR::RelativeDistance(_) => {
+                self.plan_overlap_stats(ir)?
+            }
+            GrassIR::Slop(param) => {
+                let op = ResizeOp::Slop {
+                    left: resize_amount(&param.left),
+                    right: resize_amount(&param.right),
+                };
+                self.plan_resize(ir, op, param.strand_relative, param.sorted)?
+            }
+            GrassIR::Flank(param) => {
+                let op = ResizeOp::Flank {
+                    left: resize_amount(&param.left),
+                    right: resize_amount(&param.right),
+                };
+                self.plan_resize(ir, op, param.strand_relative, param.sorted)?
+            }
+            GrassIR::Resize(param) => {
+                let anchor = match param.anchor {
+                    grass_ir::ResizeAnchor::Start => ResizeAnchor::Start,
+                    grass_ir::ResizeAnchor::Center => ResizeAnchor::Center,
+                    grass_ir::ResizeAnchor::End => ResizeAnchor::End,
+                };
+                let op = ResizeOp::Resize {
+                    size: resize_amount(&param.size),
+                    anchor,
+                };
+                self.plan_resize(ir, op, param.strand_relative, param.sorted)?
+            }
+            GrassIR::Subtract(param) => self.plan_subtract(param)?,
+            GrassIR::MergeOverlap(param) => {
+                let mut components = self.plan(&param.input_expr)?.assume_sorted().components();
+                // A cluster of overlapping records ends when the depth drops back to 0
+                Box::new(std::iter::from_fn(move || {
+                    let first = components.next()?;
+                    let end = loop {
+                        let comp = components.next()?;
+                        if comp.depth == 0 {
+                            break comp.end();
+                        }
+                    };
+                    let merged = Bed3 {
+                        chrom: first.chrom(),
+                        start: first.start(),
+                        end,
+                    };
+                    Some(DynRecord::Bed(BedRecord::new(&merged, 3)))
+                }))
+            }
+            GrassIR::Invert(param) => Box::new(
+                self.plan(&param.inner)?
+                    .map(|item| Bed3::new(&item))
+                    .assume_sorted()
+                    .invert()
+                    .map(|bed| DynRecord::Bed(BedRecord::new(&bed, 3))),
+            ),
+            GrassIR::TwoWayMerge(param) => {
+                let first = self.plan(&param.expr_1)?;
+                let second = self.plan(&param.expr_2)?;
+                // The records of the second expression are casted to the type of the first one
+                let second: RecordStream = match self.width(&param.expr_1) {
+                    Some(width) => {
+                        Box::new(second.map(move |item| DynRecord::Bed(item.to_bed(width))))
+                    }
+                    None => second,
+                };
+                Box::new(first.assume_sorted().merge_with(second.assume_sorted()))
+            }
+            GrassIR::GroupBy(param) => {
+                let (evaluator, error, keys) = (
+                    self.evaluator.clone(),
+                    self.error.clone(),
+                    param.keys.clone(),
+                );
+                let key_of = move |item: &DynRecord| -> Vec<Value> {
+                    keys.iter()
+                        .filter_map(|key| error.check(evaluator.eval(key, item)))
+                        .collect()
+                };
+                let mut items = self.plan(&param.expr)?.peekable();
+                Box::new(std::iter::from_fn(move || {
+                    let first = items.next()?;
+                    let key = key_of(&first);
+                    let mut group = vec![first];
+                    while let Some(item) = items.next_if(|item| key_of(item) == key) {
+                        group.push(item);
+                    }
+                    Some(DynRecord::Group(Rc::new(group)))
+                }))
+            }
+            GrassIR::Count(param) => {
+                let inner = self.plan(&param.what)?;
+                if param.keys.is_empty() {
+                    Box::new(
+                        inner
+                            .count_records()
+                            .map(|count| DynRecord::Count(Rc::new([]), count.count())),
+                    )
+                } else {
+                    let (evaluator, error, keys) = (
+                        self.evaluator.clone(),
+                        self.error.clone(),
+                        param.keys.clone(),
+                    );
+                    Box::new(
+                        inner
+                            .count_by(move |item| -> Vec<Value> {
+                                keys.iter()
+                                    .filter_map(|key| error.check(evaluator.eval(key, item)))
+                                    .collect()
+                            })
+                            .map(|count| DynRecord::Count(count.key.clone().into(), count.count())),
+                    )
+                }
+            }
+            GrassIR::Aggregate(param) => self.plan_aggregate(param)?,
+            GrassIR::AssignTag(param) => {
+                let tag = match &param.tag {
+                    TagValue::String(value) => Value::Str(value.as_str().into()),
+                    TagValue::Int(value) => Value::Int(*value),
+                    TagValue::Float(value) => Value::Float(*value),
+                };
+                Box::new(
+                    self.plan(&param.inner)?
+                        .map(move |item| DynRecord::Tagged(Rc::new(item), tag.clone())),
+                )
+            }
+            GrassIR::SortedRandom(param) => {
+                let count = self.const_num(&param.count)? as usize;
+                let min_length = self.const_num(&param.min_length)? as usize;
+                let max_length = self.const_num(&param.max_length)? as usize;
+                Box::new(
+                    SortedRandomInterval::new(min_length, max_length, count)
+                        .map(|bed| DynRecord::Bed(BedRecord::new(&bed, 3))),
+                )
+            }
+            _ => return unsupported(),
+        };
+        Ok(stream)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use grass_ir::GrassIR;
+
+    use super::{parse_const_bag, Interpreter, Value};
+
+    fn interpret(ir: &str, const_bag: &str) -> String {
+        let output = tempfile::NamedTempFile::new().unwrap();
+        let ir = ir.replace("$OUTPUT", output.path().to_str().unwrap());
+        let irs: Vec<GrassIR> = serde_json::from_str(&ir).unwrap();
+        let data_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../data");
+        let const_bag = parse_const_bag(Some(const_bag), &["str".to_string()]).unwrap();
+        Interpreter::new(data_dir.as_ref(), &[], const_bag)
+            .run(&irs)
+            .unwrap();
+        std::fs::read_to_string(output.path()).unwrap()
+    }
+
+    #[test]
+    fn test_interpret_merge_and_filter() {
+        let merge = r#"[{
+            "opcode": "WriteFile",
+            "target": "$OUTPUT",
+            "what": {
+                "opcode": "MergeOverlap",
+                "inner": {
+                    "opcode": "Open",
+                    "target": {"Path": {"const_bag_key": 0}},
+                    "format": "Bed",
+                    "num_of_fields": 3,
+                    "compression": false,
+                    "sorted": true
+                }
+            }
+        }]"#;
+        assert_eq!(interpret(merge, "a.bed"), "1\t10\t110\n1\t1000\t8000\n");
+
+        let filter = r#"[{
+            "opcode": "WriteFile",
+            "target": "$OUTPUT",
+            "what": {
+                "opcode": "Filter",
+                "inner": {
+                    "opcode": "Open",
+                    "target": {"Path": "a.bed"},
+                    "format": "Bed",
+                    "num_of_fields": 3,
+                    "compression": false,
+                    "sorted": true
+                },
+                "cond": {
+                    "opcode": "LessEqualThan",
+                    "lhs": {"opcode": "FieldRef", "field": "start"},
+                    "rhs": {"opcode": "ConstValue", "value": 20}
+                }
+            }
+        }]"#;
+        assert_eq!(interpret(filter, ""), "1\t10\t100\n1\t20\t50\n");
+    }
+
+    #[test]
+    fn test_interpret_intersect_with_let() {
+        let intersect = r#"[{
+            "opcode": "WriteFile",
+            "target": "$OUTPUT",
+            "what": {
+                "opcode": "Format",
+                "fmt_str": "{a}|{n}",
+                "values": {
+                    "a": {"opcode": "RecordRef", "id": 0},
+                    "n": {"opcode": "ComponentFieldRef", "target": 1, "field_name": "start"}
+                },
+                "inner": {
+                    "opcode": "Intersection",
+                    "flavor": "inner",
+                    "sorted": false,
+                    "lhs": {
+                        "opcode": "Let",
+                        "id": "a",
+                        "value": {
+                            "opcode": "Open",
+                            "target": {"Path": "a.bed"},
+                            "format": "Bed",
+                            "num_of_fields": 3,
+                            "compression": false,
+                            "sorted": true
+                        }
+                    },
+                    "rhs": {
+                        "opcode": "Filter",
+                        "inner": {"opcode": "Ref", "id": "a"},
+                        "cond": {
+                            "opcode": "Eq",
+                            "lhs": {"opcode": "FieldRef", "field": "start"},
+                            "rhs": {"opcode": "ConstValue", "value": 1500}
+                        }
+                    }
+                }
+            }
+        }]"#;
+        assert_eq!(
+            interpret(intersect, ""),
+            "1\t1000\t2000|1500\n1\t1500\t8000|1500\n"
+        );
+        assert!(
+            parse_const_bag(Some("1.5;x\\;y"), &["f64".into(), "str".into()])
+                .unwrap()
+                .into_iter()
+                .eq([Value::Float(1.5), Value::Str("x;y".into())])
+        );
+    }
+}
@@ -0,0 +1,432 @@
+use std::{
+    cmp::Ordering,
+    io::{Result, Write},
+    rc::Rc,
+};
+
+use grass_runtime::{
+    algorithm::{
+        BedGraphSegment, Coverage, DepthBin, FisherTest, GenomeHistogramLine, Jaccard,
+        RelativeDistanceBin,
+    },
+    builtin::MISSING,
+    property::{Named, RegionCore, RegionMut, Scored, Serializable, Strand, Stranded},
+    record::{Bed3, ToSelfContained},
+    ChrRef,
+};
+
+use super::value::Value;
+
+/// A BED record with 3 to 6 fields, the fields beyond `num_of_fields` are ignored
+#[derive(Clone, PartialEq)]
+pub struct BedRecord {
+    pub chrom: ChrRef<'static>,
+    pub start: u32,
+    pub end: u32,
+    pub name: Rc<str>,
+    pub score: Option<f64>,
+    pub strand: Strand,
+    pub num_of_fields: u32,
+}
+
+impl BedRecord {
+    /// Copy the properties of a record of the runtime into a BED record with the given number of
+    /// fields
+    pub fn new<'a, T>(record: &T, num_of_fields: u32) -> Self
+    where
+        T: RegionCore + Named<'a> + Scored<f64> + Stranded,
+    {
+        Self {
+            chrom: record.chrom(),
+            start: record.start(),
+            end: record.end(),
+            name: record.name().into(),
+            score: record.score(),
+            strand: record.strand(),
+            num_of_fields,
+        }
+    }
+}
+
+/// The dynamically typed record the interpreter passes between operators. The shapes mirror the
+/// records the compiled code produces, so that both of them print the same text.
+#[derive(Clone, PartialEq)]
+pub enum DynRecord {
+    Bed(BedRecord),
+    /// The placeholder of the record on the unmatched side of an outer intersection, which keeps
+    /// the number of fields to print
+    Missing(u32),
+    /// A pair of records produced by an intersection
+    Pair(Rc<(DynRecord, DynRecord)>),
+    /// A record along with one of its closest records and the distance between them, the region
+    /// is the one of the first record
+    Closest(Rc<(DynRecord, DynRecord)>, i64),
+    /// A record along with the number of records within its window
+    WindowCount(Rc<DynRecord>, usize),
+    /// A record along with how much it's covered, which is printed by the runtime
+    Coverage(Rc<Coverage<DynRecord>>),
+    /// A record along with the number of its bases at one depth
+    DepthBin(Rc<DepthBin<DynRecord>>),
+    /// A run of bases at the same depth along the genome
+    BedGraph(BedGraphSegment),
+    /// The number of bases at one depth in a chromosome or the genome
+    GenomeHistogram(GenomeHistogramLine),
+    /// The Jaccard statistic of two inputs
+    Jaccard(Jaccard),
+    /// Fisher's exact test of the overlaps between two inputs
+    Fisher(FisherTest),
+    /// The number of records in one bin of the relative distance distribution
+    RelativeDistance(RelativeDistanceBin),
+    Tagged(Rc<DynRecord>, Value),
+    Group(Rc<Vec<DynRecord>>),
+    Count(Rc<[Value]>, usize),
+    /// The columns aggregated over a group, which follow the merged region of a cluster of
+    /// overlapping records, the keys of a group are the leading columns
+    Aggregate(Option<Bed3>, Rc<[String]>),
+    /// A line of text produced by a custom format
+    Text(Rc<str>),
+}
+
+impl DynRecord {
+    pub fn pair(a: DynRecord, b: DynRecord) -> Self {
+        DynRecord::Pair(Rc::new((a, b)))
+    }
+
+    /// The idx-th record of a pair or a group, the members beyond the end of a group are missing
+    /// records, just like the `Option` members of the runtime groups
+    pub fn component(&self, idx: usize) -> Option<&DynRecord> {
+        match self {
+            DynRecord::Pair(pair) if idx == 0 => Some(&pair.0),
+            DynRecord::Pair(pair) if idx == 1 => Some(&pair.1),
+            DynRecord::Closest(pair, _) if idx == 0 => Some(&pair.0),
+            DynRecord::Closest(pair, _) if idx == 1 => Some(&pair.1),
+            DynRecord::WindowCount(inner, _) if idx == 0 => Some(inner),
+            DynRecord::Coverage(coverage) if idx == 0 => Some(&coverage.record),
+            DynRecord::DepthBin(bin) if idx == 0 => Some(&bin.record),
+            DynRecord::Group(records) => records.get(idx).or(Some(&DynRecord::Missing(3))),
+            DynRecord::Tagged(inner, _) => inner.component(idx),
+            _ => None,
+        }
+    }
+
+    pub fn number_of_components(&self) -> Option<usize> {
+        match self {
+            DynRecord::Pair(_) | DynRecord::Closest(..) => Some(2),
+            DynRecord::WindowCount(..) | DynRecord::Coverage(_) | DynRecord::DepthBin(_) => Some(1),
+            DynRecord::Group(records) => Some(records.len()),
+            DynRecord::Tagged(inner, _) => inner.number_of_components(),
+            _ => None,
+        }
+    }
+
+    /// The record which provides the name, score and strand, which is the first record of a pair
+    fn primary(&self) -> Option<&BedRecord> {
+        match self {
+            DynRecord::Bed(bed) => Some(bed),
+            DynRecord::Pair(pair) | DynRecord::Closest(pair, _) => pair.0.primary(),
+            DynRecord::Tagged(inner, _) | DynRecord::WindowCount(inner, _) => inner.primary(),
+            DynRecord::Coverage(coverage) => coverage.record.primary(),
+            DynRecord::DepthBin(bin) => bin.record.primary(),
+            _ => None,
+        }
+    }
+
+    /// Read a field of the primary record, a missing record reads the field in the same way as
+    /// the `Option` records of the runtime
+    fn primary_field(&self, read: impl Fn(&BedRecord) -> Value, missing: Value) -> Option<Value> {
+        match self {
+            DynRecord::Missing(_) => Some(missing),
+            _ => self.primary().map(read),
+        }
+    }
+
+    /// Read a field by its name, `None` means the record doesn't have the field
+    pub fn field(&self, name: &str) -> Option<Value> {
+        let value = match name {
+            "chrom" => Value::Str(self.chrom().get_chr_name().into()),
+            "start" => Value::from(self.start()),
+            "end" => Value::from(self.end()),
+            "length" => Value::from(self.length()),
+            "name" => self.primary_field(|bed| Value::Str(bed.name.clone()), MISSING.into())?,
+            "score" => match self {
+                DynRecord::BedGraph(segment) => segment.score().unwrap_or_default().into(),
+                _ => self.primary_field(|bed| bed.score.unwrap_or_default().into(), 0.0.into())?,
+            },
+            "strand" => self.primary_field(|bed| bed.strand.as_str().into(), MISSING.into())?,
+            "count" => match self {
+                DynRecord::Count(_, count) | DynRecord::WindowCount(_, count) => {
+                    Value::from(*count)
+                }
+                DynRecord::Coverage(coverage) => Value::from(coverage.count()),
+                DynRecord::RelativeDistance(bin) => Value::from(bin.count()),
+                _ => return None,
+            },
+            "forward_count" => match self {
+                DynRecord::Coverage(coverage) => Value::from(coverage.forward_count()),
+                _ => return None,
+            },
+            "reverse_count" => match self {
+                DynRecord::Coverage(coverage) => Value::from(coverage.reverse_count()),
+                _ => return None,
+            },
+            "covered" => match self {
+                DynRecord::Coverage(coverage) => Value::from(coverage.covered()),
+                DynRecord::DepthBin(bin) => Value::from(bin.covered()),
+                DynRecord::GenomeHistogram(line) => Value::Int(line.covered() as i64),
+                _ => return None,
+            },
+            "fraction" => match self {
+                DynRecord::Coverage(coverage) => Value::Float(coverage.fraction()),
+                DynRecord::DepthBin(bin) => Value::Float(bin.fraction()),
+                DynRecord::GenomeHistogram(line) => Value::Float(line.fraction()),
+                DynRecord::RelativeDistance(bin) => Value::Float(bin.fraction()),
+                _ => return None,
+            },
+            "depth" => match self {
+                DynRecord::DepthBin(bin) => Value::from(bin.depth()),
+                DynRecord::BedGraph(segment) => Value::from(segment.depth()),
+                DynRecord::GenomeHistogram(line) => Value::from(line.depth()),
+                _ => return None,
+            },
+            "size" => match self {
+                DynRecord::GenomeHistogram(line) => Value::Int(line.size() as i64),
+                _ => return None,
+            },
+            "intersection" | "union" | "n_intersections" | "jaccard" => match self {
+                DynRecord::Jaccard(jaccard) => match name {
+                    "intersection" => Value::Int(jaccard.intersection() as i64),
+                    "union" => Value::Int(jaccard.union() as i64),
+                    "n_intersections" => Value::from(jaccard.n_intersections()),
+                    _ => Value::Float(jaccard.jaccard()),
+                },
+                _ => return None,
+            },
+            "left_tail" | "right_tail" | "two_tail" | "ratio" => match self {
+                DynRecord::Fisher(test) => Value::Float(match name {
+                    "left_tail" => test.left_tail(),
+                    "right_tail" => test.right_tail(),
+                    "two_tail" => test.two_tail(),
+                    _ => test.ratio(),
+                }),
+                _ => return None,
+            },
+            "reldist" => match self {
+                DynRecord::RelativeDistance(bin) => Value::Float(bin.reldist()),
+                _ => return None,
+            },
+            "total" => match self {
+                DynRecord::RelativeDistance(bin) => Value::from(bin.total()),
+                _ => return None,
+            },
+            "distance" => match self {
+                DynRecord::Closest(_, distance) => Value::Int(*distance),
+                _ => return None,
+            },
+            "tag_str" => match self {
+                DynRecord::Tagged(_, tag) => Value::Str(tag.to_string().into()),
+                _ => Value::Str(".".into()),
+            },
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Modify a field in place, this is only allowed on the BED records
+    pub fn set_field(&mut self, name: &str, value: &Value) -> std::result::Result<(), String> {
+        let bed = match self {
+            DynRecord::Bed(bed) => bed,
+            DynRecord::Tagged(inner, _) => return Rc::make_mut(inner).set_field(name, value),
+            _ => {
+                return Err(format!(
+                    "Unable to modify field {} of a non-BED record",
+                    name
+                ))
+            }
+        };
+        match (name, value, value.as_float()) {
+            ("start", _, Some(v)) => bed.start = v as u32,
+            ("end", _, Some(v)) => bed.end = v as u32,
+            ("score", _, Some(v)) if bed.num_of_fields >= 5 => bed.score = Some(v),
+            ("name", Value::Str(v), _) if bed.num_of_fields >= 4 => bed.name = v.clone(),
+            ("strand", Value::Str(v), _) if bed.num_of_fields >= 6 => {
+                bed.strand = match v.as_ref() {
+                    "+" => Strand::Positive,
+                    "-" => Strand::Negative,
+                    _ => Strand::Unknown,
+                }
+            }
+            _ => return Err(format!("Unable to assign {} to field {}", value, name)),
+        }
+        Ok(())
+    }
+
+    /// Convert the record to a BED record with the given number of fields
+    pub fn to_bed(&self, num_of_fields: u32) -> BedRecord {
+        let mut bed = BedRecord::new(&Bed3::new(self), num_of_fields);
+        if let Some(primary) = self.primary() {
+            bed.name = primary.name.clone();
+            bed.score = primary.score;
+            bed.strand = primary.strand;
+        }
+        bed
+    }
+
+    /// The sorting order of the records, which is the genomic order
+    pub fn cmp_position(&self, other: &Self) -> Ordering {
+        (self.chrom(), self.start(), self.end()).cmp(&(other.chrom(), other.start(), other.end()))
+    }
+
+    fn outline(records: &[DynRecord]) -> Option<(ChrRef<'static>, u32, u32)> {
+        let mut ret: Option<(ChrRef<'static>, u32, u32)> = None;
+        for record in records {
+            ret = match ret {
+                Some((chrom, _, _)) if chrom != record.chrom() => return None,
+                Some((chrom, start, end)) => {
+                    Some((chrom, start.min(record.start()), end.max(record.end())))
+                }
+                None => Some((record.chrom(), record.start(), record.end())),
+            };
+        }
+        ret
+    }
+}
+
+impl RegionCore for DynRecord {
+    fn start(&self) -> u32 {
+        match self {
+            DynRecord::Bed(bed) => bed.start,
+            DynRecord::Pair(pair) => pair.as_ref().start(),
+            DynRecord::Tagged(inner, _) | DynRecord::WindowCount(inner, _) => inner.start(),
+            DynRecord::Coverage(coverage) => coverage.start(),
+            DynRecord::DepthBin(bin) => bin.start(),
+            DynRecord::BedGraph(segment) => segment.start,
+            DynRecord::Closest(pair, _) => pair.0.start(),
+            DynRecord::Group(records) => Self::outline(records).map_or(0, |(_, s, _)| s),
+            DynRecord::Aggregate(Some(region), _) => region.start,
+            _ => 0,
+        }
+    }
+    fn end(&self) -> u32 {
+        match self {
+            DynRecord::Bed(bed) => bed.end,
+            DynRecord::Pair(pair) => pair.as_ref().end(),
+            DynRecord::Tagged(inner, _) | DynRecord::WindowCount(inner, _) => inner.end(),
+            DynRecord::Coverage(coverage) => coverage.end(),
+            DynRecord::DepthBin(bin) => bin.end(),
+            DynRecord::BedGraph(segment) => segment.end,
+            DynRecord::Closest(pair, _) => pair.0.end(),
+            DynRecord::Group(records) => Self::outline(records).map_or(0, |(_, _, e)| e),
+            DynRecord::Aggregate(Some(region), _) => region.end,
+            _ => 0,
+        }
+    }
+    fn chrom(&self) -> ChrRef<'static> {
+        match self {
+            DynRecord::Bed(bed) => bed.chrom,
+            DynRecord::Pair(pair) => pair.as_ref().chrom(),
+            DynRecord::Tagged(inner, _) | DynRecord::WindowCount(inner, _) => inner.chrom(),
+            DynRecord::Coverage(coverage) => coverage.chrom(),
+            DynRecord::DepthBin(bin) => bin.chrom(),
+            DynRecord::BedGraph(segment) => segment.chrom,
+            DynRecord::GenomeHistogram(line) => line.chrom(),
+            DynRecord::Closest(pair, _) => pair.0.chrom(),
+            DynRecord::Group(records) => {
+                Self::outline(records).map_or(ChrRef::Dummy, |(c, _, _)| c)
+            }
+            DynRecord::Aggregate(Some(region), _) => region.chrom,
+            _ => ChrRef::Dummy,
+        }
+    }
+}
+
+/// Only the BED records can be moved, the interpreter checks the input before resizing
+impl RegionMut for DynRecord {
+    fn set_region(&mut self, start: u32, end: u32) {
+        if let DynRecord::Bed(bed) = self {
+            bed.start = start;
+            bed.end = end;
+        }
+    }
+}
+
+impl Stranded for DynRecord {
+    fn strand(&self) -> Strand {
+        self.primary().map_or(Strand::Unknown, |bed| bed.strand)
+    }
+}
+
+impl ToSelfContained for DynRecord {
+    type SelfContained = DynRecord;
+    fn to_self_contained(&self) -> Self::SelfContained {
+        self.clone()
+    }
+}
+
+impl Serializable for DynRecord {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        self.write_to(&mut fp)
+    }
+}
+
+impl DynRecord {
+    // The records are nested, so this writes through a trait object rather than a generic writer
+    fn write_to(&self, mut fp: &mut dyn Write) -> Result<()> {
+        match self {
+            DynRecord::Bed(bed) => {
+                Bed3::new(self).dump(&mut fp)?;
+                if bed.num_of_fields >= 4 {
+                    write!(fp, "\t{}", bed.name)?;
+                }
+                if bed.num_of_fields >= 5 {
+                    match bed.score {
+                        Some(score) => write!(fp, "\t{}", score)?,
+                        None => write!(fp, "\t.")?,
+                    }
+                }
+                if bed.num_of_fields >= 6 {
+                    write!(fp, "\t{}", bed.strand)?;
+                }
+                Ok(())
+            }
+            DynRecord::Missing(num_of_fields) => {
+                let dots = vec!["."; *num_of_fields as usize];
+                write!(fp, "{}", dots.join("\t"))
+            }
+            DynRecord::Pair(pair) | DynRecord::Closest(pair, _) => {
+                pair.0.write_to(fp)?;
+                write!(fp, "\t")?;
+                pair.1.write_to(fp)
+            }
+            DynRecord::Tagged(inner, _) => inner.write_to(fp),
+            DynRecord::WindowCount(inner, count) => {
+                inner.write_to(&mut fp)?;
+                write!(fp, "\t{}", count)
+            }
+            DynRecord::Coverage(coverage) => coverage.dump(fp),
+            DynRecord::DepthBin(bin) => bin.dump(fp),
+            DynRecord::BedGraph(segment) => segment.dump(fp),
+            DynRecord::GenomeHistogram(line) => line.dump(fp),
+            DynRecord::Jaccard(jaccard) => jaccard.dump(fp),
+            DynRecord::Fisher(test) => test.dump(fp),
+            DynRecord::RelativeDistance(bin) => bin.dump(fp),
+            DynRecord::Group(records) => {
+                Bed3::new(self).dump(&mut fp)?;
+                write!(fp, "\t{}", records.len())
+            }
+            DynRecord::Count(keys, count) => {
+                for key in keys.iter() {
+                    write!(fp, "{}\t", key)?;
+                }
+                write!(fp, "{}", count)
+            }
+            DynRecord::Aggregate(region, columns) => {
+                if let Some(region) = region {
+                    region.dump(&mut fp)?;
+                    write!(fp, "\t")?;
+                }
+                write!(fp, "{}", columns.join("\t"))
+            }
+            DynRecord::Text(text) => fp.write_all(text.as_bytes()),
+        }
+    }
+}
@@ -0,0 +1,523 @@
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    rc::Rc,
+};
+
+use grass_ir::{
+    BinaryParam, CallParam, ConstOrEnv, ConstValue, FieldExpression, FieldType, Function,
+};
+use grass_runtime::{
+    algorithm::{AggregateInput, Aggregator},
+    builtin,
+    property::Serializable,
+    Regex,
+};
+
+use super::record::DynRecord;
+
+/// The dynamically typed value of a field expression, the types are the same as the
+/// `FieldType`s the compiler infers
+#[derive(Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Str(Rc<str>),
+    Bool(bool),
+    Record(Rc<DynRecord>),
+}
+
+/// The values are aggregated in the same way as the native values of the compiled code
+impl AggregateInput for &Value {
+    fn push_to(self, aggregator: &mut Aggregator) {
+        match self {
+            Value::Int(value) => aggregator.push(*value),
+            Value::Float(value) => aggregator.push(*value),
+            Value::Str(value) => aggregator.push(value.as_ref()),
+            Value::Bool(value) => aggregator.push(*value),
+            Value::Record(_) => (),
+        }
+    }
+}
+
+impl Value {
+    fn field_type(&self) -> FieldType {
+        match self {
+            Value::Int(_) => FieldType::Int,
+            Value::Float(_) => FieldType::Float,
+            Value::Str(_) => FieldType::Str,
+            Value::Bool(_) => FieldType::Bool,
+            Value::Record(_) => FieldType::Record,
+        }
+    }
+
+    /// The value of a number, integers are widened to floats
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Int(value) => Some(*value as f64),
+            Value::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Convert the value to another type, which follows the `Cast` node of the compiled code
+    pub fn cast(self, to: FieldType) -> EvalResult {
+        let value = match (self, to) {
+            (value, to) if value.field_type() == to => value,
+            (Value::Int(value), FieldType::Float) => Value::Float(value as f64),
+            (Value::Float(value), FieldType::Int) => Value::Int(value as i64),
+            (Value::Bool(value), FieldType::Int) => Value::Int(value as i64),
+            (Value::Bool(value), FieldType::Float) => Value::Float(value as i64 as f64),
+            (Value::Int(value), FieldType::Bool) => Value::Bool(value != 0),
+            (Value::Float(value), FieldType::Bool) => Value::Bool(value != 0.0),
+            (Value::Str(text), FieldType::Int) => Value::Int(
+                text.parse()
+                    .map_err(|_| format!("Unable to cast {:?} to i64", text))?,
+            ),
+            (Value::Str(text), FieldType::Float) => Value::Float(
+                text.parse()
+                    .map_err(|_| format!("Unable to cast {:?} to f64", text))?,
+            ),
+            (value, FieldType::Str) => Value::Str(value.to_string().into()),
+            (value, to) => return Err(format!("Unable to cast {} to {}", value.field_type(), to)),
+        };
+        Ok(value)
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Str(value) => write!(f, "{}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Record(record) => {
+                let mut buffer = Vec::new();
+                record.dump(&mut buffer).map_err(|_| std::fmt::Error)?;
+                write!(f, "{}", String::from_utf8_lossy(&buffer))
+            }
+        }
+    }
+}
+
+type EvalResult = Result<Value, String>;
+
+fn mismatch(lhs: &Value, rhs: &Value) -> String {
+    format!(
+        "Unsupported operand types: {} and {}",
+        lhs.field_type(),
+        rhs.field_type()
+    )
+}
+
+/// Evaluates field expressions against the records, the constant bag values are resolved up front
+/// by the interpreter
+#[derive(Clone)]
+pub struct Evaluator {
+    const_bag: Rc<Vec<Value>>,
+    regex_cache: Rc<RefCell<HashMap<String, Regex>>>,
+}
+
+impl Evaluator {
+    pub fn new(const_bag: Vec<Value>) -> Self {
+        Self {
+            const_bag: Rc::new(const_bag),
+            regex_cache: Default::default(),
+        }
+    }
+
+    pub fn const_value<T: Clone + Into<Value>>(&self, value: &ConstOrEnv<T>) -> EvalResult {
+        match value {
+            ConstOrEnv::Const(value) => Ok(value.clone().into()),
+            ConstOrEnv::Env(key) => self
+                .const_bag
+                .get(key.key())
+                .cloned()
+                .ok_or_else(|| format!("Missing constant bag value #{}", key.key())),
+        }
+    }
+
+    fn boolean(&self, expr: &FieldExpression, record: &DynRecord) -> Result<bool, String> {
+        match self.eval(expr, record)? {
+            Value::Bool(value) => Ok(value),
+            other => Err(format!("Expect a bool, but got {}", other.field_type())),
+        }
+    }
+
+    fn operands(&self, param: &BinaryParam, record: &DynRecord) -> Result<(Value, Value), String> {
+        Ok((
+            self.eval(&param.lhs, record)?,
+            self.eval(&param.rhs, record)?,
+        ))
+    }
+
+    /// Integers stay integers, and they are widened to floats when they meet a float
+    fn arith(
+        &self,
+        param: &BinaryParam,
+        record: &DynRecord,
+        int_op: fn(i64, i64) -> Option<i64>,
+        float_op: fn(f64, f64) -> f64,
+    ) -> EvalResult {
+        match self.operands(param, record)? {
+            (Value::Int(a), Value::Int(b)) => int_op(a, b)
+                .map(Value::Int)
+                .ok_or_else(|| "Integer overflow or division by zero".to_string()),
+            (a, b) => match (a.as_float(), b.as_float()) {
+                (Some(a), Some(b)) => Ok(Value::Float(float_op(a, b))),
+                _ => Err(mismatch(&a, &b)),
+            },
+        }
+    }
+
+    /// The logic operators on booleans, and the bitwise operators on integers
+    fn logic(
+        &self,
+        param: &BinaryParam,
+        record: &DynRecord,
+        bool_op: fn(bool, bool) -> bool,
+        int_op: fn(i64, i64) -> i64,
+    ) -> EvalResult {
+        match self.operands(param, record)? {
+            (Value::Bool(a), Value::Bool(b)) => Ok(Value::Bool(bool_op(a, b))),
+            (Value::Int(a), Value::Int(b)) => Ok(Value::Int(int_op(a, b))),
+            (a, b) => Err(mismatch(&a, &b)),
+        }
+    }
+
+    fn ordering(
+        &self,
+        param: &BinaryParam,
+        record: &DynRecord,
+    ) -> Result<Option<Ordering>, String> {
+        let ordering = match self.operands(param, record)? {
+            (Value::Int(a), Value::Int(b)) => Some(a.cmp(&b)),
+            (Value::Str(a), Value::Str(b)) => Some(a.cmp(&b)),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(&b)),
+            (a, b) => match (a.as_float(), b.as_float()) {
+                (Some(a), Some(b)) => a.partial_cmp(&b),
+                _ => return Err(mismatch(&a, &b)),
+            },
+        };
+        Ok(ordering)
+    }
+
+    fn compare(
+        &self,
+        param: &BinaryParam,
+        record: &DynRecord,
+        pred: fn(Ordering) -> bool,
+    ) -> EvalResult {
+        Ok(Value::Bool(self.ordering(param, record)?.is_some_and(pred)))
+    }
+
+    fn shift(&self, param: &BinaryParam, record: &DynRecord, left: bool) -> EvalResult {
+        match self.operands(param, record)? {
+            (Value::Int(a), Value::Int(b)) if left => Ok(Value::Int(a.wrapping_shl(b as u32))),
+            (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a.wrapping_shr(b as u32))),
+            (a, b) => Err(mismatch(&a, &b)),
+        }
+    }
+
+    fn regex_match(&self, text: &str, pattern: &str) -> EvalResult {
+        let mut cache = self.regex_cache.borrow_mut();
+        if !cache.contains_key(pattern) {
+            let regex = Regex::new(pattern).map_err(|e| e.to_string())?;
+            cache.insert(pattern.to_string(), regex);
+        }
+        Ok(Value::Bool(cache[pattern].is_match(text)))
+    }
+
+    fn component<'a>(&self, record: &'a DynRecord, idx: i32) -> Result<&'a DynRecord, String> {
+        usize::try_from(idx)
+            .ok()
+            .and_then(|idx| record.component(idx))
+            .ok_or_else(|| format!("Component {} doesn't exist", idx))
+    }
+
+    fn field(&self, record: &DynRecord, name: &str) -> EvalResult {
+        record
+            .field(name)
+            .ok_or_else(|| format!("Field {} doesn't exist on the record", name))
+    }
+
+    /// Call a built-in function, the arguments are converted in the same way as the compiled code
+    fn call(&self, param: &CallParam, record: &DynRecord) -> EvalResult {
+        let args = param
+            .args
+            .iter()
+            .map(|arg| self.eval(arg, record))
+            .collect::<Result<Vec<_>, _>>()?;
+        let arg_types: Vec<_> = args.iter().map(Value::field_type).collect();
+        let (param_types, _) = param.func.signature(&arg_types)?;
+        let args = args
+            .into_iter()
+            .zip(param_types)
+            .map(|(arg, ty)| arg.cast(ty))
+            .collect::<Result<Vec<_>, _>>()?;
+        let value = match (param.func, args.as_slice()) {
+            (Function::Substr, [Value::Str(text), Value::Int(start), rest @ ..]) => {
+                let len = match rest {
+                    [Value::Int(len)] => Some(*len),
+                    _ => None,
+                };
+                builtin::substr(text, *start, len).into()
+            }
+            (Function::Concat, parts) => {
+                let parts: Vec<_> = parts
+                    .iter()
+                    .map(|part| match part {
+                        Value::Str(text) => text.as_ref(),
+                        _ => "",
+                    })
+                    .collect();
+                builtin::concat(&parts).into()
+            }
+            (Function::SplitNth, [Value::Str(text), Value::Str(sep), Value::Int(n)]) => {
+                builtin::split_nth(text, sep, *n).into()
+            }
+            (Function::Replace, [Value::Str(text), Value::Str(from), Value::Str(to)]) => {
+                builtin::replace(text, from, to).into()
+            }
+            (Function::Upper, [Value::Str(text)]) => builtin::upper(text).into(),
+            (Function::Lower, [Value::Str(text)]) => builtin::lower(text).into(),
+            (Function::StartsWith, [Value::Str(text), Value::Str(prefix)]) => {
+                builtin::starts_with(text, prefix).into()
+            }
+            (Function::ParseInt, [Value::Str(text), rest @ ..]) => {
+                let default = match rest {
+                    [Value::Int(default)] => *default,
+                    _ => 0,
+                };
+                builtin::parse_int(text, default).into()
+            }
+            (Function::ParseFloat, [Value::Str(text), rest @ ..]) => {
+                let default = match rest {
+                    [Value::Float(default)] => *default,
+                    _ => f64::NAN,
+                };
+                builtin::parse_float(text, default).into()
+            }
+            (Function::Abs, [Value::Int(value)]) => value.saturating_abs().into(),
+            (Function::Abs, [Value::Float(value)]) => value.abs().into(),
+            (Function::Min | Function::Max, [first, rest @ ..]) => {
+                let min = param.func == Function::Min;
+                rest.iter()
+                    .fold(first.clone(), |acc, value| match (acc, value) {
+                        (Value::Int(a), Value::Int(b)) if min => Value::Int(a.min(*b)),
+                        (Value::Int(a), Value::Int(b)) => Value::Int(a.max(*b)),
+                        (Value::Float(a), Value::Float(b)) if min => Value::Float(a.min(*b)),
+                        (Value::Float(a), Value::Float(b)) => Value::Float(a.max(*b)),
+                        (acc, _) => acc,
+                    })
+            }
+            (Function::Round, [Value::Float(value)]) => (value.round() as i64).into(),
+            (Function::Floor, [Value::Float(value)]) => (value.floor() as i64).into(),
+            (Function::Log, [Value::Float(value)]) => value.ln().into(),
+            (Function::Log, [Value::Float(value), Value::Float(base)]) => value.log(*base).into(),
+            (Function::Exp, [Value::Float(value)]) => value.exp().into(),
+            (Function::Pow, [Value::Float(value), Value::Float(exp)]) => value.powf(*exp).into(),
+            (func, _) => return Err(format!("Invalid arguments for {}", func.name())),
+        };
+        Ok(value)
+    }
+
+    /// Evaluate the field expression with the given record
+    pub fn eval(&self, expr: &FieldExpression, record: &DynRecord) -> EvalResult {
+        match expr {
+            FieldExpression::And(param) => self.logic(param, record, |a, b| a & b, |a, b| a & b),
+            FieldExpression::Or(param) => self.logic(param, record, |a, b| a | b, |a, b| a | b),
+            FieldExpression::Xor(param) => self.logic(param, record, |a, b| a ^ b, |a, b| a ^ b),
+            FieldExpression::Not(param) => match self.eval(&param.operand, record)? {
+                Value::Bool(value) => Ok(Value::Bool(!value)),
+                Value::Int(value) => Ok(Value::Int(!value)),
+                other => Err(format!(
+                    "Unsupported operand type for Not: {}",
+                    other.field_type()
+                )),
+            },
+            FieldExpression::Add(param) => match self.operands(param, record)? {
+                (Value::Str(a), Value::Str(b)) => Ok(Value::Str(format!("{}{}", a, b).into())),
+                _ => self.arith(param, record, i64::checked_add, |a, b| a + b),
+            },
+            FieldExpression::Sub(param) => {
+                self.arith(param, record, i64::checked_sub, |a, b| a - b)
+            }
+            FieldExpression::Mul(param) => {
+                self.arith(param, record, i64::checked_mul, |a, b| a * b)
+            }
+            FieldExpression::Div(param) => {
+                let (a, b) = self.operands(param, record)?;
+                match (a.as_float(), b.as_float()) {
+                    (Some(a), Some(b)) => Ok(Value::Float(a / b)),
+                    _ => Err(mismatch(&a, &b)),
+                }
+            }
+            FieldExpression::Mod(param) => {
+                self.arith(param, record, i64::checked_rem, |a, b| a % b)
+            }
+            FieldExpression::Eq(param) => self.compare(param, record, |o| o.is_eq()),
+            FieldExpression::Ne(param) => Ok(Value::Bool(
+                !self.ordering(param, record)?.is_some_and(|o| o.is_eq()),
+            )),
+            FieldExpression::LessThan(param) => self.compare(param, record, |o| o.is_lt()),
+            FieldExpression::GreaterThan(param) => self.compare(param, record, |o| o.is_gt()),
+            FieldExpression::LessEqualThan(param) => self.compare(param, record, |o| o.is_le()),
+            FieldExpression::GreaterEqualThan(param) => self.compare(param, record, |o| o.is_ge()),
+            FieldExpression::LeftShift(param) => self.shift(param, record, true),
+            FieldExpression::RightShift(param) => self.shift(param, record, false),
+            FieldExpression::Neg(param) => match self.eval(&param.operand, record)? {
+                Value::Int(value) => value
+                    .checked_neg()
+                    .map(Value::Int)
+                    .ok_or_else(|| "Integer overflow".to_string()),
+                Value::Float(value) => Ok(Value::Float(-value)),
+                other => Err(format!(
+                    "Unsupported operand type for Neg: {}",
+                    other.field_type()
+                )),
+            },
+            FieldExpression::Cond(param) => {
+                let value = if self.boolean(&param.cond, record)? {
+                    self.eval(&param.then, record)?
+                } else {
+                    self.eval(&param.elze, record)?
+                };
+                // An integer branch is widened if the other branch is a float
+                match value {
+                    Value::Int(_) => value.cast(expr.infer_type().map_err(|e| e.to_string())?),
+                    value => Ok(value),
+                }
+            }
+            FieldExpression::RegexMatch(param) => match self.operands(param, record)? {
+                (Value::Str(text), Value::Str(pattern)) => self.regex_match(&text, &pattern),
+                (a, b) => Err(mismatch(&a, &b)),
+            },
+            FieldExpression::FieldRef(param) => self.field(record, &param.field),
+            FieldExpression::NumberOfComponents => record
+                .number_of_components()
+                .map(Value::from)
+                .ok_or_else(|| "The record doesn't have components".to_string()),
+            FieldExpression::ComponentFieldRef(param) => {
+                self.field(self.component(record, param.target)?, &param.field_name)
+            }
+            // The numbers in the constant bag are stored as floats
+            FieldExpression::ConstValue(param) => {
+                match (self.const_value(&param.value)?, param.value_type) {
+                    (Value::Float(value), Some(FieldType::Int)) => Ok(Value::Int(value as i64)),
+                    (value, _) => Ok(value),
+                }
+            }
+            FieldExpression::FullRecordRef => Ok(Value::Record(Rc::new(record.clone()))),
+            FieldExpression::RecordRef(param) => Ok(Value::Record(Rc::new(
+                self.component(record, param.id)?.clone(),
+            ))),
+            FieldExpression::StringRepr(param) => {
+                self.eval(&param.value, record)?.cast(FieldType::Str)
+            }
+            FieldExpression::Cast(param) => self.eval(&param.value, record)?.cast(param.to),
+            FieldExpression::Call(param) => self.call(param, record),
+        }
+    }
+
+    /// Render a format string like `{name}\t{start:.2}` with the values of the record
+    pub fn format(
+        &self,
+        fmt_str: &str,
+        values: &BTreeMap<String, FieldExpression>,
+        record: &DynRecord,
+    ) -> Result<String, String> {
+        let mut ret = String::new();
+        let mut chars = fmt_str.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    ret.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    ret.push('}');
+                }
+                '{' => {
+                    let placeholder: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                    let (key, spec) = placeholder
+                        .split_once(':')
+                        .unwrap_or((placeholder.as_str(), ""));
+                    let expr = values
+                        .get(key.trim())
+                        .ok_or_else(|| format!("Undefined value {} in the format string", key))?;
+                    let value = self.eval(expr, record)?;
+                    // Like the format macro, the precision rounds the floats and truncates the
+                    // strings
+                    match (spec.strip_prefix('.').and_then(|p| p.parse().ok()), &value) {
+                        (Some(precision), Value::Float(num)) => {
+                            ret.push_str(&format!("{:.*}", precision, num))
+                        }
+                        (Some(precision), Value::Str(text)) => {
+                            ret.push_str(&format!("{:.*}", precision, text))
+                        }
+                        _ => ret.push_str(&value.to_string()),
+                    }
+                }
+                c => ret.push(c),
+            }
+        }
+        Ok(ret)
+    }
+}
+
+impl From<ConstValue> for Value {
+    fn from(value: ConstValue) -> Self {
+        match value {
+            ConstValue::Str(value) => Value::Str(value.into()),
+            ConstValue::Number(value) => Value::Int(value),
+            ConstValue::Float(value) => Value::Float(value),
+        }
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(value: usize) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Str(value.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Str(value.into())
+    }
+}
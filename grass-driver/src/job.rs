@@ -7,7 +7,7 @@ use std::{
     process::{Child, Command},
 };
 
-use grass_ir::GrassIR;
+use grass_ir::{FieldType, GrassIR};
 use serde::Deserialize;
 use sha::{sha256::Sha256, utils::DigestExt};
 use tempfile::TempDir;
@@ -15,6 +15,7 @@ use tempfile::TempDir;
 use crate::{
     cache::CacheState,
     dependency::{Dependency, DependencySource},
+    interpreter::{parse_const_bag, InterpretError, Interpreter},
 };
 
 use crate::return_true;
@@ -32,6 +33,19 @@ impl Default for BuildFlavor {
     }
 }
 
+/// The max total size of the input files of a job that is interpreted in the auto execution mode,
+/// a larger job is worth the time of building the artifact
+pub const INTERPRETER_INPUT_LIMIT: u64 = 64 << 20;
+
+#[derive(Deserialize, Default)]
+pub enum ExecutionMode {
+    /// Interpret the job if it's supported and its input is small, otherwise compile it
+    Auto,
+    Interpret,
+    #[default]
+    Compile,
+}
+
 #[derive(Deserialize)]
 #[allow(unused)]
 pub struct JobDefinition {
@@ -45,6 +59,8 @@ pub struct JobDefinition {
     env_vars: HashMap<String, String>,
     #[serde(default)]
     const_bag_types: Vec<String>,
+    #[serde(default)]
+    execution_mode: ExecutionMode,
 
     // ############# Runtime Configuration ######################
     #[serde(default = "default_runtime")]
@@ -89,6 +105,8 @@ pub struct JobDefinition {
     #[serde(skip)]
     compilation_dir: Option<TempDir>,
     #[serde(skip)]
+    ir_prepared: bool,
+    #[serde(skip)]
     ir_hash: String,
     #[serde(skip)]
     artifact_path: Option<PathBuf>,
@@ -146,6 +164,9 @@ impl JobDefinition {
         cmd.spawn()
     }
     fn populate_ir_hash(&mut self) -> Result<()> {
+        // Optimize the IR first, so that the equivalent queries share the same artifact
+        self.prepare_ir()?;
+
         let mut buffer = Vec::new();
         {
             let mut buffer_writer = Cursor::new(&mut buffer);
@@ -239,6 +260,23 @@ impl JobDefinition {
         }
         Ok(())
     }
+    fn has_cross_query_refs(&self) -> bool {
+        fn collect_symbols<'a>(ir: &'a GrassIR, defs: &mut Vec<&'a str>, uses: &mut Vec<&'a str>) {
+            match ir {
+                GrassIR::Let(param) => defs.push(&param.id),
+                GrassIR::Ref(param) => uses.push(&param.id),
+                _ => (),
+            }
+            for child in ir.children() {
+                collect_symbols(child, defs, uses);
+            }
+        }
+        self.ir.iter().any(|ir| {
+            let (mut defs, mut uses) = (Vec::new(), Vec::new());
+            collect_symbols(ir, &mut defs, &mut uses);
+            uses.iter().any(|id| !defs.contains(id))
+        })
+    }
     fn write_source_code(&self, root: &Path) -> Result<()> {
         let source_dir = root.join("src");
         std::fs::create_dir(&source_dir)?;
@@ -269,7 +307,18 @@ impl JobDefinition {
             )?;
         }
 
-        for (id, ir) in self.ir.iter().enumerate() {
+        // When a query refers to a symbol defined by another query, they have to be expanded in
+        // the same scope, so all the queries are put into a single IR list
+        let queries: Vec<serde_json::Value> = if self.has_cross_query_refs() {
+            vec![serde_json::to_value(&self.ir)?]
+        } else {
+            self.ir
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<std::result::Result<_, _>>()?
+        };
+
+        for (id, ir) in queries.iter().enumerate() {
             let ir_path = source_dir.as_path().join(format!("grass_ir_{}.json", id));
             let ir_file = File::create(&ir_path)?;
             serde_json::to_writer(ir_file, ir)?;
@@ -303,7 +352,7 @@ impl JobDefinition {
             "    let cmd_args: Vec<_> = owned_cmd_args.iter().map(|a| a.as_str()).collect();"
         )?;
 
-        for id in 0..self.ir.len() {
+        for id in 0..queries.len() {
             writeln!(
                 &mut source_file,
                 "    grass_query_{id}(&cmd_args)?;",
@@ -315,10 +364,53 @@ impl JobDefinition {
 
         Ok(())
     }
+    /// Type the constant bag values used by the field expressions with the types they are stored
+    /// as, unless the frontend has already typed them
+    fn resolve_const_bag_types(&mut self) {
+        fn visit<F: Fn(usize) -> Option<FieldType>>(ir: &mut GrassIR, types: &F) {
+            for expr in ir.field_exprs_mut() {
+                expr.resolve_const_bag_types(types);
+            }
+            for child in ir.children_mut() {
+                visit(child, types);
+            }
+        }
+        let const_bag_types = &self.const_bag_types;
+        let types = |key: usize| match const_bag_types.get(key).map(String::as_str) {
+            Some("str") => Some(FieldType::Str),
+            Some("i64") => Some(FieldType::Int),
+            Some("f64") => Some(FieldType::Float),
+            _ => None,
+        };
+        for ir in self.ir.iter_mut() {
+            visit(ir, &types);
+        }
+    }
+    /// Type, validate and optimize the IR, only once whether the job is interpreted or compiled
+    fn prepare_ir(&mut self) -> Result<()> {
+        if !self.ir_prepared {
+            self.resolve_const_bag_types();
+            self.validate_ir()?;
+            grass_ir::optimize(&mut self.ir);
+            self.ir_prepared = true;
+        }
+        Ok(())
+    }
+    fn validate_ir(&self) -> Result<()> {
+        grass_ir::validate(&self.ir).map_err(|errors| {
+            let messages: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+            Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid GRASS IR:\n{}", messages.join("\n")),
+            )
+        })
+    }
     pub fn get_compilation_dir(&mut self) -> Result<&Path> {
         if self.compilation_dir.is_some() {
             Ok(self.compilation_dir.as_ref().unwrap().as_ref())
         } else {
+            self.prepare_ir()?;
+
             let mut root_dir = tempfile::Builder::new();
 
             root_dir.prefix("grass-workspace-");
@@ -425,6 +517,40 @@ impl JobDefinition {
             .args(&self.cmdline_args)
             .spawn()?)
     }
+    /// Run the job with the interpreter if the execution mode allows it. Returns false when the
+    /// job should be compiled instead, in which case nothing has been written.
+    pub fn try_interpret(&mut self) -> std::result::Result<bool, InterpretError> {
+        let auto = match self.execution_mode {
+            ExecutionMode::Compile => return Ok(false),
+            ExecutionMode::Auto => true,
+            ExecutionMode::Interpret => false,
+        };
+        self.prepare_ir()?;
+
+        let const_bag = parse_const_bag(
+            self.env_vars.get("__GRASS_CONST_BAG").map(String::as_str),
+            &self.const_bag_types,
+        )?;
+        let mut interpreter = Interpreter::new(&self.working_dir, &self.cmdline_args, const_bag);
+        if auto {
+            match interpreter.input_size(&self.ir) {
+                Some(size) if size <= INTERPRETER_INPUT_LIMIT => (),
+                _ => {
+                    log::info!("Compiling the job, since the input is large or unknown");
+                    return Ok(false);
+                }
+            }
+        }
+        log::info!("Interpreting the job");
+        match interpreter.run(&self.ir) {
+            Ok(()) => Ok(true),
+            Err(InterpretError::Unsupported(what)) if auto => {
+                log::info!("Compiling the job, since the interpreter doesn't support {}", what);
+                Ok(false)
+            }
+            Err(err) => Err(err),
+        }
+    }
     pub fn print_expanded_code(&mut self) -> Result<()> {
         self.cargo(&["expand"], false)?.wait()?;
         Ok(())
@@ -1,5 +1,8 @@
+#[cfg(test)]
+mod compile_test;
 mod cache;
 mod dependency;
+mod interpreter;
 mod job;
 
 fn return_true() -> bool {
@@ -8,9 +11,13 @@ fn return_true() -> bool {
 
 use std::io::{BufRead, BufReader};
 
-pub use job::JobDefinition;
+pub use interpreter::{InterpretError, Interpreter};
+pub use job::{ExecutionMode, JobDefinition};
 
 pub fn execute_job(job: &mut JobDefinition) -> Result<(), Box<dyn std::error::Error>> {
+    if job.try_interpret()? {
+        return Ok(());
+    }
     match job.execute_artifact() {
         Ok(mut handle) => {
             handle.wait()?;
@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use crate::ConstOrEnv;
+use crate::{ConstOrEnv, FieldType, Function};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(tag = "opcode")]
 pub enum FieldExpression {
     And(BinaryParam),
@@ -32,14 +32,86 @@ pub enum FieldExpression {
     RecordRef(RecordRefParam),
     StringRepr(StringRepr),
     RegexMatch(BinaryParam),
+    Cast(CastParam),
+    Call(CallParam),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl FieldExpression {
+    pub fn opcode(&self) -> &'static str {
+        match self {
+            FieldExpression::And(_) => "And",
+            FieldExpression::Or(_) => "Or",
+            FieldExpression::Xor(_) => "Xor",
+            FieldExpression::Not(_) => "Not",
+            FieldExpression::Add(_) => "Add",
+            FieldExpression::Sub(_) => "Sub",
+            FieldExpression::Mul(_) => "Mul",
+            FieldExpression::Div(_) => "Div",
+            FieldExpression::Mod(_) => "Mod",
+            FieldExpression::Eq(_) => "Eq",
+            FieldExpression::Ne(_) => "Ne",
+            FieldExpression::LessThan(_) => "LessThan",
+            FieldExpression::GreaterThan(_) => "GreaterThan",
+            FieldExpression::LessEqualThan(_) => "LessEqualThan",
+            FieldExpression::GreaterEqualThan(_) => "GreaterEqualThan",
+            FieldExpression::RightShift(_) => "RightShift",
+            FieldExpression::LeftShift(_) => "LeftShift",
+            FieldExpression::Neg(_) => "Neg",
+            FieldExpression::Cond(_) => "Cond",
+            FieldExpression::FieldRef(_) => "FieldRef",
+            FieldExpression::NumberOfComponents => "NumberOfComponents",
+            FieldExpression::ComponentFieldRef(_) => "ComponentFieldRef",
+            FieldExpression::ConstValue(_) => "ConstValue",
+            FieldExpression::FullRecordRef => "FullRecordRef",
+            FieldExpression::RecordRef(_) => "RecordRef",
+            FieldExpression::StringRepr(_) => "StringRepr",
+            FieldExpression::RegexMatch(_) => "RegexMatch",
+            FieldExpression::Cast(_) => "Cast",
+            FieldExpression::Call(_) => "Call",
+        }
+    }
+
+    /// The mutable references to the operands of the expression
+    pub fn children_mut(&mut self) -> Vec<&mut FieldExpression> {
+        match self {
+            FieldExpression::And(param)
+            | FieldExpression::Or(param)
+            | FieldExpression::Xor(param)
+            | FieldExpression::Add(param)
+            | FieldExpression::Sub(param)
+            | FieldExpression::Mul(param)
+            | FieldExpression::Div(param)
+            | FieldExpression::Mod(param)
+            | FieldExpression::Eq(param)
+            | FieldExpression::Ne(param)
+            | FieldExpression::LessThan(param)
+            | FieldExpression::GreaterThan(param)
+            | FieldExpression::LessEqualThan(param)
+            | FieldExpression::GreaterEqualThan(param)
+            | FieldExpression::RightShift(param)
+            | FieldExpression::LeftShift(param)
+            | FieldExpression::RegexMatch(param) => vec![&mut param.lhs, &mut param.rhs],
+            FieldExpression::Not(param) | FieldExpression::Neg(param) => vec![&mut param.operand],
+            FieldExpression::Cond(param) => vec![&mut param.cond, &mut param.then, &mut param.elze],
+            FieldExpression::StringRepr(param) => vec![&mut param.value],
+            FieldExpression::Cast(param) => vec![&mut param.value],
+            FieldExpression::Call(param) => param.args.iter_mut().collect(),
+            FieldExpression::FieldRef(_)
+            | FieldExpression::NumberOfComponents
+            | FieldExpression::ComponentFieldRef(_)
+            | FieldExpression::ConstValue(_)
+            | FieldExpression::FullRecordRef
+            | FieldExpression::RecordRef(_) => vec![],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct RecordRefParam {
     pub id: i32,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum ConstValue {
     Str(String),
@@ -47,40 +119,56 @@ pub enum ConstValue {
     Float(f64),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ConstParam {
     pub value: ConstOrEnv<ConstValue>,
+    /// The type of the value if it's stored in the constant bag, numbers are assumed to be floats
+    /// when this is missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_type: Option<FieldType>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CastParam {
+    pub value: Box<FieldExpression>,
+    pub to: FieldType,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CallParam {
+    pub func: Function,
+    pub args: Vec<FieldExpression>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct UnaryParam {
     pub operand: Box<FieldExpression>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct BinaryParam {
     pub lhs: Box<FieldExpression>,
     pub rhs: Box<FieldExpression>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct CondParam {
     pub cond: Box<FieldExpression>,
     pub then: Box<FieldExpression>,
     pub elze: Box<FieldExpression>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct StringRepr {
     pub value: Box<FieldExpression>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct FieldRefParam {
     pub field: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ComponentFieldRefParam {
     pub target: i32,
     pub field_name: String,
@@ -0,0 +1,387 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{BinaryParam, ConstOrEnv, ConstValue, FieldExpression};
+
+/// The type of the value of a field expression
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    Int,
+    Float,
+    Str,
+    Bool,
+    Record,
+}
+
+impl FieldType {
+    pub fn is_numeric(self) -> bool {
+        matches!(self, FieldType::Int | FieldType::Float)
+    }
+
+    /// The type that both of the numeric types can be implicitly widened to
+    pub fn widen(self, other: FieldType) -> Option<FieldType> {
+        match (self, other) {
+            (FieldType::Int, FieldType::Int) => Some(FieldType::Int),
+            (a, b) if a.is_numeric() && b.is_numeric() => Some(FieldType::Float),
+            _ => None,
+        }
+    }
+
+    /// The type both sides of a comparison or a condition are converted to
+    pub fn unify(self, other: FieldType) -> Option<FieldType> {
+        match self.widen(other) {
+            Some(ty) => Some(ty),
+            None if self == other && self != FieldType::Record => Some(self),
+            None => None,
+        }
+    }
+
+    /// Check if a value of this type can be explicitly cast to the target type
+    pub fn can_cast_to(self, to: FieldType) -> bool {
+        match (self, to) {
+            (from, to) if from == to => true,
+            (_, FieldType::Str) => true,
+            (FieldType::Record, _) | (_, FieldType::Record) => false,
+            (FieldType::Str, FieldType::Bool) => false,
+            _ => true,
+        }
+    }
+
+    /// The type of a record field, `None` if the field is unknown
+    pub fn of_field(name: &str) -> Option<FieldType> {
+        match name {
+            "start" | "end" | "length" | "size" | "count" | "distance" => Some(FieldType::Int),
+            "covered" | "depth" | "forward_count" | "reverse_count" => Some(FieldType::Int),
+            "intersection" | "union" | "n_intersections" | "total" => Some(FieldType::Int),
+            "score" | "gc_content" | "fraction" | "reldist" => Some(FieldType::Float),
+            "jaccard" | "left_tail" | "right_tail" | "two_tail" | "ratio" => Some(FieldType::Float),
+            "chrom" | "name" | "strand" | "tag_str" | "seq" => Some(FieldType::Str),
+            _ => None,
+        }
+    }
+}
+
+impl Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            FieldType::Int => "int",
+            FieldType::Float => "float",
+            FieldType::Str => "str",
+            FieldType::Bool => "bool",
+            FieldType::Record => "record",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A type error found in a field expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldTypeError {
+    /// The path to the offending node relative to the root of the expression, for example `.lhs`
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for FieldTypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for FieldTypeError {}
+
+type InferResult = Result<FieldType, FieldTypeError>;
+
+fn error(message: String) -> InferResult {
+    Err(FieldTypeError {
+        path: String::new(),
+        message,
+    })
+}
+
+fn infer_child(expr: &FieldExpression, name: &str) -> InferResult {
+    expr.infer_type().map_err(|mut e| {
+        e.path = format!(".{}{}", name, e.path);
+        e
+    })
+}
+
+fn infer_operands(param: &BinaryParam) -> Result<(FieldType, FieldType), FieldTypeError> {
+    Ok((
+        infer_child(&param.lhs, "lhs")?,
+        infer_child(&param.rhs, "rhs")?,
+    ))
+}
+
+fn mismatch(op: &str, lhs: FieldType, rhs: FieldType) -> InferResult {
+    error(format!(
+        "Unsupported operand types for {}: {} and {}",
+        op, lhs, rhs
+    ))
+}
+
+impl FieldExpression {
+    /// Infer the type of the expression. Integers are implicitly widened to floats when they meet
+    /// floats, division always produces a float, and any other conversion needs a `Cast` node.
+    pub fn infer_type(&self) -> InferResult {
+        match self {
+            FieldExpression::And(param)
+            | FieldExpression::Or(param)
+            | FieldExpression::Xor(param) => match infer_operands(param)? {
+                (FieldType::Bool, FieldType::Bool) => Ok(FieldType::Bool),
+                (FieldType::Int, FieldType::Int) => Ok(FieldType::Int),
+                (lhs, rhs) => mismatch(self.opcode(), lhs, rhs),
+            },
+            FieldExpression::Not(param) => match infer_child(&param.operand, "operand")? {
+                ty @ (FieldType::Bool | FieldType::Int) => Ok(ty),
+                ty => error(format!("Unsupported operand type for Not: {}", ty)),
+            },
+            FieldExpression::Neg(param) => match infer_child(&param.operand, "operand")? {
+                ty if ty.is_numeric() => Ok(ty),
+                ty => error(format!("Unsupported operand type for Neg: {}", ty)),
+            },
+            FieldExpression::Add(param) => match infer_operands(param)? {
+                (FieldType::Str, FieldType::Str) => Ok(FieldType::Str),
+                (lhs, rhs) => lhs.widen(rhs).map_or_else(|| mismatch("Add", lhs, rhs), Ok),
+            },
+            FieldExpression::Sub(param)
+            | FieldExpression::Mul(param)
+            | FieldExpression::Mod(param) => {
+                let (lhs, rhs) = infer_operands(param)?;
+                lhs.widen(rhs)
+                    .map_or_else(|| mismatch(self.opcode(), lhs, rhs), Ok)
+            }
+            FieldExpression::Div(param) => match infer_operands(param)? {
+                (lhs, rhs) if lhs.is_numeric() && rhs.is_numeric() => Ok(FieldType::Float),
+                (lhs, rhs) => mismatch("Div", lhs, rhs),
+            },
+            FieldExpression::LeftShift(param) | FieldExpression::RightShift(param) => {
+                match infer_operands(param)? {
+                    (FieldType::Int, FieldType::Int) => Ok(FieldType::Int),
+                    (lhs, rhs) => mismatch(self.opcode(), lhs, rhs),
+                }
+            }
+            FieldExpression::Eq(param)
+            | FieldExpression::Ne(param)
+            | FieldExpression::LessThan(param)
+            | FieldExpression::GreaterThan(param)
+            | FieldExpression::LessEqualThan(param)
+            | FieldExpression::GreaterEqualThan(param) => {
+                let (lhs, rhs) = infer_operands(param)?;
+                match lhs.unify(rhs) {
+                    Some(_) => Ok(FieldType::Bool),
+                    None => mismatch(self.opcode(), lhs, rhs),
+                }
+            }
+            FieldExpression::RegexMatch(param) => match infer_operands(param)? {
+                (FieldType::Str, FieldType::Str) => Ok(FieldType::Bool),
+                (lhs, rhs) => mismatch("RegexMatch", lhs, rhs),
+            },
+            FieldExpression::Cond(param) => {
+                let cond = infer_child(&param.cond, "cond")?;
+                if cond != FieldType::Bool {
+                    return Err(FieldTypeError {
+                        path: ".cond".to_string(),
+                        message: format!("The condition should be a bool, but got {}", cond),
+                    });
+                }
+                let then = infer_child(&param.then, "then")?;
+                let elze = infer_child(&param.elze, "elze")?;
+                then.unify(elze).map_or_else(
+                    || {
+                        error(format!(
+                            "The branches of a condition have different types: {} and {}",
+                            then, elze
+                        ))
+                    },
+                    Ok,
+                )
+            }
+            FieldExpression::FieldRef(param) => FieldType::of_field(&param.field)
+                .map_or_else(|| error(format!("Unknown field {}", param.field)), Ok),
+            FieldExpression::ComponentFieldRef(param) => FieldType::of_field(&param.field_name)
+                .map_or_else(|| error(format!("Unknown field {}", param.field_name)), Ok),
+            FieldExpression::NumberOfComponents => Ok(FieldType::Int),
+            FieldExpression::ConstValue(param) => Ok(match &param.value {
+                ConstOrEnv::Const(ConstValue::Number(_)) => FieldType::Int,
+                ConstOrEnv::Const(ConstValue::Float(_)) => FieldType::Float,
+                ConstOrEnv::Const(ConstValue::Str(_)) => FieldType::Str,
+                ConstOrEnv::Env(_) => param.value_type.unwrap_or(FieldType::Float),
+            }),
+            FieldExpression::FullRecordRef | FieldExpression::RecordRef(_) => Ok(FieldType::Record),
+            FieldExpression::StringRepr(param) => {
+                infer_child(&param.value, "value")?;
+                Ok(FieldType::Str)
+            }
+            FieldExpression::Call(param) => {
+                let args = param
+                    .args
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, arg)| infer_child(arg, &format!("args[{}]", idx)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                param
+                    .func
+                    .signature(&args)
+                    .map_or_else(error, |(_, ret)| Ok(ret))
+            }
+            FieldExpression::Cast(param) => {
+                let from = infer_child(&param.value, "value")?;
+                if from.can_cast_to(param.to) {
+                    Ok(param.to)
+                } else {
+                    error(format!("Unable to cast {} to {}", from, param.to))
+                }
+            }
+        }
+    }
+}
+
+impl FieldExpression {
+    /// Fill in the missing types of the constant bag values with the types the values are
+    /// actually stored as, the values that already have a type are left unchanged
+    pub fn resolve_const_bag_types<F: Fn(usize) -> Option<FieldType>>(&mut self, types: &F) {
+        if let FieldExpression::ConstValue(param) = self {
+            if let (ConstOrEnv::Env(key), None) = (&param.value, param.value_type) {
+                param.value_type = types(key.key());
+            }
+        }
+        for child in self.children_mut() {
+            child.resolve_const_bag_types(types);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{from_value, json};
+
+    use super::FieldType;
+    use crate::FieldExpression;
+
+    fn infer(value: serde_json::Value) -> Result<FieldType, String> {
+        let expr: FieldExpression = from_value(value).unwrap();
+        expr.infer_type().map_err(|e| e.to_string())
+    }
+
+    fn field(name: &str) -> serde_json::Value {
+        json!({"opcode": "FieldRef", "field": name})
+    }
+
+    fn constant(value: serde_json::Value) -> serde_json::Value {
+        json!({"opcode": "ConstValue", "value": value})
+    }
+
+    #[test]
+    fn test_infer_type() {
+        let add = |lhs, rhs| json!({"opcode": "Add", "lhs": lhs, "rhs": rhs});
+        assert_eq!(
+            infer(add(field("start"), constant(json!(1)))),
+            Ok(FieldType::Int)
+        );
+        assert_eq!(
+            infer(add(field("start"), constant(json!(0.5)))),
+            Ok(FieldType::Float)
+        );
+        assert_eq!(
+            infer(add(field("name"), constant(json!("x")))),
+            Ok(FieldType::Str)
+        );
+        assert_eq!(
+            infer(json!({"opcode": "Div", "lhs": field("end"), "rhs": constant(json!(2))})),
+            Ok(FieldType::Float)
+        );
+        assert_eq!(
+            infer(json!({"opcode": "LessEqualThan", "lhs": field("score"), "rhs": field("start")})),
+            Ok(FieldType::Bool)
+        );
+        assert_eq!(
+            infer(
+                json!({"opcode": "Cond", "cond": {"opcode": "Eq", "lhs": field("strand"),
+                "rhs": constant(json!("+"))}, "then": field("start"), "elze": field("score")})
+            ),
+            Ok(FieldType::Float)
+        );
+        assert_eq!(
+            infer(json!({"opcode": "Cast", "value": field("name"), "to": "Int"})),
+            Ok(FieldType::Int)
+        );
+        assert_eq!(
+            infer(constant(json!({"const_bag_key": 0}))),
+            Ok(FieldType::Float)
+        );
+        assert_eq!(
+            infer(
+                json!({"opcode": "ConstValue", "value": {"const_bag_key": 0}, "value_type": "Str"})
+            ),
+            Ok(FieldType::Str)
+        );
+        let call = |func, args| json!({"opcode": "Call", "func": func, "args": args});
+        assert_eq!(
+            infer(call("min", json!([field("start"), field("end")]))),
+            Ok(FieldType::Int)
+        );
+        assert_eq!(
+            infer(call("max", json!([field("start"), field("score")]))),
+            Ok(FieldType::Float)
+        );
+        assert_eq!(
+            infer(call(
+                "split_nth",
+                json!([field("name"), constant(json!("|")), constant(json!(0))])
+            )),
+            Ok(FieldType::Str)
+        );
+        assert_eq!(
+            infer(call("round", json!([field("score")]))),
+            Ok(FieldType::Int)
+        );
+    }
+
+    #[test]
+    fn test_infer_type_errors() {
+        assert_eq!(
+            infer(json!({"opcode": "Sub", "lhs": field("start"), "rhs": field("name")})),
+            Err("Unsupported operand types for Sub: int and str".to_string())
+        );
+        assert_eq!(
+            infer(json!({"opcode": "LeftShift", "lhs": field("score"), "rhs": constant(json!(1))})),
+            Err("Unsupported operand types for LeftShift: float and int".to_string())
+        );
+        assert_eq!(
+            infer(
+                json!({"opcode": "Not", "operand": {"opcode": "Neg", "operand": field("chrom")}})
+            ),
+            Err(".operand: Unsupported operand type for Neg: str".to_string())
+        );
+        assert_eq!(
+            infer(json!({"opcode": "Cast", "value": {"opcode": "FullRecordRef"}, "to": "Int"})),
+            Err("Unable to cast record to int".to_string())
+        );
+        assert_eq!(
+            infer(
+                json!({"opcode": "Cond", "cond": field("start"), "then": field("start"),
+                "elze": field("end")})
+            ),
+            Err(".cond: The condition should be a bool, but got int".to_string())
+        );
+        assert_eq!(
+            infer(json!({"opcode": "Call", "func": "substr", "args": [field("name")]})),
+            Err("substr takes 2 to 3 arguments, but got 1".to_string())
+        );
+        assert_eq!(
+            infer(json!({"opcode": "Call", "func": "log", "args": [field("name")]})),
+            Err("The argument #0 of log should be a number, but got str".to_string())
+        );
+        assert_eq!(
+            infer(json!({"opcode": "Call", "func": "upper", "args": [
+                {"opcode": "Neg", "operand": field("name")}]})),
+            Err(".args[0]: Unsupported operand type for Neg: str".to_string())
+        );
+    }
+}
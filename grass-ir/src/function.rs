@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+
+use crate::FieldType;
+
+/// The built-in functions a `Call` node can invoke
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Function {
+    /// `substr(text, start[, len])`, the characters are counted from the end if `start` is negative
+    Substr,
+    /// `concat(text, ...)`
+    Concat,
+    /// `split_nth(text, separator, n)`, the fields are counted from the end if `n` is negative
+    SplitNth,
+    /// `replace(text, from, to)`, which replaces all the occurrences
+    Replace,
+    Upper,
+    Lower,
+    StartsWith,
+    /// `parse_int(text[, default])`, the default value is used when the text isn't an integer
+    ParseInt,
+    /// `parse_float(text[, default])`, the default value is NaN unless it's given
+    ParseFloat,
+    Abs,
+    Min,
+    Max,
+    Round,
+    Floor,
+    /// `log(value[, base])`, which is the natural logarithm unless the base is given
+    Log,
+    Exp,
+    Pow,
+}
+
+/// The type a parameter accepts
+#[derive(Clone, Copy)]
+enum Param {
+    Str,
+    Int,
+    /// A float, integers are widened
+    Float,
+    /// Either an integer or a float, all the numeric parameters of a call are widened to the same
+    /// type
+    Num,
+}
+
+impl Function {
+    pub fn name(self) -> &'static str {
+        match self {
+            Function::Substr => "substr",
+            Function::Concat => "concat",
+            Function::SplitNth => "split_nth",
+            Function::Replace => "replace",
+            Function::Upper => "upper",
+            Function::Lower => "lower",
+            Function::StartsWith => "starts_with",
+            Function::ParseInt => "parse_int",
+            Function::ParseFloat => "parse_float",
+            Function::Abs => "abs",
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::Round => "round",
+            Function::Floor => "floor",
+            Function::Log => "log",
+            Function::Exp => "exp",
+            Function::Pow => "pow",
+        }
+    }
+
+    /// The parameters, the number of the required parameters and if the last parameter can repeat
+    fn params(self) -> (&'static [Param], usize, bool) {
+        use Param::*;
+        match self {
+            Function::Substr => (&[Str, Int, Int], 2, false),
+            Function::Concat => (&[Str], 1, true),
+            Function::SplitNth => (&[Str, Str, Int], 3, false),
+            Function::Replace => (&[Str, Str, Str], 3, false),
+            Function::Upper | Function::Lower => (&[Str], 1, false),
+            Function::StartsWith => (&[Str, Str], 2, false),
+            Function::ParseInt => (&[Str, Int], 1, false),
+            Function::ParseFloat => (&[Str, Float], 1, false),
+            Function::Abs => (&[Num], 1, false),
+            Function::Min | Function::Max => (&[Num], 1, true),
+            Function::Round | Function::Floor | Function::Exp => (&[Float], 1, false),
+            Function::Log => (&[Float, Float], 1, false),
+            Function::Pow => (&[Float, Float], 2, false),
+        }
+    }
+
+    /// Check the types of the arguments. Returns the types the arguments are converted to before
+    /// the call and the type of the result.
+    pub fn signature(self, args: &[FieldType]) -> Result<(Vec<FieldType>, FieldType), String> {
+        let (params, required, variadic) = self.params();
+        if args.len() < required || (!variadic && args.len() > params.len()) {
+            let expected = match (required, params.len(), variadic) {
+                (min, _, true) => format!("at least {}", min),
+                (min, max, false) if min == max => min.to_string(),
+                (min, max, false) => format!("{} to {}", min, max),
+            };
+            return Err(format!(
+                "{} takes {} arguments, but got {}",
+                self.name(),
+                expected,
+                args.len()
+            ));
+        }
+        let param_of = |idx: usize| params[idx.min(params.len() - 1)];
+        let mut num = None;
+        for (idx, &arg) in args.iter().enumerate() {
+            let (accepted, expected) = match param_of(idx) {
+                Param::Str => (arg == FieldType::Str, "str"),
+                Param::Int => (arg == FieldType::Int, "int"),
+                Param::Float | Param::Num => (arg.is_numeric(), "number"),
+            };
+            if !accepted {
+                return Err(format!(
+                    "The argument #{} of {} should be a {}, but got {}",
+                    idx,
+                    self.name(),
+                    expected,
+                    arg
+                ));
+            }
+            if let Param::Num = param_of(idx) {
+                num = num.map_or(Some(arg), |ty: FieldType| ty.widen(arg));
+            }
+        }
+        let num = num.unwrap_or(FieldType::Float);
+        let arg_types = (0..args.len())
+            .map(|idx| match param_of(idx) {
+                Param::Str => FieldType::Str,
+                Param::Int => FieldType::Int,
+                Param::Float => FieldType::Float,
+                Param::Num => num,
+            })
+            .collect();
+        let ret = match self {
+            Function::Substr
+            | Function::Concat
+            | Function::SplitNth
+            | Function::Replace
+            | Function::Upper
+            | Function::Lower => FieldType::Str,
+            Function::StartsWith => FieldType::Bool,
+            Function::ParseInt | Function::Round | Function::Floor => FieldType::Int,
+            Function::ParseFloat | Function::Log | Function::Exp | Function::Pow => {
+                FieldType::Float
+            }
+            Function::Abs | Function::Min | Function::Max => num,
+        };
+        Ok((arg_types, ret))
+    }
+}
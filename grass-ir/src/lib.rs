@@ -1,15 +1,23 @@
 use std::{collections::BTreeMap, marker::PhantomData};
 
 pub use field_expr::{
-    BinaryParam, ComponentFieldRefParam, CondParam, ConstParam, ConstValue, FieldExpression,
-    FieldRefParam, RecordRefParam, StringRepr, UnaryParam,
+    BinaryParam, CallParam, CastParam, ComponentFieldRefParam, CondParam, ConstParam, ConstValue,
+    FieldExpression, FieldRefParam, RecordRefParam, StringRepr, UnaryParam,
 };
+pub use field_type::{FieldType, FieldTypeError};
+pub use function::Function;
 use serde::{Deserialize, Serialize};
+pub use optimize::optimize;
+pub use validate::{validate, ValidationError};
 
 mod field_expr;
+mod field_type;
+mod function;
+mod optimize;
+mod validate;
 
 // TODO: Make sure that we use this type for all the IR values that can be passed by runtime environ
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ConstBagRef<T> {
     const_bag_key: usize,
     #[serde(skip)]
@@ -17,19 +25,23 @@ pub struct ConstBagRef<T> {
 }
 
 impl<T> ConstBagRef<T> {
+    /// The index of the value in the constant bag
+    pub fn key(&self) -> usize {
+        self.const_bag_key
+    }
     pub fn get_const_bag_ident(&self) -> String {
         format!("__CONST_BAG_VALUE_{}", self.const_bag_key)
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum ConstOrEnv<T> {
     Const(T),
     Env(ConstBagRef<T>),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(tag = "opcode")]
 pub enum GrassIR {
     /// Cast the inner data stream to a bed3 data stream
@@ -50,10 +62,41 @@ pub enum GrassIR {
     MergeOverlap(MergeOverlapParam),
     /// Intersect two GRASS expression
     Intersection(IntersectParam),
+    /// Intersect paired records, for example BEDPE records, with a GRASS expression
+    PairIntersection(PairIntersectParam),
+    /// Find the closest records in a GRASS expression for each record of another one
+    Closest(ClosestParam),
+    /// Join each record in a GRASS expression with the records of another one within its window
+    Window(WindowParam),
+    /// Extend each record in a GRASS expression
+    Slop(SlopParam),
+    /// Report the regions next to each record in a GRASS expression
+    Flank(FlankParam),
+    /// Change the length of each record in a GRASS expression
+    Resize(ResizeParam),
+    /// Remove the regions covered by a GRASS expression from each record of another one
+    Subtract(SubtractParam),
+    /// Measure how much each record in a GRASS expression is covered by another one
+    Coverage(CoverageParam),
+    /// Report the depth of the records in a GRASS expression along the genome
+    GenomeCoverage(GenomeCoverageParam),
+    /// The Jaccard statistic of the bases covered by two GRASS expressions
+    Jaccard(JaccardParam),
+    /// Fisher's exact test of the overlaps between two GRASS expressions
+    Fisher(FisherParam),
+    /// The distribution of the relative distances from the records in a GRASS expression to the
+    /// records of another one
+    RelativeDistance(RelativeDistanceParam),
     /// Customize the output format of records in a GRASS expression
     Format(FormatParam),
     /// Group the records in a GRASS expression into groups
     GroupBy(GroupByParam),
+    /// Count the records in a GRASS expression, either in total or for each run of the same keys
+    Count(CountParam),
+    /// Aggregate the values of the records in each group or each cluster of overlapping records
+    Aggregate(AggregateParam),
+    /// Attach the reference sequence to each record in a GRASS expression
+    AttachSequence(AttachSequenceParam),
 
     AssumeSorted(AssumeSortedParam),
 
@@ -76,19 +119,201 @@ pub enum GrassIR {
     Nop(NopParam),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl GrassIR {
+    /// The name of the operator, which is the same as the opcode in the JSON form
+    pub fn opcode(&self) -> &'static str {
+        match self {
+            GrassIR::CastToBed(_) => "CastToBed",
+            GrassIR::Let(_) => "Let",
+            GrassIR::Ref(_) => "Ref",
+            GrassIR::Open(_) => "Open",
+            GrassIR::WriteFile(_) => "WriteFile",
+            GrassIR::Alter(_) => "Alter",
+            GrassIR::Filter(_) => "Filter",
+            GrassIR::MergeOverlap(_) => "MergeOverlap",
+            GrassIR::Intersection(_) => "Intersection",
+            GrassIR::PairIntersection(_) => "PairIntersection",
+            GrassIR::Closest(_) => "Closest",
+            GrassIR::Window(_) => "Window",
+            GrassIR::Slop(_) => "Slop",
+            GrassIR::Flank(_) => "Flank",
+            GrassIR::Resize(_) => "Resize",
+            GrassIR::Subtract(_) => "Subtract",
+            GrassIR::Coverage(_) => "Coverage",
+            GrassIR::GenomeCoverage(_) => "GenomeCoverage",
+            GrassIR::Jaccard(_) => "Jaccard",
+            GrassIR::Fisher(_) => "Fisher",
+            GrassIR::RelativeDistance(_) => "RelativeDistance",
+            GrassIR::Format(_) => "Format",
+            GrassIR::GroupBy(_) => "GroupBy",
+            GrassIR::Count(_) => "Count",
+            GrassIR::Aggregate(_) => "Aggregate",
+            GrassIR::AttachSequence(_) => "AttachSequence",
+            GrassIR::AssumeSorted(_) => "AssumeSorted",
+            GrassIR::InlineRust(_) => "InlineRust",
+            GrassIR::LoadGenomeFile(_) => "LoadGenomeFile",
+            GrassIR::SortedRandom(_) => "SortedRandom",
+            GrassIR::InternalSort(_) => "InternalSort",
+            GrassIR::Invert(_) => "Invert",
+            GrassIR::AssignTag(_) => "AssignTag",
+            GrassIR::TwoWayMerge(_) => "TwoWayMerge",
+            GrassIR::Limit(_) => "Limit",
+            GrassIR::Nop(_) => "Nop",
+        }
+    }
+
+    /// The sub-expressions this expression reads from, along with the field holding each of them
+    pub fn named_children(&self) -> Vec<(String, &GrassIR)> {
+        fn named<'a>(name: &str, child: &'a GrassIR) -> (String, &'a GrassIR) {
+            (name.to_string(), child)
+        }
+        match self {
+            GrassIR::CastToBed(param) => vec![named("inner", &param.inner)],
+            GrassIR::Let(param) => vec![named("value", &param.value)],
+            GrassIR::Ref(_) => vec![],
+            GrassIR::Open(_) => vec![],
+            GrassIR::WriteFile(param) => vec![named("what", &param.what)],
+            GrassIR::Alter(param) => vec![named("inner", &param.original_expr)],
+            GrassIR::Filter(param) => vec![named("inner", &param.input_expr)],
+            GrassIR::MergeOverlap(param) => vec![named("inner", &param.input_expr)],
+            GrassIR::Intersection(param) => {
+                vec![named("lhs", &param.lhs), named("rhs", &param.rhs)]
+            }
+            GrassIR::PairIntersection(param) => {
+                vec![named("lhs", &param.lhs), named("rhs", &param.rhs)]
+            }
+            GrassIR::Closest(param) => vec![named("lhs", &param.lhs), named("rhs", &param.rhs)],
+            GrassIR::Window(param) => vec![named("lhs", &param.lhs), named("rhs", &param.rhs)],
+            GrassIR::Slop(param) => vec![named("inner", &param.inner)],
+            GrassIR::Flank(param) => vec![named("inner", &param.inner)],
+            GrassIR::Resize(param) => vec![named("inner", &param.inner)],
+            GrassIR::Subtract(param) => vec![named("lhs", &param.lhs), named("rhs", &param.rhs)],
+            GrassIR::Coverage(param) => vec![named("lhs", &param.lhs), named("rhs", &param.rhs)],
+            GrassIR::GenomeCoverage(param) => vec![named("inner", &param.inner)],
+            GrassIR::Jaccard(param) => vec![named("lhs", &param.lhs), named("rhs", &param.rhs)],
+            GrassIR::Fisher(param) => vec![named("lhs", &param.lhs), named("rhs", &param.rhs)],
+            GrassIR::RelativeDistance(param) => {
+                vec![named("lhs", &param.lhs), named("rhs", &param.rhs)]
+            }
+            GrassIR::Format(param) => vec![named("inner", &param.expr)],
+            GrassIR::GroupBy(param) => vec![named("inner", &param.expr)],
+            GrassIR::Count(param) => vec![named("what", &param.what)],
+            GrassIR::Aggregate(param) => vec![named("inner", &param.expr)],
+            GrassIR::AttachSequence(param) => vec![named("inner", &param.inner)],
+            GrassIR::AssumeSorted(param) => vec![named("inner", &param.inner)],
+            GrassIR::InlineRust(param) => param
+                .env
+                .iter()
+                .filter_map(|(key, env)| match env {
+                    InlineRustEnviron::Iter(iter) => Some((format!("env.{}", key), iter)),
+                    InlineRustEnviron::Const(_) => None,
+                })
+                .collect(),
+            GrassIR::LoadGenomeFile(_) => vec![],
+            GrassIR::SortedRandom(_) => vec![],
+            GrassIR::InternalSort(param) => vec![named("inner", &param.inner)],
+            GrassIR::Invert(param) => vec![named("inner", &param.inner)],
+            GrassIR::AssignTag(param) => vec![named("inner", &param.inner)],
+            GrassIR::TwoWayMerge(param) => {
+                vec![named("expr_1", &param.expr_1), named("expr_2", &param.expr_2)]
+            }
+            GrassIR::Limit(param) => vec![named("what", &param.what)],
+            GrassIR::Nop(param) => vec![named("inner", &param.inner)],
+        }
+    }
+
+    /// The sub-expressions this expression reads from
+    pub fn children(&self) -> Vec<&GrassIR> {
+        self.named_children()
+            .into_iter()
+            .map(|(_, child)| child)
+            .collect()
+    }
+
+    /// The mutable references to the sub-expressions, in the same order as `children`
+    pub fn children_mut(&mut self) -> Vec<&mut GrassIR> {
+        match self {
+            GrassIR::CastToBed(param) => vec![&mut param.inner],
+            GrassIR::Let(param) => vec![&mut param.value],
+            GrassIR::Ref(_) => vec![],
+            GrassIR::Open(_) => vec![],
+            GrassIR::WriteFile(param) => vec![&mut param.what],
+            GrassIR::Alter(param) => vec![&mut param.original_expr],
+            GrassIR::Filter(param) => vec![&mut param.input_expr],
+            GrassIR::MergeOverlap(param) => vec![&mut param.input_expr],
+            GrassIR::Intersection(param) => vec![&mut param.lhs, &mut param.rhs],
+            GrassIR::PairIntersection(param) => vec![&mut param.lhs, &mut param.rhs],
+            GrassIR::Closest(param) => vec![&mut param.lhs, &mut param.rhs],
+            GrassIR::Window(param) => vec![&mut param.lhs, &mut param.rhs],
+            GrassIR::Slop(param) => vec![&mut param.inner],
+            GrassIR::Flank(param) => vec![&mut param.inner],
+            GrassIR::Resize(param) => vec![&mut param.inner],
+            GrassIR::Subtract(param) => vec![&mut param.lhs, &mut param.rhs],
+            GrassIR::Coverage(param) => vec![&mut param.lhs, &mut param.rhs],
+            GrassIR::GenomeCoverage(param) => vec![&mut param.inner],
+            GrassIR::Jaccard(param) => vec![&mut param.lhs, &mut param.rhs],
+            GrassIR::Fisher(param) => vec![&mut param.lhs, &mut param.rhs],
+            GrassIR::RelativeDistance(param) => vec![&mut param.lhs, &mut param.rhs],
+            GrassIR::Format(param) => vec![&mut param.expr],
+            GrassIR::GroupBy(param) => vec![&mut param.expr],
+            GrassIR::Count(param) => vec![&mut param.what],
+            GrassIR::Aggregate(param) => vec![&mut param.expr],
+            GrassIR::AttachSequence(param) => vec![&mut param.inner],
+            GrassIR::AssumeSorted(param) => vec![&mut param.inner],
+            GrassIR::InlineRust(param) => param
+                .env
+                .values_mut()
+                .filter_map(|env| match env {
+                    InlineRustEnviron::Iter(iter) => Some(iter),
+                    InlineRustEnviron::Const(_) => None,
+                })
+                .collect(),
+            GrassIR::LoadGenomeFile(_) => vec![],
+            GrassIR::SortedRandom(_) => vec![],
+            GrassIR::InternalSort(param) => vec![&mut param.inner],
+            GrassIR::Invert(param) => vec![&mut param.inner],
+            GrassIR::AssignTag(param) => vec![&mut param.inner],
+            GrassIR::TwoWayMerge(param) => vec![&mut param.expr_1, &mut param.expr_2],
+            GrassIR::Limit(param) => vec![&mut param.what],
+            GrassIR::Nop(param) => vec![&mut param.inner],
+        }
+    }
+
+    /// The mutable references to the field expressions evaluated by this node
+    pub fn field_exprs_mut(&mut self) -> Vec<&mut FieldExpression> {
+        match self {
+            GrassIR::Alter(param) => vec![&mut param.value],
+            GrassIR::Filter(param) => vec![&mut param.cond],
+            GrassIR::Format(param) => param.values.values_mut().collect(),
+            GrassIR::GroupBy(param) => param.keys.iter_mut().collect(),
+            GrassIR::Count(param) => param.keys.iter_mut().collect(),
+            GrassIR::Aggregate(param) => {
+                let keys = match &mut param.group {
+                    AggregateGroup::Keys(keys) => keys.iter_mut().collect(),
+                    AggregateGroup::Overlap => vec![],
+                };
+                keys.into_iter()
+                    .chain(param.columns.iter_mut().map(|column| &mut column.value))
+                    .collect()
+            }
+            _ => vec![],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct LimitParam {
     pub what: Box<GrassIR>,
     pub count: ConstOrEnv<f64>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct TwoWayMergeParam {
     pub expr_1: Box<GrassIR>,
     pub expr_2: Box<GrassIR>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum TagValue {
     String(String),
@@ -96,40 +321,40 @@ pub enum TagValue {
     Float(f64),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct AssignTagParam {
     pub inner: Box<GrassIR>,
     pub tag: TagValue,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct InvertParam {
     pub inner: Box<GrassIR>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct InternalSortParam {
     pub inner: Box<GrassIR>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct NopParam {
     pub inner: Box<GrassIR>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct SortedRandomParam {
     pub count: ConstOrEnv<usize>,
     pub min_length: ConstOrEnv<u32>,
     pub max_length: ConstOrEnv<u32>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum LoadGenomeFileParam {
     File(ConstOrEnv<String>),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum InlineRustConst {
     String(String),
@@ -137,25 +362,25 @@ pub enum InlineRustConst {
     Integer(i64)
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum InlineRustEnviron {
     Iter(GrassIR),
     Const(ConstOrEnv<InlineRustConst>)
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct InlineRustParam {
     pub env: BTreeMap<String, InlineRustEnviron>,
     pub src: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct RefParam {
     /// The symbol we are referencing
     pub id: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct GroupByParam {
     /// The expression to group
     #[serde(rename = "inner")]
@@ -164,7 +389,96 @@ pub struct GroupByParam {
     pub keys: Vec<FieldExpression>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CountParam {
+    /// The expression to count
+    pub what: Box<GrassIR>,
+    /// The key expressions, the records are counted in total if this is empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<FieldExpression>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateGroup {
+    /// Each run of the records with the same keys is a group, like `GroupBy`
+    Keys(Vec<FieldExpression>),
+    /// Each cluster of overlapping records is a group, whose region is the one `MergeOverlap`
+    /// produces
+    Overlap,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateFunction {
+    Count,
+    CountDistinct,
+    Sum,
+    Mean,
+    Median,
+    Min,
+    Max,
+    Mode,
+    Stdev,
+    Collapse,
+    Distinct,
+    First,
+    Last,
+}
+
+impl AggregateFunction {
+    pub fn name(self) -> &'static str {
+        match self {
+            AggregateFunction::Count => "count",
+            AggregateFunction::CountDistinct => "count_distinct",
+            AggregateFunction::Sum => "sum",
+            AggregateFunction::Mean => "mean",
+            AggregateFunction::Median => "median",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+            AggregateFunction::Mode => "mode",
+            AggregateFunction::Stdev => "stdev",
+            AggregateFunction::Collapse => "collapse",
+            AggregateFunction::Distinct => "distinct",
+            AggregateFunction::First => "first",
+            AggregateFunction::Last => "last",
+        }
+    }
+
+    /// If the function only takes numeric values
+    pub fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            AggregateFunction::Sum
+                | AggregateFunction::Mean
+                | AggregateFunction::Median
+                | AggregateFunction::Min
+                | AggregateFunction::Max
+                | AggregateFunction::Stdev
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AggregateColumn {
+    /// The aggregate function
+    pub func: AggregateFunction,
+    /// The value of each record to aggregate
+    pub value: FieldExpression,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AggregateParam {
+    /// The expression to aggregate
+    #[serde(rename = "inner")]
+    pub expr: Box<GrassIR>,
+    /// How the records are grouped
+    pub group: AggregateGroup,
+    /// The aggregated columns, which are written after the keys or the region of each group
+    pub columns: Vec<AggregateColumn>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct FormatParam {
     /// The expression to be formatted
     #[serde(rename = "inner")]
@@ -175,7 +489,7 @@ pub struct FormatParam {
     pub values: BTreeMap<String, FieldExpression>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum IntersectFlavor {
     #[serde(rename = "inner")]
     Inner,
@@ -187,7 +501,7 @@ pub enum IntersectFlavor {
     RightOuter,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct IntersectParam {
     /// The flavor of the intersection operator
     pub flavor: IntersectFlavor,
@@ -197,15 +511,248 @@ pub struct IntersectParam {
     pub rhs: Box<GrassIR>,
     /// If we are using the sorted algorithm
     pub sorted: bool,
+    /// Only report the pairs overlapping on blocks, for example the exons of BED12 records
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub blocks_only: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum PairIntersectMode {
+    #[serde(rename = "either")]
+    Either,
+    #[serde(rename = "both")]
+    Both,
+    #[serde(rename = "neither")]
+    Neither,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PairIntersectParam {
+    /// Which ends of the pair should overlap the regions
+    pub mode: PairIntersectMode,
+    /// The paired records
+    pub lhs: Box<GrassIR>,
+    /// The regions to intersect with
+    pub rhs: Box<GrassIR>,
+    /// If both sides are sorted, so that the regions are streamed rather than indexed
+    #[serde(default)]
+    pub sorted: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClosestTies {
+    All,
+    First,
+    Last,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClosestDirection {
+    Both,
+    /// Only the records upstream of the record, which is relative to its strand
+    Upstream,
+    /// Only the records downstream of the record, which is relative to its strand
+    Downstream,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ClosestParam {
+    /// The records to find the closest records for
+    pub lhs: Box<GrassIR>,
+    /// The records to search, both sides should be sorted
+    pub rhs: Box<GrassIR>,
+    /// Which of the equally distant records are reported
+    pub ties: ClosestTies,
+    /// Which side of the record is searched
+    pub direction: ClosestDirection,
+    /// Don't report the overlapping records
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ignore_overlaps: bool,
+    /// The number of the nearest distances to report
+    pub k: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WindowParam {
+    /// The records to join, which are reported as they are
+    pub lhs: Box<GrassIR>,
+    /// The records to search, both sides should be sorted
+    pub rhs: Box<GrassIR>,
+    /// The number of bases added before the start of each record
+    pub left: u32,
+    /// The number of bases added after the end of each record
+    pub right: u32,
+    /// Take the left flank as the upstream one, which is after the end on the negative strand
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strand_relative: bool,
+    /// Only report the number of records within the window of each record
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub count_only: bool,
+}
+
+/// The number of bases to add or keep
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResizeAmount {
+    Bases(u32),
+    /// A fraction of the length of each record
+    Fraction(f64),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResizeAnchor {
+    Start,
+    Center,
+    End,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SlopParam {
+    pub inner: Box<GrassIR>,
+    /// The number of bases added before the start
+    pub left: ResizeAmount,
+    /// The number of bases added after the end
+    pub right: ResizeAmount,
+    /// Take the left side as the upstream one, which is after the end on the negative strand
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strand_relative: bool,
+    /// If the output should be sorted again, which needs a sorted input
+    pub sorted: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FlankParam {
+    pub inner: Box<GrassIR>,
+    /// The length of the flank before the start
+    pub left: ResizeAmount,
+    /// The length of the flank after the end
+    pub right: ResizeAmount,
+    /// Take the left side as the upstream one, which is after the end on the negative strand
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strand_relative: bool,
+    /// If the output should be sorted again, which needs a sorted input
+    pub sorted: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResizeParam {
+    pub inner: Box<GrassIR>,
+    /// The new length of each record
+    pub size: ResizeAmount,
+    /// Which part of the record stays in place
+    pub anchor: ResizeAnchor,
+    /// Take the start as the upstream end, which is the end on the negative strand
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strand_relative: bool,
+    /// If the output should be sorted again, which needs a sorted input
+    pub sorted: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubtractMode {
+    /// Report the parts of each record which are not covered
+    Fragments,
+    /// Remove the records with any overlap
+    RemoveOverlapping,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SubtractParam {
+    /// The records to subtract from, the other fields are kept
+    pub lhs: Box<GrassIR>,
+    /// The regions to remove, both sides should be sorted
+    pub rhs: Box<GrassIR>,
+    pub mode: SubtractMode,
+    /// The minimum overlap to subtract, as a fraction of the length of the record
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_fraction: Option<f64>,
+    /// Only subtract the records on the same strand
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub same_strand: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CoverageParam {
+    /// The records to measure, which are reported as they are
+    pub lhs: Box<GrassIR>,
+    /// The records covering them, both sides should be sorted
+    pub rhs: Box<GrassIR>,
+    /// Report the number of bases at each depth instead, one line for each depth
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub histogram: bool,
+    /// Also count the overlapping records on each strand
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub by_strand: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GenomeCoverageReport {
+    /// The runs of bases at the same depth
+    BedGraph,
+    /// The number of bases at each depth for each chromosome and the whole genome
+    Histogram,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageStrand {
+    Positive,
+    Negative,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GenomeCoverageParam {
+    /// The records to measure the depth of, which should be sorted
+    pub inner: Box<GrassIR>,
+    pub report: GenomeCoverageReport,
+    /// Also report the runs of bases which aren't covered, only for the bedGraph
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_zero: bool,
+    /// Only count the records on this strand
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strand: Option<CoverageStrand>,
+    /// The factor the depth is multiplied with, only for the bedGraph
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JaccardParam {
+    /// Both sides should be sorted, the records of each side are merged before comparing
+    pub lhs: Box<GrassIR>,
+    pub rhs: Box<GrassIR>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FisherParam {
+    /// Both sides should be sorted, the records of each side are merged before comparing
+    pub lhs: Box<GrassIR>,
+    pub rhs: Box<GrassIR>,
+    /// The number of bases in the genome, which is the size of the loaded genome by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genome_size: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RelativeDistanceParam {
+    /// The records to measure the distances from
+    pub lhs: Box<GrassIR>,
+    /// The records to measure the distances to, both sides should be sorted
+    pub rhs: Box<GrassIR>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct MergeOverlapParam {
     #[serde(rename = "inner")]
     pub input_expr: Box<GrassIR>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct FilterParam {
     /// The original expression
     #[serde(rename = "inner")]
@@ -214,7 +761,7 @@ pub struct FilterParam {
     pub cond: FieldExpression,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct AlterParam {
     /// The original expression
     #[serde(rename = "inner")]
@@ -226,35 +773,44 @@ pub struct AlterParam {
     pub sorted: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct AssumeSortedParam {
     pub inner: Box<GrassIR>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct CastToBedParam {
     pub inner: Box<GrassIR>,
     pub num_of_fields: u32,
     pub sorted: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AttachSequenceParam {
+    pub inner: Box<GrassIR>,
+    /// The path to the FASTA file of the reference genome
+    pub reference: ConstOrEnv<String>,
+    pub sorted: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum InputFormat {
     Bam,
     Bed,
+    BedPe,
     Cram,
     Vcf,
     Fasta,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum OpenTarget {
     Path(ConstOrEnv<String>),
     FileNo(u32),
     CmdArg(u32),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct OpenParam {
     /// The path to the data source
     pub target: OpenTarget,
@@ -266,16 +822,19 @@ pub struct OpenParam {
     pub compression: bool,
     /// If this file is known sorted
     pub sorted: bool,
+    /// The reference FASTA file, this is only used by CRAM input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<ConstOrEnv<String>>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum WriteTarget {
     Path(ConstOrEnv<String>),
     FileNo(i32),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct WriteFileParam {
     /// The expression we want to write
     pub what: Box<GrassIR>,
@@ -283,7 +842,7 @@ pub struct WriteFileParam {
     pub target: WriteTarget,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct LetBinding {
     /// The symbol of the value
     pub id: String,
@@ -319,22 +878,29 @@ mod test {
 
     macro_rules! parse_test {
         ($name: ident, $path : expr) => {
+            parse_test!($name, $path, GrassIR);
+        };
+        ($name: ident, $path : expr, $ty: ty) => {
             #[test]
             fn $name() -> Result<(), Box<dyn Error>> {
                 let input = include_str!($path);
-                let data: GrassIR = from_str(input)?;
+                let data: $ty = from_str(input)?;
                 validate_object(input, &data);
                 Ok(())
             }
         };
     }
     parse_test!(parse_bam_to_bed, "../../data/ir/bam-to-bed.py.json");
+    parse_test!(parse_closest, "../../data/ir/closest.py.json");
+    parse_test!(parse_count, "../../data/ir/count.py.json");
     parse_test!(
         parse_expand_interval,
         "../../data/ir/expand-interval.py.json"
     );
     parse_test!(parse_filter, "../../data/ir/filter.py.json");
+    parse_test!(parse_getfasta, "../../data/ir/getfasta.py.json");
     parse_test!(parse_merge, "../../data/ir/merge.py.json");
+    parse_test!(parse_pair_intersect, "../../data/ir/pair-intersect.py.json");
     parse_test!(parse_slop, "../../data/ir/slop.py.json");
     parse_test!(
         parse_sorted_intersect_custom_format,
@@ -356,5 +922,24 @@ mod test {
         parse_sorted_intersect,
         "../../data/ir/sorted-intersect.py.json"
     );
+    parse_test!(
+        parse_sorted_intersect_split,
+        "../../data/ir/sorted-intersect-split.py.json"
+    );
+    parse_test!(parse_coverage, "../../data/ir/coverage.py.json");
+    parse_test!(
+        parse_genome_coverage,
+        "../../data/ir/genome_coverage.py.json"
+    );
+    parse_test!(parse_jaccard, "../../data/ir/jaccard.py.json");
+    parse_test!(parse_sorted_subtract, "../../data/ir/subtract.py.json");
     parse_test!(parse_sorted_window, "../../data/ir/window.py.json");
+    parse_test!(parse_tee, "../../data/ir/tee.json", Vec<GrassIR>);
+    parse_test!(parse_vcf, "../../data/ir/vcf.json");
+    parse_test!(parse_regex, "../../data/ir/regex.json");
+    parse_test!(parse_aggregate, "../../data/ir/aggregate.py.json");
+    parse_test!(
+        parse_merge_aggregate,
+        "../../data/ir/merge-aggregate.py.json"
+    );
 }
@@ -0,0 +1,476 @@
+use std::collections::HashMap;
+
+use crate::{
+    AssumeSortedParam, BinaryParam, ComponentFieldRefParam, FieldExpression, FieldRefParam,
+    FilterParam, GrassIR, IntersectFlavor, PairIntersectMode, RecordRefParam, RefParam,
+};
+
+// Take the node out of the tree, leaving a placeholder which is overwritten right after
+fn take(ir: &mut GrassIR) -> GrassIR {
+    std::mem::replace(ir, GrassIR::Ref(RefParam { id: String::new() }))
+}
+
+// Visit the nodes in the order of expansion, the children are rewritten before their parent
+fn rewrite_post_order<F: FnMut(&mut GrassIR)>(ir: &mut GrassIR, f: &mut F) {
+    for child in ir.children_mut() {
+        rewrite_post_order(child, f);
+    }
+    f(ir);
+}
+
+fn count_refs(ir: &GrassIR, counts: &mut HashMap<String, usize>) {
+    if let GrassIR::Ref(param) = ir {
+        *counts.entry(param.id.clone()).or_default() += 1;
+    }
+    for child in ir.children() {
+        count_refs(child, counts);
+    }
+}
+
+fn defined_symbols<'a>(ir: &'a GrassIR, symbols: &mut Vec<&'a str>) {
+    if let GrassIR::Let(param) = ir {
+        symbols.push(&param.id);
+    }
+    for child in ir.children() {
+        defined_symbols(child, symbols);
+    }
+}
+
+// A subtree that produces different records each time it's evaluated can't be shared
+fn is_deterministic(ir: &GrassIR) -> bool {
+    !matches!(ir, GrassIR::SortedRandom(_) | GrassIR::InlineRust(_))
+        && ir.children().into_iter().all(is_deterministic)
+}
+
+// The value with all the symbols resolved, so that the same computation compares equal no
+// matter how its intermediate values are named
+fn resolve_symbols(ir: &GrassIR, defs: &HashMap<String, GrassIR>) -> GrassIR {
+    let mut ret = ir.clone();
+    rewrite_post_order(&mut ret, &mut |node| match node {
+        GrassIR::Let(param) => *node = take(&mut param.value),
+        GrassIR::Ref(param) => {
+            if let Some(value) = defs.get(&param.id) {
+                *node = value.clone();
+            }
+        }
+        _ => (),
+    });
+    ret
+}
+
+#[derive(Default)]
+struct LetDeduplicator {
+    ref_counts: HashMap<String, usize>,
+    defs: HashMap<String, GrassIR>,
+    seen: Vec<(GrassIR, String)>,
+    aliases: HashMap<String, String>,
+}
+
+impl LetDeduplicator {
+    fn visit(&mut self, ir: &mut GrassIR) {
+        match ir {
+            GrassIR::Let(param) => {
+                let value = resolve_symbols(&param.value, &self.defs);
+                let mut inner_symbols = Vec::new();
+                defined_symbols(&param.value, &mut inner_symbols);
+                let shareable = is_deterministic(&value)
+                    && inner_symbols
+                        .iter()
+                        .all(|id| !self.ref_counts.contains_key(*id));
+                let existing = self
+                    .seen
+                    .iter()
+                    .find(|(seen, _)| shareable && seen == &value)
+                    .map(|(_, id)| id.clone());
+                self.defs.insert(param.id.clone(), value.clone());
+                if let Some(existing) = existing {
+                    self.aliases.insert(param.id.clone(), existing.clone());
+                    *ir = GrassIR::Ref(RefParam { id: existing });
+                    return;
+                }
+                self.seen.push((value, param.id.clone()));
+            }
+            GrassIR::Ref(param) => {
+                if let Some(alias) = self.aliases.get(&param.id) {
+                    param.id = alias.clone();
+                }
+            }
+            _ => (),
+        }
+        for child in ir.children_mut() {
+            self.visit(child);
+        }
+    }
+}
+
+/// Replace the `Let` whose value is identical to an earlier one with a reference to the earlier
+/// one, so that the value is computed only once
+fn dedup_lets(irs: &mut [GrassIR]) {
+    let mut dedup = LetDeduplicator::default();
+    irs.iter()
+        .for_each(|ir| count_refs(ir, &mut dedup.ref_counts));
+    irs.iter_mut().for_each(|ir| dedup.visit(ir));
+}
+
+/// Remove the `Let` bindings that nobody refers to, so that the other passes can see through
+fn remove_unused_lets(irs: &mut [GrassIR]) {
+    let mut ref_counts = HashMap::new();
+    irs.iter().for_each(|ir| count_refs(ir, &mut ref_counts));
+    for ir in irs.iter_mut() {
+        rewrite_post_order(ir, &mut |node| {
+            if let GrassIR::Let(param) = node {
+                if !ref_counts.contains_key(&param.id) {
+                    *node = take(&mut param.value);
+                }
+            }
+        });
+    }
+}
+
+fn remove_nops(irs: &mut [GrassIR]) {
+    for ir in irs.iter_mut() {
+        rewrite_post_order(ir, &mut |node| {
+            if let GrassIR::Nop(param) = node {
+                *node = take(&mut param.inner);
+            }
+        });
+    }
+}
+
+fn and(lhs: FieldExpression, rhs: FieldExpression) -> FieldExpression {
+    FieldExpression::And(BinaryParam {
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+    })
+}
+
+// Filter the node, the condition is fused into the node if it's a filter already
+fn add_filter(ir: &mut GrassIR, cond: FieldExpression) {
+    if let GrassIR::Filter(param) = ir {
+        let prev = std::mem::replace(&mut param.cond, FieldExpression::FullRecordRef);
+        param.cond = and(prev, cond);
+        return;
+    }
+    *ir = GrassIR::Filter(FilterParam {
+        input_expr: Box::new(take(ir)),
+        cond,
+    });
+}
+
+fn fuse_filters(irs: &mut [GrassIR]) {
+    for ir in irs.iter_mut() {
+        rewrite_post_order(ir, &mut |node| {
+            if let GrassIR::Filter(param) = node {
+                if let GrassIR::Filter(_) = param.input_expr.as_ref() {
+                    let mut inner = take(&mut param.input_expr);
+                    let cond = std::mem::replace(&mut param.cond, FieldExpression::FullRecordRef);
+                    add_filter(&mut inner, cond);
+                    *node = inner;
+                }
+            }
+        });
+    }
+}
+
+fn split_conjuncts(expr: FieldExpression, conjuncts: &mut Vec<FieldExpression>) {
+    match expr {
+        FieldExpression::And(param) => {
+            split_conjuncts(*param.lhs, conjuncts);
+            split_conjuncts(*param.rhs, conjuncts);
+        }
+        expr => conjuncts.push(expr),
+    }
+}
+
+// The side of an intersection a predicate reads from. Both sides are involved if the predicate
+// reads the pair as a whole, for example the overlapping region of the pair.
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    None,
+    Only(i32),
+    Both,
+}
+
+impl Side {
+    fn merge(self, other: Side) -> Side {
+        match (self, other) {
+            (Side::None, side) | (side, Side::None) => side,
+            (Side::Only(a), Side::Only(b)) if a == b => Side::Only(a),
+            _ => Side::Both,
+        }
+    }
+}
+
+fn visit_field_expr_mut<F: FnMut(&mut FieldExpression)>(expr: &mut FieldExpression, f: &mut F) {
+    match expr {
+        FieldExpression::And(param)
+        | FieldExpression::Or(param)
+        | FieldExpression::Xor(param)
+        | FieldExpression::Add(param)
+        | FieldExpression::Sub(param)
+        | FieldExpression::Mul(param)
+        | FieldExpression::Div(param)
+        | FieldExpression::Mod(param)
+        | FieldExpression::Eq(param)
+        | FieldExpression::Ne(param)
+        | FieldExpression::LessThan(param)
+        | FieldExpression::GreaterThan(param)
+        | FieldExpression::LessEqualThan(param)
+        | FieldExpression::GreaterEqualThan(param)
+        | FieldExpression::RightShift(param)
+        | FieldExpression::LeftShift(param)
+        | FieldExpression::RegexMatch(param) => {
+            visit_field_expr_mut(&mut param.lhs, f);
+            visit_field_expr_mut(&mut param.rhs, f);
+        }
+        FieldExpression::Not(param) | FieldExpression::Neg(param) => {
+            visit_field_expr_mut(&mut param.operand, f)
+        }
+        FieldExpression::Cond(param) => {
+            visit_field_expr_mut(&mut param.cond, f);
+            visit_field_expr_mut(&mut param.then, f);
+            visit_field_expr_mut(&mut param.elze, f);
+        }
+        FieldExpression::StringRepr(param) => visit_field_expr_mut(&mut param.value, f),
+        FieldExpression::Cast(param) => visit_field_expr_mut(&mut param.value, f),
+        FieldExpression::Call(param) => {
+            for arg in param.args.iter_mut() {
+                visit_field_expr_mut(arg, f);
+            }
+        }
+        _ => (),
+    }
+    f(expr);
+}
+
+fn side_of(expr: &FieldExpression) -> Side {
+    let mut side = Side::None;
+    visit_field_expr_mut(&mut expr.clone(), &mut |node| {
+        side = side.merge(match node {
+            FieldExpression::ComponentFieldRef(param) => Side::Only(param.target),
+            FieldExpression::RecordRef(param) => Side::Only(param.id),
+            FieldExpression::FieldRef(_)
+            | FieldExpression::FullRecordRef
+            | FieldExpression::NumberOfComponents => Side::Both,
+            _ => Side::None,
+        })
+    });
+    side
+}
+
+// Rewrite a predicate on one side of the pair to a predicate on the record of that side
+fn to_single_record(mut expr: FieldExpression) -> FieldExpression {
+    visit_field_expr_mut(&mut expr, &mut |node| match node {
+        FieldExpression::ComponentFieldRef(ComponentFieldRefParam { field_name, .. }) => {
+            *node = FieldExpression::FieldRef(FieldRefParam {
+                field: std::mem::take(field_name),
+            })
+        }
+        FieldExpression::RecordRef(RecordRefParam { .. }) => *node = FieldExpression::FullRecordRef,
+        _ => (),
+    });
+    expr
+}
+
+/// Move the predicates only touching one side of an inner intersection below the intersection,
+/// so that the records are filtered before they are paired
+fn push_filters_down(irs: &mut [GrassIR]) {
+    for ir in irs.iter_mut() {
+        rewrite_post_order(ir, &mut |node| {
+            let param = match node {
+                GrassIR::Filter(param) => param,
+                _ => return,
+            };
+            let intersect = match param.input_expr.as_mut() {
+                GrassIR::Intersection(intersect) => intersect,
+                _ => return,
+            };
+            if !matches!(intersect.flavor, IntersectFlavor::Inner) {
+                return;
+            }
+            let mut conjuncts = Vec::new();
+            let cond = std::mem::replace(&mut param.cond, FieldExpression::FullRecordRef);
+            split_conjuncts(cond, &mut conjuncts);
+            let mut rest: Option<FieldExpression> = None;
+            for conjunct in conjuncts {
+                match side_of(&conjunct) {
+                    Side::Only(0) => add_filter(&mut intersect.lhs, to_single_record(conjunct)),
+                    Side::Only(1) => add_filter(&mut intersect.rhs, to_single_record(conjunct)),
+                    _ => {
+                        rest = Some(match rest.take() {
+                            Some(prev) => and(prev, conjunct),
+                            None => conjunct,
+                        })
+                    }
+                }
+            }
+            match rest {
+                Some(cond) => param.cond = cond,
+                None => *node = take(&mut param.input_expr),
+            }
+        });
+    }
+}
+
+#[derive(Default)]
+struct SortednessTracker {
+    symbols: HashMap<String, bool>,
+}
+
+impl SortednessTracker {
+    // Returns if the output of the node is sorted after the rewrite
+    fn visit(&mut self, ir: &mut GrassIR) -> bool {
+        let children: Vec<_> = ir
+            .children_mut()
+            .into_iter()
+            .map(|child| self.visit(child))
+            .collect();
+        let input = children.first().copied().unwrap_or(false);
+        match ir {
+            GrassIR::InternalSort(param) if input => {
+                *ir = GrassIR::AssumeSorted(AssumeSortedParam {
+                    inner: Box::new(take(&mut param.inner)),
+                });
+                true
+            }
+            GrassIR::AssumeSorted(param) => {
+                if let GrassIR::AssumeSorted(_) | GrassIR::InternalSort(_) = param.inner.as_ref() {
+                    *ir = take(&mut param.inner);
+                }
+                true
+            }
+            GrassIR::Let(param) => {
+                self.symbols.insert(param.id.clone(), input);
+                input
+            }
+            GrassIR::Ref(param) => self.symbols.get(&param.id).copied().unwrap_or(false),
+            GrassIR::Open(param) => param.sorted,
+            GrassIR::CastToBed(param) => param.sorted,
+            GrassIR::Alter(param) => param.sorted,
+            GrassIR::AttachSequence(param) => param.sorted,
+            GrassIR::Slop(param) => param.sorted,
+            GrassIR::Flank(param) => param.sorted,
+            GrassIR::Resize(param) => param.sorted,
+            GrassIR::Intersection(param) => param.sorted,
+            GrassIR::PairIntersection(param) => {
+                matches!(param.mode, PairIntersectMode::Neither) && input
+            }
+            GrassIR::Filter(_) | GrassIR::AssignTag(_) | GrassIR::Nop(_) => input,
+            GrassIR::InternalSort(_)
+            | GrassIR::MergeOverlap(_)
+            | GrassIR::Invert(_)
+            | GrassIR::SortedRandom(_)
+            | GrassIR::Subtract(_)
+            | GrassIR::TwoWayMerge(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Replace the sorts of sorted inputs with a cheap assumption, and collapse stacked assumptions
+fn remove_redundant_sorts(irs: &mut [GrassIR]) {
+    let mut tracker = SortednessTracker::default();
+    irs.iter_mut().for_each(|ir| {
+        tracker.visit(ir);
+    });
+}
+
+/// Rewrite the IR to an equivalent but cheaper form. The IRs are optimized together, as a
+/// symbol defined in an IR can be referenced by the IRs after it.
+pub fn optimize(irs: &mut [GrassIR]) {
+    dedup_lets(irs);
+    remove_unused_lets(irs);
+    remove_nops(irs);
+    fuse_filters(irs);
+    push_filters_down(irs);
+    remove_redundant_sorts(irs);
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{from_str, json, to_value, Value};
+
+    use super::optimize;
+    use crate::{validate, GrassIR};
+
+    fn open(arg: u32, sorted: bool) -> Value {
+        json!({"opcode": "Open", "target": {"CmdArg": arg}, "format": "Bed",
+            "num_of_fields": 6, "compression": false, "sorted": sorted})
+    }
+
+    fn component_eq(target: i32, field: &str, value: &str) -> Value {
+        json!({"opcode": "Eq",
+            "lhs": {"opcode": "ComponentFieldRef", "target": target, "field_name": field},
+            "rhs": {"opcode": "ConstValue", "value": value}})
+    }
+
+    fn field_eq(field: &str, value: &str) -> Value {
+        json!({"opcode": "Eq",
+            "lhs": {"opcode": "FieldRef", "field": field},
+            "rhs": {"opcode": "ConstValue", "value": value}})
+    }
+
+    fn optimized(input: Value) -> Value {
+        let mut irs: Vec<GrassIR> = serde_json::from_value(input).unwrap();
+        optimize(&mut irs);
+        assert_eq!(validate(&irs), Ok(()));
+        to_value(&irs).unwrap()
+    }
+
+    #[test]
+    fn test_push_filters_down() {
+        let intersect = json!({"opcode": "Let", "id": "x", "value": {
+            "opcode": "Intersection", "flavor": "inner", "sorted": true,
+            "lhs": {"opcode": "Let", "id": "a", "value": {"opcode": "Nop", "inner": open(1, true)}},
+            "rhs": open(2, true)}});
+        let filter = json!({"opcode": "Filter", "cond": component_eq(0, "name", "a"),
+            "inner": {"opcode": "Filter", "inner": intersect,
+                "cond": {"opcode": "And", "lhs": component_eq(1, "strand", "+"),
+                    "rhs": field_eq("name", "b")}}});
+        let expected = json!({"opcode": "Filter", "cond": field_eq("name", "b"), "inner": {
+            "opcode": "Intersection", "flavor": "inner", "sorted": true,
+            "lhs": {"opcode": "Filter", "inner": open(1, true), "cond": field_eq("name", "a")},
+            "rhs": {"opcode": "Filter", "inner": open(2, true), "cond": field_eq("strand", "+")}}});
+        assert_eq!(optimized(json!([filter])), json!([expected]));
+    }
+
+    #[test]
+    fn test_dedup_lets_and_sorts() {
+        let input = json!([
+            {"opcode": "WriteFile", "target": 1, "what": {"opcode": "Let", "id": "a", "value": {
+                "opcode": "InternalSort", "inner": {"opcode": "Let", "id": "b", "value": open(1, true)}}}},
+            {"opcode": "WriteFile", "target": 2, "what": {"opcode": "Let", "id": "c", "value": {
+                "opcode": "InternalSort", "inner": {"opcode": "Let", "id": "d", "value": open(1, true)}}}},
+        ]);
+        let expected = json!([
+            {"opcode": "WriteFile", "target": 1, "what": {"opcode": "Let", "id": "a", "value": {
+                "opcode": "AssumeSorted", "inner": open(1, true)}}},
+            {"opcode": "WriteFile", "target": 2, "what": {"opcode": "Ref", "id": "a"}},
+        ]);
+        assert_eq!(optimized(input), expected);
+    }
+
+    macro_rules! optimize_test {
+        ($($path : expr),*) => {
+            $(
+                let ir: GrassIR = from_str(include_str!($path)).unwrap();
+                let mut irs = vec![ir];
+                optimize(&mut irs);
+                assert_eq!(validate(&irs), Ok(()), "{}", $path);
+            )*
+        };
+    }
+
+    #[test]
+    fn test_optimize_examples() {
+        optimize_test!(
+            "../../data/ir/bam-to-bed.py.json",
+            "../../data/ir/count.py.json",
+            "../../data/ir/merge.py.json",
+            "../../data/ir/pair-intersect.py.json",
+            "../../data/ir/sorted-intersect-group.py.json",
+            "../../data/ir/sorted-intersect-overlap-filter.py.json",
+            "../../data/ir/window.py.json"
+        );
+    }
+}
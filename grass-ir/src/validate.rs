@@ -0,0 +1,906 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    AggregateGroup, FieldExpression, FieldType, GenomeCoverageReport, GrassIR, InputFormat,
+    PairIntersectMode, ResizeAmount,
+};
+
+/// An error found by the validator
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// The path to the offending IR node, for example `$[0].what.value.lhs`
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// The record type inferred for an IR node
+#[derive(Debug, Clone, PartialEq)]
+enum RecordType {
+    Bed(i32),
+    BedPe,
+    Bam,
+    Vcf,
+    Sequenced(Box<RecordType>),
+    Tagged(Box<RecordType>),
+    /// The output of an intersection, which is a pair of records
+    Pair(Box<RecordType>, Box<RecordType>),
+    /// A record along with one of its closest records
+    Closest(Box<RecordType>, Box<RecordType>),
+    /// A record along with the number of records within its window
+    WindowCount(Box<RecordType>),
+    /// A record along with how much it's covered, the flag is set if the records on each strand
+    /// are counted
+    Coverage(Box<RecordType>, bool),
+    /// A record along with the number of its bases at one depth
+    DepthBin(Box<RecordType>),
+    /// A run of bases at the same depth along the genome
+    BedGraph,
+    /// The number of bases at one depth in a chromosome or the genome
+    GenomeHistogram,
+    /// The Jaccard statistic of two inputs
+    Jaccard,
+    /// Fisher's exact test of the overlaps between two inputs
+    Fisher,
+    /// The number of records in one bin of the relative distance distribution
+    RelativeDistance,
+    Group(Box<RecordType>),
+    Count,
+    /// Formatted text, which doesn't have any field
+    Text,
+    /// We don't know anything about the type, so the fields are not checked
+    Unknown,
+}
+
+const REGION_FIELDS: [&str; 3] = ["chrom", "start", "end"];
+
+impl RecordType {
+    fn fields(&self) -> Option<Vec<&'static str>> {
+        let mut fields = REGION_FIELDS.to_vec();
+        match self {
+            RecordType::Bed(num_of_fields) => {
+                let optional = ["name", "score", "strand"];
+                let count = (*num_of_fields - 3).clamp(0, optional.len() as i32) as usize;
+                fields.extend_from_slice(&optional[..count]);
+            }
+            RecordType::BedPe => fields.extend(["name", "score", "strand"]),
+            RecordType::Bam => fields.extend(["score", "strand"]),
+            RecordType::Vcf => fields.extend(["name", "score"]),
+            RecordType::Sequenced(inner) => {
+                fields = inner.fields()?;
+                fields.extend(["seq", "gc_content"]);
+            }
+            RecordType::Tagged(inner) => {
+                fields = inner.fields()?;
+                fields.push("tag_str");
+            }
+            // The fields of a pair other than the region are taken from the first record
+            RecordType::Pair(first, _) => {
+                let first = first.fields()?;
+                fields.extend(
+                    ["name", "score", "strand"]
+                        .into_iter()
+                        .filter(|field| first.contains(field)),
+                );
+            }
+            RecordType::Closest(first, _) => {
+                fields = first.fields()?;
+                fields.push("distance");
+            }
+            RecordType::WindowCount(inner) => {
+                fields = inner.fields()?;
+                fields.push("count");
+            }
+            RecordType::Coverage(inner, by_strand) => {
+                fields = inner.fields()?;
+                fields.extend(["count", "covered", "fraction"]);
+                if *by_strand {
+                    fields.extend(["forward_count", "reverse_count"]);
+                }
+            }
+            RecordType::DepthBin(inner) => {
+                fields = inner.fields()?;
+                fields.extend(["depth", "covered", "fraction"]);
+            }
+            RecordType::BedGraph => fields.extend(["depth", "score"]),
+            RecordType::GenomeHistogram => {
+                fields = vec!["chrom", "depth", "covered", "size", "fraction"]
+            }
+            RecordType::Jaccard => {
+                fields = vec!["intersection", "union", "jaccard", "n_intersections"]
+            }
+            RecordType::Fisher => fields = vec!["left_tail", "right_tail", "two_tail", "ratio"],
+            RecordType::RelativeDistance => fields = vec!["reldist", "count", "total", "fraction"],
+            RecordType::Group(_) => (),
+            RecordType::Count => fields = vec!["count"],
+            RecordType::Text => fields = vec![],
+            RecordType::Unknown => return None,
+        }
+        Some(fields)
+    }
+
+    fn component(&self, idx: i32) -> Option<&RecordType> {
+        match (self, idx) {
+            (RecordType::Pair(first, _), 0) => Some(first),
+            (RecordType::Pair(_, second), 1) => Some(second),
+            (RecordType::Closest(first, _), 0) => Some(first),
+            (RecordType::Closest(_, second), 1) => Some(second),
+            (RecordType::WindowCount(inner), 0) => Some(inner),
+            (RecordType::Coverage(inner, _), 0) => Some(inner),
+            (RecordType::DepthBin(inner), 0) => Some(inner),
+            (RecordType::Group(inner), _) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct NodeInfo {
+    ty: RecordType,
+    sorted: bool,
+}
+
+impl NodeInfo {
+    fn new(ty: RecordType, sorted: bool) -> Self {
+        Self { ty, sorted }
+    }
+    fn unknown() -> Self {
+        Self::new(RecordType::Unknown, false)
+    }
+}
+
+#[derive(Default)]
+struct Validator {
+    symbols: HashMap<String, NodeInfo>,
+    errors: Vec<ValidationError>,
+}
+
+impl Validator {
+    fn error(&mut self, path: &str, message: String) {
+        self.errors.push(ValidationError {
+            path: path.to_string(),
+            message,
+        });
+    }
+
+    fn require_sorted(&mut self, path: &str, ir: &GrassIR, input: &NodeInfo) {
+        if !input.sorted {
+            self.error(path, format!("{} requires a sorted input", ir.opcode()));
+        }
+    }
+
+    /// The operators changing the region of a record only support BED3 to BED6 records
+    fn require_bed(&mut self, path: &str, ir: &GrassIR, input: &NodeInfo) {
+        if !matches!(input.ty, RecordType::Bed(3..=6) | RecordType::Unknown) {
+            self.error(
+                path,
+                format!(
+                    "{} only supports BED records, but got {:?}",
+                    ir.opcode(),
+                    input.ty
+                ),
+            );
+        }
+    }
+
+    /// The operators walking along the genome only need the region of a record
+    fn require_region(&mut self, path: &str, ir: &GrassIR, input: &NodeInfo) {
+        if matches!(input.ty, RecordType::Count | RecordType::Text) {
+            self.error(
+                path,
+                format!(
+                    "{} only supports records with regions, but got {:?}",
+                    ir.opcode(),
+                    input.ty
+                ),
+            );
+        }
+    }
+
+    fn check_resize(
+        &mut self,
+        path: &str,
+        ir: &GrassIR,
+        input: &NodeInfo,
+        amounts: &[&ResizeAmount],
+        sorted: bool,
+    ) -> NodeInfo {
+        if sorted {
+            self.require_sorted(path, ir, input);
+        }
+        self.require_bed(path, ir, input);
+        for amount in amounts {
+            if let ResizeAmount::Fraction(fraction) = amount {
+                if !(fraction.is_finite() && *fraction >= 0.0) {
+                    self.error(
+                        path,
+                        format!("The fraction of {} should not be negative", ir.opcode()),
+                    );
+                }
+            }
+        }
+        NodeInfo::new(input.ty.clone(), sorted)
+    }
+
+    fn check_field(&mut self, path: &str, ty: &RecordType, field: &str) {
+        if let Some(fields) = ty.fields() {
+            if !fields.contains(&field) {
+                self.error(
+                    path,
+                    format!("Field {} doesn't exist on record type {:?}", field, ty),
+                );
+            }
+        }
+    }
+
+    fn check_component(&mut self, path: &str, ty: &RecordType, idx: i32) -> Option<RecordType> {
+        if ty == &RecordType::Unknown {
+            return None;
+        }
+        let component = ty.component(idx).cloned();
+        if component.is_none() {
+            self.error(
+                path,
+                format!("Component {} doesn't exist on record type {:?}", idx, ty),
+            );
+        }
+        component
+    }
+
+    /// Infer the type of a field expression, the type error is reported at the offending node
+    fn check_type(&mut self, path: &str, expr: &FieldExpression) -> Option<FieldType> {
+        match expr.infer_type() {
+            Ok(ty) => Some(ty),
+            Err(e) => {
+                self.error(&format!("{}{}", path, e.path), e.message);
+                None
+            }
+        }
+    }
+
+    fn visit_field_expr(&mut self, path: &str, expr: &FieldExpression, ty: &RecordType) {
+        match expr {
+            FieldExpression::And(param)
+            | FieldExpression::Or(param)
+            | FieldExpression::Xor(param)
+            | FieldExpression::Add(param)
+            | FieldExpression::Sub(param)
+            | FieldExpression::Mul(param)
+            | FieldExpression::Div(param)
+            | FieldExpression::Mod(param)
+            | FieldExpression::Eq(param)
+            | FieldExpression::Ne(param)
+            | FieldExpression::LessThan(param)
+            | FieldExpression::GreaterThan(param)
+            | FieldExpression::LessEqualThan(param)
+            | FieldExpression::GreaterEqualThan(param)
+            | FieldExpression::RightShift(param)
+            | FieldExpression::LeftShift(param)
+            | FieldExpression::RegexMatch(param) => {
+                self.visit_field_expr(&format!("{}.lhs", path), &param.lhs, ty);
+                self.visit_field_expr(&format!("{}.rhs", path), &param.rhs, ty);
+            }
+            FieldExpression::Not(param) | FieldExpression::Neg(param) => {
+                self.visit_field_expr(&format!("{}.operand", path), &param.operand, ty);
+            }
+            FieldExpression::Cond(param) => {
+                self.visit_field_expr(&format!("{}.cond", path), &param.cond, ty);
+                self.visit_field_expr(&format!("{}.then", path), &param.then, ty);
+                self.visit_field_expr(&format!("{}.elze", path), &param.elze, ty);
+            }
+            FieldExpression::StringRepr(param) => {
+                self.visit_field_expr(&format!("{}.value", path), &param.value, ty);
+            }
+            FieldExpression::Cast(param) => {
+                self.visit_field_expr(&format!("{}.value", path), &param.value, ty);
+            }
+            FieldExpression::Call(param) => {
+                for (idx, arg) in param.args.iter().enumerate() {
+                    self.visit_field_expr(&format!("{}.args[{}]", path, idx), arg, ty);
+                }
+            }
+            FieldExpression::FieldRef(param) => self.check_field(path, ty, &param.field),
+            FieldExpression::ComponentFieldRef(param) => {
+                if let Some(component) = self.check_component(path, ty, param.target) {
+                    self.check_field(path, &component, &param.field_name);
+                }
+            }
+            FieldExpression::RecordRef(param) => {
+                self.check_component(path, ty, param.id);
+            }
+            FieldExpression::NumberOfComponents => {
+                if !matches!(ty, RecordType::Group(_) | RecordType::Unknown) {
+                    self.error(
+                        path,
+                        format!("Record type {:?} doesn't have components", ty),
+                    );
+                }
+            }
+            FieldExpression::ConstValue(_) | FieldExpression::FullRecordRef => (),
+        }
+    }
+
+    fn visit(&mut self, path: &str, ir: &GrassIR) -> NodeInfo {
+        let children: Vec<_> = ir
+            .named_children()
+            .into_iter()
+            .map(|(name, child)| {
+                let child_path = format!("{}.{}", path, name);
+                let info = self.visit(&child_path, child);
+                (child_path, info)
+            })
+            .collect();
+        let input = children
+            .first()
+            .map_or_else(NodeInfo::unknown, |(_, info)| info.clone());
+
+        match ir {
+            GrassIR::Let(param) => {
+                self.symbols.insert(param.id.clone(), input.clone());
+                input
+            }
+            GrassIR::Ref(param) => match self.symbols.get(&param.id) {
+                Some(info) => info.clone(),
+                None => {
+                    self.error(path, format!("Reference to undefined symbol {}", param.id));
+                    NodeInfo::unknown()
+                }
+            },
+            GrassIR::Open(param) => {
+                let ty = match param.format {
+                    InputFormat::Bed => RecordType::Bed(param.num_of_fields),
+                    InputFormat::BedPe => RecordType::BedPe,
+                    InputFormat::Bam | InputFormat::Cram => RecordType::Bam,
+                    InputFormat::Vcf => RecordType::Vcf,
+                    InputFormat::Fasta => RecordType::Unknown,
+                };
+                NodeInfo::new(ty, param.sorted)
+            }
+            GrassIR::CastToBed(param) => {
+                let ty = RecordType::Bed(param.num_of_fields as i32);
+                let ty = match input.ty {
+                    RecordType::Tagged(_) => RecordType::Tagged(Box::new(ty)),
+                    _ => ty,
+                };
+                NodeInfo::new(ty, param.sorted)
+            }
+            GrassIR::Alter(param) => {
+                self.check_field(path, &input.ty, &param.field);
+                let value_path = format!("{}.value", path);
+                self.visit_field_expr(&value_path, &param.value, &input.ty);
+                let value_ty = self.check_type(&value_path, &param.value);
+                if let (Some(field_ty), Some(value_ty)) =
+                    (FieldType::of_field(&param.field), value_ty)
+                {
+                    if field_ty.widen(value_ty).is_none() && field_ty != value_ty {
+                        self.error(
+                            &value_path,
+                            format!("Unable to assign a {} to field {}", value_ty, param.field),
+                        );
+                    }
+                }
+                NodeInfo::new(input.ty, param.sorted)
+            }
+            GrassIR::Filter(param) => {
+                let cond_path = format!("{}.cond", path);
+                self.visit_field_expr(&cond_path, &param.cond, &input.ty);
+                match self.check_type(&cond_path, &param.cond) {
+                    Some(FieldType::Bool) | None => (),
+                    Some(ty) => self.error(
+                        &cond_path,
+                        format!("The filter condition should be a bool, but got {}", ty),
+                    ),
+                }
+                input
+            }
+            GrassIR::Format(param) => {
+                for (key, value) in param.values.iter() {
+                    let value_path = format!("{}.values.{}", path, key);
+                    self.visit_field_expr(&value_path, value, &input.ty);
+                    self.check_type(&value_path, value);
+                }
+                NodeInfo::new(RecordType::Text, false)
+            }
+            GrassIR::GroupBy(param) => {
+                for (idx, key) in param.keys.iter().enumerate() {
+                    let key_path = format!("{}.keys[{}]", path, idx);
+                    self.visit_field_expr(&key_path, key, &input.ty);
+                    self.check_type(&key_path, key);
+                }
+                NodeInfo::new(RecordType::Group(Box::new(input.ty)), false)
+            }
+            GrassIR::Count(param) => {
+                for (idx, key) in param.keys.iter().enumerate() {
+                    let key_path = format!("{}.keys[{}]", path, idx);
+                    self.visit_field_expr(&key_path, key, &input.ty);
+                    self.check_type(&key_path, key);
+                }
+                NodeInfo::new(RecordType::Count, false)
+            }
+            GrassIR::Aggregate(param) => {
+                let info = match &param.group {
+                    AggregateGroup::Keys(keys) => {
+                        for (idx, key) in keys.iter().enumerate() {
+                            let key_path = format!("{}.group.keys[{}]", path, idx);
+                            self.visit_field_expr(&key_path, key, &input.ty);
+                            self.check_type(&key_path, key);
+                        }
+                        NodeInfo::new(RecordType::Text, false)
+                    }
+                    AggregateGroup::Overlap => {
+                        self.require_sorted(path, ir, &input);
+                        NodeInfo::new(RecordType::Bed(3), true)
+                    }
+                };
+                for (idx, column) in param.columns.iter().enumerate() {
+                    let value_path = format!("{}.columns[{}].value", path, idx);
+                    self.visit_field_expr(&value_path, &column.value, &input.ty);
+                    let expected = if column.func.is_numeric() {
+                        "a number"
+                    } else {
+                        "a number, a string or a bool"
+                    };
+                    match self.check_type(&value_path, &column.value) {
+                        Some(ty)
+                            if ty == FieldType::Record
+                                || (column.func.is_numeric() && !ty.is_numeric()) =>
+                        {
+                            self.error(
+                                &value_path,
+                                format!(
+                                    "The value of {} should be {}, but got {}",
+                                    column.func.name(),
+                                    expected,
+                                    ty
+                                ),
+                            )
+                        }
+                        _ => (),
+                    }
+                }
+                info
+            }
+            GrassIR::MergeOverlap(_) | GrassIR::Invert(_) => {
+                self.require_sorted(path, ir, &input);
+                NodeInfo::new(RecordType::Bed(3), true)
+            }
+            GrassIR::Closest(param) => {
+                for (child_path, info) in children.iter() {
+                    self.require_sorted(child_path, ir, info);
+                }
+                if param.k == 0 {
+                    self.error(path, "Closest should report at least 1 record".to_string());
+                }
+                let rhs = children[1].1.ty.clone();
+                let ty = RecordType::Closest(Box::new(input.ty), Box::new(rhs));
+                NodeInfo::new(ty, input.sorted)
+            }
+            GrassIR::Window(param) => {
+                for (child_path, info) in children.iter() {
+                    self.require_sorted(child_path, ir, info);
+                }
+                if param.count_only {
+                    NodeInfo::new(RecordType::WindowCount(Box::new(input.ty)), input.sorted)
+                } else {
+                    // The region of a pair is the overlap, which isn't there for most of the pairs
+                    let rhs = children[1].1.ty.clone();
+                    let ty = RecordType::Pair(Box::new(input.ty), Box::new(rhs));
+                    NodeInfo::new(ty, false)
+                }
+            }
+            GrassIR::Coverage(param) => {
+                for (child_path, info) in children.iter() {
+                    self.require_sorted(child_path, ir, info);
+                }
+                if param.histogram && param.by_strand {
+                    self.error(
+                        path,
+                        "The depth histogram of Coverage can't be split by strand".to_string(),
+                    );
+                }
+                let ty = if param.histogram {
+                    RecordType::DepthBin(Box::new(input.ty))
+                } else {
+                    RecordType::Coverage(Box::new(input.ty), param.by_strand)
+                };
+                NodeInfo::new(ty, input.sorted)
+            }
+            GrassIR::GenomeCoverage(param) => {
+                self.require_sorted(path, ir, &input);
+                self.require_region(path, ir, &input);
+                if let Some(scale) = param.scale {
+                    if !(scale.is_finite() && scale > 0.0) {
+                        self.error(
+                            path,
+                            "The scale of GenomeCoverage should be positive".to_string(),
+                        );
+                    }
+                }
+                match param.report {
+                    GenomeCoverageReport::BedGraph => NodeInfo::new(RecordType::BedGraph, true),
+                    GenomeCoverageReport::Histogram => {
+                        if param.include_zero || param.scale.is_some() {
+                            self.error(
+                                path,
+                                "The histogram of GenomeCoverage can't include zero or be scaled"
+                                    .to_string(),
+                            );
+                        }
+                        NodeInfo::new(RecordType::GenomeHistogram, false)
+                    }
+                }
+            }
+            GrassIR::Jaccard(_) | GrassIR::Fisher(_) | GrassIR::RelativeDistance(_) => {
+                for (child_path, info) in children.iter() {
+                    self.require_sorted(child_path, ir, info);
+                    self.require_region(child_path, ir, info);
+                }
+                let ty = match ir {
+                    GrassIR::Jaccard(_) => RecordType::Jaccard,
+                    GrassIR::Fisher(param) => {
+                        if param.genome_size == Some(0) {
+                            self.error(
+                                path,
+                                "The genome size of Fisher should be positive".to_string(),
+                            );
+                        }
+                        RecordType::Fisher
+                    }
+                    _ => RecordType::RelativeDistance,
+                };
+                NodeInfo::new(ty, false)
+            }
+            GrassIR::Intersection(param) => {
+                if param.sorted {
+                    for (child_path, info) in children.iter() {
+                        self.require_sorted(child_path, ir, info);
+                    }
+                }
+                let rhs = children[1].1.ty.clone();
+                let ty = RecordType::Pair(Box::new(input.ty), Box::new(rhs));
+                NodeInfo::new(ty, param.sorted)
+            }
+            GrassIR::PairIntersection(param) => {
+                if param.sorted {
+                    for (child_path, info) in children.iter() {
+                        self.require_sorted(child_path, ir, info);
+                    }
+                }
+                match param.mode {
+                    PairIntersectMode::Neither => input,
+                    _ => {
+                        let rhs = children[1].1.ty.clone();
+                        let ty = RecordType::Pair(Box::new(input.ty), Box::new(rhs));
+                        NodeInfo::new(ty, false)
+                    }
+                }
+            }
+            GrassIR::TwoWayMerge(_) => {
+                for (child_path, info) in children.iter() {
+                    self.require_sorted(child_path, ir, info);
+                }
+                let ty = if input.ty == children[1].1.ty {
+                    input.ty
+                } else {
+                    RecordType::Unknown
+                };
+                NodeInfo::new(ty, true)
+            }
+            GrassIR::AttachSequence(param) => {
+                NodeInfo::new(RecordType::Sequenced(Box::new(input.ty)), param.sorted)
+            }
+            GrassIR::Slop(param) => {
+                let amounts = [&param.left, &param.right];
+                self.check_resize(path, ir, &input, &amounts, param.sorted)
+            }
+            GrassIR::Flank(param) => {
+                let amounts = [&param.left, &param.right];
+                self.check_resize(path, ir, &input, &amounts, param.sorted)
+            }
+            GrassIR::Resize(param) => {
+                self.check_resize(path, ir, &input, &[&param.size], param.sorted)
+            }
+            GrassIR::Subtract(param) => {
+                for (child_path, info) in children.iter() {
+                    self.require_sorted(child_path, ir, info);
+                }
+                self.require_bed(path, ir, &input);
+                if !(0.0..=1.0).contains(&param.min_fraction.unwrap_or(0.0)) {
+                    self.error(
+                        path,
+                        "The minimum fraction of Subtract should be between 0 and 1".to_string(),
+                    );
+                }
+                NodeInfo::new(input.ty, true)
+            }
+            GrassIR::AssignTag(_) => {
+                NodeInfo::new(RecordType::Tagged(Box::new(input.ty)), input.sorted)
+            }
+            GrassIR::AssumeSorted(_) | GrassIR::InternalSort(_) => NodeInfo::new(input.ty, true),
+            GrassIR::SortedRandom(_) => NodeInfo::new(RecordType::Bed(3), true),
+            // Taking the first records doesn't change the order, but the runtime doesn't know
+            GrassIR::Limit(_) => NodeInfo::new(input.ty, false),
+            GrassIR::Nop(_) | GrassIR::WriteFile(_) => input,
+            GrassIR::InlineRust(_) | GrassIR::LoadGenomeFile(_) => NodeInfo::unknown(),
+        }
+    }
+}
+
+/// Check the IR before the code generation, so that the mistakes are reported with the path to
+/// the offending node, rather than as rustc errors in the generated code. The IRs are checked
+/// in order, so a symbol defined in an IR can be referenced by the IRs after it.
+pub fn validate(irs: &[GrassIR]) -> Result<(), Vec<ValidationError>> {
+    let mut validator = Validator::default();
+    for (idx, ir) in irs.iter().enumerate() {
+        validator.visit(&format!("$[{}]", idx), ir);
+    }
+    if validator.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::from_str;
+
+    use super::validate;
+    use crate::GrassIR;
+
+    fn errors(input: &str) -> Vec<String> {
+        let ir: Vec<GrassIR> = from_str(input).unwrap();
+        validate(&ir)
+            .err()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    macro_rules! valid_test {
+        ($($path : expr),*) => {
+            $(
+                let ir: GrassIR = from_str(include_str!($path)).unwrap();
+                assert_eq!(validate(&[ir]), Ok(()), "{}", $path);
+            )*
+        };
+    }
+
+    #[test]
+    fn test_validate_examples() {
+        valid_test!(
+            "../../data/ir/aggregate.py.json",
+            "../../data/ir/bam-to-bed.py.json",
+            "../../data/ir/closest.py.json",
+            "../../data/ir/count.py.json",
+            "../../data/ir/coverage.py.json",
+            "../../data/ir/expand-interval.py.json",
+            "../../data/ir/filter.py.json",
+            "../../data/ir/flank.py.json",
+            "../../data/ir/genome_coverage.py.json",
+            "../../data/ir/getfasta.py.json",
+            "../../data/ir/inline_rust.py.json",
+            "../../data/ir/jaccard.py.json",
+            "../../data/ir/merge-aggregate.py.json",
+            "../../data/ir/merge.py.json",
+            "../../data/ir/pair-intersect.py.json",
+            "../../data/ir/regex.json",
+            "../../data/ir/shift.py.json",
+            "../../data/ir/slop.py.json",
+            "../../data/ir/sorted-intersect-custom-fmt.py.json",
+            "../../data/ir/sorted-intersect-group.py.json",
+            "../../data/ir/sorted-intersect-leftouter.py.json",
+            "../../data/ir/sorted-intersect-overlap-filter.py.json",
+            "../../data/ir/sorted-intersect.py.json",
+            "../../data/ir/sorted-intersect-split.py.json",
+            "../../data/ir/subtract.py.json",
+            "../../data/ir/vcf.json",
+            "../../data/ir/window.py.json"
+        );
+        let irs: Vec<GrassIR> = from_str(include_str!("../../data/ir/tee.json")).unwrap();
+        assert_eq!(validate(&irs), Ok(()));
+    }
+
+    const BED3: &str = r#"{"opcode": "Open", "target": {"CmdArg": 1}, "format": "Bed",
+        "num_of_fields": 3, "compression": false, "sorted": false}"#;
+
+    #[test]
+    fn test_validate_undefined_symbol() {
+        let undefined =
+            r#"[{"opcode": "WriteFile", "target": 1, "what": {"opcode": "Ref", "id": "x"}}]"#;
+        assert_eq!(
+            errors(undefined),
+            vec!["$[0].what: Reference to undefined symbol x"]
+        );
+    }
+
+    #[test]
+    fn test_validate_record_fields() {
+        let field = format!(
+            r#"[{{"opcode": "Filter", "inner": {}, "cond": {{"opcode": "Eq",
+                "lhs": {{"opcode": "FieldRef", "field": "seq"}},
+                "rhs": {{"opcode": "ConstValue", "value": "a"}}}}}}]"#,
+            BED3
+        );
+        assert_eq!(
+            errors(&field),
+            vec!["$[0].cond.lhs: Field seq doesn't exist on record type Bed(3)"]
+        );
+
+        let name = format!(
+            r#"[{{"opcode": "Filter", "inner": {}, "cond": {{"opcode": "Eq",
+                "lhs": {{"opcode": "FieldRef", "field": "name"}},
+                "rhs": {{"opcode": "ConstValue", "value": "a"}}}}}}]"#,
+            BED3
+        );
+        assert_eq!(
+            errors(&name),
+            vec!["$[0].cond.lhs: Field name doesn't exist on record type Bed(3)"]
+        );
+
+        let strand = format!(
+            r#"[{{"opcode": "Filter", "inner": {{"opcode": "CastToBed", "inner": {}, "num_of_fields": 5,
+                "sorted": false}}, "cond": {{"opcode": "Eq",
+                "lhs": {{"opcode": "FieldRef", "field": "strand"}},
+                "rhs": {{"opcode": "ConstValue", "value": "-"}}}}}}]"#,
+            BED3
+        );
+        assert_eq!(
+            errors(&strand),
+            vec!["$[0].cond.lhs: Field strand doesn't exist on record type Bed(5)"]
+        );
+
+        let cast = format!(
+            r#"[{{"opcode": "Filter", "inner": {{"opcode": "CastToBed", "inner": {}, "num_of_fields": 6,
+                "sorted": false}}, "cond": {{"opcode": "Eq",
+                "lhs": {{"opcode": "FieldRef", "field": "strand"}},
+                "rhs": {{"opcode": "ConstValue", "value": "-"}}}}}}]"#,
+            BED3
+        );
+        assert!(errors(&cast).is_empty());
+    }
+
+    #[test]
+    fn test_validate_field_types() {
+        let typed = format!(
+            r#"[{{"opcode": "Filter", "inner": {}, "cond": {{"opcode": "Add",
+                "lhs": {{"opcode": "FieldRef", "field": "start"}},
+                "rhs": {{"opcode": "ConstValue", "value": 1.5}}}}}},
+                {{"opcode": "Filter", "inner": {}, "cond": {{"opcode": "Eq",
+                "lhs": {{"opcode": "FieldRef", "field": "chrom"}},
+                "rhs": {{"opcode": "ConstValue", "value": 1}}}}}}]"#,
+            BED3, BED3
+        );
+        assert_eq!(
+            errors(&typed),
+            vec![
+                "$[0].cond: The filter condition should be a bool, but got float",
+                "$[1].cond: Unsupported operand types for Eq: str and int",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_aggregate() {
+        let aggregate = format!(
+            r#"[{{"opcode": "Aggregate", "inner": {}, "group": "overlap", "columns": [
+                {{"func": "collapse", "value": {{"opcode": "FieldRef", "field": "chrom"}}}},
+                {{"func": "sum", "value": {{"opcode": "FieldRef", "field": "chrom"}}}}]}}]"#,
+            BED3
+        );
+        assert_eq!(
+            errors(&aggregate),
+            vec![
+                "$[0]: Aggregate requires a sorted input",
+                "$[0].columns[1].value: The value of sum should be a number, but got str",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_resize() {
+        let resize = format!(
+            r#"[{{"opcode": "Slop", "inner": {}, "left": {{"bases": 10}},
+                "right": {{"fraction": -0.5}}, "sorted": true}},
+                {{"opcode": "Resize", "inner": {{"opcode": "Count", "what": {}, "keys": []}},
+                "size": {{"bases": 10}}, "anchor": "center", "sorted": false}}]"#,
+            BED3, BED3
+        );
+        assert_eq!(
+            errors(&resize),
+            vec![
+                "$[0]: Slop requires a sorted input",
+                "$[0]: The fraction of Slop should not be negative",
+                "$[1]: Resize only supports BED records, but got Count",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_subtract() {
+        let subtract = format!(
+            r#"[{{"opcode": "Subtract", "lhs": {{"opcode": "AssumeSorted", "inner": {}}},
+                "rhs": {}, "mode": "fragments", "min_fraction": 1.5}}]"#,
+            BED3, BED3
+        );
+        assert_eq!(
+            errors(&subtract),
+            vec![
+                "$[0].rhs: Subtract requires a sorted input",
+                "$[0]: The minimum fraction of Subtract should be between 0 and 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_coverage() {
+        let coverage = format!(
+            r#"[{{"opcode": "Coverage", "lhs": {{"opcode": "AssumeSorted", "inner": {}}},
+                "rhs": {{"opcode": "AssumeSorted", "inner": {}}},
+                "histogram": true, "by_strand": true}}]"#,
+            BED3, BED3
+        );
+        assert_eq!(
+            errors(&coverage),
+            vec!["$[0]: The depth histogram of Coverage can't be split by strand"]
+        );
+    }
+
+    #[test]
+    fn test_validate_genome_coverage() {
+        let genome_coverage = format!(
+            r#"[{{"opcode": "GenomeCoverage", "inner": {}, "report": "histogram", "scale": 2.0}}]"#,
+            BED3
+        );
+        assert_eq!(
+            errors(&genome_coverage),
+            vec![
+                "$[0]: GenomeCoverage requires a sorted input",
+                "$[0]: The histogram of GenomeCoverage can't include zero or be scaled",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_fisher() {
+        let fisher = format!(
+            r#"[{{"opcode": "Fisher", "lhs": {}, "rhs": {{"opcode": "AssumeSorted", "inner": {}}},
+                "genome_size": 0}}]"#,
+            BED3, BED3
+        );
+        assert_eq!(
+            errors(&fisher),
+            vec![
+                "$[0].lhs: Fisher requires a sorted input",
+                "$[0]: The genome size of Fisher should be positive",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_sortedness() {
+        let unsorted = format!(
+            r#"[{{"opcode": "Let", "id": "a", "value": {}}},
+                {{"opcode": "Intersection", "flavor": "inner", "sorted": true,
+                  "lhs": {{"opcode": "Ref", "id": "a"}},
+                  "rhs": {{"opcode": "AssumeSorted", "inner": {}}}}},
+                {{"opcode": "MergeOverlap", "inner": {}}}]"#,
+            BED3, BED3, BED3
+        );
+        assert_eq!(
+            errors(&unsorted),
+            vec![
+                "$[1].lhs: Intersection requires a sorted input",
+                "$[2]: MergeOverlap requires a sorted input",
+            ]
+        );
+    }
+}
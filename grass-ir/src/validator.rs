@@ -1,12 +1,34 @@
-use std::{env::args, fs::File};
+use std::{env::args, fs::File, process::exit};
 
-use grass_ir::GrassIR;
+use grass_ir::{validate, GrassIR};
+use serde::Deserialize;
 use serde_json::from_reader;
 
+/// A file holds either a single GrassIR or an array of them
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IrFile {
+    Single(GrassIR),
+    Multiple(Vec<GrassIR>),
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut has_error = false;
     for path in args().skip(1) {
-        let fp = File::open(path)?;
-        let _ir: GrassIR = from_reader(fp)?;
+        let fp = File::open(&path)?;
+        let ir = match from_reader(fp)? {
+            IrFile::Single(ir) => vec![ir],
+            IrFile::Multiple(ir) => ir,
+        };
+        if let Err(errors) = validate(&ir) {
+            for error in errors {
+                eprintln!("{}: {}", path, error);
+            }
+            has_error = true;
+        }
+    }
+    if has_error {
+        exit(1);
     }
     Ok(())
 }
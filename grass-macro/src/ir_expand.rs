@@ -5,15 +5,23 @@ use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use uuid::adapter::Simple;
 
+pub use self::field_expr::expand_field_expr;
 use self::format::expand_write_record_rec;
 
+mod aggregate;
 mod alter;
 mod assign_tag;
+mod attach_seq;
 mod assume_sorted;
 mod cast;
+mod closest;
+mod coverage;
+mod genome_cov;
+mod count;
 mod field_expr;
 mod filter;
 mod format;
+mod group_by;
 mod inline_rust;
 mod internal_sort;
 mod intersect;
@@ -23,8 +31,13 @@ mod load_genome;
 mod merge_overlap;
 mod nop;
 mod open;
+mod pair_intersect;
 mod random;
+mod resize;
+mod stats;
+mod subtract;
 mod twoway_merge;
+mod window;
 mod write;
 mod limit;
 
@@ -33,14 +46,29 @@ pub fn expand_grass_ir(ir: &GrassIR, ctx: &mut ExpansionContext) -> ExpandResult
         GrassIR::Open(open_param) => open_param.expand(ctx),
         GrassIR::WriteFile(write_param) => match write_param.what.as_ref() {
             GrassIR::Let(param) => match param.value.as_ref() {
-                GrassIR::Format(param) => expand_write_record_rec(param, &write_param.target, ctx),
+                GrassIR::Format(format) if ctx.ref_count(&param.id) == 0 => {
+                    expand_write_record_rec(format, &write_param.target, ctx)
+                }
                 _ => write_param.expand(ctx),
             },
             GrassIR::Format(param) => expand_write_record_rec(param, &write_param.target, ctx),
             _ => write_param.expand(ctx),
         },
         GrassIR::Let(param) => param.expand(ctx),
+        GrassIR::Ref(param) => param.expand(ctx),
         GrassIR::Intersection(param) => param.expand(ctx),
+        GrassIR::PairIntersection(param) => param.expand(ctx),
+        GrassIR::Closest(param) => param.expand(ctx),
+        GrassIR::Window(param) => param.expand(ctx),
+        GrassIR::Coverage(param) => param.expand(ctx),
+        GrassIR::GenomeCoverage(param) => param.expand(ctx),
+        GrassIR::Jaccard(param) => param.expand(ctx),
+        GrassIR::Fisher(param) => param.expand(ctx),
+        GrassIR::RelativeDistance(param) => param.expand(ctx),
+        GrassIR::Slop(param) => param.expand(ctx),
+        GrassIR::Flank(param) => param.expand(ctx),
+        GrassIR::Resize(param) => param.expand(ctx),
+        GrassIR::Subtract(param) => param.expand(ctx),
         GrassIR::Filter(param) => param.expand(ctx),
         GrassIR::Alter(param) => param.expand(ctx),
         GrassIR::AssumeSorted(param) => param.expand(ctx),
@@ -55,17 +83,22 @@ pub fn expand_grass_ir(ir: &GrassIR, ctx: &mut ExpansionContext) -> ExpandResult
         GrassIR::AssignTag(param) => param.expand(ctx),
         GrassIR::TwoWayMerge(param) => param.expand(ctx),
         GrassIR::Limit(param) => param.expand(ctx),
+        GrassIR::GroupBy(param) => param.expand(ctx),
+        GrassIR::Count(param) => param.expand(ctx),
+        GrassIR::Aggregate(param) => param.expand(ctx),
+        GrassIR::AttachSequence(param) => param.expand(ctx),
         _ => panic!("Unimplemented IR {}", serde_json::to_string(ir).unwrap()),
     }
 }
 
 pub type TempVar = Simple;
 
-#[allow(dead_code)]
 pub struct ExpansionContext {
     span: Span,
     code_fragments: Vec<TokenStream>,
     symbol_table: HashMap<String, TempVar>,
+    ref_counts: HashMap<String, usize>,
+    tees: Vec<TempVar>,
 }
 
 impl ExpansionContext {
@@ -74,8 +107,22 @@ impl ExpansionContext {
             span,
             code_fragments: Vec::new(),
             symbol_table: HashMap::new(),
+            ref_counts: HashMap::new(),
+            tees: Vec::new(),
         }
     }
+    /// Count the references to each symbol in the IR, this should be done before the expansion
+    pub fn count_refs(&mut self, ir: &GrassIR) {
+        if let GrassIR::Ref(param) = ir {
+            *self.ref_counts.entry(param.id.clone()).or_default() += 1;
+        }
+        for child in ir.children() {
+            self.count_refs(child);
+        }
+    }
+    pub fn ref_count(&self, id: &str) -> usize {
+        self.ref_counts.get(id).copied().unwrap_or(0)
+    }
     pub fn push(&mut self, expr: TokenStream) -> TempVar {
         let uuid = uuid::Uuid::new_v4().to_simple();
         let fresh_id = self.get_var_ref(&uuid);
@@ -86,13 +133,28 @@ impl ExpansionContext {
         self.code_fragments.push(code);
         uuid
     }
+    /// Drop a value that nothing consumes, for example the branch of a tee that would otherwise
+    /// hold all the records until the end of the query
+    pub fn drop_var(&mut self, id: &TempVar) {
+        let var = self.get_var_ref(id);
+        self.code_fragments.push(quote! {
+            drop(#var);
+        });
+    }
+    /// Register a tee, whose IO error is reported after all the code of the query
+    pub fn add_tee(&mut self, id: TempVar) {
+        self.tees.push(id);
+    }
     pub fn get_var_ref(&self, id: &TempVar) -> syn::Ident {
         syn::Ident::new(&format!("_grass_query_temp_{}", id), self.span)
     }
     pub fn to_token_stream(&self) -> TokenStream {
         let fragments = self.code_fragments.as_slice();
+        let tees = self.tees.iter().map(|id| self.get_var_ref(id));
         quote! {
             #(#fragments)*
+            #(#tees.check()?;)*
+            grass_runtime::builtin::take_error()?;
         }
     }
     pub fn span(&self) -> Span {
@@ -0,0 +1,86 @@
+use grass_ir::{AggregateFunction, AggregateGroup, AggregateParam, FieldType};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+use super::{
+    expand_grass_ir,
+    field_expr::{expand_field_expr, expand_field_expr_as},
+    Expand, ExpandResult, ExpansionContext,
+};
+
+fn expand_func(func: AggregateFunction) -> Ident {
+    let name = match func {
+        AggregateFunction::Count => "Count",
+        AggregateFunction::CountDistinct => "CountDistinct",
+        AggregateFunction::Sum => "Sum",
+        AggregateFunction::Mean => "Mean",
+        AggregateFunction::Median => "Median",
+        AggregateFunction::Min => "Min",
+        AggregateFunction::Max => "Max",
+        AggregateFunction::Mode => "Mode",
+        AggregateFunction::Stdev => "Stdev",
+        AggregateFunction::Collapse => "Collapse",
+        AggregateFunction::Distinct => "Distinct",
+        AggregateFunction::First => "First",
+        AggregateFunction::Last => "Last",
+    };
+    Ident::new(name, Span::call_site())
+}
+
+impl Expand for AggregateParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let inner = expand_grass_ir(self.expr.as_ref(), ctx)?;
+        let inner_id = ctx.get_var_ref(&inner);
+
+        let funcs: Vec<_> = self
+            .columns
+            .iter()
+            .map(|col| expand_func(col.func))
+            .collect();
+        let pushes: Vec<TokenStream> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(idx, col)| {
+                // The numeric functions take floats, the others take the values as they are
+                let value = if col.func.is_numeric() {
+                    expand_field_expr_as(&col.value, FieldType::Float, ctx.span())?
+                } else {
+                    expand_field_expr(&col.value, ctx.span())?
+                };
+                Ok(quote! { aggs[#idx].push(Some(item).map(#value).unwrap()); })
+            })
+            .collect::<Result<_, syn::Error>>()?;
+        let push = quote! {
+            |item, aggs: &mut [Aggregator]| {
+                #(#pushes)*
+            }
+        };
+        let funcs = quote! { [#(AggregateFunc::#funcs),*] };
+
+        let code = match &self.group {
+            AggregateGroup::Keys(keys) => {
+                let keys: Vec<_> = keys
+                    .iter()
+                    .map(|key| {
+                        let key = expand_field_expr(key, ctx.span())?;
+                        Ok(quote! { Some(item).map(#key).unwrap().to_owned() })
+                    })
+                    .collect::<Result<_, syn::Error>>()?;
+                quote! {
+                    {
+                        use grass_runtime::algorithm::{AggregateExt, AggregateFunc, Aggregator};
+                        #inner_id . aggregate_by(|item| (#(#keys,)*), #funcs, #push)
+                    }
+                }
+            }
+            AggregateGroup::Overlap => quote! {
+                {
+                    use grass_runtime::algorithm::{AggregateExt, AggregateFunc, Aggregator};
+                    #inner_id . aggregate_overlaps(#funcs, #push)
+                }
+            },
+        };
+        Ok(ctx.push(code))
+    }
+}
@@ -1,8 +1,8 @@
-use grass_ir::AlterParam;
+use grass_ir::{AlterParam, FieldType};
 use quote::quote;
 
 use super::{
-    expand_grass_ir, field_expr::expand_field_expr, Expand, ExpandResult, ExpansionContext,
+    expand_grass_ir, field_expr::{expand_field_expr, expand_field_expr_as}, Expand, ExpandResult, ExpansionContext,
 };
 
 impl Expand for AlterParam {
@@ -12,7 +12,13 @@ impl Expand for AlterParam {
 
         let setter_id = syn::Ident::new(&format!("set_{}", self.field), ctx.span());
 
-        let value = expand_field_expr(&self.value, ctx.span());
+        // The setters of the coordinates and the score take floats
+        let value = match FieldType::of_field(&self.field) {
+            Some(FieldType::Int | FieldType::Float) => {
+                expand_field_expr_as(&self.value, FieldType::Float, ctx.span())?
+            }
+            _ => expand_field_expr(&self.value, ctx.span())?,
+        };
 
         let post_steps = if self.sorted {
             quote! { let ret = ret.assume_sorted(); }
@@ -0,0 +1,40 @@
+use grass_ir::{AttachSequenceParam, ConstOrEnv};
+use proc_macro2::Ident;
+use quote::quote;
+
+use super::{expand_grass_ir, Expand, ExpandResult, ExpansionContext};
+
+impl Expand for AttachSequenceParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let inner_id = expand_grass_ir(&self.inner, ctx)?;
+        let inner = ctx.get_var_ref(&inner_id);
+        let path = match &self.reference {
+            ConstOrEnv::Const(path) => quote! { #path },
+            ConstOrEnv::Env(key) => {
+                let id = Ident::new(&key.get_const_bag_ident(), ctx.span());
+                quote! { #id.value() }
+            }
+        };
+        let reference = ctx.push(quote! {
+            {
+                use grass_runtime::record::FastaReference;
+                FastaReference::open(#path)?
+            }
+        });
+        let reference = ctx.get_var_ref(&reference);
+        let post_steps = if self.sorted {
+            quote! { let result = result.assume_sorted(); }
+        } else {
+            quote! { (); }
+        };
+        let code = quote! {
+            {
+                use grass_runtime::algorithm::AssumeSorted;
+                let result = #reference.attach_iter(#inner);
+                #post_steps;
+                result
+            }
+        };
+        Ok(ctx.push(code))
+    }
+}
@@ -0,0 +1,39 @@
+use grass_ir::{ClosestDirection, ClosestParam, ClosestTies};
+use quote::quote;
+
+use super::{expand_grass_ir, Expand, ExpandResult, ExpansionContext};
+
+impl Expand for ClosestParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let left = expand_grass_ir(self.lhs.as_ref(), ctx)?;
+        let right = expand_grass_ir(self.rhs.as_ref(), ctx)?;
+        let left_token = ctx.get_var_ref(&left);
+        let right_token = ctx.get_var_ref(&right);
+        let ties = match self.ties {
+            ClosestTies::All => quote! { ClosestTies::All },
+            ClosestTies::First => quote! { ClosestTies::First },
+            ClosestTies::Last => quote! { ClosestTies::Last },
+        };
+        let direction = match self.direction {
+            ClosestDirection::Both => quote! { ClosestDirection::Both },
+            ClosestDirection::Upstream => quote! { ClosestDirection::Upstream },
+            ClosestDirection::Downstream => quote! { ClosestDirection::Downstream },
+        };
+        let ignore_overlaps = self.ignore_overlaps;
+        let k = self.k as usize;
+        Ok(ctx.push(quote! {
+            {
+                use grass_runtime::algorithm::{
+                    ClosestDirection, ClosestOptions, ClosestTies, SortedClosest,
+                };
+                let options = ClosestOptions {
+                    ties: #ties,
+                    direction: #direction,
+                    ignore_overlaps: #ignore_overlaps,
+                    k: #k,
+                };
+                #left_token . sorted_closest(#right_token, options)
+            }
+        }))
+    }
+}
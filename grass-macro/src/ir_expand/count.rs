@@ -0,0 +1,38 @@
+use grass_ir::CountParam;
+use quote::quote;
+
+use super::{
+    expand_grass_ir, field_expr::expand_field_expr, Expand, ExpandResult, ExpansionContext,
+};
+
+impl Expand for CountParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let inner = expand_grass_ir(self.what.as_ref(), ctx)?;
+        let inner_id = ctx.get_var_ref(&inner);
+
+        if self.keys.is_empty() {
+            return Ok(ctx.push(quote! {
+                {
+                    use grass_runtime::algorithm::CountExt;
+                    #inner_id . count_records()
+                }
+            }));
+        }
+
+        let keys: Vec<_> = self
+            .keys
+            .iter()
+            .map(|key| {
+                let key = expand_field_expr(key, ctx.span())?;
+                Ok(quote! { Some(item).map(#key).unwrap().to_owned() })
+            })
+            .collect::<Result<_, syn::Error>>()?;
+
+        Ok(ctx.push(quote! {
+            {
+                use grass_runtime::algorithm::CountExt;
+                #inner_id . count_by(|item| (#(#keys,)*))
+            }
+        }))
+    }
+}
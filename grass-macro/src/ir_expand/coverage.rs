@@ -0,0 +1,30 @@
+use grass_ir::CoverageParam;
+use quote::quote;
+
+use super::{expand_grass_ir, Expand, ExpandResult, ExpansionContext};
+
+impl Expand for CoverageParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let left = expand_grass_ir(self.lhs.as_ref(), ctx)?;
+        let right = expand_grass_ir(self.rhs.as_ref(), ctx)?;
+        let left_token = ctx.get_var_ref(&left);
+        let right_token = ctx.get_var_ref(&right);
+        let by_strand = self.by_strand;
+        let code = if self.histogram {
+            quote! { #left_token . sorted_depth_histogram(#right_token) }
+        } else {
+            quote! {
+                #left_token . sorted_coverage(
+                    #right_token,
+                    grass_runtime::algorithm::CoverageOptions { by_strand: #by_strand },
+                )
+            }
+        };
+        Ok(ctx.push(quote! {
+            {
+                use grass_runtime::algorithm::SortedCoverage;
+                #code
+            }
+        }))
+    }
+}
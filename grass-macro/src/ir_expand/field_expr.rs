@@ -1,229 +1,412 @@
-use grass_ir::{ConstOrEnv, ConstValue, FieldExpression};
-use proc_macro2::{Span, TokenStream};
+use grass_ir::{
+    BinaryParam, CallParam, ConstOrEnv, ConstValue, FieldExpression, FieldType, Function,
+};
+use proc_macro2::{Literal, Span, TokenStream};
 use quote::quote;
 
-pub fn expand_field_expr(expr: &FieldExpression, span: Span) -> TokenStream {
-    let mut env_const_defs = Vec::new();
-    let expr = expand_field_expr_impl(expr, span, &mut env_const_defs);
-    quote! {
+/// Expand the field expression to a closure, which takes a reference to the record
+pub fn expand_field_expr(expr: &FieldExpression, span: Span) -> Result<TokenStream, syn::Error> {
+    let ty = infer_type(expr, span)?;
+    expand_field_expr_as(expr, ty, span)
+}
+
+/// Expand the field expression to a closure and convert the result to the given type
+pub fn expand_field_expr_as(
+    expr: &FieldExpression,
+    ty: FieldType,
+    span: Span,
+) -> Result<TokenStream, syn::Error> {
+    let mut lowering = Lowering {
+        span,
+        env_const_defs: Vec::new(),
+    };
+    let (expr, expr_ty) = lowering.expand(expr)?;
+    let expr = cast(expr, expr_ty, ty, span)?;
+    let env_const_defs = lowering.env_const_defs;
+    Ok(quote! {
         {
             #(#env_const_defs)*
             move |_arg| {
                 #expr
             }
         }
-    }
+    })
 }
 
-fn expand_field_expr_impl(expr: &FieldExpression, span: Span, env_const_defs: &mut Vec<TokenStream>) -> TokenStream {
-    match expr {
-        FieldExpression::And(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) & (#rhs)}
-        }
-        FieldExpression::Or(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) | (#rhs)}
-        }
-        FieldExpression::Xor(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) ^ (#rhs)}
-        }
-        FieldExpression::Not(param) => {
-            let inner = expand_field_expr_impl(param.operand.as_ref(), span, env_const_defs);
-            quote! {!(#inner)}
-        }
-        FieldExpression::Add(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) + (#rhs)}
-        }
-        FieldExpression::Sub(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) - (#rhs)}
-        }
-        FieldExpression::Mul(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) * (#rhs)}
-        }
-        FieldExpression::Div(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) / (#rhs)}
-        }
-        FieldExpression::Mod(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) % (#rhs)}
-        }
-        FieldExpression::Eq(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) == (#rhs)}
-        }
-        FieldExpression::Ne(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) != (#rhs)}
-        }
-        FieldExpression::LessThan(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) < (#rhs)}
-        }
-        FieldExpression::GreaterThan(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) > (#rhs)}
+fn infer_type(expr: &FieldExpression, span: Span) -> Result<FieldType, syn::Error> {
+    expr.infer_type()
+        .map_err(|e| syn::Error::new(span, format!("Type error in field expression: {}", e)))
+}
+
+/// Borrow a string typed value as `&str`, the string values are either `&str` or `String`
+fn as_str(value: TokenStream) -> TokenStream {
+    quote! { AsRef::<str>::as_ref(&(#value)) }
+}
+
+/// Convert a value from one type to another, the conversion should be allowed by
+/// `FieldType::can_cast_to`
+fn cast(
+    value: TokenStream,
+    from: FieldType,
+    to: FieldType,
+    span: Span,
+) -> Result<TokenStream, syn::Error> {
+    let code = match (from, to) {
+        (from, to) if from == to => value,
+        (FieldType::Int, FieldType::Float) => quote! { ((#value) as f64) },
+        (FieldType::Float, FieldType::Int) | (FieldType::Bool, FieldType::Int) => {
+            quote! { ((#value) as i64) }
         }
-        FieldExpression::LessEqualThan(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) >= (#rhs)}
+        (FieldType::Bool, FieldType::Float) => quote! { ((#value) as i64 as f64) },
+        (FieldType::Int, FieldType::Bool) => quote! { ((#value) != 0) },
+        (FieldType::Float, FieldType::Bool) => quote! { ((#value) != 0.0) },
+        (FieldType::Str, FieldType::Int) => {
+            let text = as_str(value);
+            quote! { grass_runtime::builtin::cast_str::<i64>(#text, "i64", 0) }
         }
-        FieldExpression::GreaterEqualThan(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) <= (#rhs)}
+        (FieldType::Str, FieldType::Float) => {
+            let text = as_str(value);
+            quote! { grass_runtime::builtin::cast_str::<f64>(#text, "f64", f64::NAN) }
         }
-        FieldExpression::RightShift(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) << (#rhs)}
+        (FieldType::Record, FieldType::Str) => quote! {
+            {
+                use grass_runtime::property::*;
+                let mut buffer = Vec::new();
+                (#value) . dump(&mut buffer).unwrap();
+                String::from_utf8(buffer).unwrap()
+            }
+        },
+        (_, FieldType::Str) => quote! { (#value).to_string() },
+        (from, to) => {
+            let message = format!("Unable to cast {} to {}", from, to);
+            return Err(syn::Error::new(span, message));
         }
-        FieldExpression::LeftShift(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            quote! {(#lhs) >> (#rhs)}
+    };
+    Ok(code)
+}
+
+/// Read a field of the record, `target` is an expression that evaluates to a reference to the
+/// record
+fn field_access(target: TokenStream, field: &str, span: Span) -> TokenStream {
+    let method = syn::Ident::new(field, span);
+    let value = match field {
+        "start" | "end" | "length" | "size" | "count" | "covered" | "depth" | "forward_count"
+        | "reverse_count" | "intersection" | "union" | "n_intersections" | "total" => {
+            quote! { (#target . #method () as i64) }
         }
-        FieldExpression::Neg(param) => {
-            let operand = expand_field_expr_impl(param.operand.as_ref(), span, env_const_defs);
-            quote! { !(#operand) }
+        "score" => quote! { (#target . score() . unwrap_or_default() as f64) },
+        "chrom" => quote! { #target . chrom() . get_chr_name() },
+        "strand" => quote! { #target . strand() . as_str() },
+        _ => quote! { #target . #method () },
+    };
+    quote! {
+        ({
+            use grass_runtime::property::*;
+            #value
+        })
+    }
+}
+
+struct Lowering {
+    span: Span,
+    env_const_defs: Vec<TokenStream>,
+}
+
+impl Lowering {
+    /// Expand both operands and convert them to the given type
+    fn expand_operands(
+        &mut self,
+        param: &BinaryParam,
+        ty: FieldType,
+    ) -> Result<(TokenStream, TokenStream), syn::Error> {
+        let (lhs, lhs_ty) = self.expand(&param.lhs)?;
+        let (rhs, rhs_ty) = self.expand(&param.rhs)?;
+        Ok((
+            cast(lhs, lhs_ty, ty, self.span)?,
+            cast(rhs, rhs_ty, ty, self.span)?,
+        ))
+    }
+
+    fn expand_compare(
+        &mut self,
+        param: &BinaryParam,
+        op: TokenStream,
+    ) -> Result<TokenStream, syn::Error> {
+        let ty = infer_type(&param.lhs, self.span)?;
+        let ty = ty.unify(infer_type(&param.rhs, self.span)?).unwrap_or(ty);
+        let (lhs, rhs) = self.expand_operands(param, ty)?;
+        if ty == FieldType::Str {
+            let lhs = as_str(lhs);
+            let rhs = as_str(rhs);
+            Ok(quote! { (#lhs #op #rhs) })
+        } else {
+            Ok(quote! { ((#lhs) #op (#rhs)) })
         }
-        FieldExpression::Cond(param) => {
-            let cond = expand_field_expr_impl(param.cond.as_ref(), span, env_const_defs);
-            let then = expand_field_expr_impl(param.then.as_ref(), span, env_const_defs);
-            let elze = expand_field_expr_impl(param.elze.as_ref(), span, env_const_defs);
-            quote! { if #cond {#then} else {#elze} }
+    }
+
+    fn expand_call(&mut self, param: &CallParam) -> Result<TokenStream, syn::Error> {
+        let arg_types = param
+            .args
+            .iter()
+            .map(|arg| infer_type(arg, self.span))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (param_types, ret) = param
+            .func
+            .signature(&arg_types)
+            .map_err(|e| syn::Error::new(self.span, e))?;
+        let mut args = Vec::new();
+        for (arg, ty) in param.args.iter().zip(param_types) {
+            let (arg, arg_ty) = self.expand(arg)?;
+            let arg = cast(arg, arg_ty, ty, self.span)?;
+            args.push(if ty == FieldType::Str {
+                as_str(arg)
+            } else {
+                arg
+            });
         }
-        FieldExpression::RegexMatch(param) => {
-            let lhs = expand_field_expr_impl(param.lhs.as_ref(), span, env_const_defs);
-            let rhs = expand_field_expr_impl(param.rhs.as_ref(), span, env_const_defs);
-            match param.rhs.as_ref() {
-                FieldExpression::ConstValue(_) => {
-                    let regex_id = syn::Ident::new(&format!("__local_regex_{}", env_const_defs.len()), span);
-                    env_const_defs.push(quote!{ 
-                        let #regex_id = grass_runtime::Regex::new(#rhs).unwrap(); 
-                    });
-                    quote!{ #regex_id . is_match(#lhs) }
-                }
-                _ => {
-                    // FIXME: Once we are in this case, we just do a simple contain
-                    // But this is not correct
-                    quote! { (#lhs).contains(#rhs) }
+        let func = syn::Ident::new(param.func.name(), self.span);
+        let code = match (param.func, args.as_slice()) {
+            (Function::Substr, [text, start]) => {
+                quote! { grass_runtime::builtin::substr(#text, #start, None) }
+            }
+            (Function::Substr, [text, start, len]) => {
+                quote! { grass_runtime::builtin::substr(#text, #start, Some(#len)) }
+            }
+            (Function::Concat, parts) => quote! { grass_runtime::builtin::concat(&[#(#parts),*]) },
+            (Function::ParseInt, [text]) => quote! { grass_runtime::builtin::parse_int(#text, 0) },
+            (Function::ParseFloat, [text]) => {
+                quote! { grass_runtime::builtin::parse_float(#text, f64::NAN) }
+            }
+            (
+                Function::SplitNth
+                | Function::Replace
+                | Function::Upper
+                | Function::Lower
+                | Function::StartsWith
+                | Function::ParseInt
+                | Function::ParseFloat,
+                args,
+            ) => quote! { grass_runtime::builtin::#func(#(#args),*) },
+            (Function::Abs, [value]) if ret == FieldType::Int => {
+                quote! { (#value).saturating_abs() }
+            }
+            (Function::Abs, [value]) => quote! { (#value).abs() },
+            (Function::Min | Function::Max, [first, rest @ ..]) => {
+                quote! { (#first) #(.#func(#rest))* }
+            }
+            (Function::Round, [value]) => quote! { ((#value).round() as i64) },
+            (Function::Floor, [value]) => quote! { ((#value).floor() as i64) },
+            (Function::Log, [value]) => quote! { (#value).ln() },
+            (Function::Log, [value, base]) => quote! { (#value).log(#base) },
+            (Function::Exp, [value]) => quote! { (#value).exp() },
+            (Function::Pow, [value, exp]) => quote! { (#value).powf(#exp) },
+            (func, args) => unreachable!("{} with {} arguments", func.name(), args.len()),
+        };
+        Ok(code)
+    }
+
+    fn expand(&mut self, expr: &FieldExpression) -> Result<(TokenStream, FieldType), syn::Error> {
+        let ty = infer_type(expr, self.span)?;
+        let code = match expr {
+            FieldExpression::And(param) => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! {((#lhs) & (#rhs))}
+            }
+            FieldExpression::Or(param) => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! {((#lhs) | (#rhs))}
+            }
+            FieldExpression::Xor(param) => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! {((#lhs) ^ (#rhs))}
+            }
+            // This is a logic not for booleans and a bitwise not for integers
+            FieldExpression::Not(param) => {
+                let (operand, _) = self.expand(&param.operand)?;
+                quote! {(!(#operand))}
+            }
+            FieldExpression::Neg(param) if ty == FieldType::Int => {
+                let (operand, _) = self.expand(&param.operand)?;
+                quote! { grass_runtime::builtin::neg(#operand) }
+            }
+            FieldExpression::Neg(param) => {
+                let (operand, _) = self.expand(&param.operand)?;
+                quote! {(-(#operand))}
+            }
+            FieldExpression::Add(param) if ty == FieldType::Str => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! {format!("{}{}", #lhs, #rhs)}
+            }
+            // The integer operators report an overflow rather than panicking, the same way as
+            // the interpreter
+            FieldExpression::Add(param) if ty == FieldType::Int => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! { grass_runtime::builtin::add(#lhs, #rhs) }
+            }
+            FieldExpression::Add(param) => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! {((#lhs) + (#rhs))}
+            }
+            FieldExpression::Sub(param) if ty == FieldType::Int => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! { grass_runtime::builtin::sub(#lhs, #rhs) }
+            }
+            FieldExpression::Sub(param) => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! {((#lhs) - (#rhs))}
+            }
+            FieldExpression::Mul(param) if ty == FieldType::Int => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! { grass_runtime::builtin::mul(#lhs, #rhs) }
+            }
+            FieldExpression::Mul(param) => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! {((#lhs) * (#rhs))}
+            }
+            FieldExpression::Div(param) if ty == FieldType::Int => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! { grass_runtime::builtin::div(#lhs, #rhs) }
+            }
+            FieldExpression::Div(param) => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! {((#lhs) / (#rhs))}
+            }
+            FieldExpression::Mod(param) if ty == FieldType::Int => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! { grass_runtime::builtin::rem(#lhs, #rhs) }
+            }
+            FieldExpression::Mod(param) => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! {((#lhs) % (#rhs))}
+            }
+            FieldExpression::LeftShift(param) => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! {(#lhs).wrapping_shl((#rhs) as u32)}
+            }
+            FieldExpression::RightShift(param) => {
+                let (lhs, rhs) = self.expand_operands(param, ty)?;
+                quote! {(#lhs).wrapping_shr((#rhs) as u32)}
+            }
+            FieldExpression::Eq(param) => self.expand_compare(param, quote! {==})?,
+            FieldExpression::Ne(param) => self.expand_compare(param, quote! {!=})?,
+            FieldExpression::LessThan(param) => self.expand_compare(param, quote! {<})?,
+            FieldExpression::GreaterThan(param) => self.expand_compare(param, quote! {>})?,
+            FieldExpression::LessEqualThan(param) => self.expand_compare(param, quote! {<=})?,
+            FieldExpression::GreaterEqualThan(param) => self.expand_compare(param, quote! {>=})?,
+            FieldExpression::Cond(param) => {
+                let (cond, _) = self.expand(&param.cond)?;
+                let (then, then_ty) = self.expand(&param.then)?;
+                let (elze, elze_ty) = self.expand(&param.elze)?;
+                let (then, elze) = if ty == FieldType::Str {
+                    // The branches may borrow or own the string, so both of them are copied
+                    (
+                        quote! { (#then).to_string() },
+                        quote! { (#elze).to_string() },
+                    )
+                } else {
+                    (
+                        cast(then, then_ty, ty, self.span)?,
+                        cast(elze, elze_ty, ty, self.span)?,
+                    )
+                };
+                quote! { (if #cond {#then} else {#elze}) }
+            }
+            FieldExpression::RegexMatch(param) => {
+                let (lhs, _) = self.expand(&param.lhs)?;
+                let (rhs, _) = self.expand(&param.rhs)?;
+                let text = as_str(lhs);
+                match param.rhs.as_ref() {
+                    // The constant patterns are compiled only once
+                    FieldExpression::ConstValue(_) => {
+                        let regex_id = syn::Ident::new(
+                            &format!("__local_regex_{}", self.env_const_defs.len()),
+                            self.span,
+                        );
+                        let pattern = as_str(rhs);
+                        self.env_const_defs.push(quote! {
+                            let #regex_id = grass_runtime::builtin::regex(#pattern);
+                        });
+                        quote! { grass_runtime::builtin::regex_match(&#regex_id, #text) }
+                    }
+                    _ => {
+                        let pattern = as_str(rhs);
+                        quote! {
+                            grass_runtime::builtin::regex_match(
+                                &grass_runtime::builtin::regex(#pattern),
+                                #text,
+                            )
+                        }
+                    }
                 }
             }
-        }
-        FieldExpression::FieldRef(param) => {
-            let p = syn::Ident::new(param.field.as_str(), span);
-            match param.field.as_str() {
-                "start" | "end" => quote! {
-                    ({
-                        use grass_runtime::property::*;
-                        _arg . #p ()
-                    } as f64)
-                },
-                "score" => quote! {
-                    ({
-                        use grass_runtime::property::*;
-                        _arg . #p () .unwrap_or_default()
-                    })
-                },
-                _ => quote! {
+            FieldExpression::FieldRef(param) => {
+                field_access(quote! { _arg }, &param.field, self.span)
+            }
+            FieldExpression::NumberOfComponents => {
+                quote! {
                     ({
                         use grass_runtime::property::*;
-                        _arg . #p ()
+                        _arg.size() as i64
                     })
                 }
             }
-        }
-        FieldExpression::NumberOfComponents => {
-            quote! {
-                ({
-                    use grass_runtime::property::*;
-                    _arg.size()
-                })
+            FieldExpression::ComponentFieldRef(param) => {
+                let comp_idx = syn::LitInt::new(&format!("{}", param.target), self.span);
+                let target = quote! { RecordAt::<#comp_idx>::record_at(_arg) };
+                field_access(target, &param.field_name, self.span)
             }
-        }
-        FieldExpression::ComponentFieldRef(param) => {
-            let field_name = syn::Ident::new(param.field_name.as_str(), span);
-            let comp_idx = syn::LitInt::new(&format!("{}", param.target), span);
-            if param.field_name != "start" && param.field_name != "end" {
+            FieldExpression::ConstValue(param) => match &param.value {
+                ConstOrEnv::Const(ConstValue::Float(value)) => {
+                    let tk = Literal::f64_suffixed(*value);
+                    quote! { (#tk) }
+                }
+                ConstOrEnv::Const(ConstValue::Number(value)) => {
+                    let tk = Literal::i64_suffixed(*value);
+                    quote! { (#tk) }
+                }
+                ConstOrEnv::Const(ConstValue::Str(value)) => {
+                    let tk = syn::LitStr::new(value, self.span);
+                    quote! { #tk }
+                }
+                ConstOrEnv::Env(key) => {
+                    let global_tk = syn::Ident::new(&key.get_const_bag_ident(), self.span);
+                    let local_tk = syn::Ident::new(
+                        &format!("__env_const_ref_{}", self.env_const_defs.len()),
+                        self.span,
+                    );
+                    self.env_const_defs
+                        .push(quote! { let #local_tk = #global_tk.value(); });
+                    // The numbers in the constant bag are stored as floats
+                    match ty {
+                        FieldType::Int => quote! { (#local_tk as i64) },
+                        _ => quote! { #local_tk },
+                    }
+                }
+            },
+            FieldExpression::FullRecordRef => {
                 quote! {
-                    ({
-                        use grass_runtime::property::*;
-                        _arg. #comp_idx . #field_name ()
-                    })
+                    &_arg
                 }
-            } else {
+            }
+            FieldExpression::RecordRef(param) => {
+                let id = syn::LitInt::new(&format!("{}", param.id), self.span);
                 quote! {
                     ({
                         use grass_runtime::property::*;
-                        _arg. #comp_idx . #field_name ()
-                    } as f64)
+                        RecordAt::<#id>::record_at(_arg)
+                    })
                 }
             }
-        }
-        FieldExpression::ConstValue(param) => match &param.value {
-            ConstOrEnv::Const(ConstValue::Float(value)) => {
-                let tk = syn::LitFloat::new(&format!("{}f64", value), span);
-                quote! { #tk }
-            }
-            ConstOrEnv::Const(ConstValue::Number(value)) => {
-                let tk = syn::LitFloat::new(&format!("{}f64", value), span);
-                quote! { #tk }
-            }
-            ConstOrEnv::Const(ConstValue::Str(value)) => {
-                let tk = syn::LitStr::new(value, span);
-                quote! { #tk }
+            FieldExpression::StringRepr(param) => {
+                let (inner, inner_ty) = self.expand(&param.value)?;
+                cast(inner, inner_ty, FieldType::Str, self.span)?
             }
-            ConstOrEnv::Env(key) => {
-                let global_tk = syn::Ident::new(&key.get_const_bag_ident(), span);
-                let local_tk = syn::Ident::new(&format!("__env_const_ref_{}", env_const_defs.len()), span);
-                env_const_defs.push(quote! { let #local_tk = #global_tk.value(); });
-                quote! { #local_tk }
+            FieldExpression::Cast(param) => {
+                let (inner, inner_ty) = self.expand(&param.value)?;
+                cast(inner, inner_ty, param.to, self.span)?
             }
-        },
-        FieldExpression::FullRecordRef => {
-            quote! {
-                &_arg
-            }
-        }
-        FieldExpression::RecordRef(param) => {
-            let id = syn::LitInt::new(&format!("{}", param.id), span);
-            quote! {
-                ({
-                    use grass_runtime::property::*;
-                    &_arg . #id
-                })
-            }
-        }
-        FieldExpression::StringRepr(param) => {
-            let inner = expand_field_expr_impl(param.value.as_ref(), span, env_const_defs);
-            quote! {
-                {
-                    use grass_runtime::property::*;
-                    let mut buffer = Vec::new();
-                    #inner . dump(&mut buffer).unwrap();
-                    String::from_utf8(buffer).unwrap()
-                }
-            }
-        }
+            FieldExpression::Call(param) => self.expand_call(param)?,
+        };
+        Ok((code, ty))
     }
-    .into()
 }
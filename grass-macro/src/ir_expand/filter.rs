@@ -7,7 +7,7 @@ use super::{
 
 impl Expand for FilterParam {
     fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
-        let cond = expand_field_expr(&self.cond, ctx.span());
+        let cond = expand_field_expr(&self.cond, ctx.span())?;
         let inner_id = expand_grass_ir(&self.input_expr, ctx)?;
         let inner = ctx.get_var_ref(&inner_id);
         let code = quote! {
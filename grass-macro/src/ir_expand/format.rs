@@ -1,4 +1,4 @@
-use grass_ir::{ConstOrEnv, FormatParam, WriteTarget};
+use grass_ir::{ConstOrEnv, FieldExpression, FormatParam, StringRepr, WriteTarget};
 use proc_macro2::Ident;
 use quote::quote;
 
@@ -15,7 +15,16 @@ pub fn expand_write_record_rec(
     let mut arguments = vec![];
     for (k, v) in param.values.iter() {
         let key_id = syn::Ident::new(k, ctx.span());
-        let value = expand_field_expr(v, ctx.span());
+        let value = match v {
+            // A record has no Display form, so we print it as it would be written to a file
+            FieldExpression::RecordRef(_) | FieldExpression::FullRecordRef => {
+                let repr = FieldExpression::StringRepr(StringRepr {
+                    value: Box::new(v.clone()),
+                });
+                expand_field_expr(&repr, ctx.span())?
+            }
+            _ => expand_field_expr(v, ctx.span())?,
+        };
         arguments.push(quote! {#key_id  = {
             Some(&item).map(#value).unwrap()
         }})
@@ -0,0 +1,34 @@
+use grass_ir::{CoverageStrand, GenomeCoverageParam, GenomeCoverageReport};
+use quote::quote;
+
+use super::{expand_grass_ir, Expand, ExpandResult, ExpansionContext};
+
+impl Expand for GenomeCoverageParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let inner = expand_grass_ir(self.inner.as_ref(), ctx)?;
+        let inner_token = ctx.get_var_ref(&inner);
+        let include_zero = self.include_zero;
+        let strand = match self.strand {
+            Some(CoverageStrand::Positive) => quote! { Some(Strand::Positive) },
+            Some(CoverageStrand::Negative) => quote! { Some(Strand::Negative) },
+            None => quote! { None },
+        };
+        let scale = self.scale.unwrap_or(1.0);
+        let method = match self.report {
+            GenomeCoverageReport::BedGraph => quote! { genome_coverage },
+            GenomeCoverageReport::Histogram => quote! { genome_histogram },
+        };
+        Ok(ctx.push(quote! {
+            {
+                use grass_runtime::algorithm::{GenomeCoverageExt, GenomeCoverageOptions};
+                use grass_runtime::property::Strand;
+                let options = GenomeCoverageOptions {
+                    include_zero: #include_zero,
+                    strand: #strand,
+                    scale: #scale,
+                };
+                #inner_token . #method(options)
+            }
+        }))
+    }
+}
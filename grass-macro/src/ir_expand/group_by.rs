@@ -0,0 +1,38 @@
+use grass_ir::GroupByParam;
+use quote::quote;
+
+use super::{
+    expand_grass_ir, field_expr::expand_field_expr, Expand, ExpandResult, ExpansionContext,
+};
+
+impl Expand for GroupByParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let inner = expand_grass_ir(self.expr.as_ref(), ctx)?;
+        let inner_id = ctx.get_var_ref(&inner);
+
+        let keys: Vec<_> = self
+            .keys
+            .iter()
+            .map(|key| {
+                let key = expand_field_expr(key, ctx.span())?;
+                Ok(quote! { Some(item).map(#key).unwrap().to_owned() })
+            })
+            .collect::<Result<_, syn::Error>>()?;
+
+        let group_by = ctx.push(quote! {
+            {
+                use grass_runtime::Itertools;
+                #inner_id . group_by(|item| (#(#keys,)*))
+            }
+        });
+        let group_by_id = ctx.get_var_ref(&group_by);
+
+        let code = quote! {
+            {
+                use grass_runtime::algorithm::Groups;
+                Groups::new(&#group_by_id)
+            }
+        };
+        Ok(ctx.push(code))
+    }
+}
@@ -3,13 +3,54 @@ use quote::quote;
 
 use super::{expand_grass_ir, Expand, ExpandResult, ExpansionContext};
 
+// Block-only intersection is the plain inner intersection with the pairs that only overlap on the
+// gaps between blocks dropped. The filter keeps the sortedness of the input.
+fn expand_blocks_only(
+    param: &IntersectParam,
+    ctx: &mut ExpansionContext,
+    left_token: syn::Ident,
+    right_token: syn::Ident,
+) -> ExpandResult {
+    if !matches!(param.flavor, IntersectFlavor::Inner) {
+        return Err(syn::Error::new(
+            ctx.span(),
+            "Block-only intersection is only supported by the inner flavor",
+        ));
+    }
+    let intersection = if param.sorted {
+        quote! {
+            {
+                use grass_runtime::algorithm::SortedIntersect;
+                #left_token .sorted_intersect(#right_token)
+            }
+        }
+    } else {
+        quote! {
+            {
+                use grass_runtime::algorithm::{IndexedIntersect, IntervalIndex};
+                #left_token . indexed_intersect(#right_token.collect::<IntervalIndex<_>>())
+            }
+        }
+    };
+    let code = quote! {
+        {
+            use grass_runtime::property::Blocked;
+            #intersection . filter(|(a, b)| a.overlaps_on_blocks(b))
+        }
+    };
+    Ok(ctx.push(code))
+}
+
 impl Expand for IntersectParam {
     fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let left = expand_grass_ir(self.lhs.as_ref(), ctx)?;
+        let right = expand_grass_ir(self.rhs.as_ref(), ctx)?;
+        let left_token = ctx.get_var_ref(&left);
+        let right_token = ctx.get_var_ref(&right);
+        if self.blocks_only {
+            return expand_blocks_only(self, ctx, left_token, right_token);
+        }
         if self.sorted {
-            let left = expand_grass_ir(self.lhs.as_ref(), ctx)?;
-            let right = expand_grass_ir(self.rhs.as_ref(), ctx)?;
-            let left_token = ctx.get_var_ref(&left);
-            let right_token = ctx.get_var_ref(&right);
             let code = match self.flavor {
                 IntersectFlavor::Inner => quote! {
                     {
@@ -29,11 +70,44 @@ impl Expand for IntersectParam {
                         #right_token . sorted_left_outer_intersect(#left_token) . map (|i| (i.1, i.0)) . assume_sorted()
                     }
                 },
-                _ => todo!(),
+                IntersectFlavor::Outer => quote! {
+                    {
+                        use grass_runtime::algorithm::SortedIntersect;
+                        #left_token . sorted_full_outer_intersect(#right_token)
+                    }
+                },
             };
             Ok(ctx.push(code))
         } else {
-            todo!("unsorted intersect is not implemented yet")
+            // Neither side is known to be sorted, so we build an interval index on one side and
+            // stream the other side through it. The output follows the order of the streamed side.
+            let code = match self.flavor {
+                IntersectFlavor::Inner => quote! {
+                    {
+                        use grass_runtime::algorithm::{IndexedIntersect, IntervalIndex};
+                        #left_token . indexed_intersect(#right_token.collect::<IntervalIndex<_>>())
+                    }
+                },
+                IntersectFlavor::LeftOuter => quote! {
+                    {
+                        use grass_runtime::algorithm::{IndexedIntersect, IntervalIndex};
+                        #left_token . indexed_left_outer_intersect(#right_token.collect::<IntervalIndex<_>>())
+                    }
+                },
+                IntersectFlavor::RightOuter => quote! {
+                    {
+                        use grass_runtime::algorithm::{IndexedIntersect, IntervalIndex};
+                        #right_token . indexed_left_outer_intersect(#left_token.collect::<IntervalIndex<_>>()) . map (|i| (i.1, i.0))
+                    }
+                },
+                IntersectFlavor::Outer => quote! {
+                    {
+                        use grass_runtime::algorithm::{IndexedIntersect, IntervalIndex};
+                        #left_token . indexed_full_outer_intersect(#right_token.collect::<IntervalIndex<_>>())
+                    }
+                },
+            };
+            Ok(ctx.push(code))
         }
     }
 }
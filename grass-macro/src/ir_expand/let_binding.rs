@@ -1,11 +1,47 @@
+use quote::quote;
+
 use super::{expand_grass_ir, Expand, ExpandResult, ExpansionContext};
 
-use grass_ir::LetBinding;
+use grass_ir::{LetBinding, RefParam};
 
 impl Expand for LetBinding {
     fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
         let inner_id = expand_grass_ir(&self.value, ctx)?;
-        ctx.symbol_table.insert(self.id.clone(), inner_id);
-        Ok(inner_id)
+        let ref_count = ctx.ref_count(&self.id);
+        if ref_count == 0 {
+            ctx.symbol_table.insert(self.id.clone(), inner_id);
+            return Ok(inner_id);
+        }
+        // An iterator can only be consumed once, so when the value is referenced elsewhere, we
+        // fan it out with a tee, which has one branch for the binding itself and one for each
+        // reference. The symbol then refers to the tee rather than the value.
+        let inner = ctx.get_var_ref(&inner_id);
+        let branches = ref_count + 1;
+        let tee_id = ctx.push(quote! {
+            {
+                use grass_runtime::algorithm::{InMemoryTee, SpillingTee, TeeSource};
+                (&&TeeSource::new(#inner)).make_tee(#branches)?
+            }
+        });
+        ctx.add_tee(tee_id);
+        ctx.symbol_table.insert(self.id.clone(), tee_id);
+        let tee = ctx.get_var_ref(&tee_id);
+        Ok(ctx.push(quote! { #tee.branch() }))
+    }
+}
+
+impl Expand for RefParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let tee_id = match ctx.symbol_table.get(&self.id) {
+            Some(id) => *id,
+            None => {
+                return Err(syn::Error::new(
+                    ctx.span(),
+                    format!("Reference to undefined symbol {}", self.id),
+                ))
+            }
+        };
+        let tee = ctx.get_var_ref(&tee_id);
+        Ok(ctx.push(quote! { #tee.branch() }))
     }
 }
@@ -24,63 +24,150 @@ fn expand_path(span: Span, target: &OpenTarget) -> Result<TokenStream, u32> {
     }
 }
 
+fn expand_text_input(span: Span, param: &OpenParam) -> TokenStream {
+    let open_expr = match expand_path(span, &param.target) {
+        Ok(path) => quote! { std::fs::File::open(#path)? },
+        Err(0) => quote! { std::io::stdin() },
+        Err(1) => quote! { std::io::stdout() },
+        Err(2) => quote! { std::io::stderr() },
+        Err(fd) => panic!("Unsupported file descriptor #{}", fd),
+    };
+    if param.compression {
+        quote! { grass_runtime::GzipReader::new(#open_expr) }
+    } else {
+        open_expr
+    }
+}
+
 impl Expand for OpenParam {
     fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
         match &self.format {
             InputFormat::Bed => {
-                let open_expr = match expand_path(ctx.span(), &self.target) {
-                    Ok(path) => quote! { std::fs::File::open(#path)? },
-                    Err(0) => quote! { std::io::stdin() },
-                    Err(1) => quote! { std::io::stdout() },
-                    Err(2) => quote! { std::io::stderr() },
-                    Err(fd) => panic!("Unsupported file descriptor #{}", fd),
+                let open_expr = expand_text_input(ctx.span(), self);
+                let bed_type_name = format!("Bed{}", self.num_of_fields);
+                let bed_type_id = syn::Ident::new(&bed_type_name, ctx.span());
+                let imports = if self.sorted {
+                    quote! {
+                        use grass_runtime::LineRecordStreamExt;
+                        use grass_runtime::algorithm::AssumeSorted;
+                    }
+                } else {
+                    quote! {
+                        use grass_runtime::LineRecordStreamExt;
+                    }
+                };
+                let assume_sorted = if self.sorted {
+                    quote! {.assume_sorted()}
+                } else {
+                    quote! {}
+                };
+                let code = quote! {
+                    {
+                        #imports
+                        (#open_expr).into_record_iter::<grass_runtime::record::#bed_type_id>()
+                        #assume_sorted
+                    }
                 };
-                if !self.compression {
-                    let bed_type_name = format!("Bed{}", self.num_of_fields);
-                    let bed_type_id = syn::Ident::new(&bed_type_name, ctx.span());
-                    let imports = if self.sorted {
-                        quote! {
+                Ok(ctx.push(code))
+            }
+            InputFormat::BedPe => {
+                let open_expr = expand_text_input(ctx.span(), self);
+                let code = if self.sorted {
+                    quote! {
+                        {
                             use grass_runtime::LineRecordStreamExt;
                             use grass_runtime::algorithm::AssumeSorted;
+                            (#open_expr).into_record_iter::<grass_runtime::record::BedPe>().assume_sorted()
                         }
-                    } else {
-                        quote! {
+                    }
+                } else {
+                    quote! {
+                        {
                             use grass_runtime::LineRecordStreamExt;
+                            (#open_expr).into_record_iter::<grass_runtime::record::BedPe>()
                         }
-                    };
-                    let assume_sorted = if self.sorted {
-                        quote! {.assume_sorted()}
-                    } else {
-                        quote! {}
-                    };
-                    let code = quote! {
+                    }
+                };
+                Ok(ctx.push(code))
+            }
+            InputFormat::Vcf => {
+                let open_expr = expand_text_input(ctx.span(), self);
+                let vcf_file = ctx.push(quote! {
+                    {
+                        use grass_runtime::record::VcfReader;
+                        VcfReader::new(#open_expr)?
+                    }
+                });
+                let vcf_file_id = ctx.get_var_ref(&vcf_file);
+                let code = if self.sorted {
+                    quote! {
                         {
-                            #imports
-                            (#open_expr).into_record_iter::<grass_runtime::record::#bed_type_id>()
-                            #assume_sorted
+                            use grass_runtime::algorithm::AssumeSorted;
+                            #vcf_file_id.into_record_iter().assume_sorted()
                         }
-                    };
-                    Ok(ctx.push(code))
+                    }
                 } else {
-                    todo!()
-                }
+                    quote! { #vcf_file_id.into_record_iter() }
+                };
+                Ok(ctx.push(code))
+            }
+            InputFormat::Fasta => {
+                let path = match expand_path(ctx.span(), &self.target) {
+                    Ok(path) => path,
+                    Err(fd) => {
+                        return Err(syn::Error::new(
+                            ctx.span(),
+                            format!("Reading FASTA from file descriptor #{} isn't supported yet", fd),
+                        ))
+                    }
+                };
+                let code = quote! {
+                    {
+                        use grass_runtime::record::FastaReference;
+                        FastaReference::open(#path)?.into_record_iter()
+                    }
+                };
+                Ok(ctx.push(code))
+            }
+            InputFormat::Cram => {
+                let open_expr = match expand_path(ctx.span(), &self.target) {
+                    Ok(path) => quote! { BamReader::open(#path)? },
+                    Err(fd) => quote! { BamReader::from_fd(#fd)? },
+                };
+                let open_expr = match &self.reference {
+                    Some(ConstOrEnv::Const(reference)) => quote! { #open_expr.with_reference(#reference) },
+                    Some(ConstOrEnv::Env(key)) => {
+                        let reference = Ident::new(&key.get_const_bag_ident(), ctx.span());
+                        quote! { #open_expr.with_reference(#reference.value()) }
+                    }
+                    // Without a reference, htslib falls back to the REF_PATH and REF_CACHE lookup
+                    None => open_expr,
+                };
+                let cram_file = ctx.push(quote!{
+                    {
+                        use grass_runtime::record::BamReader;
+                        #open_expr
+                    }
+                });
+                let cram_file_id = ctx.get_var_ref(&cram_file);
+
+                Ok(ctx.push(quote! {#cram_file_id.iter()}))
             }
             InputFormat::Bam => {
-                let path = expand_path(ctx.span(), &self.target).expect("Reading bam from pipe isn't supported yet");
+                let open_expr = match expand_path(ctx.span(), &self.target) {
+                    Ok(path) => quote! { BamReader::open(#path)? },
+                    Err(fd) => quote! { BamReader::from_fd(#fd)? },
+                };
                 let bam_file = ctx.push(quote!{ 
                     {
                         use grass_runtime::record::BamReader;
-                        BamReader::open(#path)?
+                        #open_expr
                     } 
                 });
                 let bam_file_id = ctx.get_var_ref(&bam_file);
 
                 Ok(ctx.push(quote! {#bam_file_id.iter()}))
             }
-            whatever => Err(syn::Error::new(
-                ctx.span(),
-                format!("Unsupported input file type: {:?}", whatever),
-            )),
         }
     }
 }
@@ -0,0 +1,51 @@
+use grass_ir::{PairIntersectMode, PairIntersectParam};
+use quote::quote;
+
+use super::{expand_grass_ir, Expand, ExpandResult, ExpansionContext};
+
+impl Expand for PairIntersectParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let left = expand_grass_ir(self.lhs.as_ref(), ctx)?;
+        let right = expand_grass_ir(self.rhs.as_ref(), ctx)?;
+        let left_token = ctx.get_var_ref(&left);
+        let right_token = ctx.get_var_ref(&right);
+        if self.sorted {
+            let code = match self.mode {
+                PairIntersectMode::Either => quote! {
+                    #left_token . sorted_pair_intersect(#right_token, PairOverlap::Either)
+                },
+                PairIntersectMode::Both => quote! {
+                    #left_token . sorted_pair_intersect(#right_token, PairOverlap::Both)
+                },
+                PairIntersectMode::Neither => quote! {
+                    #left_token . sorted_pair_exclude(#right_token)
+                },
+            };
+            return Ok(ctx.push(quote! {
+                {
+                    use grass_runtime::algorithm::{PairOverlap, SortedPairedIntersect};
+                    #code
+                }
+            }));
+        }
+        // The second ends are not sorted, so the right-hand-side is indexed
+        let index = quote! { #right_token.collect::<IntervalIndex<_>>() };
+        let code = match self.mode {
+            PairIntersectMode::Either => quote! {
+                #left_token . pair_intersect(#index, PairOverlap::Either)
+            },
+            PairIntersectMode::Both => quote! {
+                #left_token . pair_intersect(#index, PairOverlap::Both)
+            },
+            PairIntersectMode::Neither => quote! {
+                #left_token . pair_exclude(#index)
+            },
+        };
+        Ok(ctx.push(quote! {
+            {
+                use grass_runtime::algorithm::{IntervalIndex, PairOverlap, PairedIntersect};
+                #code
+            }
+        }))
+    }
+}
@@ -0,0 +1,69 @@
+use grass_ir::{FlankParam, GrassIR, ResizeAmount, ResizeAnchor, ResizeParam, SlopParam};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::{expand_grass_ir, Expand, ExpandResult, ExpansionContext};
+
+fn expand_amount(amount: &ResizeAmount) -> TokenStream {
+    match amount {
+        ResizeAmount::Bases(bases) => quote! { grass_runtime::algorithm::Amount::Bases(#bases) },
+        ResizeAmount::Fraction(fraction) => {
+            quote! { grass_runtime::algorithm::Amount::Fraction(#fraction) }
+        }
+    }
+}
+
+fn expand_resize(
+    inner: &GrassIR,
+    op: TokenStream,
+    strand_relative: bool,
+    sorted: bool,
+    ctx: &mut ExpansionContext,
+) -> ExpandResult {
+    let inner = expand_grass_ir(inner, ctx)?;
+    let inner_token = ctx.get_var_ref(&inner);
+    let method = if sorted {
+        quote! { sorted_resize_regions }
+    } else {
+        quote! { resize_regions }
+    };
+    Ok(ctx.push(quote! {
+        {
+            use grass_runtime::algorithm::ResizeExt;
+            #inner_token . #method(#op, #strand_relative)
+        }
+    }))
+}
+
+impl Expand for SlopParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let left = expand_amount(&self.left);
+        let right = expand_amount(&self.right);
+        let op = quote! { grass_runtime::algorithm::ResizeOp::Slop { left: #left, right: #right } };
+        expand_resize(&self.inner, op, self.strand_relative, self.sorted, ctx)
+    }
+}
+
+impl Expand for FlankParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let left = expand_amount(&self.left);
+        let right = expand_amount(&self.right);
+        let op =
+            quote! { grass_runtime::algorithm::ResizeOp::Flank { left: #left, right: #right } };
+        expand_resize(&self.inner, op, self.strand_relative, self.sorted, ctx)
+    }
+}
+
+impl Expand for ResizeParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let size = expand_amount(&self.size);
+        let anchor = match self.anchor {
+            ResizeAnchor::Start => quote! { grass_runtime::algorithm::ResizeAnchor::Start },
+            ResizeAnchor::Center => quote! { grass_runtime::algorithm::ResizeAnchor::Center },
+            ResizeAnchor::End => quote! { grass_runtime::algorithm::ResizeAnchor::End },
+        };
+        let op =
+            quote! { grass_runtime::algorithm::ResizeOp::Resize { size: #size, anchor: #anchor } };
+        expand_resize(&self.inner, op, self.strand_relative, self.sorted, ctx)
+    }
+}
@@ -0,0 +1,53 @@
+use grass_ir::{FisherParam, GrassIR, JaccardParam, RelativeDistanceParam};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use super::{expand_grass_ir, Expand, ExpandResult, ExpansionContext};
+
+/// Call a method of the statistics extension on the left side, which takes the right side
+fn expand_stats(
+    lhs: &GrassIR,
+    rhs: &GrassIR,
+    ctx: &mut ExpansionContext,
+    method: impl FnOnce(syn::Ident) -> TokenStream,
+) -> ExpandResult {
+    let left = expand_grass_ir(lhs, ctx)?;
+    let right = expand_grass_ir(rhs, ctx)?;
+    let left_token = ctx.get_var_ref(&left);
+    let right_token = ctx.get_var_ref(&right);
+    let call = method(right_token);
+    Ok(ctx.push(quote! {
+        {
+            use grass_runtime::algorithm::OverlapStatsExt;
+            #left_token . #call
+        }
+    }))
+}
+
+impl Expand for JaccardParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        expand_stats(&self.lhs, &self.rhs, ctx, |right| {
+            quote! { sorted_jaccard(#right) }
+        })
+    }
+}
+
+impl Expand for FisherParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let genome_size = match self.genome_size {
+            Some(size) => quote! { Some(#size) },
+            None => quote! { None },
+        };
+        expand_stats(&self.lhs, &self.rhs, ctx, |right| {
+            quote! { sorted_fisher(#right, #genome_size) }
+        })
+    }
+}
+
+impl Expand for RelativeDistanceParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        expand_stats(&self.lhs, &self.rhs, ctx, |right| {
+            quote! { sorted_relative_distance(#right) }
+        })
+    }
+}
@@ -0,0 +1,30 @@
+use grass_ir::{SubtractMode, SubtractParam};
+use quote::quote;
+
+use super::{expand_grass_ir, Expand, ExpandResult, ExpansionContext};
+
+impl Expand for SubtractParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let left = expand_grass_ir(self.lhs.as_ref(), ctx)?;
+        let right = expand_grass_ir(self.rhs.as_ref(), ctx)?;
+        let left_token = ctx.get_var_ref(&left);
+        let right_token = ctx.get_var_ref(&right);
+        let mode = match self.mode {
+            SubtractMode::Fragments => quote! { SubtractMode::Fragments },
+            SubtractMode::RemoveOverlapping => quote! { SubtractMode::RemoveOverlapping },
+        };
+        let min_fraction = self.min_fraction.unwrap_or(0.0);
+        let same_strand = self.same_strand;
+        Ok(ctx.push(quote! {
+            {
+                use grass_runtime::algorithm::{SortedSubtract, SubtractMode, SubtractOptions};
+                let options = SubtractOptions {
+                    mode: #mode,
+                    min_fraction: #min_fraction,
+                    same_strand: #same_strand,
+                };
+                #left_token . sorted_subtract(#right_token, options)
+            }
+        }))
+    }
+}
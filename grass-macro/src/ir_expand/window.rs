@@ -0,0 +1,32 @@
+use grass_ir::WindowParam;
+use quote::quote;
+
+use super::{expand_grass_ir, Expand, ExpandResult, ExpansionContext};
+
+impl Expand for WindowParam {
+    fn expand(&self, ctx: &mut ExpansionContext) -> ExpandResult {
+        let left = expand_grass_ir(self.lhs.as_ref(), ctx)?;
+        let right = expand_grass_ir(self.rhs.as_ref(), ctx)?;
+        let left_token = ctx.get_var_ref(&left);
+        let right_token = ctx.get_var_ref(&right);
+        let left_flank = self.left;
+        let right_flank = self.right;
+        let strand_relative = self.strand_relative;
+        let method = if self.count_only {
+            quote! { sorted_window_count }
+        } else {
+            quote! { sorted_window }
+        };
+        Ok(ctx.push(quote! {
+            {
+                use grass_runtime::algorithm::{SortedWindow, WindowOptions};
+                let options = WindowOptions {
+                    left: #left_flank,
+                    right: #right_flank,
+                    strand_relative: #strand_relative,
+                };
+                #left_token . #method(#right_token, options)
+            }
+        }))
+    }
+}
@@ -17,7 +17,12 @@ impl Expand for WriteFileParam {
                         use std::io::Write;
                         use grass_runtime::property::Serializable;
                         let mut out_f = std::io::BufWriter::new(unsafe { std::fs::File::from_raw_fd(#fd) });
+                        let mut first = true;
                         for item in #inner_ref {
+                            if first {
+                                item.dump_header(&mut out_f)?;
+                                first = false;
+                            }
                             item.dump(&mut out_f)?;
                             out_f.write_all(b"\n")?;
                         }
@@ -40,7 +45,12 @@ impl Expand for WriteFileParam {
                         use std::io::Write;
                         use grass_runtime::property::Serializable;
                         let mut out_f = std::io::BufWriter::new(std::fs::File::create(#path)?);
+                        let mut first = true;
                         for item in #inner_ref {
+                            if first {
+                                item.dump_header(&mut out_f)?;
+                                first = false;
+                            }
                             item.dump(&mut out_f)?;
                             out_f.write_all(b"\n")?;
                         }
@@ -1,6 +1,6 @@
 use std::{fs::File, io::Read, path::Path};
 
-use grass_ir::GrassIR;
+use grass_ir::{FieldExpression, GrassIR, WriteFileParam, WriteTarget};
 use ir_expand::ExpansionContext;
 use proc_macro::TokenStream;
 use proc_macro2::Span;
@@ -10,7 +10,14 @@ use syn::{parse_macro_input, LitStr};
 mod ir_expand;
 
 fn grass_impl(ir: &str, span: Span) -> TokenStream {
-    let ir: GrassIR = match serde_json::from_str(ir) {
+    // The input is either a single IR or a list of IRs sharing the same symbols
+    let ir: Vec<GrassIR> = match serde_json::from_str::<serde_json::Value>(ir).and_then(|value| {
+        if value.is_array() {
+            serde_json::from_value(value)
+        } else {
+            serde_json::from_value(value).map(|ir| vec![ir])
+        }
+    }) {
         Err(e) => {
             return syn::Error::new(span, format!("Unable to parse Grass IR {}", e.to_string()))
                 .to_compile_error()
@@ -19,10 +26,30 @@ fn grass_impl(ir: &str, span: Span) -> TokenStream {
         Ok(ir) => ir,
     };
 
+    // A count at the top level is a drain, which prints the number to stdout
+    let ir: Vec<GrassIR> = ir
+        .into_iter()
+        .map(|ir| match ir {
+            GrassIR::Count(_) => GrassIR::WriteFile(WriteFileParam {
+                what: Box::new(ir),
+                target: WriteTarget::FileNo(1),
+            }),
+            ir => ir,
+        })
+        .collect();
+
     let mut ctx = ExpansionContext::new(span);
+    ir.iter().for_each(|ir| ctx.count_refs(ir));
 
-    let result = match ir_expand::expand_grass_ir(&ir, &mut ctx) {
-        Ok(_code) => {
+    let result = match ir.iter().try_for_each(|ir| {
+        let id = ir_expand::expand_grass_ir(ir, &mut ctx)?;
+        // Nothing consumes a binding at the top level, only the references to it do
+        if let GrassIR::Let(_) = ir {
+            ctx.drop_var(&id);
+        }
+        Ok::<_, syn::Error>(())
+    }) {
+        Ok(_) => {
             let code = ctx.to_token_stream();
             quote! {
                 #code;
@@ -89,3 +116,25 @@ pub fn import_grass_ir(input: TokenStream) -> TokenStream {
     let ir_lit = parse_macro_input!(input as LitStr);
     grass_impl(ir_lit.value().as_str(), ir_lit.span())
 }
+
+/// Expand a single field expression to a closure that takes a reference to the record, this is
+/// how the field expressions in the IR are compiled
+#[proc_macro]
+pub fn field_expr(input: TokenStream) -> TokenStream {
+    let expr_lit = parse_macro_input!(input as LitStr);
+    let expr: FieldExpression = match serde_json::from_str(&expr_lit.value()) {
+        Ok(expr) => expr,
+        Err(e) => {
+            return syn::Error::new(
+                expr_lit.span(),
+                format!("Unable to parse field expression {}", e),
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+    match ir_expand::expand_field_expr(&expr, expr_lit.span()) {
+        Ok(code) => code.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
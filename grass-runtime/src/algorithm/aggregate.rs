@@ -0,0 +1,429 @@
+use std::{
+    collections::HashMap,
+    io::{Result, Write},
+};
+
+use crate::{
+    property::{Region, RegionCore, Serializable},
+    record::Bed3,
+    ChrRef,
+};
+
+use super::{CountKey, Sorted};
+
+/// A function reducing the values of the records in a group to a single column
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AggregateFunc {
+    Count,
+    CountDistinct,
+    Sum,
+    Mean,
+    Median,
+    Min,
+    Max,
+    /// The most frequent value, the value seen first wins a tie
+    Mode,
+    /// The population standard deviation
+    Stdev,
+    /// All the values separated by commas
+    Collapse,
+    /// The distinct values in the order they are first seen, separated by commas
+    Distinct,
+    First,
+    Last,
+}
+
+impl AggregateFunc {
+    /// If the function only takes numbers, the other functions take the values as text
+    pub fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            AggregateFunc::Sum
+                | AggregateFunc::Mean
+                | AggregateFunc::Median
+                | AggregateFunc::Min
+                | AggregateFunc::Max
+                | AggregateFunc::Stdev
+        )
+    }
+}
+
+/// The state of an aggregate function over the records of the current group
+pub struct Aggregator {
+    func: AggregateFunc,
+    count: usize,
+    numbers: Vec<f64>,
+    texts: Vec<String>,
+}
+
+impl Aggregator {
+    pub fn new(func: AggregateFunc) -> Self {
+        Self {
+            func,
+            count: 0,
+            numbers: Vec::new(),
+            texts: Vec::new(),
+        }
+    }
+
+    pub fn func(&self) -> AggregateFunc {
+        self.func
+    }
+
+    pub fn push_number(&mut self, value: f64) {
+        if self.func.is_numeric() {
+            self.count += 1;
+            self.numbers.push(value);
+        } else {
+            self.push_text(&value.to_string());
+        }
+    }
+
+    pub fn push_text(&mut self, value: &str) {
+        self.count += 1;
+        match self.func {
+            AggregateFunc::Count => (),
+            AggregateFunc::First if !self.texts.is_empty() => (),
+            AggregateFunc::Last => {
+                self.texts.clear();
+                self.texts.push(value.to_string());
+            }
+            _ => self.texts.push(value.to_string()),
+        }
+    }
+
+    /// Produce the column and reset the state for the next group
+    pub fn finish(&mut self) -> String {
+        let numbers = std::mem::take(&mut self.numbers);
+        let texts = std::mem::take(&mut self.texts);
+        let count = std::mem::take(&mut self.count);
+        let mean = || numbers.iter().sum::<f64>() / numbers.len() as f64;
+        let distinct = || {
+            let mut seen = HashMap::new();
+            let mut order = Vec::new();
+            for text in texts.iter() {
+                *seen.entry(text.as_str()).or_insert_with(|| {
+                    order.push(text.as_str());
+                    0
+                }) += 1;
+            }
+            (order, seen)
+        };
+        match self.func {
+            AggregateFunc::Count => count.to_string(),
+            AggregateFunc::CountDistinct => distinct().0.len().to_string(),
+            AggregateFunc::Sum => numbers.iter().sum::<f64>().to_string(),
+            AggregateFunc::Mean => mean().to_string(),
+            AggregateFunc::Median => {
+                let mut numbers = numbers.clone();
+                numbers.sort_by(f64::total_cmp);
+                let mid = numbers.len() / 2;
+                match numbers.len() {
+                    0 => f64::NAN,
+                    len if len % 2 == 0 => (numbers[mid - 1] + numbers[mid]) / 2.0,
+                    _ => numbers[mid],
+                }
+                .to_string()
+            }
+            AggregateFunc::Min => numbers.iter().copied().fold(f64::NAN, f64::min).to_string(),
+            AggregateFunc::Max => numbers.iter().copied().fold(f64::NAN, f64::max).to_string(),
+            AggregateFunc::Mode => {
+                let (order, seen) = distinct();
+                let mut mode = None;
+                for text in order {
+                    if !matches!(mode, Some((_, count)) if seen[text] <= count) {
+                        mode = Some((text, seen[text]));
+                    }
+                }
+                mode.map_or("", |(text, _)| text).to_string()
+            }
+            AggregateFunc::Stdev => {
+                let mean = mean();
+                let var = numbers.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>()
+                    / numbers.len() as f64;
+                var.sqrt().to_string()
+            }
+            AggregateFunc::Collapse => texts.join(","),
+            AggregateFunc::Distinct => distinct().0.join(","),
+            AggregateFunc::First | AggregateFunc::Last => texts.concat(),
+        }
+    }
+}
+
+/// A value that can be aggregated, the numbers are taken as text by the non-numeric functions
+pub trait AggregateInput {
+    fn push_to(self, aggregator: &mut Aggregator);
+}
+
+impl AggregateInput for f64 {
+    fn push_to(self, aggregator: &mut Aggregator) {
+        aggregator.push_number(self)
+    }
+}
+
+impl AggregateInput for i64 {
+    fn push_to(self, aggregator: &mut Aggregator) {
+        if aggregator.func().is_numeric() {
+            aggregator.push_number(self as f64)
+        } else {
+            aggregator.push_text(&self.to_string())
+        }
+    }
+}
+
+impl AggregateInput for bool {
+    fn push_to(self, aggregator: &mut Aggregator) {
+        aggregator.push_text(if self { "true" } else { "false" })
+    }
+}
+
+impl AggregateInput for &str {
+    fn push_to(self, aggregator: &mut Aggregator) {
+        aggregator.push_text(self)
+    }
+}
+
+impl AggregateInput for String {
+    fn push_to(self, aggregator: &mut Aggregator) {
+        aggregator.push_text(&self)
+    }
+}
+
+impl Aggregator {
+    pub fn push<T: AggregateInput>(&mut self, value: T) {
+        value.push_to(self)
+    }
+}
+
+/// A merged region used as the key of a cluster takes the three columns of a BED3 record
+impl CountKey for Bed3 {
+    fn dump_key<W: Write>(&self, mut fp: W) -> Result<()> {
+        self.dump(&mut fp)?;
+        write!(fp, "\t")
+    }
+}
+
+/// The key of a group followed by the aggregated columns
+pub struct AggregateRecord<K> {
+    pub key: K,
+    pub columns: Vec<String>,
+}
+
+impl<K: CountKey> Serializable for AggregateRecord<K> {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        self.key.dump_key(&mut fp)?;
+        write!(fp, "{}", self.columns.join("\t"))
+    }
+}
+
+impl<K: RegionCore> RegionCore for AggregateRecord<K> {
+    fn start(&self) -> u32 {
+        self.key.start()
+    }
+    fn end(&self) -> u32 {
+        self.key.end()
+    }
+    fn chrom(&self) -> ChrRef<'static> {
+        self.key.chrom()
+    }
+}
+
+/// How the records are grouped for the aggregation
+pub trait Grouping<T> {
+    type Key;
+    /// Returns `None` if the record belongs to the current group, which may extend the key of the
+    /// group, otherwise returns the key of the new group the record starts
+    fn group(&mut self, item: &T, current: Option<&mut Self::Key>) -> Option<Self::Key>;
+}
+
+/// Each run of the records with the same key is a group
+pub struct ByKey<F>(F);
+
+impl<T, K: PartialEq, F: FnMut(&T) -> K> Grouping<T> for ByKey<F> {
+    type Key = K;
+    fn group(&mut self, item: &T, current: Option<&mut K>) -> Option<K> {
+        let key = (self.0)(item);
+        match current {
+            Some(current) if *current == key => None,
+            _ => Some(key),
+        }
+    }
+}
+
+/// Each cluster of the overlapping records is a group, the key is the merged region
+pub struct ByOverlap;
+
+impl<T: Region> Grouping<T> for ByOverlap {
+    type Key = Bed3;
+    fn group(&mut self, item: &T, current: Option<&mut Bed3>) -> Option<Bed3> {
+        match current {
+            Some(cluster) if cluster.chrom == item.chrom() && item.start() < cluster.end => {
+                cluster.end = cluster.end.max(item.end());
+                None
+            }
+            _ => Some(Bed3::new(item)),
+        }
+    }
+}
+
+pub struct AggregateIter<I: Iterator, G: Grouping<I::Item>, P> {
+    iter: I,
+    grouping: G,
+    push: P,
+    current: Option<G::Key>,
+    aggregators: Vec<Aggregator>,
+}
+
+impl<I: Iterator, G: Grouping<I::Item>, P> AggregateIter<I, G, P> {
+    fn new(iter: I, grouping: G, funcs: impl IntoIterator<Item = AggregateFunc>, push: P) -> Self {
+        Self {
+            iter,
+            grouping,
+            push,
+            current: None,
+            aggregators: funcs.into_iter().map(Aggregator::new).collect(),
+        }
+    }
+
+    fn finish(&mut self, key: G::Key) -> AggregateRecord<G::Key> {
+        AggregateRecord {
+            key,
+            columns: self
+                .aggregators
+                .iter_mut()
+                .map(Aggregator::finish)
+                .collect(),
+        }
+    }
+}
+
+impl<I, G, P> Iterator for AggregateIter<I, G, P>
+where
+    I: Iterator,
+    G: Grouping<I::Item>,
+    P: FnMut(&I::Item, &mut [Aggregator]),
+{
+    type Item = AggregateRecord<G::Key>;
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(item) = self.iter.next() {
+            // The previous group is finished before the record starting a new group is pushed
+            let finished = match self.grouping.group(&item, self.current.as_mut()) {
+                Some(key) => self
+                    .current
+                    .replace(key)
+                    .map(|prev_key| self.finish(prev_key)),
+                None => None,
+            };
+            (self.push)(&item, &mut self.aggregators);
+            if finished.is_some() {
+                return finished;
+            }
+        }
+        let key = self.current.take()?;
+        Some(self.finish(key))
+    }
+}
+
+impl<I, P> Sorted for AggregateIter<I, ByOverlap, P>
+where
+    I: Iterator + Sorted,
+    I::Item: Region,
+    P: FnMut(&I::Item, &mut [Aggregator]),
+{
+}
+
+/// Aggregate the values of the records in each group without buffering the records. `push` feeds
+/// the values of a record to the aggregators, which are created from `funcs` in the same order.
+pub trait AggregateExt: Iterator + Sized {
+    /// Aggregate each run of the records with the same key
+    fn aggregate_by<K, F, P>(
+        self,
+        key_func: F,
+        funcs: impl IntoIterator<Item = AggregateFunc>,
+        push: P,
+    ) -> AggregateIter<Self, ByKey<F>, P>
+    where
+        K: PartialEq,
+        F: FnMut(&Self::Item) -> K,
+        P: FnMut(&Self::Item, &mut [Aggregator]),
+    {
+        AggregateIter::new(self, ByKey(key_func), funcs, push)
+    }
+
+    /// Aggregate each cluster of the overlapping records, which is the region `MergeOverlap`
+    /// produces
+    fn aggregate_overlaps<P>(
+        self,
+        funcs: impl IntoIterator<Item = AggregateFunc>,
+        push: P,
+    ) -> AggregateIter<Self, ByOverlap, P>
+    where
+        Self: Sorted,
+        Self::Item: Region,
+        P: FnMut(&Self::Item, &mut [Aggregator]),
+    {
+        AggregateIter::new(self, ByOverlap, funcs, push)
+    }
+}
+
+impl<I: Iterator> AggregateExt for I {}
+
+#[cfg(test)]
+mod test {
+    use super::{AggregateExt, AggregateFunc, Aggregator};
+    use crate::{
+        algorithm::AssumeSorted,
+        property::{RegionCore, Serializable},
+        record::Bed3,
+        LineRecordStreamExt,
+    };
+
+    fn aggregate(func: AggregateFunc, values: &[f64]) -> String {
+        let mut aggregator = Aggregator::new(func);
+        for value in values {
+            aggregator.push(*value);
+        }
+        aggregator.finish()
+    }
+
+    #[test]
+    fn test_aggregator() {
+        let values = [3.0, 1.0, 2.5, 1.0];
+        assert_eq!(aggregate(AggregateFunc::Count, &values), "4");
+        assert_eq!(aggregate(AggregateFunc::CountDistinct, &values), "3");
+        assert_eq!(aggregate(AggregateFunc::Sum, &values), "7.5");
+        assert_eq!(aggregate(AggregateFunc::Mean, &values), "1.875");
+        assert_eq!(aggregate(AggregateFunc::Median, &values), "1.75");
+        assert_eq!(aggregate(AggregateFunc::Min, &values), "1");
+        assert_eq!(aggregate(AggregateFunc::Max, &values), "3");
+        assert_eq!(aggregate(AggregateFunc::Mode, &values), "1");
+        assert_eq!(aggregate(AggregateFunc::Stdev, &[1.0, 3.0]), "1");
+        assert_eq!(aggregate(AggregateFunc::Collapse, &values), "3,1,2.5,1");
+        assert_eq!(aggregate(AggregateFunc::Distinct, &values), "3,1,2.5");
+        assert_eq!(aggregate(AggregateFunc::First, &values), "3");
+        assert_eq!(aggregate(AggregateFunc::Last, &values), "1");
+    }
+
+    #[test]
+    fn test_aggregate_overlaps() {
+        let input = include_bytes!("../../../data/a.bed");
+        let funcs = [AggregateFunc::Count, AggregateFunc::Collapse];
+        let clusters: Vec<_> = input
+            .into_record_iter::<Bed3>()
+            .assume_sorted()
+            .aggregate_overlaps(funcs, |item, aggs| {
+                aggs[0].push(item.start() as i64);
+                aggs[1].push(item.start() as i64);
+            })
+            .map(|record| {
+                let mut buffer = Vec::new();
+                record.dump(&mut buffer).unwrap();
+                String::from_utf8(buffer).unwrap()
+            })
+            .collect();
+        assert_eq!(
+            clusters,
+            ["1\t10\t110\t3\t10,20,30", "1\t1000\t8000\t2\t1000,1500"]
+        );
+    }
+}
@@ -0,0 +1,451 @@
+use std::{
+    collections::BTreeSet,
+    io::{Result, Write},
+    iter::Peekable,
+};
+
+use crate::{
+    property::{Named, RecordAt, Region, RegionCore, Scored, Serializable, Strand, Stranded},
+    ChrRef,
+};
+
+use super::Sorted;
+
+/// Which of the equally distant records are reported
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClosestTies {
+    All,
+    /// The first record in the genome order
+    First,
+    /// The last record in the genome order
+    Last,
+}
+
+/// Which side of the record the closest records are searched on, the upstream and downstream are
+/// relative to the strand of the record, a record without a strand is taken as on the positive
+/// strand
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClosestDirection {
+    Both,
+    Upstream,
+    Downstream,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ClosestOptions {
+    pub ties: ClosestTies,
+    pub direction: ClosestDirection,
+    /// Don't report the overlapping records
+    pub ignore_overlaps: bool,
+    /// The number of distinct distances to report, which should be at least 1. The ties at each
+    /// of the distances are handled by `ties`.
+    pub k: usize,
+}
+
+impl Default for ClosestOptions {
+    fn default() -> Self {
+        Self {
+            ties: ClosestTies::All,
+            direction: ClosestDirection::Both,
+            ignore_overlaps: false,
+            k: 1,
+        }
+    }
+}
+
+/// A record along with one of its closest records, which is `None` if nothing is found on the
+/// chromosome
+#[derive(Clone)]
+pub struct ClosestPair<A, B> {
+    pub a: A,
+    pub b: Option<B>,
+    distance: i64,
+}
+
+impl<A, B> ClosestPair<A, B> {
+    /// The distance to the closest record in the same way as `bedtools closest -D a`: 0 if they
+    /// overlap, otherwise the gap plus 1, which is negative if the closest record is upstream.
+    /// The distance is -1 if there isn't a closest record.
+    pub fn distance(&self) -> i64 {
+        self.distance
+    }
+}
+
+impl<A: Region, B> RegionCore for ClosestPair<A, B> {
+    fn start(&self) -> u32 {
+        self.a.start()
+    }
+    fn end(&self) -> u32 {
+        self.a.end()
+    }
+    fn chrom(&self) -> ChrRef<'static> {
+        self.a.chrom()
+    }
+}
+
+impl<A: Stranded, B> Stranded for ClosestPair<A, B> {
+    fn strand(&self) -> Strand {
+        self.a.strand()
+    }
+}
+
+impl<'a, A: Named<'a>, B> Named<'a> for ClosestPair<A, B> {
+    fn name(&self) -> &str {
+        self.a.name()
+    }
+}
+
+impl<S, A: Scored<S>, B> Scored<S> for ClosestPair<A, B> {
+    fn score(&self) -> Option<S> {
+        self.a.score()
+    }
+}
+
+impl<A, B> RecordAt<0> for ClosestPair<A, B> {
+    type Output<'a>
+        = &'a A
+    where
+        Self: 'a;
+    fn record_at(&self) -> &A {
+        &self.a
+    }
+}
+
+impl<A, B> RecordAt<1> for ClosestPair<A, B> {
+    type Output<'a>
+        = &'a Option<B>
+    where
+        Self: 'a;
+    fn record_at(&self) -> &Option<B> {
+        &self.b
+    }
+}
+
+impl<A: Serializable, B> Serializable for ClosestPair<A, B>
+where
+    Option<B>: Serializable,
+{
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        self.a.dump(&mut fp)?;
+        write!(fp, "\t")?;
+        self.b.dump(&mut fp)
+    }
+}
+
+/// Report the closest records in a sorted iterator for each record of another sorted iterator.
+/// The records of the current chromosome are buffered.
+pub struct ClosestIter<IA, IB>
+where
+    IA: Iterator,
+    IB: Iterator,
+{
+    iter_a: IA,
+    iter_b: Peekable<IB>,
+    options: ClosestOptions,
+    chrom: Option<ChrRef<'static>>,
+    /// The records on the current chromosome, which are sorted by the start
+    buffer: Vec<IB::Item>,
+    /// The indices of the records in the buffer sorted by the end
+    by_end: Vec<usize>,
+    /// The records in the buffer that may overlap the current record or a later one
+    active: Vec<usize>,
+    /// The first record in the buffer that hasn't been activated
+    next_active: usize,
+    /// The closest records of the current record
+    pending: Vec<ClosestPair<IA::Item, IB::Item>>,
+}
+
+impl<IA, IB> ClosestIter<IA, IB>
+where
+    IA: Iterator,
+    IB: Iterator,
+    IA::Item: Region + Stranded + Clone,
+    IB::Item: Region + Clone,
+{
+    fn load_chrom(&mut self, chrom: ChrRef<'static>) {
+        if self.chrom == Some(chrom) {
+            return;
+        }
+        self.chrom = Some(chrom);
+        self.buffer.clear();
+        self.active.clear();
+        self.next_active = 0;
+        while self.iter_b.next_if(|b| b.chrom() < chrom).is_some() {}
+        while let Some(b) = self.iter_b.next_if(|b| b.chrom() == chrom) {
+            self.buffer.push(b);
+        }
+        let buffer = &self.buffer;
+        self.by_end = (0..buffer.len()).collect();
+        self.by_end.sort_by_key(|&idx| (buffer[idx].end(), idx));
+    }
+
+    /// The indices of the candidate records in the buffer along with their unsigned distances.
+    /// Only the `k` nearest distances on each side are searched.
+    fn search(&mut self, a: &IA::Item, left: bool, right: bool) -> Vec<(usize, i64)> {
+        let k = self.options.k;
+        let (start, end) = (a.start() as i64, a.end() as i64);
+        let mut found = Vec::new();
+
+        // The records are activated once they start before the end of a record, and they are
+        // dropped once they end before its start, since the later records don't start before it
+        while let Some(b) = self.buffer.get(self.next_active) {
+            if b.start() as i64 >= end {
+                break;
+            }
+            self.active.push(self.next_active);
+            self.next_active += 1;
+        }
+        let buffer = &self.buffer;
+        self.active.retain(|&idx| buffer[idx].end() as i64 > start);
+        if !self.options.ignore_overlaps {
+            for &idx in self.active.iter() {
+                if (buffer[idx].start() as i64) < end {
+                    found.push((idx, 0));
+                }
+            }
+        }
+
+        if right {
+            // The records from this one start after the end of the record
+            let first_right = buffer.partition_point(|b| (b.start() as i64) < end);
+            let mut distances = BTreeSet::new();
+            for (idx, b) in buffer.iter().enumerate().skip(first_right) {
+                let distance = b.start() as i64 - end + 1;
+                if distances.len() >= k && !distances.contains(&distance) {
+                    break;
+                }
+                distances.insert(distance);
+                found.push((idx, distance));
+            }
+        }
+
+        if left {
+            // The records before this one end before the start of the record
+            let first_after = self
+                .by_end
+                .partition_point(|&idx| buffer[idx].end() as i64 <= start);
+            let mut distances = BTreeSet::new();
+            for &idx in self.by_end[..first_after].iter().rev() {
+                let distance = start - buffer[idx].end() as i64 + 1;
+                if distances.len() >= k && !distances.contains(&distance) {
+                    break;
+                }
+                distances.insert(distance);
+                found.push((idx, distance));
+            }
+        }
+        found
+    }
+
+    fn find_closest(&mut self, a: IA::Item) {
+        self.load_chrom(a.chrom());
+        let upstream_is_left = !a.strand().is_negative();
+        let (left, right) = match (self.options.direction, upstream_is_left) {
+            (ClosestDirection::Both, _) => (true, true),
+            (ClosestDirection::Upstream, true) | (ClosestDirection::Downstream, false) => {
+                (true, false)
+            }
+            (ClosestDirection::Upstream, false) | (ClosestDirection::Downstream, true) => {
+                (false, true)
+            }
+        };
+        let mut found = self.search(&a, left, right);
+        found.sort_by_key(|&(idx, distance)| (distance, idx));
+
+        let mut selected = Vec::new();
+        for group in found.chunk_by(|x, y| x.1 == y.1).take(self.options.k) {
+            match self.options.ties {
+                ClosestTies::All => selected.extend_from_slice(group),
+                ClosestTies::First => selected.push(group[0]),
+                ClosestTies::Last => selected.push(group[group.len() - 1]),
+            }
+        }
+
+        if selected.is_empty() {
+            self.pending.push(ClosestPair {
+                a,
+                b: None,
+                distance: -1,
+            });
+            return;
+        }
+        // The pending records are popped from the back
+        for &(idx, distance) in selected.iter().rev() {
+            let b = &self.buffer[idx];
+            let is_left = distance > 0 && b.end() <= a.start();
+            let distance = if is_left == upstream_is_left {
+                -distance
+            } else {
+                distance
+            };
+            self.pending.push(ClosestPair {
+                a: a.clone(),
+                b: Some(b.clone()),
+                distance,
+            });
+        }
+    }
+}
+
+impl<IA, IB> Iterator for ClosestIter<IA, IB>
+where
+    IA: Iterator,
+    IB: Iterator,
+    IA::Item: Region + Stranded + Clone,
+    IB::Item: Region + Clone,
+{
+    type Item = ClosestPair<IA::Item, IB::Item>;
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let a = self.iter_a.next()?;
+            self.find_closest(a);
+        }
+        self.pending.pop()
+    }
+}
+
+impl<IA, IB> Sorted for ClosestIter<IA, IB>
+where
+    IA: Iterator + Sorted,
+    IB: Iterator + Sorted,
+    IA::Item: Region + Stranded + Clone,
+    IB::Item: Region + Clone,
+{
+}
+
+pub trait SortedClosest: Iterator + Sorted + Sized {
+    /// Find the closest records in another sorted iterator for each record, similar to
+    /// `bedtools closest`. Panics if `options.k` is 0.
+    fn sorted_closest<Other>(
+        self,
+        other: Other,
+        options: ClosestOptions,
+    ) -> ClosestIter<Self, Other>
+    where
+        Other: Iterator + Sorted,
+        Self::Item: Region + Stranded + Clone,
+        Other::Item: Region + Clone,
+    {
+        assert!(options.k > 0, "Closest should report at least 1 record");
+        ClosestIter {
+            iter_a: self,
+            iter_b: other.peekable(),
+            options,
+            chrom: None,
+            buffer: Vec::new(),
+            by_end: Vec::new(),
+            active: Vec::new(),
+            next_active: 0,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<I: Iterator + Sorted> SortedClosest for I {}
+
+#[cfg(test)]
+mod test {
+    use super::{ClosestDirection, ClosestOptions, ClosestTies, SortedClosest};
+    use crate::{
+        algorithm::AssumeSorted,
+        property::{RegionCore, Serializable},
+        record::Bed3,
+        LineRecordStreamExt,
+    };
+
+    const A: &[u8] = b"chr1\t100\t200\nchr1\t500\t510\nchr1\t1000\t1010\nchr2\t10\t20\n";
+    const B: &[u8] = b"chr1\t20\t50\nchr1\t150\t160\nchr1\t300\t400\nchr1\t520\t530\n\
+        chr1\t985\t990\nchr1\t1020\t1025\n";
+
+    fn closest(options: ClosestOptions) -> Vec<String> {
+        closest_between(A, B, options)
+    }
+
+    fn closest_between(a: &[u8], b: &[u8], options: ClosestOptions) -> Vec<String> {
+        let a = a.into_record_iter::<Bed3>().assume_sorted();
+        let b = b.into_record_iter::<Bed3>().assume_sorted();
+        a.sorted_closest(b, options)
+            .map(|pair| {
+                let mut buffer = Vec::new();
+                pair.b.dump(&mut buffer).unwrap();
+                let b = String::from_utf8(buffer).unwrap();
+                format!(
+                    "{}:{}:{}",
+                    pair.a.start(),
+                    b.replace('\t', ","),
+                    pair.distance()
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sorted_closest() {
+        let default = ClosestOptions::default();
+        assert_eq!(
+            closest(default),
+            [
+                "100:chr1,150,160:0",
+                "500:chr1,520,530:11",
+                "1000:chr1,985,990:-11",
+                "1000:chr1,1020,1025:11",
+                "10:.,.,.:-1",
+            ]
+        );
+        let options = ClosestOptions {
+            ties: ClosestTies::First,
+            ignore_overlaps: true,
+            ..default
+        };
+        assert_eq!(
+            closest(options)[..3],
+            [
+                "100:chr1,20,50:-51",
+                "500:chr1,520,530:11",
+                "1000:chr1,985,990:-11"
+            ]
+        );
+        let options = ClosestOptions {
+            direction: ClosestDirection::Upstream,
+            k: 2,
+            ..default
+        };
+        assert_eq!(
+            closest(options)[..4],
+            [
+                "100:chr1,150,160:0",
+                "100:chr1,20,50:-51",
+                "500:chr1,300,400:-101",
+                "500:chr1,150,160:-341",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_closest_past_long_record() {
+        let a = b"chr1\t500\t510\nchr1\t600\t610\n";
+        let b = b"chr1\t0\t10000\nchr1\t100\t110\nchr1\t300\t310\nchr1\t20000\t20010\n";
+        let default = ClosestOptions::default();
+        assert_eq!(
+            closest_between(a, b, default),
+            ["500:chr1,0,10000:0", "600:chr1,0,10000:0"]
+        );
+        let options = ClosestOptions {
+            ignore_overlaps: true,
+            ..default
+        };
+        assert_eq!(
+            closest_between(a, b, options),
+            ["500:chr1,300,310:-191", "600:chr1,300,310:-291"]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 1 record")]
+    fn test_closest_zero_k() {
+        closest(ClosestOptions {
+            k: 0,
+            ..ClosestOptions::default()
+        });
+    }
+}
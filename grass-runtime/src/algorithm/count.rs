@@ -0,0 +1,163 @@
+use std::{
+    fmt::Display,
+    io::{Result, Write},
+};
+
+use crate::property::Serializable;
+
+/// The key of a count, which is written before the count itself, one column per key
+pub trait CountKey {
+    fn dump_key<W: Write>(&self, fp: W) -> Result<()>;
+}
+
+impl CountKey for () {
+    fn dump_key<W: Write>(&self, _fp: W) -> Result<()> {
+        Ok(())
+    }
+}
+
+macro_rules! impl_count_key_for_tuple {
+    ($($name:ident),*) => {
+        impl<$($name: Display),*> CountKey for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn dump_key<W: Write>(&self, mut fp: W) -> Result<()> {
+                let ($($name,)*) = self;
+                $(write!(fp, "{}\t", $name)?;)*
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_count_key_for_tuple!(A);
+impl_count_key_for_tuple!(A, B);
+impl_count_key_for_tuple!(A, B, C);
+impl_count_key_for_tuple!(A, B, C, D);
+impl_count_key_for_tuple!(A, B, C, D, E);
+impl_count_key_for_tuple!(A, B, C, D, E, F);
+
+/// The number of records sharing the same key
+#[derive(Clone, PartialEq, Debug)]
+pub struct RecordCount<K> {
+    pub key: K,
+    count: usize,
+}
+
+impl<K> RecordCount<K> {
+    #[inline(always)]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl<K: CountKey> Serializable for RecordCount<K> {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        self.key.dump_key(&mut fp)?;
+        write!(fp, "{}", self.count)
+    }
+}
+
+pub struct CountIter<I: Iterator, K, F> {
+    iter: I,
+    key_func: F,
+    current: Option<(K, usize)>,
+    /// The key reported when the input is empty, only counting the whole stream has one
+    empty_key: Option<K>,
+}
+
+impl<I, K, F> Iterator for CountIter<I, K, F>
+where
+    I: Iterator,
+    K: PartialEq,
+    F: FnMut(&I::Item) -> K,
+{
+    type Item = RecordCount<K>;
+    fn next(&mut self) -> Option<Self::Item> {
+        for item in self.iter.by_ref() {
+            self.empty_key = None;
+            let key = (self.key_func)(&item);
+            match self.current.as_mut() {
+                Some((current_key, count)) if *current_key == key => *count += 1,
+                _ => {
+                    if let Some((key, count)) = self.current.replace((key, 1)) {
+                        return Some(RecordCount { key, count });
+                    }
+                }
+            }
+        }
+        if let Some((key, count)) = self.current.take() {
+            return Some(RecordCount { key, count });
+        }
+        self.empty_key
+            .take()
+            .map(|key| RecordCount { key, count: 0 })
+    }
+}
+
+/// Count the records without buffering them. Like `group_by`, the records are counted by runs of
+/// the same key, so the input should be sorted by the key to get one count per key.
+pub trait CountExt: Iterator + Sized {
+    /// Count all the records in the stream, which yields exactly one count even if the stream is
+    /// empty
+    fn count_records(self) -> CountIter<Self, (), fn(&Self::Item)> {
+        CountIter {
+            iter: self,
+            key_func: |_| (),
+            current: None,
+            empty_key: Some(()),
+        }
+    }
+
+    /// Count the records for each run of the same key
+    fn count_by<K: PartialEq, F: FnMut(&Self::Item) -> K>(
+        self,
+        key_func: F,
+    ) -> CountIter<Self, K, F> {
+        CountIter {
+            iter: self,
+            key_func,
+            current: None,
+            empty_key: None,
+        }
+    }
+}
+
+impl<I: Iterator> CountExt for I {}
+
+#[cfg(test)]
+mod test {
+    use super::CountExt;
+    use crate::{
+        property::{RegionCore, Serializable},
+        record::Bed3,
+        LineRecordStreamExt,
+    };
+
+    #[test]
+    fn test_count() {
+        let input = include_bytes!("../../../data/a.bed");
+        let records = || input.into_record_iter::<Bed3>();
+
+        let total: Vec<_> = records().count_records().map(|c| c.count()).collect();
+        assert_eq!(total, vec![5]);
+        assert_eq!(
+            std::iter::empty::<Bed3>()
+                .count_records()
+                .next()
+                .unwrap()
+                .count(),
+            0
+        );
+
+        let counts: Vec<_> = records()
+            .count_by(|r| (r.chrom(), r.start() < 1000))
+            .collect();
+        assert_eq!(counts.len(), 2);
+        assert_eq!((counts[0].key.1, counts[0].count()), (true, 3));
+        assert_eq!((counts[1].key.1, counts[1].count()), (false, 2));
+
+        let mut buffer = Vec::new();
+        counts[1].dump(&mut buffer).unwrap();
+        assert_eq!(buffer, b"1\tfalse\t2");
+    }
+}
@@ -0,0 +1,399 @@
+use std::io::{Result, Write};
+
+use crate::{
+    property::{Named, RecordAt, Region, RegionCore, Scored, Serializable, Strand, Stranded},
+    ChrRef,
+};
+
+use super::{window::WindowBuffer, Sorted};
+
+/// What is reported along with the number of overlapping records
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct CoverageOptions {
+    /// Also count the overlapping records on each strand separately
+    pub by_strand: bool,
+}
+
+/// The part of a record covered by the overlapping records
+fn covered_fraction(covered: u32, length: u32) -> f64 {
+    if length == 0 {
+        0.0
+    } else {
+        covered as f64 / length as f64
+    }
+}
+
+/// The number of bases of a record at each depth, the overlaps should be sorted by their starts
+fn depth_histogram(start: u32, end: u32, overlaps: &[(u32, u32)]) -> Vec<u32> {
+    let mut events: Vec<_> = overlaps
+        .iter()
+        .flat_map(|&(s, e)| [(s, 1i32), (e, -1)])
+        .collect();
+    events.sort_unstable();
+    let mut histogram = vec![0; overlaps.len() + 1];
+    let (mut depth, mut last) = (0, start);
+    for (position, delta) in events {
+        histogram[depth as usize] += position - last;
+        depth += delta;
+        last = position;
+    }
+    histogram[0] += end - last;
+    histogram
+}
+
+/// A record along with how much it's covered by the records of another iterator, similar to a
+/// line of `bedtools coverage`
+#[derive(Clone, PartialEq)]
+pub struct Coverage<A> {
+    pub record: A,
+    count: usize,
+    /// The number of records on the positive and the negative strand, if they're counted
+    strand_counts: Option<(usize, usize)>,
+    covered: u32,
+}
+
+impl<A: Region> Coverage<A> {
+    /// The number of overlapping records
+    #[inline(always)]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+    /// The number of overlapping records on the positive strand
+    #[inline(always)]
+    pub fn forward_count(&self) -> usize {
+        self.strand_counts.map_or(0, |(forward, _)| forward)
+    }
+    /// The number of overlapping records on the negative strand
+    #[inline(always)]
+    pub fn reverse_count(&self) -> usize {
+        self.strand_counts.map_or(0, |(_, reverse)| reverse)
+    }
+    /// The number of bases covered by at least one record
+    #[inline(always)]
+    pub fn covered(&self) -> u32 {
+        self.covered
+    }
+    #[inline(always)]
+    pub fn fraction(&self) -> f64 {
+        covered_fraction(self.covered, self.record.length())
+    }
+}
+
+/// The number of bases of a record at one depth, similar to a line of `bedtools coverage -hist`
+#[derive(Clone, PartialEq)]
+pub struct DepthBin<A> {
+    pub record: A,
+    depth: usize,
+    covered: u32,
+}
+
+impl<A: Region> DepthBin<A> {
+    #[inline(always)]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+    /// The number of bases at this depth
+    #[inline(always)]
+    pub fn covered(&self) -> u32 {
+        self.covered
+    }
+    #[inline(always)]
+    pub fn fraction(&self) -> f64 {
+        covered_fraction(self.covered, self.record.length())
+    }
+}
+
+macro_rules! impl_coverage_record {
+    ($name: ident) => {
+        impl<A: Region> RegionCore for $name<A> {
+            fn start(&self) -> u32 {
+                self.record.start()
+            }
+            fn end(&self) -> u32 {
+                self.record.end()
+            }
+            fn chrom(&self) -> ChrRef<'static> {
+                self.record.chrom()
+            }
+        }
+
+        impl<A: Stranded> Stranded for $name<A> {
+            fn strand(&self) -> Strand {
+                self.record.strand()
+            }
+        }
+
+        impl<'a, A: Named<'a>> Named<'a> for $name<A> {
+            fn name(&self) -> &str {
+                self.record.name()
+            }
+        }
+
+        impl<S, A: Scored<S>> Scored<S> for $name<A> {
+            fn score(&self) -> Option<S> {
+                self.record.score()
+            }
+        }
+
+        impl<A> RecordAt<0> for $name<A> {
+            type Output<'a>
+                = &'a A
+            where
+                Self: 'a;
+            fn record_at(&self) -> &A {
+                &self.record
+            }
+        }
+    };
+}
+
+impl_coverage_record!(Coverage);
+impl_coverage_record!(DepthBin);
+
+impl<A: Region + Serializable> Serializable for Coverage<A> {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        self.record.dump(&mut fp)?;
+        write!(fp, "\t{}", self.count)?;
+        if let Some((forward, reverse)) = self.strand_counts {
+            write!(fp, "\t{}\t{}", forward, reverse)?;
+        }
+        let length = self.record.length();
+        write!(fp, "\t{}\t{}\t{:.7}", self.covered, length, self.fraction())
+    }
+}
+
+impl<A: Region + Serializable> Serializable for DepthBin<A> {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        self.record.dump(&mut fp)?;
+        let length = self.record.length();
+        write!(
+            fp,
+            "\t{}\t{}\t{}\t{:.7}",
+            self.depth,
+            self.covered,
+            length,
+            self.fraction()
+        )
+    }
+}
+
+/// Find the parts of each record of a sorted iterator overlapping the records of another sorted
+/// iterator
+struct OverlapSweep<IB: Iterator> {
+    buffer: WindowBuffer<IB>,
+    /// The overlapping parts of the current record, in the order of their starts
+    overlaps: Vec<(u32, u32)>,
+}
+
+impl<IB> OverlapSweep<IB>
+where
+    IB: Iterator,
+    IB::Item: Region + Stranded,
+{
+    fn new(iter: IB) -> Self {
+        Self {
+            buffer: WindowBuffer::new(iter),
+            overlaps: Vec::new(),
+        }
+    }
+
+    /// Collect the overlaps of a record and the number of overlapping records on each strand
+    fn sweep<A: Region>(&mut self, record: &A) -> (usize, usize) {
+        let (start, end) = (record.start(), record.end());
+        let overlaps = &mut self.overlaps;
+        let mut strand_counts = (0, 0);
+        overlaps.clear();
+        self.buffer.scan(record, (start, end), 0, |b| {
+            overlaps.push((b.start().max(start), b.end().min(end)));
+            match b.strand() {
+                Strand::Positive => strand_counts.0 += 1,
+                Strand::Negative => strand_counts.1 += 1,
+                Strand::Unknown => (),
+            }
+        });
+        strand_counts
+    }
+
+    fn covered(&self) -> u32 {
+        let mut covered = 0;
+        let mut cursor = 0;
+        for &(start, end) in self.overlaps.iter() {
+            let start = start.max(cursor);
+            if start < end {
+                covered += end - start;
+                cursor = end;
+            }
+        }
+        covered
+    }
+}
+
+/// Measure how much each record of a sorted iterator is covered by the records of another sorted
+/// iterator, similar to `bedtools coverage`
+pub struct CoverageIter<IA, IB: Iterator> {
+    iter_a: IA,
+    sweep: OverlapSweep<IB>,
+    options: CoverageOptions,
+}
+
+impl<IA, IB> Iterator for CoverageIter<IA, IB>
+where
+    IA: Iterator,
+    IB: Iterator,
+    IA::Item: Region,
+    IB::Item: Region + Stranded,
+{
+    type Item = Coverage<IA::Item>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.iter_a.next()?;
+        let strand_counts = self.sweep.sweep(&record);
+        Some(Coverage {
+            count: self.sweep.overlaps.len(),
+            strand_counts: Some(strand_counts).filter(|_| self.options.by_strand),
+            covered: self.sweep.covered(),
+            record,
+        })
+    }
+}
+
+impl<IA, IB> Sorted for CoverageIter<IA, IB>
+where
+    IA: Iterator + Sorted,
+    IB: Iterator + Sorted,
+    IA::Item: Region,
+    IB::Item: Region + Stranded,
+{
+}
+
+/// Report the number of bases at each depth for each record of a sorted iterator, including the
+/// uncovered bases at depth 0, similar to `bedtools coverage -hist`
+pub struct DepthHistogramIter<IA: Iterator, IB: Iterator> {
+    iter_a: IA,
+    sweep: OverlapSweep<IB>,
+    /// The bins of the current record, which are popped from the back
+    bins: Vec<DepthBin<IA::Item>>,
+}
+
+impl<IA, IB> Iterator for DepthHistogramIter<IA, IB>
+where
+    IA: Iterator,
+    IB: Iterator,
+    IA::Item: Region + Clone,
+    IB::Item: Region + Stranded,
+{
+    type Item = DepthBin<IA::Item>;
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.bins.is_empty() {
+            let record = self.iter_a.next()?;
+            self.sweep.sweep(&record);
+            let histogram = depth_histogram(record.start(), record.end(), &self.sweep.overlaps);
+            for (depth, &covered) in histogram.iter().enumerate().rev() {
+                if covered > 0 {
+                    let record = record.clone();
+                    self.bins.push(DepthBin {
+                        record,
+                        depth,
+                        covered,
+                    });
+                }
+            }
+        }
+        self.bins.pop()
+    }
+}
+
+impl<IA, IB> Sorted for DepthHistogramIter<IA, IB>
+where
+    IA: Iterator + Sorted,
+    IB: Iterator + Sorted,
+    IA::Item: Region + Clone,
+    IB::Item: Region + Stranded,
+{
+}
+
+pub trait SortedCoverage: Iterator + Sorted + Sized {
+    /// Measure how much each record is covered by the records of another sorted iterator
+    fn sorted_coverage<Other>(
+        self,
+        other: Other,
+        options: CoverageOptions,
+    ) -> CoverageIter<Self, Other>
+    where
+        Other: Iterator + Sorted,
+        Self::Item: Region,
+        Other::Item: Region + Stranded,
+    {
+        CoverageIter {
+            iter_a: self,
+            sweep: OverlapSweep::new(other),
+            options,
+        }
+    }
+
+    /// Report the number of bases at each depth for each record
+    fn sorted_depth_histogram<Other>(self, other: Other) -> DepthHistogramIter<Self, Other>
+    where
+        Other: Iterator + Sorted,
+        Self::Item: Region + Clone,
+        Other::Item: Region + Stranded,
+    {
+        DepthHistogramIter {
+            iter_a: self,
+            sweep: OverlapSweep::new(other),
+            bins: Vec::new(),
+        }
+    }
+}
+
+impl<I: Iterator + Sorted> SortedCoverage for I {}
+
+#[cfg(test)]
+mod test {
+    use super::{CoverageOptions, SortedCoverage};
+    use crate::{algorithm::AssumeSorted, record::Bed6, test_utils::dump, LineRecordStreamExt};
+
+    const A: &[u8] = b"chr1\t0\t100\ta\t0\t+\nchr1\t150\t160\tb\t0\t-\nchr2\t0\t10\tc\t0\t+\n";
+    const B: &[u8] = b"chr1\t10\t30\tx\t0\t+\nchr1\t20\t40\ty\t0\t-\nchr1\t90\t155\tz\t0\t+\n";
+
+    fn coverage(options: CoverageOptions) -> Vec<String> {
+        let a = A.into_record_iter::<Bed6>().assume_sorted();
+        let b = B.into_record_iter::<Bed6>().assume_sorted();
+        a.sorted_coverage(b, options).map(dump).collect()
+    }
+
+    #[test]
+    fn test_coverage() {
+        assert_eq!(
+            coverage(CoverageOptions { by_strand: false }),
+            [
+                "chr1\t0\t100\ta\t0\t+\t3\t40\t100\t0.4000000",
+                "chr1\t150\t160\tb\t0\t-\t1\t5\t10\t0.5000000",
+                "chr2\t0\t10\tc\t0\t+\t0\t0\t10\t0.0000000",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_coverage_by_strand() {
+        assert_eq!(
+            coverage(CoverageOptions { by_strand: true }),
+            [
+                "chr1\t0\t100\ta\t0\t+\t3\t2\t1\t40\t100\t0.4000000",
+                "chr1\t150\t160\tb\t0\t-\t1\t1\t0\t5\t10\t0.5000000",
+                "chr2\t0\t10\tc\t0\t+\t0\t0\t0\t0\t10\t0.0000000",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_depth_histogram() {
+        let a = A.into_record_iter::<Bed6>().assume_sorted();
+        let b = B.into_record_iter::<Bed6>().assume_sorted();
+        let bins: Vec<_> = a
+            .sorted_depth_histogram(b)
+            .take(3)
+            .map(|bin| (bin.depth(), bin.covered()))
+            .collect();
+        assert_eq!(bins, [(0, 60), (1, 30), (2, 10)]);
+    }
+}
@@ -0,0 +1,520 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::{Result, Write},
+};
+
+use crate::{
+    builtin::report_error,
+    property::{Region, RegionCore, Scored, Serializable, Strand, Stranded},
+    ChrRef, Genome,
+};
+
+use super::{
+    components::{ComponentsIter, TaggedComponent, TaggedComponentExt},
+    Components, Sorted,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct GenomeCoverageOptions {
+    /// Also report the runs of bases which aren't covered, similar to `bedtools genomecov -bga`
+    pub include_zero: bool,
+    /// Only count the records on this strand
+    pub strand: Option<Strand>,
+    /// The factor the depth is multiplied with in the reported value
+    pub scale: f64,
+}
+
+impl Default for GenomeCoverageOptions {
+    fn default() -> Self {
+        Self {
+            include_zero: false,
+            strand: None,
+            scale: 1.0,
+        }
+    }
+}
+
+/// A run of bases at the same depth, which is a line of a bedGraph file
+#[derive(Clone, Copy, PartialEq)]
+pub struct BedGraphSegment {
+    pub chrom: ChrRef<'static>,
+    pub start: u32,
+    pub end: u32,
+    depth: usize,
+    scale: f64,
+}
+
+impl BedGraphSegment {
+    #[inline(always)]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+impl RegionCore for BedGraphSegment {
+    fn start(&self) -> u32 {
+        self.start
+    }
+    fn end(&self) -> u32 {
+        self.end
+    }
+    fn chrom(&self) -> ChrRef<'static> {
+        self.chrom
+    }
+}
+
+/// The scaled depth, which is the value of the bedGraph line
+impl Scored<f64> for BedGraphSegment {
+    fn score(&self) -> Option<f64> {
+        Some(self.depth as f64 * self.scale)
+    }
+}
+
+impl Serializable for BedGraphSegment {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        write!(
+            fp,
+            "{}\t{}\t{}\t{}",
+            self.chrom.get_chr_name(),
+            self.start,
+            self.end,
+            self.depth as f64 * self.scale
+        )
+    }
+}
+
+type StrandFilter<R> = fn(&R) -> bool;
+
+/// Report the depth of the records of a sorted iterator along the genome as bedGraph segments,
+/// similar to `bedtools genomecov -bg`. The chromosomes are taken from the genome in its order,
+/// the ones without any record are reported as uncovered. A chromosome which isn't in the genome
+/// is reported up to its last covered base. The records of a chromosome coming after the later
+/// ones in the genome are skipped with an error, since the chromosome has been reported.
+pub struct GenomeCoverageIter<I>
+where
+    I: Iterator + Sorted,
+    I::Item: Region + Clone,
+{
+    components: TaggedComponent<ComponentsIter<I>, I::Item, bool, StrandFilter<I::Item>>,
+    include_zero: bool,
+    scale: f64,
+    /// The chromosome being scanned, along with the position and the depth since the last change
+    current: Option<(ChrRef<'static>, u32, usize)>,
+    /// The size of the current chromosome, the bases after it are not reported
+    limit: u32,
+    /// The id of the first chromosome in the genome which isn't reported yet
+    next_chrom_id: usize,
+    /// The last segment, which may be extended by the following one at the same depth
+    last: Option<BedGraphSegment>,
+    /// The chromosome out of the genome order, whose records are skipped
+    skipped: Option<ChrRef<'static>>,
+    ready: VecDeque<BedGraphSegment>,
+    done: bool,
+}
+
+impl<I> GenomeCoverageIter<I>
+where
+    I: Iterator + Sorted,
+    I::Item: Region + Clone + Stranded,
+{
+    fn new(iter: I, options: GenomeCoverageOptions) -> Self {
+        // The records on the other strand are tagged as false, so they are not counted
+        let counted: StrandFilter<I::Item> = match options.strand {
+            Some(Strand::Positive) => |record| record.strand().is_positive(),
+            Some(Strand::Negative) => |record| record.strand().is_negative(),
+            _ => |_| true,
+        };
+        Self {
+            components: iter.components().with_tag(counted),
+            include_zero: options.include_zero,
+            scale: options.scale,
+            current: None,
+            limit: u32::MAX,
+            next_chrom_id: 0,
+            last: None,
+            skipped: None,
+            ready: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    fn push_segment(&mut self, chrom: ChrRef<'static>, start: u32, end: u32, depth: usize) {
+        if start >= end {
+            return;
+        }
+        if let Some(last) = self.last.as_mut() {
+            if last.chrom == chrom && last.end == start && last.depth == depth {
+                last.end = end;
+                return;
+            }
+        }
+        let segment = BedGraphSegment {
+            chrom,
+            start,
+            end,
+            depth,
+            scale: self.scale,
+        };
+        if let Some(last) = self.last.replace(segment) {
+            self.ready.push_back(last);
+        }
+    }
+
+    /// Report the rest of the current chromosome, which isn't covered
+    fn finish_chrom(&mut self) {
+        if let Some((chrom, start, depth)) = self.current.take() {
+            let end = if self.limit < u32::MAX {
+                self.limit
+            } else {
+                start
+            };
+            self.push_segment(chrom, start, end, depth);
+        }
+    }
+
+    /// Report the chromosomes in the genome before the given one, or all of the remaining ones
+    fn report_empty_chroms(&mut self, until: Option<ChrRef<'static>>) {
+        let until = match until {
+            Some(chrom) => match chrom.id() {
+                Some(id) => id,
+                // A chromosome not in the genome doesn't have a place in it
+                None => return,
+            },
+            None => usize::MAX,
+        };
+        while self.next_chrom_id < until {
+            let chrom = match Genome::get_chr_by_id(self.next_chrom_id) {
+                Some(chrom) => chrom,
+                None => break,
+            };
+            if let Some(size) = chrom.get_chr_size() {
+                self.push_segment(chrom, 0, size as u32, 0);
+            }
+            self.next_chrom_id += 1;
+        }
+        self.next_chrom_id = self.next_chrom_id.max(until.saturating_add(1));
+    }
+
+    fn update(&mut self, chrom: ChrRef<'static>, position: u32, depth: usize) {
+        if !matches!(self.current, Some((current, ..)) if current == chrom) {
+            if self.skipped == Some(chrom) {
+                return;
+            }
+            if chrom.id().is_some_and(|id| id < self.next_chrom_id) {
+                report_error(format!(
+                    "The records of chromosome {} don't follow the order of the genome",
+                    chrom
+                ));
+                self.skipped = Some(chrom);
+                return;
+            }
+            self.finish_chrom();
+            self.report_empty_chroms(Some(chrom));
+            self.limit = chrom.get_chr_size().map_or(u32::MAX, |size| size as u32);
+        }
+        let position = position.min(self.limit);
+        match self.current {
+            Some((current, start, last_depth)) if current == chrom => {
+                self.push_segment(chrom, start, position, last_depth);
+            }
+            _ => self.push_segment(chrom, 0, position, 0),
+        }
+        self.current = Some((chrom, position, depth));
+    }
+}
+
+impl<I> Iterator for GenomeCoverageIter<I>
+where
+    I: Iterator + Sorted,
+    I::Item: Region + Clone + Stranded,
+{
+    type Item = BedGraphSegment;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while let Some(segment) = self.ready.pop_front() {
+                if self.include_zero || segment.depth > 0 {
+                    return Some(segment);
+                }
+            }
+            if self.done {
+                return None;
+            }
+            match self.components.next() {
+                Some((true, component)) => {
+                    let (chrom, position) = component.position();
+                    self.update(chrom, position, component.depth());
+                }
+                Some((false, _)) => (),
+                None => {
+                    self.finish_chrom();
+                    self.report_empty_chroms(None);
+                    self.ready.extend(self.last.take());
+                    self.done = true;
+                }
+            }
+        }
+    }
+}
+
+impl<I> Sorted for GenomeCoverageIter<I>
+where
+    I: Iterator + Sorted,
+    I::Item: Region + Clone + Stranded,
+{
+}
+
+/// The number of bases at one depth in a chromosome or in the whole genome, similar to a line of
+/// `bedtools genomecov`
+#[derive(Clone, Copy, PartialEq)]
+pub struct GenomeHistogramLine {
+    /// The chromosome, `None` for the whole genome
+    chrom: Option<ChrRef<'static>>,
+    depth: usize,
+    covered: u64,
+    size: u64,
+}
+
+impl GenomeHistogramLine {
+    /// The chromosome of the line, which is named `genome` for the whole genome
+    pub fn chrom(&self) -> ChrRef<'static> {
+        self.chrom.unwrap_or(ChrRef::Unassigned("genome"))
+    }
+    #[inline(always)]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+    /// The number of bases at this depth
+    #[inline(always)]
+    pub fn covered(&self) -> u64 {
+        self.covered
+    }
+    /// The size of the chromosome or the genome
+    #[inline(always)]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+    #[inline(always)]
+    pub fn fraction(&self) -> f64 {
+        self.covered as f64 / self.size as f64
+    }
+}
+
+impl Serializable for GenomeHistogramLine {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        write!(
+            fp,
+            "{}\t{}\t{}\t{}\t{}",
+            self.chrom().get_chr_name(),
+            self.depth,
+            self.covered,
+            self.size,
+            self.fraction()
+        )
+    }
+}
+
+/// Report the number of bases at each depth for each chromosome, and then for the whole genome,
+/// similar to `bedtools genomecov`
+pub struct GenomeHistogramIter<I>
+where
+    I: Iterator + Sorted,
+    I::Item: Region + Clone,
+{
+    segments: GenomeCoverageIter<I>,
+    chrom: Option<ChrRef<'static>>,
+    chrom_histogram: BTreeMap<usize, u64>,
+    genome_histogram: BTreeMap<usize, u64>,
+    ready: VecDeque<GenomeHistogramLine>,
+    done: bool,
+}
+
+impl<I> GenomeHistogramIter<I>
+where
+    I: Iterator + Sorted,
+    I::Item: Region + Clone + Stranded,
+{
+    fn report(&mut self, chrom: Option<ChrRef<'static>>, histogram: &BTreeMap<usize, u64>) {
+        let size = histogram.values().sum();
+        self.ready.extend(
+            histogram
+                .iter()
+                .map(|(&depth, &covered)| GenomeHistogramLine {
+                    chrom,
+                    depth,
+                    covered,
+                    size,
+                }),
+        );
+    }
+
+    fn finish_chrom(&mut self) {
+        let histogram = std::mem::take(&mut self.chrom_histogram);
+        if let Some(chrom) = self.chrom.take() {
+            self.report(Some(chrom), &histogram);
+        }
+    }
+}
+
+impl<I> Iterator for GenomeHistogramIter<I>
+where
+    I: Iterator + Sorted,
+    I::Item: Region + Clone + Stranded,
+{
+    type Item = GenomeHistogramLine;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(line) = self.ready.pop_front() {
+                return Some(line);
+            }
+            if self.done {
+                return None;
+            }
+            match self.segments.next() {
+                Some(segment) => {
+                    if self.chrom != Some(segment.chrom) {
+                        self.finish_chrom();
+                        self.chrom = Some(segment.chrom);
+                    }
+                    let length = (segment.end - segment.start) as u64;
+                    *self.chrom_histogram.entry(segment.depth).or_default() += length;
+                    *self.genome_histogram.entry(segment.depth).or_default() += length;
+                }
+                None => {
+                    self.finish_chrom();
+                    let histogram = std::mem::take(&mut self.genome_histogram);
+                    self.report(None, &histogram);
+                    self.done = true;
+                }
+            }
+        }
+    }
+}
+
+pub trait GenomeCoverageExt: Iterator + Sorted + Sized
+where
+    Self::Item: Region + Clone + Stranded,
+{
+    /// Report the depth of the records along the genome as bedGraph segments
+    fn genome_coverage(self, options: GenomeCoverageOptions) -> GenomeCoverageIter<Self> {
+        GenomeCoverageIter::new(self, options)
+    }
+
+    /// Report the number of bases at each depth for each chromosome and the whole genome, only the
+    /// strand of the options is used
+    fn genome_histogram(self, options: GenomeCoverageOptions) -> GenomeHistogramIter<Self> {
+        let options = GenomeCoverageOptions {
+            include_zero: true,
+            scale: 1.0,
+            ..options
+        };
+        GenomeHistogramIter {
+            segments: GenomeCoverageIter::new(self, options),
+            chrom: None,
+            chrom_histogram: BTreeMap::new(),
+            genome_histogram: BTreeMap::new(),
+            ready: VecDeque::new(),
+            done: false,
+        }
+    }
+}
+
+impl<I> GenomeCoverageExt for I
+where
+    I: Iterator + Sorted,
+    I::Item: Region + Clone + Stranded,
+{
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GenomeCoverageExt, GenomeCoverageOptions};
+    use crate::{
+        algorithm::AssumeSorted,
+        property::{RegionCore, Strand},
+        record::Bed6,
+        test_utils::dump,
+        LineRecordStreamExt,
+    };
+
+    // The chromosomes are not in the genome, so they are reported up to the last covered base. The
+    // chromosomes of the genome are reported after them, which may be loaded by the other tests.
+    // See tests/genome_coverage.rs for the coverage with a loaded genome.
+    const INPUT: &[u8] = b"cov1\t10\t30\ta\t0\t+\ncov1\t20\t40\tb\t0\t-\ncov1\t40\t50\tc\t0\t+\n\
+        cov2\t5\t10\td\t0\t-\n";
+
+    #[test]
+    fn test_bed_graph() {
+        let input = INPUT.into_record_iter::<Bed6>().assume_sorted();
+        let lines: Vec<_> = input
+            .genome_coverage(Default::default())
+            .take(4)
+            .map(dump)
+            .collect();
+        // The segments at the same depth are merged
+        assert_eq!(
+            lines,
+            [
+                "cov1\t10\t20\t1",
+                "cov1\t20\t30\t2",
+                "cov1\t30\t50\t1",
+                "cov2\t5\t10\t1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bed_graph_with_zero_and_scale() {
+        let input = INPUT.into_record_iter::<Bed6>().assume_sorted();
+        let options = GenomeCoverageOptions {
+            include_zero: true,
+            scale: 0.5,
+            ..Default::default()
+        };
+        let lines: Vec<_> = input.genome_coverage(options).take(6).map(dump).collect();
+        assert_eq!(
+            lines,
+            [
+                "cov1\t0\t10\t0",
+                "cov1\t10\t20\t0.5",
+                "cov1\t20\t30\t1",
+                "cov1\t30\t50\t0.5",
+                "cov2\t0\t5\t0",
+                "cov2\t5\t10\t0.5",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bed_graph_on_strand() {
+        let input = INPUT.into_record_iter::<Bed6>().assume_sorted();
+        let options = GenomeCoverageOptions {
+            strand: Some(Strand::Negative),
+            ..Default::default()
+        };
+        let segments: Vec<_> = input
+            .genome_coverage(options)
+            .map(|segment| (segment.start(), segment.end(), segment.depth()))
+            .collect();
+        assert_eq!(segments, [(20, 40, 1), (5, 10, 1)]);
+    }
+
+    #[test]
+    fn test_histogram() {
+        let input = INPUT.into_record_iter::<Bed6>().assume_sorted();
+        let lines: Vec<_> = input
+            .genome_histogram(Default::default())
+            .take(5)
+            .map(dump)
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "cov1\t0\t10\t50\t0.2",
+                "cov1\t1\t30\t50\t0.6",
+                "cov1\t2\t10\t50\t0.2",
+                "cov2\t0\t5\t10\t0.5",
+                "cov2\t1\t5\t10\t0.5",
+            ]
+        );
+    }
+}
@@ -3,12 +3,13 @@
 use std::{
     borrow::Borrow,
     cell::{Cell, RefCell},
+    io::{Result, Write},
     rc::Rc,
 };
 
 use crate::{
-    property::{Region, RegionCore},
-    record::ToSelfContained,
+    property::{GroupOps, RecordAt, Region, RegionCore, Serializable},
+    record::{Bed3, ToSelfContained},
     ChrRef,
 };
 
@@ -26,11 +27,17 @@ impl<K: ToOwned, T: 'static + Region> GroupBuffer<K, T> {
     {
         GroupOverlap(self)
     }
+    pub fn key(&self) -> &<K as ToOwned>::Owned {
+        &self.key
+    }
+    pub fn records(&self) -> &[T] {
+        self.buffer.as_slice()
+    }
     fn compute_overlap(&self) {
         let mut ret: Option<(_, u32, u32)> = None;
         for region in self.buffer.iter() {
             if let Some(cur) = ret {
-                if region.overlaps(&cur) {
+                if !region.overlaps(&cur) {
                     ret = None;
                     break;
                 } else {
@@ -97,8 +104,44 @@ impl<K: ToOwned, T: 'static + Region> RegionCore for GroupBuffer<K, T> {
         self.get_outline().map_or(ChrRef::Dummy, |(c, _, _)| c)
     }
 }
+impl<K: ToOwned, T: 'static + Region> GroupOps for GroupBuffer<K, T> {
+    fn component(&self, idx: usize) -> &dyn RegionCore {
+        match self.buffer.get(idx) {
+            Some(record) => record,
+            None => &None::<T>,
+        }
+    }
+    fn size(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+/// A group may have fewer than N + 1 members, in which case the member is missing
+impl<const N: usize, K: ToOwned, T: 'static + Region> RecordAt<N> for GroupBuffer<K, T> {
+    type Output<'a>
+        = Option<&'a T>
+    where
+        Self: 'a;
+    fn record_at(&self) -> Option<&T> {
+        self.buffer.get(N)
+    }
+}
+
+impl<K: ToOwned, T: 'static + Region> Serializable for GroupBuffer<K, T> {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        Bed3::new(self).dump(&mut fp)?;
+        write!(fp, "\t{}", self.size())
+    }
+}
+
 pub struct GroupOverlap<K: ToOwned, T: 'static + Region>(GroupBuffer<K, T>);
 
+impl<K: ToOwned, T: 'static + Region> GroupOverlap<K, T> {
+    pub fn group(&self) -> &GroupBuffer<K, T> {
+        &self.0
+    }
+}
+
 impl<K: ToOwned, T: 'static + Region> RegionCore for GroupOverlap<K, T> {
     fn start(&self) -> u32 {
         self.0.get_overlap().map_or(0, |(_, s, _)| s)
@@ -113,6 +156,12 @@ impl<K: ToOwned, T: 'static + Region> RegionCore for GroupOverlap<K, T> {
     }
 }
 
+impl<K: ToOwned, T: 'static + Region> Serializable for GroupOverlap<K, T> {
+    fn dump<W: Write>(&self, fp: W) -> Result<()> {
+        Bed3::new(self).dump(fp)
+    }
+}
+
 pub struct Groups<'a, K, I, F>
 where
     K: ToOwned + PartialEq,
@@ -122,6 +171,20 @@ where
     inner: itertools::Groups<'a, K, I, F>,
 }
 
+impl<'a, K, I, F> Groups<'a, K, I, F>
+where
+    K: ToOwned + PartialEq,
+    I: Iterator,
+    I::Item: ToSelfContained,
+    F: FnMut(&I::Item) -> K,
+{
+    pub fn new(group_by: &'a itertools::GroupBy<K, I, F>) -> Self {
+        Self {
+            inner: group_by.into_iter(),
+        }
+    }
+}
+
 impl<'a, K, I, F> Iterator for Groups<'a, K, I, F>
 where
     K: ToOwned + PartialEq,
@@ -144,23 +207,57 @@ where
 #[cfg(test)]
 mod test {
     use crate::{
-        algorithm::{AssumeSorted, Components},
+        algorithm::{AssumeSorted, Groups},
+        property::{GroupOps, RecordAt, RegionCore},
         record::Bed3,
-        LineRecordStreamExt,
+        test_utils::dump,
+        Itertools, LineRecordStreamExt,
     };
 
     #[test]
     fn test_group_by() -> Result<(), Box<dyn std::error::Error>> {
         let input = include_bytes!("../../../data/a.bed");
         let bed3 = input.into_record_iter::<Bed3>().assume_sorted();
-        let comp_iter = bed3.components();
-        /*let mut idx = 0;
-        comp_iter.grou(move |comp| {
-            if comp.depth() == 0 {
-            idx += 1;
-            }
-            idx
-        )*/
+        let group_by = bed3.group_by(|item| item.start() < 1000);
+        let groups: Vec<_> = Groups::new(&group_by).collect();
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].size(), 3);
+        assert_eq!((groups[0].start(), groups[0].end()), (10, 110));
+        assert_eq!(groups[1].size(), 2);
+        assert_eq!((groups[1].start(), groups[1].end()), (1000, 8000));
+
+        let overlap = groups.into_iter().next().unwrap().overlap();
+        assert_eq!((overlap.start(), overlap.end()), (30, 50));
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_record_at_short_group() {
+        let input = include_bytes!("../../../data/a.bed");
+        let bed3 = input.into_record_iter::<Bed3>().assume_sorted();
+        let group_by = bed3.group_by(|item| item.start() < 1000);
+        let groups: Vec<_> = Groups::new(&group_by).collect();
+
+        let short = &groups[1];
+        assert_eq!(short.size(), 2);
+        let first = RecordAt::<0>::record_at(short);
+        assert_eq!((first.start(), first.end()), (1000, 2000));
+        assert!(RecordAt::<2>::record_at(short).is_none());
+        assert_eq!(RecordAt::<5>::record_at(short).start(), 0);
+        assert_eq!(dump(RecordAt::<5>::record_at(short)), ".\t.\t.");
+    }
+
+    #[test]
+    fn test_component_out_of_range() {
+        let input = include_bytes!("../../../data/a.bed");
+        let bed3 = input.into_record_iter::<Bed3>().assume_sorted();
+        let group_by = bed3.group_by(|item| item.start() < 1000);
+        let groups: Vec<_> = Groups::new(&group_by).collect();
+
+        let short = &groups[1];
+        assert_eq!(short.component(1).end(), 8000);
+        let missing = short.component(2);
+        assert_eq!((missing.start(), missing.end()), (0, 0));
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use crate::property::Region;
+
+// An implicit augmented interval tree: the regions are sorted by start position and the tree is
+// laid out in-order over the sorted array, so that we only need to keep the max end position of
+// each subtree.
+struct ChromIndex<T: Region> {
+    regions: Vec<T>,
+    max_end: Vec<u32>,
+    max_level: usize,
+}
+
+impl<T: Region> ChromIndex<T> {
+    fn new(mut regions: Vec<T>) -> Self {
+        regions.sort_by_key(|r| r.start());
+        let mut ret = Self {
+            max_end: regions.iter().map(|r| r.end()).collect(),
+            regions,
+            max_level: 0,
+        };
+        ret.build();
+        ret
+    }
+
+    fn build(&mut self) {
+        let n = self.regions.len();
+        if n == 0 {
+            return;
+        }
+        let mut last_idx = 0;
+        let mut last = 0;
+        for idx in (0..n).step_by(2) {
+            last_idx = idx;
+            last = self.max_end[idx];
+        }
+        let mut level = 1;
+        while 1 << level <= n {
+            let x = 1 << (level - 1);
+            let first = (x << 1) - 1;
+            let step = x << 2;
+            for idx in (first..n).step_by(step) {
+                let left = self.max_end[idx - x];
+                let right = if idx + x < n {
+                    self.max_end[idx + x]
+                } else {
+                    last
+                };
+                self.max_end[idx] = self.max_end[idx].max(left).max(right);
+            }
+            last_idx = if (last_idx >> level) & 1 != 0 {
+                last_idx - x
+            } else {
+                last_idx + x
+            };
+            if last_idx < n && self.max_end[last_idx] > last {
+                last = self.max_end[last_idx];
+            }
+            level += 1;
+        }
+        self.max_level = level - 1;
+    }
+
+    fn query(&self, start: u32, end: u32, result: &mut Vec<usize>) {
+        let n = self.regions.len();
+        if n == 0 {
+            return;
+        }
+        let mut stack = vec![((1usize << self.max_level) - 1, self.max_level, false)];
+        while let Some((node, level, visited)) = stack.pop() {
+            if level <= 3 {
+                // The subtree is small enough, just do a linear scan
+                let first = node >> level << level;
+                let last = (first + (1 << (level + 1)) - 1).min(n);
+                for idx in first..last {
+                    if self.regions[idx].start() >= end {
+                        break;
+                    }
+                    if start < self.regions[idx].end() {
+                        result.push(idx);
+                    }
+                }
+            } else if !visited {
+                stack.push((node, level, true));
+                let left = node - (1 << (level - 1));
+                if left >= n || self.max_end[left] > start {
+                    stack.push((left, level - 1, false));
+                }
+            } else if node < n && self.regions[node].start() < end {
+                if start < self.regions[node].end() {
+                    result.push(node);
+                }
+                stack.push((node + (1 << (level - 1)), level - 1, false));
+            }
+        }
+    }
+}
+
+/// An in-memory index of regions which answers overlap queries without requiring any input to
+/// be sorted
+pub struct IntervalIndex<T: Region> {
+    chroms: HashMap<usize, ChromIndex<T>>,
+    size: usize,
+}
+
+impl<T: Region> FromIterator<T> for IntervalIndex<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut buckets: HashMap<usize, Vec<T>> = HashMap::new();
+        let mut size = 0;
+        for region in iter {
+            let chrom_id = region.chrom().get_id_or_update();
+            buckets.entry(chrom_id).or_default().push(region);
+            size += 1;
+        }
+        Self {
+            chroms: buckets
+                .into_iter()
+                .map(|(chrom_id, regions)| (chrom_id, ChromIndex::new(regions)))
+                .collect(),
+            size,
+        }
+    }
+}
+
+impl<T: Region> IntervalIndex<T> {
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Find all the indexed regions that overlaps the given region
+    pub fn query<R: Region>(&self, region: &R) -> Vec<&T> {
+        let mut slots = Vec::new();
+        self.query_slots(region, &mut slots);
+        slots.into_iter().map(|slot| self.get_slot(slot)).collect()
+    }
+
+    /// Find the slots, aka (chrom_id, offset) pairs, of the regions overlapping the given region
+    pub(crate) fn query_slots<R: Region>(&self, region: &R, result: &mut Vec<(usize, usize)>) {
+        let chrom_id = match region.chrom().id() {
+            Some(id) => id,
+            None => return,
+        };
+        if let Some(chrom_idx) = self.chroms.get(&chrom_id) {
+            let mut buffer = Vec::new();
+            chrom_idx.query(region.start(), region.end(), &mut buffer);
+            result.extend(
+                buffer
+                    .into_iter()
+                    .filter(|&idx| chrom_idx.regions[idx].overlaps(region))
+                    .map(|idx| (chrom_id, idx)),
+            );
+        }
+    }
+
+    pub(crate) fn get_slot(&self, slot: (usize, usize)) -> &T {
+        &self.chroms[&slot.0].regions[slot.1]
+    }
+
+    /// Iterate over all the slots in the index, ordered by chromosome and start position
+    pub(crate) fn slots(&self) -> Vec<(usize, usize)> {
+        let mut chrom_ids: Vec<_> = self.chroms.keys().copied().collect();
+        chrom_ids.sort_unstable();
+        chrom_ids
+            .into_iter()
+            .flat_map(|chrom_id| {
+                let size = self.chroms[&chrom_id].regions.len();
+                (0..size).map(move |idx| (chrom_id, idx))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::IntervalIndex;
+    use crate::{property::RegionCore, record::Bed3, LineRecordStreamExt};
+
+    #[test]
+    fn test_interval_index() {
+        let records: Vec<_> = include_bytes!("../../../data/a.bed")
+            .into_record_iter::<Bed3>()
+            .collect();
+        let index: IntervalIndex<_> = records.iter().copied().collect();
+        assert_eq!(index.len(), 5);
+
+        let probe = Bed3 {
+            chrom: records[0].chrom,
+            start: 105,
+            end: 1200,
+        };
+        let mut result: Vec<_> = index.query(&probe).iter().map(|r| r.start()).collect();
+        result.sort_unstable();
+        assert_eq!(result, vec![30, 1000]);
+    }
+}
@@ -40,9 +40,9 @@ impl<T: Region> RegionHeap<T> {
 
     fn adjust_up(&mut self, mut node: usize) {
         let node_key = self.data[node].end();
-        while node > 0 && self.data[node / 2].end() > node_key {
-            self.data.swap(node / 2, node);
-            node /= 2;
+        while node > 0 && self.data[(node - 1) / 2].end() > node_key {
+            self.data.swap((node - 1) / 2, node);
+            node = (node - 1) / 2;
         }
     }
 
@@ -72,3 +72,24 @@ impl<T: Region> RegionHeap<T> {
         self.data.len()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::RegionHeap;
+    use crate::{property::RegionCore, ChrRef};
+
+    #[test]
+    fn test_pop_in_order_of_end() {
+        let mut heap = RegionHeap::<(ChrRef, u32, u32)>::default();
+        let mut popped = Vec::new();
+        // A zero pops the region with the smallest end
+        for end in [20, 12, 31, 45, 0, 44, 36, 70, 0, 56, 0, 0] {
+            if end == 0 {
+                popped.extend(heap.pop().map(|region| region.end()));
+            } else {
+                heap.push((ChrRef::Dummy, 0, end));
+            }
+        }
+        assert_eq!(popped, vec![12, 20, 31, 36]);
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+
+use crate::{algorithm::IntervalIndex, property::Region};
+
+// The shared state of all the indexed intersection flavors: the record we are currently probing
+// and the slots of the indexed records that overlaps it.
+struct Probe<I: Iterator, T: Region> {
+    iter: I,
+    index: IntervalIndex<T>,
+    current: Option<I::Item>,
+    slots: Vec<(usize, usize)>,
+    offset: usize,
+}
+
+impl<I, T> Probe<I, T>
+where
+    I: Iterator,
+    I::Item: Region + Clone,
+    T: Region + Clone,
+{
+    fn new(iter: I, index: IntervalIndex<T>) -> Self {
+        Self {
+            iter,
+            index,
+            current: None,
+            slots: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    fn read_next(&mut self) -> Option<&I::Item> {
+        self.current = self.iter.next();
+        self.slots.clear();
+        self.offset = 0;
+        let current = self.current.as_ref()?;
+        self.index.query_slots(current, &mut self.slots);
+        Some(current)
+    }
+
+    fn next_match(&mut self) -> Option<(usize, usize)> {
+        let slot = *self.slots.get(self.offset)?;
+        self.offset += 1;
+        Some(slot)
+    }
+}
+
+pub struct IndexedIntersectIter<I: Iterator, T: Region> {
+    probe: Probe<I, T>,
+}
+
+impl<I, T> Iterator for IndexedIntersectIter<I, T>
+where
+    I: Iterator,
+    I::Item: Region + Clone,
+    T: Region + Clone,
+{
+    type Item = (I::Item, T);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(slot) = self.probe.next_match() {
+                let current = self.probe.current.clone()?;
+                return Some((current, self.probe.index.get_slot(slot).clone()));
+            }
+            self.probe.read_next()?;
+        }
+    }
+}
+
+pub struct IndexedLeftOuterIter<I: Iterator, T: Region> {
+    probe: Probe<I, T>,
+}
+
+impl<I, T> Iterator for IndexedLeftOuterIter<I, T>
+where
+    I: Iterator,
+    I::Item: Region + Clone,
+    T: Region + Clone,
+{
+    type Item = (I::Item, Option<T>);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(slot) = self.probe.next_match() {
+                let current = self.probe.current.clone()?;
+                return Some((current, Some(self.probe.index.get_slot(slot).clone())));
+            }
+            let current = self.probe.read_next()?.clone();
+            if self.probe.slots.is_empty() {
+                return Some((current, None));
+            }
+        }
+    }
+}
+
+pub struct IndexedFullOuterIter<I: Iterator, T: Region> {
+    probe: Probe<I, T>,
+    matched: HashSet<(usize, usize)>,
+    unmatched: Option<std::vec::IntoIter<(usize, usize)>>,
+}
+
+impl<I, T> Iterator for IndexedFullOuterIter<I, T>
+where
+    I: Iterator,
+    I::Item: Region + Clone,
+    T: Region + Clone,
+{
+    type Item = (Option<I::Item>, Option<T>);
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(unmatched) = self.unmatched.as_mut() {
+            // The probing side has been exhausted, report the indexed records nobody overlaps
+            let slot = unmatched.next()?;
+            return Some((None, Some(self.probe.index.get_slot(slot).clone())));
+        }
+        loop {
+            if let Some(slot) = self.probe.next_match() {
+                self.matched.insert(slot);
+                let current = self.probe.current.clone();
+                return Some((current, Some(self.probe.index.get_slot(slot).clone())));
+            }
+            match self.probe.read_next().cloned() {
+                Some(current) => {
+                    if self.probe.slots.is_empty() {
+                        return Some((Some(current), None));
+                    }
+                }
+                None => {
+                    let matched = &self.matched;
+                    let unmatched: Vec<_> = self
+                        .probe
+                        .index
+                        .slots()
+                        .into_iter()
+                        .filter(|slot| !matched.contains(slot))
+                        .collect();
+                    self.unmatched = Some(unmatched.into_iter());
+                    return self.next();
+                }
+            }
+        }
+    }
+}
+
+/// Intersect a stream of regions with an in-memory index, neither side needs to be sorted
+pub trait IndexedIntersect: Iterator + Sized
+where
+    Self::Item: Region + Clone,
+{
+    fn indexed_intersect<T: Region + Clone>(
+        self,
+        index: IntervalIndex<T>,
+    ) -> IndexedIntersectIter<Self, T> {
+        IndexedIntersectIter {
+            probe: Probe::new(self, index),
+        }
+    }
+
+    fn indexed_left_outer_intersect<T: Region + Clone>(
+        self,
+        index: IntervalIndex<T>,
+    ) -> IndexedLeftOuterIter<Self, T> {
+        IndexedLeftOuterIter {
+            probe: Probe::new(self, index),
+        }
+    }
+
+    fn indexed_full_outer_intersect<T: Region + Clone>(
+        self,
+        index: IntervalIndex<T>,
+    ) -> IndexedFullOuterIter<Self, T> {
+        IndexedFullOuterIter {
+            probe: Probe::new(self, index),
+            matched: HashSet::new(),
+            unmatched: None,
+        }
+    }
+}
+
+impl<I: Iterator> IndexedIntersect for I where I::Item: Region + Clone {}
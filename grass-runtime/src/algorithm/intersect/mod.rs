@@ -1,13 +1,22 @@
 mod heap;
+mod indexed;
 mod inner;
 mod outer;
+mod pair;
 
 use crate::property::Region;
 use crate::{algorithm::markers::Sorted, record::ToSelfContained};
 
 use inner::{Context, State};
 
+pub use indexed::{
+    IndexedFullOuterIter, IndexedIntersect, IndexedIntersectIter, IndexedLeftOuterIter,
+};
 pub use inner::SortedIntersectIter;
+pub use pair::{
+    PairExcludeIter, PairIntersectIter, PairOverlap, PairedIntersect,
+    SortedPairExcludeIter, SortedPairIntersectIter, SortedPairedIntersect,
+};
 
 pub struct ToSelfContainedIter<T: Iterator>
 where
@@ -55,6 +64,16 @@ pub trait SortedIntersect: Iterator + Sorted + Sized {
     {
         outer::LeftOuterJoinIter::new(self, other)
     }
+
+    fn sorted_full_outer_intersect<U: Region + Clone, Other: Iterator<Item = U> + Sorted>(
+        self,
+        other: Other,
+    ) -> outer::FullOuterJoinIter<Self, Other>
+    where
+        Self::Item: Region + Clone,
+    {
+        outer::FullOuterJoinIter::new(self, other)
+    }
 }
 
 impl<I: Iterator + Sorted> SortedIntersect for I {}
@@ -1,3 +1,5 @@
+use std::{collections::VecDeque, iter::Peekable};
+
 use super::heap::RegionHeap;
 use super::Sorted;
 use crate::{
@@ -16,10 +18,10 @@ where
     iter_b: IB,
     active_regions: RegionHeap<IB::Item>,
     current_chrom: Option<ChrRef<'static>>,
-    limit: u32,
     current_a: Option<IA::Item>,
     current_b: Option<IB::Item>,
     current_b_idx: usize,
+    has_match: bool,
 }
 
 impl<IA, IB> LeftOuterJoinIter<IA, IB>
@@ -36,10 +38,10 @@ where
             iter_b,
             active_regions: Default::default(),
             current_chrom: None,
-            limit: 0,
             current_a: None,
             current_b,
             current_b_idx: 0,
+            has_match: false,
         };
         ret.read_next_a();
         ret
@@ -51,28 +53,31 @@ where
 
         if Some(&cur_a.chrom()) != self.current_chrom.as_ref() {
             self.current_chrom = Some(cur_a.chrom().clone());
-            self.limit = 0;
             self.active_regions.data.clear();
         }
 
-        self.limit = self.limit.max(cur_a.end());
-
         while let Some(ref b) = self.current_b {
-            if Some(&b.chrom()) > self.current_chrom.as_ref() || self.limit <= b.end() {
+            if b.chrom().cmp(&cur_a.chrom()).is_gt()
+                || (b.chrom() == cur_a.chrom() && b.start() >= cur_a.end())
+            {
                 break;
             }
-            self.active_regions.push(self.current_b.take().unwrap());
+            let b = self.current_b.take().unwrap();
+            if b.chrom() == cur_a.chrom() {
+                self.active_regions.push(b);
+            }
             self.current_b = self.iter_b.next();
         }
 
         while let Some(top) = self.active_regions.peek() {
-            if top.end() < cur_a.start() {
+            if top.end() <= cur_a.start() {
                 self.active_regions.pop();
             } else {
                 break;
             }
         }
         self.current_b_idx = 0;
+        self.has_match = false;
         Some(())
     }
 }
@@ -89,17 +94,349 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             let cur_a = self.current_a.as_ref()?;
-            if self.current_b_idx < self.active_regions.data.len() {
-                self.current_b_idx += 1;
-                return Some((
-                    cur_a.clone(),
-                    Some(self.active_regions.data[self.current_b_idx - 1].clone()),
-                ));
-            } else if self.current_b_idx == 0 && self.active_regions.data.len() == 0 {
+            // The active regions may be activated by a previous longer region, so we still need
+            // to check if they actually overlaps the current one
+            while self.current_b_idx < self.active_regions.data.len() {
+                let b = &self.active_regions.data[self.current_b_idx];
                 self.current_b_idx += 1;
+                if b.overlaps(cur_a) {
+                    self.has_match = true;
+                    return Some((cur_a.clone(), Some(b.clone())));
+                }
+            }
+            if !self.has_match {
+                self.has_match = true;
                 return Some((cur_a.clone(), None));
             }
             self.read_next_a()?;
         }
     }
 }
+
+enum EntryState {
+    Ready,
+    Pending,
+    Dropped,
+}
+
+struct OuterEntry<A, B> {
+    value: (Option<A>, Option<B>),
+    state: EntryState,
+}
+
+pub struct FullOuterJoinIter<IA, IB>
+where
+    IA: Iterator + Sorted,
+    IB: Iterator + Sorted,
+    IA::Item: Region + Clone,
+    IB::Item: Region + Clone,
+{
+    iter_a: Peekable<IA>,
+    iter_b: Peekable<IB>,
+    active_a: Vec<(IA::Item, usize)>,
+    active_b: Vec<(IB::Item, usize)>,
+    current_chrom: Option<ChrRef<'static>>,
+    // The output queue, a record that doesn't overlap anything yet stays pending until nothing
+    // from the other side could overlap it any more
+    queue: VecDeque<OuterEntry<IA::Item, IB::Item>>,
+    queue_base: usize,
+}
+
+impl<IA, IB> FullOuterJoinIter<IA, IB>
+where
+    IA: Iterator + Sorted,
+    IB: Iterator + Sorted,
+    IA::Item: Region + Clone,
+    IB::Item: Region + Clone,
+{
+    pub(super) fn new(iter_a: IA, iter_b: IB) -> Self {
+        Self {
+            iter_a: iter_a.peekable(),
+            iter_b: iter_b.peekable(),
+            active_a: Vec::new(),
+            active_b: Vec::new(),
+            current_chrom: None,
+            queue: VecDeque::new(),
+            queue_base: 0,
+        }
+    }
+
+    fn push_entry(
+        &mut self,
+        value: (Option<IA::Item>, Option<IB::Item>),
+        state: EntryState,
+    ) -> usize {
+        self.queue.push_back(OuterEntry { value, state });
+        self.queue_base + self.queue.len() - 1
+    }
+
+    // Once an entry left the queue, it can't be changed anymore. This only happens to records
+    // that have been matched already.
+    fn entry_mut(&mut self, seq: usize) -> Option<&mut OuterEntry<IA::Item, IB::Item>> {
+        let idx = seq.checked_sub(self.queue_base)?;
+        self.queue.get_mut(idx)
+    }
+
+    fn mark_matched(&mut self, seq: usize) {
+        if let Some(entry) = self.entry_mut(seq) {
+            entry.state = EntryState::Dropped;
+        }
+    }
+
+    fn mark_resolved(&mut self, seq: usize) {
+        if let Some(entry) = self.entry_mut(seq) {
+            if let EntryState::Pending = entry.state {
+                entry.state = EntryState::Ready;
+            }
+        }
+    }
+
+    fn retire_regions(&mut self, limit: Option<u32>) {
+        let mut retired = Vec::new();
+        self.active_a.retain(|(region, seq)| {
+            let keep = limit.is_some_and(|limit| region.end() > limit);
+            if !keep {
+                retired.push(*seq);
+            }
+            keep
+        });
+        self.active_b.retain(|(region, seq)| {
+            let keep = limit.is_some_and(|limit| region.end() > limit);
+            if !keep {
+                retired.push(*seq);
+            }
+            keep
+        });
+        for seq in retired {
+            self.mark_resolved(seq);
+        }
+    }
+
+    fn enter_chrom(&mut self, chrom: ChrRef<'static>, pos: u32) {
+        if self.current_chrom != Some(chrom) {
+            self.retire_regions(None);
+            self.current_chrom = Some(chrom);
+        } else {
+            self.retire_regions(Some(pos));
+        }
+    }
+
+    fn ingest_a(&mut self, region: IA::Item) {
+        self.enter_chrom(region.chrom(), region.start());
+        let mut matched = Vec::new();
+        for (other, seq) in self.active_b.iter() {
+            if region.overlaps(other) {
+                matched.push((other.clone(), *seq));
+            }
+        }
+        for (other, seq) in matched.iter() {
+            self.mark_matched(*seq);
+            self.push_entry((Some(region.clone()), Some(other.clone())), EntryState::Ready);
+        }
+        let state = if matched.is_empty() {
+            EntryState::Pending
+        } else {
+            EntryState::Dropped
+        };
+        let seq = self.push_entry((Some(region.clone()), None), state);
+        self.active_a.push((region, seq));
+    }
+
+    fn ingest_b(&mut self, region: IB::Item) {
+        self.enter_chrom(region.chrom(), region.start());
+        let mut matched = Vec::new();
+        for (other, seq) in self.active_a.iter() {
+            if region.overlaps(other) {
+                matched.push((other.clone(), *seq));
+            }
+        }
+        for (other, seq) in matched.iter() {
+            self.mark_matched(*seq);
+            self.push_entry((Some(other.clone()), Some(region.clone())), EntryState::Ready);
+        }
+        let state = if matched.is_empty() {
+            EntryState::Pending
+        } else {
+            EntryState::Dropped
+        };
+        let seq = self.push_entry((None, Some(region.clone())), state);
+        self.active_b.push((region, seq));
+    }
+}
+
+impl<IA, IB> Sorted for FullOuterJoinIter<IA, IB>
+where
+    IA: Iterator + Sorted,
+    IB: Iterator + Sorted,
+    IA::Item: Region + Clone,
+    IB::Item: Region + Clone,
+{
+}
+
+impl<IA, IB> Iterator for FullOuterJoinIter<IA, IB>
+where
+    IA: Iterator + Sorted,
+    IB: Iterator + Sorted,
+    IA::Item: Region + Clone,
+    IB::Item: Region + Clone,
+{
+    type Item = (Option<IA::Item>, Option<IB::Item>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while let Some(front) = self.queue.front() {
+                match front.state {
+                    EntryState::Pending => break,
+                    EntryState::Ready => {
+                        self.queue_base += 1;
+                        return self.queue.pop_front().map(|entry| entry.value);
+                    }
+                    EntryState::Dropped => {
+                        self.queue_base += 1;
+                        self.queue.pop_front();
+                    }
+                }
+            }
+
+            let take_a = match (self.iter_a.peek(), self.iter_b.peek()) {
+                // ChrRef's partial order can't compare the unassigned and dummy chromosomes, so we
+                // use the total order, which puts the dummy chromosome last
+                (Some(a), Some(b)) => {
+                    Ord::cmp(&(a.chrom(), a.start()), &(b.chrom(), b.start())).is_le()
+                }
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => {
+                    if self.queue.is_empty() {
+                        return None;
+                    }
+                    self.retire_regions(None);
+                    continue;
+                }
+            };
+
+            if take_a {
+                let region = self.iter_a.next().unwrap();
+                self.ingest_a(region);
+            } else {
+                let region = self.iter_b.next().unwrap();
+                self.ingest_b(region);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algorithm::{AssumeSorted, SortedIntersect},
+        property::{RegionCore, Serializable},
+        record::Bed3,
+        ChrRef, LineRecordStreamExt,
+    };
+
+    fn left_outer(a: &str, b: &str) -> Vec<(u32, Option<u32>)> {
+        let a = a.as_bytes().into_record_iter::<Bed3>().assume_sorted();
+        let b = b.as_bytes().into_record_iter::<Bed3>().assume_sorted();
+        a.sorted_left_outer_intersect(b)
+            .map(|(a, b)| (a.start(), b.map(|b| b.start())))
+            .collect()
+    }
+
+    #[test]
+    fn test_left_outer_b_ends_after_a() {
+        let result = left_outer("chr1\t10\t100\n", "chr1\t50\t200\n");
+        assert_eq!(result, vec![(10, Some(50))]);
+    }
+
+    #[test]
+    fn test_left_outer_b_activated_by_longer_a() {
+        let result = left_outer(
+            "chr1\t0\t1000\nchr1\t10\t20\n",
+            "chr1\t500\t600\n",
+        );
+        assert_eq!(result, vec![(0, Some(500)), (10, None)]);
+    }
+
+    #[test]
+    fn test_left_outer_adjacent_regions() {
+        let result = left_outer("chr1\t10\t20\n", "chr1\t0\t10\nchr1\t20\t30\n");
+        assert_eq!(result, vec![(10, None)]);
+    }
+
+    #[test]
+    fn test_left_outer_dump_missing() {
+        let a = b"chr1\t10\t20\n".into_record_iter::<Bed3>().assume_sorted();
+        let b = b"chr1\t30\t40\n".into_record_iter::<Bed3>().assume_sorted();
+        let mut buffer = Vec::new();
+        for pair in a.sorted_left_outer_intersect(b) {
+            pair.dump(&mut buffer).unwrap();
+            buffer.push(b'\n');
+        }
+        // The missing record has as many columns as a BED3 record, without a trailing tab
+        assert_eq!(buffer, b"chr1\t10\t20\t.\t.\t.\n");
+    }
+
+    fn full_outer(b: &[u8]) -> Vec<(Option<u32>, Option<u32>)> {
+        let a = include_bytes!("../../../../data/a.bed")
+            .into_record_iter::<Bed3>()
+            .assume_sorted();
+        let b = b.into_record_iter::<Bed3>().assume_sorted();
+        a.sorted_full_outer_intersect(b)
+            .map(|(a, b)| (a.map(|a| a.start()), b.map(|b| b.start())))
+            .collect()
+    }
+
+    #[test]
+    fn test_full_outer_intersect() {
+        let result = full_outer(include_bytes!("../../../../data/b.bed"));
+        assert_eq!(
+            result,
+            vec![
+                (Some(10), Some(20)),
+                (Some(20), Some(20)),
+                (Some(30), Some(20)),
+                (Some(1000), None),
+                (Some(1500), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_full_outer_unmatched_b() {
+        let result = full_outer(include_bytes!("../../../../data/c.bed"));
+        assert_eq!(
+            result,
+            vec![
+                (None, Some(0)),
+                (Some(10), Some(20)),
+                (Some(20), Some(20)),
+                (Some(30), Some(20)),
+                (None, Some(200)),
+                (Some(1000), None),
+                (Some(1500), None),
+                (None, Some(9000)),
+                // The record on chromosome 2
+                (None, Some(10)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_full_outer_dummy_chrom() {
+        let a = b"chr1\t10\t20\nchr1\t50\t60\n"
+            .into_record_iter::<Bed3>()
+            .map(|a| (a.chrom(), a.start(), a.end()))
+            .assume_sorted();
+        // The dummy chromosome is sorted after every other chromosome
+        let b = vec![(ChrRef::Dummy, 0, 5)].into_iter().assume_sorted();
+        let result: Vec<_> = a
+            .sorted_full_outer_intersect(b)
+            .map(|(a, b)| (a.map(|a| a.start()), b.map(|b| b.start())))
+            .collect();
+        assert_eq!(
+            result,
+            vec![(Some(10), None), (Some(50), None), (None, Some(0))]
+        );
+    }
+}
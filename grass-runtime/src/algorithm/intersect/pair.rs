@@ -0,0 +1,618 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    iter::Peekable,
+};
+
+use crate::{
+    algorithm::{IntervalIndex, Sorted},
+    builtin::report_error,
+    property::{Paired, Region, RegionCore},
+    ChrRef,
+};
+
+/// Which ends of a pair should overlap a region to make a match
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PairOverlap {
+    /// Either end overlaps the region
+    Either,
+    /// Both ends of the pair have overlaps
+    Both,
+}
+
+pub struct PairIntersectIter<I: Iterator, T: Region> {
+    iter: I,
+    index: IntervalIndex<T>,
+    mode: PairOverlap,
+    current: Option<I::Item>,
+    slots: Vec<(usize, usize)>,
+    offset: usize,
+}
+
+impl<I, T> PairIntersectIter<I, T>
+where
+    I: Iterator,
+    I::Item: Paired,
+    T: Region,
+{
+    fn read_next(&mut self) -> Option<()> {
+        self.slots.clear();
+        self.offset = 0;
+        let current = self.iter.next()?;
+        self.index.query_slots(current.first_end(), &mut self.slots);
+        let first_matches = self.slots.len();
+        self.index
+            .query_slots(current.second_end(), &mut self.slots);
+        let second_matches = self.slots.len() - first_matches;
+        if self.mode == PairOverlap::Both && (first_matches == 0 || second_matches == 0) {
+            self.slots.clear();
+        }
+        // A region overlapping both ends should be reported only once
+        self.slots.sort_unstable();
+        self.slots.dedup();
+        self.current = Some(current);
+        Some(())
+    }
+}
+
+impl<I, T> Iterator for PairIntersectIter<I, T>
+where
+    I: Iterator,
+    I::Item: Paired + Clone,
+    T: Region + Clone,
+{
+    type Item = (I::Item, T);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(&slot) = self.slots.get(self.offset) {
+                self.offset += 1;
+                let current = self.current.clone()?;
+                return Some((current, self.index.get_slot(slot).clone()));
+            }
+            self.read_next()?;
+        }
+    }
+}
+
+pub struct PairExcludeIter<I: Iterator, T: Region> {
+    iter: I,
+    index: IntervalIndex<T>,
+    buffer: Vec<(usize, usize)>,
+}
+
+impl<I, T> Iterator for PairExcludeIter<I, T>
+where
+    I: Iterator,
+    I::Item: Paired,
+    T: Region,
+{
+    type Item = I::Item;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let current = self.iter.next()?;
+            self.buffer.clear();
+            self.index
+                .query_slots(current.first_end(), &mut self.buffer);
+            self.index
+                .query_slots(current.second_end(), &mut self.buffer);
+            if self.buffer.is_empty() {
+                return Some(current);
+            }
+        }
+    }
+}
+
+impl<I: Iterator + Sorted, T: Region> Sorted for PairExcludeIter<I, T> where I::Item: Paired {}
+
+/// Intersect a stream of pairs, for example BEDPE records, with an in-memory index of regions.
+/// The second ends of the pairs are not sorted, so this works for any input. See
+/// `SortedPairedIntersect` for the sorted inputs. The output follows the order of the pairs.
+pub trait PairedIntersect: Iterator + Sized
+where
+    Self::Item: Paired,
+{
+    /// Report each pair along with each region overlapping its ends under the given mode
+    fn pair_intersect<T: Region + Clone>(
+        self,
+        index: IntervalIndex<T>,
+        mode: PairOverlap,
+    ) -> PairIntersectIter<Self, T> {
+        PairIntersectIter {
+            iter: self,
+            index,
+            mode,
+            current: None,
+            slots: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Only keep the pairs that neither end overlaps any region in the index
+    fn pair_exclude<T: Region>(self, index: IntervalIndex<T>) -> PairExcludeIter<Self, T> {
+        PairExcludeIter {
+            iter: self,
+            index,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl<I: Iterator> PairedIntersect for I where I::Item: Paired {}
+
+/// The regions of a sorted iterator which may overlap the current end or the following ones
+struct RegionWindow<IB: Iterator> {
+    iter: Peekable<IB>,
+    /// The active regions along with their positions in the stream
+    active: Vec<(usize, IB::Item)>,
+    next_seq: usize,
+}
+
+impl<IB> RegionWindow<IB>
+where
+    IB: Iterator,
+    IB::Item: Region,
+{
+    fn new(iter: IB) -> Self {
+        Self {
+            iter: iter.peekable(),
+            active: Vec::new(),
+            next_seq: 0,
+        }
+    }
+
+    /// Call `f` for each region overlapping the end. The ends should be visited in the sorted
+    /// order, so the regions ending before the end are dropped.
+    fn visit(&mut self, end: &EndRegion, mut f: impl FnMut(usize, &IB::Item)) {
+        let (chrom, start, limit) = *end;
+        let keep = |b: &IB::Item| Ord::cmp(&(b.chrom(), b.end()), &(chrom, start)).is_gt();
+        self.active.retain(|(_, b)| keep(b));
+        while let Some(b) = self
+            .iter
+            .next_if(|b| Ord::cmp(&(b.chrom(), b.start()), &(chrom, limit)).is_lt())
+        {
+            let seq = self.next_seq;
+            self.next_seq += 1;
+            if keep(&b) {
+                self.active.push((seq, b));
+            }
+        }
+        for (seq, b) in self.active.iter() {
+            if b.overlaps(end) {
+                f(*seq, b);
+            }
+        }
+    }
+}
+
+type EndRegion = (ChrRef<'static>, u32, u32);
+
+fn end_region<R: RegionCore>(end: &R) -> EndRegion {
+    (end.chrom(), end.start(), end.end())
+}
+
+/// Sweep the sorted regions along both ends of the pairs. The first ends come in the sorted
+/// order, and the second ends wait in a heap until the first ends pass them, so all the ends are
+/// visited in the sorted order and only the regions around the current end are kept. An end on
+/// the dummy chromosome, for example ". -1 -1" in a BEDPE file, overlaps nothing.
+///
+/// A pair waits in the queue along with what's collected for it, `M`, until both of its ends are
+/// visited, so the pairs come out in the input order.
+struct PairSweep<I: Iterator, IB: Iterator, M> {
+    iter: Peekable<I>,
+    window: RegionWindow<IB>,
+    /// The second ends to visit, along with the sequence number of the pair
+    second_ends: BinaryHeap<Reverse<(EndRegion, usize)>>,
+    /// The pairs along with what's collected for them and whether all their ends are visited
+    queue: VecDeque<(I::Item, M, bool)>,
+    queue_base: usize,
+}
+
+impl<I, IB, M> PairSweep<I, IB, M>
+where
+    I: Iterator,
+    I::Item: Paired,
+    IB: Iterator,
+    IB::Item: Region,
+    M: Default,
+{
+    fn new(iter: I, regions: IB) -> Self {
+        Self {
+            iter: iter.peekable(),
+            window: RegionWindow::new(regions),
+            second_ends: BinaryHeap::new(),
+            queue: VecDeque::new(),
+            queue_base: 0,
+        }
+    }
+
+    /// Visit the next end in the sorted order, `f` is called with what's collected for the pair,
+    /// the position of an overlapping region in the stream, the region and whether it's the
+    /// second end. Returns false once all the ends are visited.
+    fn advance(&mut self, mut f: impl FnMut(&mut M, usize, &IB::Item, bool)) -> bool {
+        let next_first = self.iter.peek().map(|pair| end_region(pair.first_end()));
+        let take_second = match (self.second_ends.peek(), next_first) {
+            (Some(Reverse((second, _))), Some(first)) => {
+                Ord::cmp(&(second.0, second.1), &(first.0, first.1)).is_lt()
+            }
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+
+        if take_second {
+            let Reverse((second, seq)) = self.second_ends.pop().unwrap();
+            let (_, collected, done) = &mut self.queue[seq - self.queue_base];
+            self.window
+                .visit(&second, |idx, b| f(collected, idx, b, true));
+            *done = true;
+            return true;
+        }
+
+        let pair = match self.iter.next() {
+            Some(pair) => pair,
+            None => return false,
+        };
+        let seq = self.queue_base + self.queue.len();
+        let (first, second) = (end_region(pair.first_end()), end_region(pair.second_end()));
+        let mut collected = M::default();
+        if !matches!(first.0, ChrRef::Dummy) {
+            self.window
+                .visit(&first, |idx, b| f(&mut collected, idx, b, false));
+        }
+        let done = if matches!(second.0, ChrRef::Dummy) {
+            true
+        } else {
+            if !matches!(first.0, ChrRef::Dummy)
+                && Ord::cmp(&(second.0, second.1), &(first.0, first.1)).is_lt()
+            {
+                report_error(format!(
+                    "The second end of the pair at {}:{} comes before its first end, which the \
+                     sorted pair intersection doesn't support",
+                    first.0, first.1
+                ));
+            }
+            self.second_ends.push(Reverse((second, seq)));
+            false
+        };
+        self.queue.push_back((pair, collected, done));
+        true
+    }
+
+    /// The next pair whose ends are all visited, along with what's collected for it
+    fn next_pair(
+        &mut self,
+        mut f: impl FnMut(&mut M, usize, &IB::Item, bool),
+    ) -> Option<(I::Item, M)> {
+        loop {
+            if matches!(self.queue.front(), Some((_, _, true))) {
+                self.queue_base += 1;
+                return self
+                    .queue
+                    .pop_front()
+                    .map(|(pair, collected, _)| (pair, collected));
+            }
+            if !self.advance(&mut f) {
+                return None;
+            }
+        }
+    }
+}
+
+/// The overlapping regions of a pair along with their positions in the stream, and whether the
+/// first and the second end have overlaps
+type PairMatches<T> = (Vec<(usize, T)>, bool, bool);
+
+pub struct SortedPairIntersectIter<I: Iterator, IB: Iterator>
+where
+    IB::Item: Clone,
+{
+    sweep: PairSweep<I, IB, PairMatches<IB::Item>>,
+    mode: PairOverlap,
+    current: Option<I::Item>,
+    /// The regions overlapping the current pair in the reversed order
+    matches: Vec<IB::Item>,
+}
+
+impl<I, IB> Iterator for SortedPairIntersectIter<I, IB>
+where
+    I: Iterator,
+    I::Item: Paired + Clone,
+    IB: Iterator,
+    IB::Item: Region + Clone,
+{
+    type Item = (I::Item, IB::Item);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(region) = self.matches.pop() {
+                let current = self.current.clone()?;
+                return Some((current, region));
+            }
+            let (current, (mut regions, on_first, on_second)) =
+                self.sweep
+                    .next_pair(|(regions, on_first, on_second), idx, b, second| {
+                        regions.push((idx, b.clone()));
+                        if second {
+                            *on_second = true;
+                        } else {
+                            *on_first = true;
+                        }
+                    })?;
+            if self.mode == PairOverlap::Both && !(on_first && on_second) {
+                regions.clear();
+            }
+            // A region overlapping both ends should be reported only once
+            regions.sort_by_key(|(idx, _)| *idx);
+            regions.dedup_by_key(|(idx, _)| *idx);
+            self.matches = regions.into_iter().rev().map(|(_, b)| b).collect();
+            self.current = Some(current);
+        }
+    }
+}
+
+pub struct SortedPairExcludeIter<I: Iterator, IB: Iterator> {
+    sweep: PairSweep<I, IB, bool>,
+}
+
+impl<I, IB> Iterator for SortedPairExcludeIter<I, IB>
+where
+    I: Iterator,
+    I::Item: Paired,
+    IB: Iterator,
+    IB::Item: Region,
+{
+    type Item = I::Item;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (current, overlapped) = self
+                .sweep
+                .next_pair(|overlapped, _, _, _| *overlapped = true)?;
+            if !overlapped {
+                return Some(current);
+            }
+        }
+    }
+}
+
+impl<I, IB> Sorted for SortedPairExcludeIter<I, IB>
+where
+    I: Iterator + Sorted,
+    I::Item: Paired,
+    IB: Iterator,
+    IB::Item: Region,
+{
+}
+
+/// Intersect a stream of pairs sorted by the first end with a sorted stream of regions, the
+/// regions are streamed rather than indexed. The second end of each pair shouldn't come before
+/// its first end, otherwise an error is reported. The output follows the order of the pairs, so
+/// a pair is held until the regions reach its second end.
+pub trait SortedPairedIntersect: Iterator + Sorted + Sized
+where
+    Self::Item: Paired,
+{
+    /// Report each pair along with each region overlapping its ends under the given mode
+    fn sorted_pair_intersect<Other>(
+        self,
+        other: Other,
+        mode: PairOverlap,
+    ) -> SortedPairIntersectIter<Self, Other>
+    where
+        Other: Iterator + Sorted,
+        Other::Item: Region + Clone,
+    {
+        SortedPairIntersectIter {
+            sweep: PairSweep::new(self, other),
+            mode,
+            current: None,
+            matches: Vec::new(),
+        }
+    }
+
+    /// Only keep the pairs that neither end overlaps any region of the other iterator
+    fn sorted_pair_exclude<Other>(self, other: Other) -> SortedPairExcludeIter<Self, Other>
+    where
+        Other: Iterator + Sorted,
+        Other::Item: Region,
+    {
+        SortedPairExcludeIter {
+            sweep: PairSweep::new(self, other),
+        }
+    }
+}
+
+impl<I: Iterator + Sorted> SortedPairedIntersect for I where I::Item: Paired {}
+
+#[cfg(test)]
+mod test {
+    use super::{PairOverlap, PairedIntersect, SortedPairedIntersect};
+    use crate::{
+        algorithm::{AssumeSorted, IntervalIndex},
+        builtin::take_error,
+        property::{Paired, RegionCore},
+        record::{Bed3, BedPe},
+        LineRecordStreamExt,
+    };
+
+    const PAIRS: &str = "1\t100\t200\t1\t5000\t5100\tboth\t0\t+\t-\n\
+        1\t300\t400\t1\t7000\t7100\tfirst\t0\t+\t-\n\
+        1\t600\t700\t1\t800\t900\tnone\t0\t+\t-\n";
+
+    #[test]
+    fn test_pair_intersect() {
+        let pairs: Vec<BedPe> = PAIRS.as_bytes().into_record_iter().collect();
+        let chrom = pairs[0].chrom();
+        let regions = [(150, 160), (5050, 5060), (350, 360)]
+            .map(|(start, end)| crate::record::Bed3 { chrom, start, end });
+        let index = || regions.iter().copied().collect::<IntervalIndex<_>>();
+        let names = |mode| {
+            pairs
+                .iter()
+                .cloned()
+                .pair_intersect(index(), mode)
+                .map(|(p, r)| (p.name.to_string(), r.start()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(PairOverlap::Either),
+            vec![
+                ("both".to_string(), 150),
+                ("both".to_string(), 5050),
+                ("first".to_string(), 350),
+            ]
+        );
+        assert_eq!(
+            names(PairOverlap::Both),
+            vec![("both".to_string(), 150), ("both".to_string(), 5050)]
+        );
+        let excluded: Vec<_> = pairs
+            .iter()
+            .cloned()
+            .pair_exclude(index())
+            .map(|p| p.name.to_string())
+            .collect();
+        assert_eq!(excluded, vec!["none".to_string()]);
+    }
+
+    #[test]
+    fn test_sorted_pair_intersect() {
+        let pairs: Vec<BedPe> = PAIRS.as_bytes().into_record_iter().collect();
+        let chrom = pairs[0].chrom();
+        let regions =
+            [(150, 160), (350, 360), (5050, 5060)].map(|(start, end)| Bed3 { chrom, start, end });
+        let names = |mode| {
+            pairs
+                .iter()
+                .cloned()
+                .assume_sorted()
+                .sorted_pair_intersect(regions.iter().copied().assume_sorted(), mode)
+                .map(|(p, r)| (p.name.to_string(), r.start()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(PairOverlap::Either),
+            vec![
+                ("both".to_string(), 150),
+                ("both".to_string(), 5050),
+                ("first".to_string(), 350),
+            ]
+        );
+        assert_eq!(
+            names(PairOverlap::Both),
+            vec![("both".to_string(), 150), ("both".to_string(), 5050)]
+        );
+        let excluded: Vec<_> = pairs
+            .iter()
+            .cloned()
+            .assume_sorted()
+            .sorted_pair_exclude(regions.iter().copied().assume_sorted())
+            .map(|p| p.name.to_string())
+            .collect();
+        assert_eq!(excluded, vec!["none".to_string()]);
+        assert!(take_error().is_ok());
+    }
+
+    #[test]
+    fn test_sorted_pair_intersect_reversed_ends() {
+        let pairs = "1\t5000\t5100\t1\t100\t200\treversed\t0\t+\t-\n";
+        let pairs: Vec<BedPe> = pairs.as_bytes().into_record_iter().collect();
+        let chrom = pairs[0].chrom();
+        let regions = [Bed3 {
+            chrom,
+            start: 150,
+            end: 160,
+        }];
+        let _ = pairs
+            .into_iter()
+            .assume_sorted()
+            .sorted_pair_exclude(regions.into_iter().assume_sorted())
+            .count();
+        assert!(take_error().is_err());
+    }
+
+    #[test]
+    fn test_sorted_pair_intersect_dummy_second_end() {
+        let pairs = "pd1\t100\t200\t.\t-1\t-1\tsingle\t0\t+\t.\n\
+            pd1\t300\t400\t.\t-1\t-1\tnone\t0\t+\t.\n";
+        let pairs: Vec<BedPe> = pairs.as_bytes().into_record_iter().collect();
+        let chrom = pairs[0].chrom();
+        let regions = [Bed3 {
+            chrom,
+            start: 150,
+            end: 160,
+        }];
+        let names = |mode| {
+            pairs
+                .iter()
+                .cloned()
+                .assume_sorted()
+                .sorted_pair_intersect(regions.iter().copied().assume_sorted(), mode)
+                .map(|(p, r)| (p.name.to_string(), r.start()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(PairOverlap::Either),
+            vec![("single".to_string(), 150)]
+        );
+        assert!(names(PairOverlap::Both).is_empty());
+        let excluded: Vec<_> = pairs
+            .iter()
+            .cloned()
+            .assume_sorted()
+            .sorted_pair_exclude(regions.iter().copied().assume_sorted())
+            .map(|p| p.name.to_string())
+            .collect();
+        assert_eq!(excluded, vec!["none".to_string()]);
+        assert!(take_error().is_ok());
+    }
+
+    #[test]
+    fn test_sorted_pair_intersect_interchromosomal() {
+        let pairs = "pi1\t100\t200\tpi2\t100\t200\tfar\t0\t+\t-\n\
+            pi1\t300\t400\tpi1\t500\t600\tnear\t0\t+\t-\n";
+        let pairs: Vec<BedPe> = pairs.as_bytes().into_record_iter().collect();
+        let (chr1, chr2) = (pairs[0].chrom(), pairs[0].second_end().chrom());
+        let filler = (0..100).map(|i| Bed3 {
+            chrom: chr1,
+            start: 1000 + i * 10,
+            end: 1005 + i * 10,
+        });
+        let regions: Vec<_> = [(chr1, 150), (chr1, 350), (chr1, 550)]
+            .into_iter()
+            .map(|(chrom, start)| Bed3 {
+                chrom,
+                start,
+                end: start + 10,
+            })
+            .chain(filler)
+            .chain(std::iter::once(Bed3 {
+                chrom: chr2,
+                start: 150,
+                end: 160,
+            }))
+            .collect();
+
+        let mut iter = pairs
+            .into_iter()
+            .assume_sorted()
+            .sorted_pair_intersect(regions.into_iter().assume_sorted(), PairOverlap::Both);
+        let mut result = vec![];
+        while let Some((p, r)) = iter.next() {
+            // Only the regions around the current end are kept
+            assert!(iter.sweep.window.active.len() <= 2);
+            result.push((p.name.to_string(), r.chrom() == chr2, r.start()));
+        }
+
+        assert_eq!(
+            result,
+            vec![
+                ("far".to_string(), false, 150),
+                ("far".to_string(), true, 150),
+                ("near".to_string(), false, 350),
+                ("near".to_string(), false, 550),
+            ]
+        );
+        assert!(take_error().is_ok());
+    }
+}
@@ -1,5 +1,46 @@
 mod intersect;
-pub use intersect::{SortedIntersect, SortedIntersectIter};
+pub use intersect::{
+    IndexedFullOuterIter, IndexedIntersect, IndexedIntersectIter, IndexedLeftOuterIter,
+    PairExcludeIter, PairIntersectIter, PairOverlap, PairedIntersect,
+    SortedIntersect, SortedIntersectIter, SortedPairExcludeIter, SortedPairIntersectIter,
+    SortedPairedIntersect,
+};
+
+mod closest;
+pub use closest::{
+    ClosestDirection, ClosestIter, ClosestOptions, ClosestPair, ClosestTies, SortedClosest,
+};
+
+mod window;
+pub use window::{SortedWindow, WindowCount, WindowCountIter, WindowIter, WindowOptions};
+
+mod coverage;
+pub use coverage::{
+    Coverage, CoverageIter, CoverageOptions, DepthBin, DepthHistogramIter, SortedCoverage,
+};
+
+mod genome_cov;
+pub use genome_cov::{
+    BedGraphSegment, GenomeCoverageExt, GenomeCoverageIter, GenomeCoverageOptions,
+    GenomeHistogramIter, GenomeHistogramLine,
+};
+
+mod stats;
+pub use stats::{
+    FisherIter, FisherTest, InputComponents, InputRegion, Jaccard, JaccardIter, OverlapStatsExt,
+    RelativeDistanceBin, RelativeDistanceIter,
+};
+
+mod reorder;
+
+mod resize;
+pub use resize::{Amount, ResizeAnchor, ResizeExt, ResizeIter, ResizeOp};
+
+mod subtract;
+pub use subtract::{SortedSubtract, SubtractIter, SubtractMode, SubtractOptions};
+
+mod index;
+pub use index::IntervalIndex;
 
 mod markers;
 pub use markers::{AssumeSorted, AssumingSortedIter, Sorted};
@@ -12,8 +53,17 @@ pub use components::{
 mod random;
 pub use random::SortedRandomInterval;
 
+mod count;
+pub use count::{CountExt, CountIter, CountKey, RecordCount};
+
+mod aggregate;
+pub use aggregate::{
+    AggregateExt, AggregateFunc, AggregateInput, AggregateIter, AggregateRecord, Aggregator, ByKey,
+    ByOverlap, Grouping,
+};
+
 mod groupby;
-pub use groupby::{GroupBuffer, Groups};
+pub use groupby::{GroupBuffer, GroupOverlap, Groups};
 
 mod invert;
 pub use invert::SortedInversionExt;
@@ -23,3 +73,6 @@ pub use merge::TwoWayMergeExt;
 
 mod tag;
 pub use tag::{TaggedIterExt, TagAssignmentExt};
+
+mod tee;
+pub use tee::{InMemoryTee, SpillingTee, Tee, TeeBranch, TeeSource, TEE_MEMORY_LIMIT};
@@ -0,0 +1,109 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+};
+
+use crate::{property::RegionCore, ChrRef};
+
+/// A record waiting to be reported, which is ordered by the region and then the order it's
+/// produced
+struct Buffered<T> {
+    key: (u32, u32, usize),
+    record: T,
+}
+
+impl<T> PartialEq for Buffered<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<T> Eq for Buffered<T> {}
+
+impl<T> PartialOrd for Buffered<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Buffered<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+/// What to do after reading the input of a `ReorderBuffer`
+pub(crate) enum Step<P> {
+    /// Produce the records of the input record
+    Process(P),
+    /// Report the buffered records first
+    Drain,
+    Done,
+}
+
+/// Restore the order of the records produced from a sorted input. The records are buffered until
+/// the producer tells that none of the following records can start before them. All of the
+/// buffered records are on the same chromosome, so the first input record of the next chromosome
+/// waits until the buffer is drained.
+pub(crate) struct ReorderBuffer<P, T> {
+    heap: BinaryHeap<Reverse<Buffered<T>>>,
+    serial: usize,
+    chrom: Option<ChrRef<'static>>,
+    /// The buffered records starting before this position are ready to report
+    ready_before: u64,
+    pending: Option<P>,
+}
+
+impl<P: RegionCore, T: RegionCore> ReorderBuffer<P, T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            serial: 0,
+            chrom: None,
+            ready_before: 0,
+            pending: None,
+        }
+    }
+
+    /// None of the records produced later can start before the position
+    pub(crate) fn set_ready_before(&mut self, position: u64) {
+        self.ready_before = position;
+    }
+
+    pub(crate) fn push(&mut self, record: T) {
+        let key = (record.start(), record.end(), self.serial);
+        self.serial += 1;
+        self.heap.push(Reverse(Buffered { key, record }));
+    }
+
+    /// The next record which is ready to report
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        let Reverse(top) = self.heap.peek()?;
+        if (top.key.0 as u64) < self.ready_before {
+            self.heap.pop().map(|Reverse(item)| item.record)
+        } else {
+            None
+        }
+    }
+
+    /// Read the next input record when the buffered records are not ready
+    pub(crate) fn feed(&mut self, input: &mut impl Iterator<Item = P>) -> Step<P> {
+        let next = self.pending.take().or_else(|| input.next());
+        match next {
+            Some(record) if self.heap.is_empty() || self.chrom == Some(record.chrom()) => {
+                self.chrom = Some(record.chrom());
+                Step::Process(record)
+            }
+            Some(record) => {
+                self.pending = Some(record);
+                self.ready_before = u64::MAX;
+                Step::Drain
+            }
+            None if self.heap.is_empty() => Step::Done,
+            None => {
+                self.ready_before = u64::MAX;
+                Step::Drain
+            }
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use crate::property::{RegionCore, RegionMut, Stranded};
+
+use super::{
+    reorder::{ReorderBuffer, Step},
+    AssumeSorted, AssumingSortedIter, Sorted,
+};
+
+/// The number of bases to add or keep, either absolute or relative to the length of a record
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Amount {
+    Bases(u32),
+    /// A fraction of the length of the record, which is truncated to whole bases
+    Fraction(f64),
+}
+
+impl Amount {
+    fn of_length(&self, length: u32) -> i64 {
+        match self {
+            Amount::Bases(bases) => *bases as i64,
+            Amount::Fraction(fraction) => (length as f64 * fraction) as i64,
+        }
+    }
+
+    /// The largest number of bases regardless of the length, which is unknown for a fraction
+    fn bound(&self) -> Option<u32> {
+        match self {
+            Amount::Bases(bases) => Some(*bases),
+            Amount::Fraction(_) => None,
+        }
+    }
+}
+
+/// Which part of a record is kept when it's resized
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ResizeAnchor {
+    Start,
+    Center,
+    End,
+}
+
+/// How the region of each record is changed
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ResizeOp {
+    /// Extend the record, similar to `bedtools slop`
+    Slop { left: Amount, right: Amount },
+    /// Report the regions next to the record instead of the record, similar to `bedtools flank`.
+    /// The empty flanks are not reported.
+    Flank { left: Amount, right: Amount },
+    /// Change the length of the record while keeping the anchor in place
+    Resize { size: Amount, anchor: ResizeAnchor },
+}
+
+impl ResizeOp {
+    /// The new regions of a record, which are clamped to the chromosome if its size is known.
+    /// When `strand_relative` is set, the left side is the upstream of the record, which is after
+    /// the end of a record on the negative strand.
+    fn apply<T: RegionMut + Stranded>(
+        &self,
+        record: &T,
+        strand_relative: bool,
+    ) -> [Option<(u32, u32)>; 2] {
+        let (start, end) = (record.start() as i64, record.end() as i64);
+        let length = record.length();
+        let flipped = strand_relative && record.strand().is_negative();
+        let limit = record
+            .chrom()
+            .get_chr_size()
+            .map_or(u32::MAX as i64, |size| size as i64);
+        let clamp = |(start, end): (i64, i64)| {
+            let (start, end) = (start.clamp(0, limit), end.clamp(0, limit));
+            (start as u32, end.max(start) as u32)
+        };
+        match *self {
+            ResizeOp::Slop { left, right } => {
+                let (left, right) = (left.of_length(length), right.of_length(length));
+                let (before, after) = if flipped {
+                    (right, left)
+                } else {
+                    (left, right)
+                };
+                [Some(clamp((start - before, end + after))), None]
+            }
+            ResizeOp::Flank { left, right } => {
+                let (left, right) = (left.of_length(length), right.of_length(length));
+                let (before, after) = if flipped {
+                    (right, left)
+                } else {
+                    (left, right)
+                };
+                let before = clamp((start - before, start));
+                let after = clamp((end, end + after));
+                [
+                    Some(before).filter(|(s, e)| s < e),
+                    Some(after).filter(|(s, e)| s < e),
+                ]
+            }
+            ResizeOp::Resize { size, anchor } => {
+                let size = size.of_length(length);
+                let anchor = match (anchor, flipped) {
+                    (ResizeAnchor::Start, true) => ResizeAnchor::End,
+                    (ResizeAnchor::End, true) => ResizeAnchor::Start,
+                    (anchor, _) => anchor,
+                };
+                let new_start = match anchor {
+                    ResizeAnchor::Start => start,
+                    ResizeAnchor::Center => start + (end - start - size).div_euclid(2),
+                    ResizeAnchor::End => end - size,
+                };
+                [Some(clamp((new_start, new_start + size))), None]
+            }
+        }
+    }
+
+    /// How far before the start of a record a new region can start, which is unknown if it
+    /// depends on the length of the record
+    fn max_shift(&self) -> Option<u32> {
+        match self {
+            ResizeOp::Slop { left, right } | ResizeOp::Flank { left, right } => {
+                Some(left.bound()?.max(right.bound()?))
+            }
+            ResizeOp::Resize { size, .. } => match size {
+                Amount::Bases(bases) => Some(*bases),
+                // A record only moves after its start when it shrinks
+                Amount::Fraction(fraction) if *fraction <= 1.0 => Some(0),
+                Amount::Fraction(_) => None,
+            },
+        }
+    }
+}
+
+/// Change the region of each record. For a sorted input, the records are buffered until none of
+/// the following records can be placed before them, so that the output is sorted as well.
+pub struct ResizeIter<I: Iterator> {
+    iter: I,
+    op: ResizeOp,
+    strand_relative: bool,
+    sorted: bool,
+    buffer: ReorderBuffer<I::Item, I::Item>,
+}
+
+impl<I> ResizeIter<I>
+where
+    I: Iterator,
+    I::Item: RegionMut + Stranded + Clone,
+{
+    fn new(iter: I, op: ResizeOp, strand_relative: bool, sorted: bool) -> Self {
+        Self {
+            iter,
+            op,
+            strand_relative,
+            sorted,
+            buffer: ReorderBuffer::new(),
+        }
+    }
+
+    fn resize_and_push(&mut self, record: I::Item) {
+        // None of the following records can start before this one moves to
+        self.buffer
+            .set_ready_before(match (self.sorted, self.op.max_shift()) {
+                (false, _) => u64::MAX,
+                (true, Some(shift)) => record.start().saturating_sub(shift) as u64,
+                (true, None) => 0,
+            });
+        for (start, end) in self
+            .op
+            .apply(&record, self.strand_relative)
+            .into_iter()
+            .flatten()
+        {
+            let mut resized = record.clone();
+            resized.set_region(start, end);
+            self.buffer.push(resized);
+        }
+    }
+}
+
+impl<I> Iterator for ResizeIter<I>
+where
+    I: Iterator,
+    I::Item: RegionMut + Stranded + Clone,
+{
+    type Item = I::Item;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.buffer.pop() {
+                return Some(record);
+            }
+            match self.buffer.feed(&mut self.iter) {
+                Step::Process(record) => self.resize_and_push(record),
+                Step::Drain => (),
+                Step::Done => return None,
+            }
+        }
+    }
+}
+
+pub trait ResizeExt: Iterator + Sized
+where
+    Self::Item: RegionMut + Stranded + Clone,
+{
+    /// Change the region of each record, the output may not be sorted
+    fn resize_regions(self, op: ResizeOp, strand_relative: bool) -> ResizeIter<Self> {
+        ResizeIter::new(self, op, strand_relative, false)
+    }
+
+    /// Change the region of each record in a sorted iterator, the output is sorted again
+    fn sorted_resize_regions(
+        self,
+        op: ResizeOp,
+        strand_relative: bool,
+    ) -> AssumingSortedIter<ResizeIter<Self>>
+    where
+        Self: Sorted,
+    {
+        ResizeIter::new(self, op, strand_relative, true).assume_sorted()
+    }
+}
+
+impl<I: Iterator> ResizeExt for I where I::Item: RegionMut + Stranded + Clone {}
+
+#[cfg(test)]
+mod test {
+    use super::{Amount, ResizeAnchor, ResizeExt, ResizeOp};
+    use crate::{algorithm::AssumeSorted, property::RegionCore, record::Bed6, LineRecordStreamExt};
+
+    const INPUT: &[u8] = b"chr1\t5\t20\ta\t0\t+\nchr1\t30\t40\tb\t0\t-\n\
+        chr1\t100\t300\tc\t0\t-\nchr2\t10\t20\td\t0\t+\n";
+
+    fn resize(op: ResizeOp, strand_relative: bool) -> Vec<(String, u32, u32)> {
+        INPUT
+            .into_record_iter::<Bed6>()
+            .assume_sorted()
+            .sorted_resize_regions(op, strand_relative)
+            .map(|bed| (bed.name.to_string(), bed.start(), bed.end()))
+            .collect()
+    }
+
+    fn regions(result: &[(String, u32, u32)]) -> Vec<(&str, u32, u32)> {
+        result
+            .iter()
+            .map(|(n, s, e)| (n.as_str(), *s, *e))
+            .collect()
+    }
+
+    #[test]
+    fn test_resize_regions() {
+        let slop = ResizeOp::Slop {
+            left: Amount::Bases(10),
+            right: Amount::Fraction(0.1),
+        };
+        assert_eq!(
+            regions(&resize(slop, true)),
+            [("a", 0, 21), ("b", 29, 50), ("c", 80, 310), ("d", 0, 21)]
+        );
+
+        // The right flank of the first record is after the left flank of the second one
+        let flank = ResizeOp::Flank {
+            left: Amount::Bases(20),
+            right: Amount::Bases(0),
+        };
+        assert_eq!(
+            regions(&resize(flank, true)),
+            [("a", 0, 5), ("b", 40, 60), ("c", 300, 320), ("d", 0, 10)]
+        );
+        let flank = ResizeOp::Flank {
+            left: Amount::Bases(8),
+            right: Amount::Bases(8),
+        };
+        assert_eq!(
+            regions(&resize(flank, false))[..4],
+            [("a", 0, 5), ("a", 20, 28), ("b", 22, 30), ("b", 40, 48)]
+        );
+
+        let resize_op = ResizeOp::Resize {
+            size: Amount::Bases(4),
+            anchor: ResizeAnchor::Start,
+        };
+        assert_eq!(
+            regions(&resize(resize_op, true)),
+            [("a", 5, 9), ("b", 36, 40), ("c", 296, 300), ("d", 10, 14)]
+        );
+        let resize_op = ResizeOp::Resize {
+            size: Amount::Fraction(2.0),
+            anchor: ResizeAnchor::Center,
+        };
+        assert_eq!(
+            regions(&resize(resize_op, false))[..3],
+            [("a", 0, 27), ("c", 0, 400), ("b", 25, 45)]
+        );
+    }
+}
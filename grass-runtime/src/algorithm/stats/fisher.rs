@@ -0,0 +1,157 @@
+use std::io::{Result, Write};
+
+use crate::{
+    algorithm::RegionComponent,
+    property::{Region, Serializable},
+    Genome,
+};
+
+use super::{OverlapSummary, BOTH, FIRST, SECOND};
+
+/// The logarithm of n!, the small ones are exact and the rest use Stirling's series
+fn ln_factorial(n: u64) -> f64 {
+    if n < 16 {
+        return (2..=n).map(|i| (i as f64).ln()).sum();
+    }
+    let n = n as f64;
+    let n2 = n * n;
+    n * n.ln() - n + 0.5 * (2.0 * std::f64::consts::PI * n).ln() + 1.0 / (12.0 * n)
+        - 1.0 / (360.0 * n * n2)
+        + 1.0 / (1260.0 * n * n2 * n2)
+}
+
+fn ln_choose(n: u64, k: u64) -> f64 {
+    ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)
+}
+
+/// Fisher's exact test of the overlaps between two sets of records, similar to the output of
+/// `bedtools fisher`. The contingency table counts the merged runs of records: n11 is the number
+/// of intersections, n12 and n21 are the runs of the first and the second set which don't
+/// intersect, and n22 is the rest of the runs the genome can hold, which is estimated by the
+/// genome size divided by the mean length of the runs.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FisherTest {
+    table: [[u64; 2]; 2],
+    left_tail: f64,
+    right_tail: f64,
+    two_tail: f64,
+}
+
+impl FisherTest {
+    fn new(summary: &OverlapSummary, genome_size: u64) -> Self {
+        let n11 = summary.runs[BOTH] as u64;
+        let n12 = (summary.runs[FIRST] as u64).saturating_sub(n11);
+        let n21 = (summary.runs[SECOND] as u64).saturating_sub(n11);
+        let runs = summary.runs[FIRST] + summary.runs[SECOND];
+        let bases = summary.bases[FIRST] + summary.bases[SECOND];
+        let possible = (genome_size * runs as u64).checked_div(bases).unwrap_or(0);
+        let n22 = possible.max(n11 + n12 + n21) - n11 - n12 - n21;
+
+        // The probability of each n11 with the same margins follows the hypergeometric
+        // distribution
+        let (row, column, total) = (n11 + n12, n11 + n21, n11 + n12 + n21 + n22);
+        let ln_denominator = ln_choose(total, row);
+        let probability = |k: u64| {
+            (ln_choose(column, k) + ln_choose(total - column, row - k) - ln_denominator).exp()
+        };
+        let observed = probability(n11);
+        let (mut left_tail, mut right_tail, mut two_tail) = (0.0, 0.0, 0.0);
+        for k in (row + column).saturating_sub(total)..=row.min(column) {
+            let p = probability(k);
+            if k <= n11 {
+                left_tail += p;
+            }
+            if k >= n11 {
+                right_tail += p;
+            }
+            if p <= observed * (1.0 + 1e-7) {
+                two_tail += p;
+            }
+        }
+        Self {
+            table: [[n11, n12], [n21, n22]],
+            left_tail: f64::min(left_tail, 1.0),
+            right_tail: f64::min(right_tail, 1.0),
+            two_tail: f64::min(two_tail, 1.0),
+        }
+    }
+
+    /// The contingency table, the rows are in and not in the first set, and the columns are in
+    /// and not in the second set
+    #[inline(always)]
+    pub fn table(&self) -> [[u64; 2]; 2] {
+        self.table
+    }
+    /// The p-value of fewer overlaps than expected
+    #[inline(always)]
+    pub fn left_tail(&self) -> f64 {
+        self.left_tail
+    }
+    /// The p-value of more overlaps than expected
+    #[inline(always)]
+    pub fn right_tail(&self) -> f64 {
+        self.right_tail
+    }
+    #[inline(always)]
+    pub fn two_tail(&self) -> f64 {
+        self.two_tail
+    }
+    /// The odds ratio of the table
+    #[inline(always)]
+    pub fn ratio(&self) -> f64 {
+        let [[n11, n12], [n21, n22]] = self.table;
+        (n11 as f64 * n22 as f64) / (n12 as f64 * n21 as f64)
+    }
+}
+
+impl Serializable for FisherTest {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        let [[n11, n12], [n21, n22]] = self.table;
+        write!(
+            fp,
+            "{}\t{}\t{}\t{}\t{:.5e}\t{:.5e}\t{:.5e}\t{:.3}",
+            n11,
+            n12,
+            n21,
+            n22,
+            self.left_tail,
+            self.right_tail,
+            self.two_tail,
+            self.ratio()
+        )
+    }
+}
+
+/// Run Fisher's exact test on the components of two inputs, which is the only item
+pub struct FisherIter<I> {
+    components: Option<I>,
+    genome_size: Option<u64>,
+}
+
+impl<I> FisherIter<I> {
+    pub(super) fn new(components: I, genome_size: Option<u64>) -> Self {
+        Self {
+            components: Some(components),
+            genome_size,
+        }
+    }
+}
+
+impl<I, R> Iterator for FisherIter<I>
+where
+    I: Iterator<Item = (usize, RegionComponent<R>)>,
+    R: Region,
+{
+    type Item = FisherTest;
+    fn next(&mut self) -> Option<Self::Item> {
+        let summary = OverlapSummary::scan(self.components.take()?);
+        // The genome size is read when the test runs, which is after the genome file is loaded
+        let genome_size = self.genome_size.unwrap_or_else(|| {
+            Genome::get_chrom_sizes()
+                .into_iter()
+                .map(|(_, size)| size as u64)
+                .sum()
+        });
+        Some(FisherTest::new(&summary, genome_size))
+    }
+}
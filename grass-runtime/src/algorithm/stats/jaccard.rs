@@ -0,0 +1,84 @@
+use std::io::{Result, Write};
+
+use crate::{
+    algorithm::RegionComponent,
+    property::{Region, Serializable},
+};
+
+use super::{OverlapSummary, BOTH, FIRST, SECOND};
+
+/// The Jaccard statistic of two sets of records, similar to the output of `bedtools jaccard`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Jaccard {
+    intersection: u64,
+    union: u64,
+    n_intersections: usize,
+}
+
+impl Jaccard {
+    /// The number of bases covered by both sets
+    #[inline(always)]
+    pub fn intersection(&self) -> u64 {
+        self.intersection
+    }
+    /// The number of bases covered by either set
+    #[inline(always)]
+    pub fn union(&self) -> u64 {
+        self.union
+    }
+    /// The number of runs of bases covered by both sets
+    #[inline(always)]
+    pub fn n_intersections(&self) -> usize {
+        self.n_intersections
+    }
+    #[inline(always)]
+    pub fn jaccard(&self) -> f64 {
+        if self.union == 0 {
+            0.0
+        } else {
+            self.intersection as f64 / self.union as f64
+        }
+    }
+}
+
+impl Serializable for Jaccard {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        write!(
+            fp,
+            "{}\t{}\t{}\t{}",
+            self.intersection,
+            self.union,
+            self.jaccard(),
+            self.n_intersections
+        )
+    }
+}
+
+/// Compute the Jaccard statistic of the components of two inputs, which is the only item
+pub struct JaccardIter<I> {
+    components: Option<I>,
+}
+
+impl<I> JaccardIter<I> {
+    pub(super) fn new(components: I) -> Self {
+        Self {
+            components: Some(components),
+        }
+    }
+}
+
+impl<I, R> Iterator for JaccardIter<I>
+where
+    I: Iterator<Item = (usize, RegionComponent<R>)>,
+    R: Region,
+{
+    type Item = Jaccard;
+    fn next(&mut self) -> Option<Self::Item> {
+        let summary = OverlapSummary::scan(self.components.take()?);
+        Some(Jaccard {
+            intersection: summary.bases[BOTH],
+            union: summary.bases[FIRST] + summary.bases[SECOND] - summary.bases[BOTH],
+            n_intersections: summary.runs[BOTH],
+        })
+    }
+}
@@ -0,0 +1,191 @@
+mod fisher;
+mod jaccard;
+mod reldist;
+
+pub use fisher::{FisherIter, FisherTest};
+pub use jaccard::{Jaccard, JaccardIter};
+pub use reldist::{RelativeDistanceBin, RelativeDistanceIter};
+
+use crate::{
+    property::{Region, Tagged},
+    record::{Bed3, CastIter},
+    ChrRef,
+};
+
+use super::{
+    components::{ComponentsIter, RegionComponent, TaggedComponent, TaggedComponentExt},
+    merge::{TwoWayMerge, TwoWayMergeExt},
+    tag::{TaggedItem, TaggedIter, TaggedIterExt},
+    Components, Sorted,
+};
+
+/// The region of a record, tagged by the input it comes from, 0 for the first input and 1 for
+/// the second one
+pub type InputRegion = TaggedItem<usize, Bed3>;
+
+type TaggedInput<I> = TaggedIter<CastIter<I, Bed3>, usize>;
+
+/// The components of the records of two sorted iterators in a single stream, each of them is
+/// tagged by the input it comes from
+pub type InputComponents<IA, IB> = TaggedComponent<
+    ComponentsIter<
+        TwoWayMerge<TaggedInput<IA>, CastIter<TaggedInput<IB>, InputRegion>, InputRegion>,
+    >,
+    InputRegion,
+    usize,
+    fn(&InputRegion) -> usize,
+>;
+
+fn input_components<IA, IB>(iter_a: IA, iter_b: IB) -> InputComponents<IA, IB>
+where
+    IA: Iterator + Sorted,
+    IB: Iterator + Sorted,
+    IA::Item: Region,
+    IB::Item: Region,
+{
+    let tag_of: fn(&InputRegion) -> usize = |region| region.tag().unwrap_or(0);
+    let iter_a = CastIter::cast(iter_a).tagged(0);
+    let iter_b = CastIter::cast(iter_b).tagged(1);
+    iter_a.merge_with(iter_b).components().with_tag(tag_of)
+}
+
+// The kinds of runs, which are covered by the first input, the second input and both of them
+const FIRST: usize = 0;
+const SECOND: usize = 1;
+const BOTH: usize = 2;
+
+/// The number of bases and the number of runs covered by the first input, the second input and
+/// both of them. The overlapping and book-ended records of an input are one run, which is the
+/// same as merging the records beforehand.
+#[derive(Default)]
+struct OverlapSummary {
+    bases: [u64; 3],
+    runs: [usize; 3],
+}
+
+impl OverlapSummary {
+    /// Walk through the components of both inputs, the tag of a component is the input it comes
+    /// from and its depth is the number of records of that input covering its position
+    fn scan<I, R>(components: I) -> Self
+    where
+        I: Iterator<Item = (usize, RegionComponent<R>)>,
+        R: Region,
+    {
+        let mut summary = Self::default();
+        let mut chrom: Option<ChrRef<'static>> = None;
+        let mut position = 0;
+        let mut depth = [0; 2];
+        // If each kind of run is still open, so the book-ended ones are counted once
+        let mut in_run = [false; 3];
+        for (tag, component) in components {
+            let (this_chrom, this_position) = component.position();
+            if chrom != Some(this_chrom) {
+                in_run = [false; 3];
+            } else if this_position > position {
+                let length = (this_position - position) as u64;
+                let covered = [
+                    depth[FIRST] > 0,
+                    depth[SECOND] > 0,
+                    depth[FIRST] > 0 && depth[SECOND] > 0,
+                ];
+                for kind in [FIRST, SECOND, BOTH] {
+                    if covered[kind] {
+                        summary.bases[kind] += length;
+                        summary.runs[kind] += !in_run[kind] as usize;
+                    }
+                    in_run[kind] = covered[kind];
+                }
+            }
+            chrom = Some(this_chrom);
+            position = this_position;
+            depth[tag.min(SECOND)] = component.depth();
+        }
+        summary
+    }
+}
+
+pub trait OverlapStatsExt: Iterator + Sorted + Sized
+where
+    Self::Item: Region,
+{
+    /// The Jaccard statistic of the bases covered by the records and another sorted iterator
+    fn sorted_jaccard<Other>(self, other: Other) -> JaccardIter<InputComponents<Self, Other>>
+    where
+        Other: Iterator + Sorted,
+        Other::Item: Region,
+    {
+        JaccardIter::new(input_components(self, other))
+    }
+
+    /// Fisher's exact test of the overlaps between the records and another sorted iterator, the
+    /// size of the loaded genome is used if the genome size isn't given
+    fn sorted_fisher<Other>(
+        self,
+        other: Other,
+        genome_size: Option<u64>,
+    ) -> FisherIter<InputComponents<Self, Other>>
+    where
+        Other: Iterator + Sorted,
+        Other::Item: Region,
+    {
+        FisherIter::new(input_components(self, other), genome_size)
+    }
+
+    /// The distribution of the relative distances from the records to the records of another
+    /// sorted iterator
+    fn sorted_relative_distance<Other>(
+        self,
+        other: Other,
+    ) -> RelativeDistanceIter<InputComponents<Self, Other>>
+    where
+        Other: Iterator + Sorted,
+        Other::Item: Region,
+    {
+        RelativeDistanceIter::new(input_components(self, other))
+    }
+}
+
+impl<I> OverlapStatsExt for I
+where
+    I: Iterator + Sorted,
+    I::Item: Region,
+{
+}
+
+#[cfg(test)]
+mod test {
+    use super::OverlapStatsExt;
+    use crate::{algorithm::AssumeSorted, record::Bed3, test_utils::dump, LineRecordStreamExt};
+
+    const A: &[u8] = b"chr1\t0\t10\nchr1\t20\t40\nchr1\t100\t120\nchr2\t0\t10\n";
+    // The first two records are book-ended, which are a single run
+    const B: &[u8] = b"chr1\t5\t25\nchr1\t25\t28\nchr1\t30\t35\nchr1\t200\t300\nchr2\t50\t60\n";
+
+    #[test]
+    fn test_jaccard() {
+        let a = A.into_record_iter::<Bed3>().assume_sorted();
+        let b = B.into_record_iter::<Bed3>().assume_sorted();
+        let jaccard: Vec<_> = a.sorted_jaccard(b).collect();
+        assert_eq!(jaccard.len(), 1);
+        assert_eq!(dump(jaccard[0]), "18\t180\t0.1\t3");
+    }
+
+    #[test]
+    fn test_fisher() {
+        let a = A.into_record_iter::<Bed3>().assume_sorted();
+        let b = B.into_record_iter::<Bed3>().assume_sorted();
+        let fisher: Vec<_> = a.sorted_fisher(b, Some(1000)).collect();
+        assert_eq!(fisher.len(), 1);
+        assert_eq!(fisher[0].table(), [[3, 1], [1, 35]]);
+        assert!((fisher[0].right_tail() - 0.0015866).abs() < 1e-6);
+        assert!((fisher[0].left_tail() - 0.9999891).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_relative_distance() {
+        let a = A.into_record_iter::<Bed3>().assume_sorted();
+        let b = B.into_record_iter::<Bed3>().assume_sorted();
+        let reldist: Vec<_> = a.sorted_relative_distance(b).map(dump).collect();
+        assert_eq!(reldist, ["0.33\t1\t2\t0.500", "0.35\t1\t2\t0.500"]);
+    }
+}
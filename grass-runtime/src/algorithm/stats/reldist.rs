@@ -0,0 +1,173 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, BinaryHeap, VecDeque},
+    io::{Result, Write},
+    ops::Bound,
+};
+
+use crate::{
+    algorithm::RegionComponent,
+    property::{Region, Serializable},
+    ChrRef,
+};
+
+/// The number of records with the relative distance in one bin of the distribution, each bin is
+/// 0.01 wide, similar to a line of `bedtools reldist`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RelativeDistanceBin {
+    /// The lower bound of the bin in percent
+    percent: u32,
+    count: usize,
+    total: usize,
+}
+
+impl RelativeDistanceBin {
+    /// The lower bound of the relative distances in the bin, which is between 0 and 0.5
+    #[inline(always)]
+    pub fn reldist(&self) -> f64 {
+        self.percent as f64 / 100.0
+    }
+    #[inline(always)]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+    /// The number of records with a relative distance, which have records on both sides
+    #[inline(always)]
+    pub fn total(&self) -> usize {
+        self.total
+    }
+    #[inline(always)]
+    pub fn fraction(&self) -> f64 {
+        self.count as f64 / self.total as f64
+    }
+}
+
+impl Serializable for RelativeDistanceBin {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        write!(
+            fp,
+            "{:.2}\t{}\t{}\t{:.3}",
+            self.reldist(),
+            self.count,
+            self.total,
+            self.fraction()
+        )
+    }
+}
+
+/// The midpoints of the records on the current chromosome, the ones of the first input wait
+/// until both of their closest midpoints of the second input are known
+#[derive(Default)]
+struct MidpointSweep {
+    chrom: Option<ChrRef<'static>>,
+    midpoints: BTreeSet<u32>,
+    pending: BinaryHeap<Reverse<u32>>,
+    histogram: BTreeMap<u32, usize>,
+}
+
+impl MidpointSweep {
+    /// Measure the relative distances of the pending midpoints at or before the position, which
+    /// means all the records starting before it are seen, or all of them at the end of a
+    /// chromosome
+    fn resolve(&mut self, position: Option<u32>) {
+        while let Some(&Reverse(midpoint)) = self.pending.peek() {
+            let right = self
+                .midpoints
+                .range((Bound::Excluded(midpoint), Bound::Unbounded))
+                .next()
+                .copied();
+            // The records starting after the position may still have a closer midpoint
+            if let Some(position) = position {
+                if !matches!(right, Some(right) if right <= position) {
+                    break;
+                }
+            }
+            self.pending.pop();
+            let left = self.midpoints.range(..=midpoint).next_back().copied();
+            if let (Some(left), Some(right)) = (left, right) {
+                let distance = (midpoint - left).min(right - midpoint) as u64;
+                let percent = distance * 100 / (right - left) as u64;
+                *self.histogram.entry(percent as u32).or_default() += 1;
+            }
+        }
+        match position {
+            Some(position) => {
+                // Only the closest midpoint before the pending ones and the following records
+                // is still needed
+                let bound = self
+                    .pending
+                    .peek()
+                    .map_or(position, |&Reverse(midpoint)| midpoint.min(position));
+                if let Some(&left) = self.midpoints.range(..=bound).next_back() {
+                    self.midpoints = self.midpoints.split_off(&left);
+                }
+            }
+            None => self.midpoints.clear(),
+        }
+    }
+
+    fn push<R: Region>(&mut self, tag: usize, component: &RegionComponent<R>) {
+        let (chrom, position) = component.position();
+        if self.chrom != Some(chrom) {
+            self.resolve(None);
+            self.chrom = Some(chrom);
+        } else {
+            self.resolve(Some(position));
+        }
+        if component.is_open {
+            let midpoint = ((component.start() as u64 + component.end() as u64) / 2) as u32;
+            if tag == 0 {
+                self.pending.push(Reverse(midpoint));
+            } else {
+                self.midpoints.insert(midpoint);
+            }
+        }
+    }
+}
+
+/// Compute the distribution of the relative distances from the records of the first input to
+/// the records of the second input, similar to `bedtools reldist`. The relative distance of a
+/// record is the distance from its midpoint to the closer one of the midpoints right before and
+/// after it, divided by the distance between these two midpoints. The records without a midpoint
+/// on either side on the same chromosome are skipped.
+pub struct RelativeDistanceIter<I> {
+    components: Option<I>,
+    bins: VecDeque<RelativeDistanceBin>,
+}
+
+impl<I> RelativeDistanceIter<I> {
+    pub(super) fn new(components: I) -> Self {
+        Self {
+            components: Some(components),
+            bins: VecDeque::new(),
+        }
+    }
+}
+
+impl<I, R> Iterator for RelativeDistanceIter<I>
+where
+    I: Iterator<Item = (usize, RegionComponent<R>)>,
+    R: Region,
+{
+    type Item = RelativeDistanceBin;
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(components) = self.components.take() {
+            let mut sweep = MidpointSweep::default();
+            for (tag, component) in components {
+                sweep.push(tag, &component);
+            }
+            sweep.resolve(None);
+            let total = sweep.histogram.values().sum();
+            let bins = sweep
+                .histogram
+                .into_iter()
+                .map(|(percent, count)| RelativeDistanceBin {
+                    percent,
+                    count,
+                    total,
+                });
+            self.bins.extend(bins);
+        }
+        self.bins.pop_front()
+    }
+}
@@ -0,0 +1,192 @@
+use crate::property::{Region, RegionCore, RegionMut, Stranded};
+
+use super::{
+    reorder::{ReorderBuffer, Step},
+    window::WindowBuffer,
+    AssumeSorted, AssumingSortedIter, Sorted,
+};
+
+/// What is left of a record overlapping the other records
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SubtractMode {
+    /// Remove the overlapping parts, the rest of the record is reported as fragments
+    Fragments,
+    /// Remove the whole record if it overlaps any record, similar to `bedtools subtract -A`
+    RemoveOverlapping,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SubtractOptions {
+    pub mode: SubtractMode,
+    /// The minimum overlap of a record to subtract, as a fraction of the length of the record
+    /// subtracted from
+    pub min_fraction: f64,
+    /// Only subtract the records on the same strand
+    pub same_strand: bool,
+}
+
+impl Default for SubtractOptions {
+    fn default() -> Self {
+        Self {
+            mode: SubtractMode::Fragments,
+            min_fraction: 0.0,
+            same_strand: false,
+        }
+    }
+}
+
+/// Remove the regions covered by the records of a sorted iterator from each record of another
+/// sorted iterator, similar to `bedtools subtract`. Each fragment is a copy of the original
+/// record, so the other fields are kept.
+pub struct SubtractIter<IA: Iterator, IB: Iterator> {
+    iter_a: IA,
+    iter_b: WindowBuffer<IB>,
+    options: SubtractOptions,
+    buffer: ReorderBuffer<IA::Item, IA::Item>,
+    /// The regions removed from the current record
+    removed: Vec<(u32, u32)>,
+}
+
+impl<IA, IB> SubtractIter<IA, IB>
+where
+    IA: Iterator,
+    IB: Iterator,
+    IA::Item: RegionMut + Stranded + Clone,
+    IB::Item: Region + Stranded,
+{
+    fn subtract(&mut self, a: IA::Item) {
+        let (start, end) = (a.start(), a.end());
+        let min_overlap = a.length() as f64 * self.options.min_fraction;
+        let options = self.options;
+        let removed = &mut self.removed;
+        removed.clear();
+        self.iter_b.scan(&a, (start, end), 0, |b| {
+            if options.same_strand && b.strand() != a.strand() {
+                return;
+            }
+            let overlap = (b.start().max(start), b.end().min(end));
+            if ((overlap.1 - overlap.0) as f64) >= min_overlap {
+                removed.push(overlap);
+            }
+        });
+        // The fragments don't start before the record
+        self.buffer.set_ready_before(start as u64);
+
+        if removed.is_empty() {
+            self.buffer.push(a);
+            return;
+        }
+        if options.mode == SubtractMode::RemoveOverlapping {
+            return;
+        }
+        // The overlaps are in the order of their starts
+        let mut cursor = start;
+        for &(removed_start, removed_end) in removed.iter() {
+            if cursor < removed_start {
+                let mut fragment = a.clone();
+                fragment.set_region(cursor, removed_start);
+                self.buffer.push(fragment);
+            }
+            cursor = cursor.max(removed_end);
+        }
+        if cursor < end {
+            let mut fragment = a;
+            fragment.set_region(cursor, end);
+            self.buffer.push(fragment);
+        }
+    }
+}
+
+impl<IA, IB> Iterator for SubtractIter<IA, IB>
+where
+    IA: Iterator,
+    IB: Iterator,
+    IA::Item: RegionMut + Stranded + Clone,
+    IB::Item: Region + Stranded,
+{
+    type Item = IA::Item;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.buffer.pop() {
+                return Some(record);
+            }
+            match self.buffer.feed(&mut self.iter_a) {
+                Step::Process(record) => self.subtract(record),
+                Step::Drain => (),
+                Step::Done => return None,
+            }
+        }
+    }
+}
+
+pub trait SortedSubtract: Iterator + Sorted + Sized {
+    /// Remove the regions covered by the records of another sorted iterator from each record
+    fn sorted_subtract<Other>(
+        self,
+        other: Other,
+        options: SubtractOptions,
+    ) -> AssumingSortedIter<SubtractIter<Self, Other>>
+    where
+        Other: Iterator + Sorted,
+        Self::Item: RegionMut + Stranded + Clone,
+        Other::Item: Region + Stranded,
+    {
+        SubtractIter {
+            iter_a: self,
+            iter_b: WindowBuffer::new(other),
+            options,
+            buffer: ReorderBuffer::new(),
+            removed: Vec::new(),
+        }
+        .assume_sorted()
+    }
+}
+
+impl<I: Iterator + Sorted> SortedSubtract for I {}
+
+#[cfg(test)]
+mod test {
+    use super::{SortedSubtract, SubtractMode, SubtractOptions};
+    use crate::{
+        algorithm::AssumeSorted,
+        property::{Named, RegionCore},
+        record::Bed6,
+        LineRecordStreamExt,
+    };
+
+    const A: &[u8] = b"chr1\t0\t100\ta\t0\t+\nchr1\t5\t50\tb\t0\t-\nchr2\t0\t10\tc\t0\t+\n";
+    const B: &[u8] = b"chr1\t10\t20\tx\t0\t+\nchr1\t15\t30\ty\t0\t-\nchr1\t90\t200\tz\t0\t+\n";
+
+    fn subtract(options: SubtractOptions) -> Vec<String> {
+        let a = A.into_record_iter::<Bed6>().assume_sorted();
+        let b = B.into_record_iter::<Bed6>().assume_sorted();
+        a.sorted_subtract(b, options)
+            .map(|bed| format!("{}:{}-{}", bed.name(), bed.start(), bed.end()))
+            .collect()
+    }
+
+    #[test]
+    fn test_sorted_subtract() {
+        let default = SubtractOptions::default();
+        // The fragments of the first record are sorted along with the second record
+        assert_eq!(
+            subtract(default),
+            ["a:0-10", "b:5-10", "b:30-50", "a:30-90", "c:0-10"]
+        );
+        let options = SubtractOptions {
+            same_strand: true,
+            ..default
+        };
+        assert_eq!(
+            subtract(options),
+            ["a:0-10", "b:5-15", "a:20-90", "b:30-50", "c:0-10"]
+        );
+        let options = SubtractOptions {
+            mode: SubtractMode::RemoveOverlapping,
+            min_fraction: 0.2,
+            ..default
+        };
+        // None of the overlaps covers a fifth of the first record
+        assert_eq!(subtract(options), ["a:0-100", "c:0-10"]);
+    }
+}
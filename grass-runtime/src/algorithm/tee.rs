@@ -0,0 +1,419 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    fs::File,
+    io::{BufWriter, Error, ErrorKind, Result, Write},
+    path::PathBuf,
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    property::{Parsable, Serializable},
+    LineRecordStreamExt,
+};
+
+use super::Sorted;
+
+/// The max number of records a tee keeps in memory for the branches falling behind. Once the
+/// limit is reached, the records that can be written as text are spilled to a temp file, which
+/// isn't created until then.
+pub const TEE_MEMORY_LIMIT: usize = 1 << 16;
+
+// The storage of the records that have been spilled. This is object safe, so that the tees with
+// and without spilling support share the same type.
+trait SpillFile<T> {
+    fn write(&mut self, item: &T) -> Result<()>;
+    fn flush(&mut self) -> Result<()>;
+    fn open_reader(&self) -> Result<Box<dyn Iterator<Item = T>>>;
+}
+
+struct TempSpillFile {
+    path: PathBuf,
+    /// The file is created by the first write
+    writer: Option<BufWriter<File>>,
+    dirty: bool,
+}
+
+impl TempSpillFile {
+    fn new() -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "grass-tee-{}-{}.txt",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        Self {
+            path,
+            writer: None,
+            dirty: false,
+        }
+    }
+}
+
+impl Drop for TempSpillFile {
+    fn drop(&mut self) {
+        if self.writer.is_some() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+impl<T: Serializable + Parsable + 'static> SpillFile<T> for TempSpillFile {
+    fn write(&mut self, item: &T) -> Result<()> {
+        let writer = match self.writer.as_mut() {
+            Some(writer) => writer,
+            None => self
+                .writer
+                .insert(BufWriter::new(File::create(&self.path)?)),
+        };
+        item.dump(&mut *writer)?;
+        writer.write_all(b"\n")?;
+        self.dirty = true;
+        Ok(())
+    }
+    fn flush(&mut self) -> Result<()> {
+        // Only complete lines are flushed, so a reader never sees a partial record
+        if let (true, Some(writer)) = (self.dirty, self.writer.as_mut()) {
+            writer.flush()?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+    fn open_reader(&self) -> Result<Box<dyn Iterator<Item = T>>> {
+        Ok(Box::new(File::open(&self.path)?.into_record_iter::<T>()))
+    }
+}
+
+struct TeeState<I: Iterator> {
+    iter: I,
+    /// The records in memory, which starts from the record with index `base`
+    memory: VecDeque<I::Item>,
+    base: usize,
+    /// The number of records pulled from the upstream
+    total: usize,
+    exhausted: bool,
+    spill: Option<Box<dyn SpillFile<I::Item>>>,
+    /// The index of the first spilled record, all the records after this are in the spill file
+    spill_start: Option<usize>,
+    /// The index of the next record for each branch, a dropped branch is set to usize::MAX
+    positions: Vec<usize>,
+    readers: Vec<Option<Box<dyn Iterator<Item = I::Item>>>>,
+    /// The IO error the spill file ran into, which ends all the branches
+    error: Option<Error>,
+}
+
+impl<I: Iterator> TeeState<I>
+where
+    I::Item: Clone,
+{
+    fn pull(&mut self) -> bool {
+        let item = match self.iter.next() {
+            Some(item) => item,
+            None => {
+                self.exhausted = true;
+                return false;
+            }
+        };
+        if self.spill_start.is_none() && self.spill.is_some() && self.memory.len() >= TEE_MEMORY_LIMIT
+        {
+            self.spill_start = Some(self.total);
+        }
+        self.total += 1;
+        match (self.spill_start, self.spill.as_mut()) {
+            (Some(_), Some(spill)) => {
+                if let Err(err) = spill.write(&item) {
+                    self.fail(err);
+                    return false;
+                }
+            }
+            _ => self.memory.push_back(item),
+        }
+        true
+    }
+
+    fn fail(&mut self, err: Error) {
+        self.exhausted = true;
+        self.error.get_or_insert(err);
+    }
+
+    fn try_read_spilled(&mut self, branch: usize) -> Result<I::Item> {
+        if let Some(spill) = self.spill.as_mut() {
+            spill.flush()?;
+            if self.readers[branch].is_none() {
+                self.readers[branch] = Some(spill.open_reader()?);
+            }
+        }
+        // Every record before `total` has been written, so the reader runs out only when the
+        // file can't be read back
+        self.readers[branch]
+            .as_mut()
+            .and_then(|reader| reader.next())
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "Unable to read the records back from the tee spill file",
+                )
+            })
+    }
+
+    fn read_spilled(&mut self, branch: usize) -> Option<I::Item> {
+        match self.try_read_spilled(branch) {
+            Ok(item) => Some(item),
+            Err(err) => {
+                self.fail(err);
+                None
+            }
+        }
+    }
+
+    fn release(&mut self) {
+        let min_pos = self.positions.iter().copied().min().unwrap_or(usize::MAX);
+        while self.base < min_pos && !self.memory.is_empty() {
+            self.memory.pop_front();
+            self.base += 1;
+        }
+    }
+
+    fn next_for(&mut self, branch: usize) -> Option<I::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        let pos = self.positions[branch];
+        if pos == self.total && (self.exhausted || !self.pull()) {
+            return None;
+        }
+        let item = match self.spill_start {
+            Some(spill_start) if pos >= spill_start => self.read_spilled(branch)?,
+            _ => self.memory[pos - self.base].clone(),
+        };
+        self.positions[branch] += 1;
+        self.release();
+        Some(item)
+    }
+}
+
+/// Fan out a stream to a fixed number of branches, each of which sees all the records of the
+/// stream. The records are buffered until every branch has consumed them.
+pub struct Tee<I: Iterator> {
+    state: Rc<RefCell<TeeState<I>>>,
+    next_branch: Cell<usize>,
+}
+
+impl<I: Iterator> Tee<I>
+where
+    I::Item: Clone,
+{
+    fn with_spill(iter: I, branches: usize, spill: Option<Box<dyn SpillFile<I::Item>>>) -> Self {
+        let state = TeeState {
+            iter,
+            memory: VecDeque::new(),
+            base: 0,
+            total: 0,
+            exhausted: false,
+            spill,
+            spill_start: None,
+            positions: vec![0; branches],
+            readers: (0..branches).map(|_| None).collect(),
+            error: None,
+        };
+        Self {
+            state: Rc::new(RefCell::new(state)),
+            next_branch: Cell::new(0),
+        }
+    }
+
+    /// Create a tee which keeps all the pending records in memory
+    pub fn new(iter: I, branches: usize) -> Self {
+        Self::with_spill(iter, branches, None)
+    }
+
+    /// Take the next branch of the tee. All the branches are registered when the tee is created,
+    /// so a branch taken late still starts from the first record.
+    pub fn branch(&self) -> TeeBranch<I> {
+        let id = self.next_branch.get();
+        assert!(
+            id < self.state.borrow().positions.len(),
+            "Too many branches are taken from the tee"
+        );
+        self.next_branch.set(id + 1);
+        TeeBranch {
+            state: self.state.clone(),
+            id,
+        }
+    }
+
+    /// Report the IO error of the spill file, the branches end early once the tee runs into an
+    /// error, so this should be checked after the branches are consumed
+    pub fn check(&self) -> Result<()> {
+        match self.state.borrow_mut().error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<I: Iterator> Tee<I>
+where
+    I::Item: Clone + Serializable + Parsable + 'static,
+{
+    /// Create a tee which spills the pending records to a temp file once there are more than
+    /// `TEE_MEMORY_LIMIT` of them
+    pub fn spilling(iter: I, branches: usize) -> Result<Self> {
+        let spill: Box<dyn SpillFile<I::Item>> = Box::new(TempSpillFile::new());
+        Ok(Self::with_spill(iter, branches, Some(spill)))
+    }
+}
+
+pub struct TeeBranch<I: Iterator> {
+    state: Rc<RefCell<TeeState<I>>>,
+    id: usize,
+}
+
+impl<I: Iterator> Iterator for TeeBranch<I>
+where
+    I::Item: Clone,
+{
+    type Item = I::Item;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.state.borrow_mut().next_for(self.id)
+    }
+}
+
+impl<I: Iterator> Drop for TeeBranch<I> {
+    fn drop(&mut self) {
+        // A dropped branch shouldn't hold the records in the buffer anymore
+        if let Ok(mut state) = self.state.try_borrow_mut() {
+            state.positions[self.id] = usize::MAX;
+        }
+    }
+}
+
+impl<I: Iterator + Sorted> Sorted for TeeBranch<I> where I::Item: Clone {}
+
+/// The source of a tee, which picks the spilling tee when the records can be written as text and
+/// the in-memory tee otherwise. The choice is made by method resolution, so call `make_tee` with
+/// two levels of reference, i.e. `(&&TeeSource::new(iter)).make_tee(n)`, with both `SpillingTee`
+/// and `InMemoryTee` in scope.
+pub struct TeeSource<I>(RefCell<Option<I>>);
+
+impl<I> TeeSource<I> {
+    pub fn new(iter: I) -> Self {
+        Self(RefCell::new(Some(iter)))
+    }
+    fn take(&self) -> I {
+        self.0.borrow_mut().take().expect("The tee source has been used")
+    }
+}
+
+pub trait SpillingTee<I: Iterator> {
+    fn make_tee(&self, branches: usize) -> Result<Tee<I>>;
+}
+
+impl<I: Iterator> SpillingTee<I> for &TeeSource<I>
+where
+    I::Item: Clone + Serializable + Parsable + 'static,
+{
+    fn make_tee(&self, branches: usize) -> Result<Tee<I>> {
+        Tee::spilling(self.take(), branches)
+    }
+}
+
+pub trait InMemoryTee<I: Iterator> {
+    fn make_tee(&self, branches: usize) -> Result<Tee<I>>;
+}
+
+impl<I: Iterator> InMemoryTee<I> for TeeSource<I>
+where
+    I::Item: Clone,
+{
+    fn make_tee(&self, branches: usize) -> Result<Tee<I>> {
+        Ok(Tee::new(self.take(), branches))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Error, Result};
+
+    use super::{
+        InMemoryTee, SpillFile, SpillingTee, Tee, TeeSource, TempSpillFile, TEE_MEMORY_LIMIT,
+    };
+    use crate::{property::RegionCore, record::Bed3, ChrRef};
+
+    /// A spill file on a full disk
+    struct FullSpillFile;
+
+    impl SpillFile<u32> for FullSpillFile {
+        fn write(&mut self, _item: &u32) -> Result<()> {
+            Err(Error::other("No space left on device"))
+        }
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+        fn open_reader(&self) -> Result<Box<dyn Iterator<Item = u32>>> {
+            Ok(Box::new(std::iter::empty()))
+        }
+    }
+
+    fn bed3(start: u32) -> Bed3 {
+        Bed3 {
+            chrom: ChrRef::Unassigned("chr1"),
+            start,
+            end: start + 1,
+        }
+    }
+
+    #[test]
+    fn test_tee() {
+        let tee = Tee::new(0..10, 2);
+        let (mut a, mut b) = (tee.branch(), tee.branch());
+        assert_eq!(a.next(), Some(0));
+        assert_eq!(b.by_ref().take(5).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(a.collect::<Vec<_>>(), (1..10).collect::<Vec<_>>());
+        assert_eq!(b.collect::<Vec<_>>(), (5..10).collect::<Vec<_>>());
+
+        // A tuple can't be written as text, so this picks the in-memory tee
+        #[allow(clippy::needless_borrow)]
+        let tee = (&&TeeSource::new((0..3).map(|x| (x, x)))).make_tee(2).unwrap();
+        assert_eq!(tee.branch().count(), 3);
+        assert_eq!(tee.branch().count(), 3);
+    }
+
+    #[test]
+    fn test_spilling_tee() {
+        let count = TEE_MEMORY_LIMIT as u32 + 100;
+        let records = (0..count).map(bed3);
+        let tee = (&&TeeSource::new(records)).make_tee(2).unwrap();
+        let (first, second) = (tee.branch(), tee.branch());
+        // Drain the first branch completely before the second one starts, so that the second
+        // branch reads most of the records back from the spill file
+        assert_eq!(first.count(), count as usize);
+        let starts: Vec<_> = second.map(|r| r.start()).collect();
+        assert_eq!(starts, (0..count).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_spill_file_created_lazily() {
+        let mut spill = TempSpillFile::new();
+        assert!(!spill.path.exists());
+        SpillFile::<Bed3>::write(&mut spill, &bed3(0)).unwrap();
+        assert!(spill.path.exists());
+        let path = spill.path.clone();
+        drop(spill);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_spill_error() {
+        let count = TEE_MEMORY_LIMIT + 100;
+        let tee = Tee::with_spill(0..count as u32, 2, Some(Box::new(FullSpillFile)));
+        let (first, second) = (tee.branch(), tee.branch());
+        // The records are kept in memory until the limit, and the branches end at the failed write
+        assert_eq!(first.count(), TEE_MEMORY_LIMIT);
+        assert_eq!(second.count(), 0);
+        let err = tee.check().unwrap_err();
+        assert_eq!(err.to_string(), "No space left on device");
+        assert!(tee.check().is_ok());
+    }
+}
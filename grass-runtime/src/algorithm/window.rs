@@ -0,0 +1,305 @@
+use std::{
+    io::{Result, Write},
+    iter::Peekable,
+};
+
+use crate::{
+    property::{Named, RecordAt, Region, RegionCore, Scored, Serializable, Strand, Stranded},
+    ChrRef,
+};
+
+use super::Sorted;
+
+/// The flanks added to a record before looking for the records within its window
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct WindowOptions {
+    /// The number of bases added before the start
+    pub left: u32,
+    /// The number of bases added after the end
+    pub right: u32,
+    /// Take the left flank as the upstream one, which is after the end of a record on the
+    /// negative strand
+    pub strand_relative: bool,
+}
+
+impl WindowOptions {
+    fn window_of<T: Region + Stranded>(&self, record: &T) -> (u32, u32) {
+        let (before, after) = if self.strand_relative && record.strand().is_negative() {
+            (self.right, self.left)
+        } else {
+            (self.left, self.right)
+        };
+        (
+            record.start().saturating_sub(before),
+            record.end().saturating_add(after),
+        )
+    }
+}
+
+/// The records of the other side which may be within the window of the current record and the
+/// following ones
+pub(super) struct WindowBuffer<IB: Iterator> {
+    iter: Peekable<IB>,
+    chrom: Option<ChrRef<'static>>,
+    active: Vec<IB::Item>,
+}
+
+impl<IB> WindowBuffer<IB>
+where
+    IB: Iterator,
+    IB::Item: Region,
+{
+    pub(super) fn new(iter: IB) -> Self {
+        Self {
+            iter: iter.peekable(),
+            chrom: None,
+            active: Vec::new(),
+        }
+    }
+
+    /// Load the records up to the end of the window and call `f` for each of the ones overlapping
+    /// the window. `reach` is the largest flank before any record, so that the records ending
+    /// before it can't be within the windows of the following records.
+    pub(super) fn scan<A: Region>(
+        &mut self,
+        record: &A,
+        (start, end): (u32, u32),
+        reach: u32,
+        mut f: impl FnMut(&IB::Item),
+    ) {
+        let chrom = record.chrom();
+        if self.chrom != Some(chrom) {
+            self.chrom = Some(chrom);
+            self.active.clear();
+            while self.iter.next_if(|b| b.chrom() < chrom).is_some() {}
+        }
+        let lower_bound = record.start().saturating_sub(reach);
+        self.active.retain(|b| b.end() > lower_bound);
+        while let Some(b) = self.iter.next_if(|b| b.chrom() == chrom && b.start() < end) {
+            if b.end() > lower_bound {
+                self.active.push(b);
+            }
+        }
+        for b in self.active.iter() {
+            if b.start() < end && start < b.end() {
+                f(b);
+            }
+        }
+    }
+}
+
+/// Join each record of a sorted iterator with the records of another sorted iterator within its
+/// window, similar to `bedtools window`. The records of both sides are kept as they are. The
+/// region of a pair is where they overlap, so the pairs are not sorted.
+pub struct WindowIter<IA, IB>
+where
+    IA: Iterator,
+    IB: Iterator,
+{
+    iter_a: IA,
+    buffer: WindowBuffer<IB>,
+    options: WindowOptions,
+    current: Option<IA::Item>,
+    matches: Vec<IB::Item>,
+}
+
+impl<IA, IB> Iterator for WindowIter<IA, IB>
+where
+    IA: Iterator,
+    IB: Iterator,
+    IA::Item: Region + Stranded + Clone,
+    IB::Item: Region + Clone,
+{
+    type Item = (IA::Item, IB::Item);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(b) = self.matches.pop() {
+                return Some((self.current.clone()?, b));
+            }
+            let a = self.iter_a.next()?;
+            let window = self.options.window_of(&a);
+            let reach = self.options.left.max(self.options.right);
+            let matches = &mut self.matches;
+            self.buffer
+                .scan(&a, window, reach, |b| matches.push(b.clone()));
+            // The matches are popped from the back
+            matches.reverse();
+            self.current = Some(a);
+        }
+    }
+}
+
+/// A record along with the number of records within its window
+#[derive(Clone)]
+pub struct WindowCount<A> {
+    pub record: A,
+    count: usize,
+}
+
+impl<A> WindowCount<A> {
+    #[inline(always)]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl<A: Region> RegionCore for WindowCount<A> {
+    fn start(&self) -> u32 {
+        self.record.start()
+    }
+    fn end(&self) -> u32 {
+        self.record.end()
+    }
+    fn chrom(&self) -> ChrRef<'static> {
+        self.record.chrom()
+    }
+}
+
+impl<A: Stranded> Stranded for WindowCount<A> {
+    fn strand(&self) -> Strand {
+        self.record.strand()
+    }
+}
+
+impl<'a, A: Named<'a>> Named<'a> for WindowCount<A> {
+    fn name(&self) -> &str {
+        self.record.name()
+    }
+}
+
+impl<S, A: Scored<S>> Scored<S> for WindowCount<A> {
+    fn score(&self) -> Option<S> {
+        self.record.score()
+    }
+}
+
+impl<A> RecordAt<0> for WindowCount<A> {
+    type Output<'a>
+        = &'a A
+    where
+        Self: 'a;
+    fn record_at(&self) -> &A {
+        &self.record
+    }
+}
+
+impl<A: Serializable> Serializable for WindowCount<A> {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        self.record.dump(&mut fp)?;
+        write!(fp, "\t{}", self.count)
+    }
+}
+
+/// Count the records of a sorted iterator within the window of each record of another sorted
+/// iterator, similar to `bedtools window -c`
+pub struct WindowCountIter<IA, IB>
+where
+    IB: Iterator,
+{
+    iter_a: IA,
+    buffer: WindowBuffer<IB>,
+    options: WindowOptions,
+}
+
+impl<IA, IB> Iterator for WindowCountIter<IA, IB>
+where
+    IA: Iterator,
+    IB: Iterator,
+    IA::Item: Region + Stranded,
+    IB::Item: Region,
+{
+    type Item = WindowCount<IA::Item>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.iter_a.next()?;
+        let window = self.options.window_of(&record);
+        let reach = self.options.left.max(self.options.right);
+        let mut count = 0;
+        self.buffer.scan(&record, window, reach, |_| count += 1);
+        Some(WindowCount { record, count })
+    }
+}
+
+impl<IA, IB> Sorted for WindowCountIter<IA, IB>
+where
+    IA: Iterator + Sorted,
+    IB: Iterator + Sorted,
+    IA::Item: Region + Stranded,
+    IB::Item: Region,
+{
+}
+
+pub trait SortedWindow: Iterator + Sorted + Sized {
+    /// Join each record with the records of another sorted iterator within its window
+    fn sorted_window<Other>(self, other: Other, options: WindowOptions) -> WindowIter<Self, Other>
+    where
+        Other: Iterator + Sorted,
+        Self::Item: Region + Stranded + Clone,
+        Other::Item: Region + Clone,
+    {
+        WindowIter {
+            iter_a: self,
+            buffer: WindowBuffer::new(other),
+            options,
+            current: None,
+            matches: Vec::new(),
+        }
+    }
+
+    /// Count the records of another sorted iterator within the window of each record
+    fn sorted_window_count<Other>(
+        self,
+        other: Other,
+        options: WindowOptions,
+    ) -> WindowCountIter<Self, Other>
+    where
+        Other: Iterator + Sorted,
+        Self::Item: Region + Stranded,
+        Other::Item: Region,
+    {
+        WindowCountIter {
+            iter_a: self,
+            buffer: WindowBuffer::new(other),
+            options,
+        }
+    }
+}
+
+impl<I: Iterator + Sorted> SortedWindow for I {}
+
+#[cfg(test)]
+mod test {
+    use super::{SortedWindow, WindowOptions};
+    use crate::{algorithm::AssumeSorted, property::RegionCore, record::Bed6, LineRecordStreamExt};
+
+    const A: &[u8] = b"chr1\t100\t200\ta\t0\t+\nchr1\t500\t510\tb\t0\t-\nchr2\t10\t20\tc\t0\t+\n";
+    const B: &[u8] = b"chr1\t20\t50\tx\t0\t+\nchr1\t150\t160\ty\t0\t+\n\
+        chr1\t300\t400\tz\t0\t+\nchr1\t560\t565\tu\t0\t+\nchr1\t590\t600\tw\t0\t+\nchr3\t0\t10\tv\t0\t+\n";
+
+    #[test]
+    fn test_sorted_window() {
+        let options = WindowOptions {
+            left: 60,
+            right: 101,
+            strand_relative: true,
+        };
+        let a = A.into_record_iter::<Bed6>().assume_sorted();
+        let b = B.into_record_iter::<Bed6>().assume_sorted();
+        let pairs: Vec<_> = a
+            .sorted_window(b, options)
+            .map(|(a, b)| (a.start(), b.start()))
+            .collect();
+        // The flanks of the second record are swapped since it's on the negative strand
+        assert_eq!(
+            pairs,
+            [(100, 20), (100, 150), (100, 300), (500, 300), (500, 560)]
+        );
+
+        let a = A.into_record_iter::<Bed6>().assume_sorted();
+        let b = B.into_record_iter::<Bed6>().assume_sorted();
+        let counts: Vec<_> = a
+            .sorted_window_count(b, WindowOptions::default())
+            .map(|count| count.count())
+            .collect();
+        assert_eq!(counts, [1, 0, 0]);
+    }
+}
@@ -0,0 +1,255 @@
+//! The string functions of the field expressions, which are shared by the compiled code and the
+//! interpreter.
+//!
+//! The string fields of the missing records of an outer intersection read as `.`, so `.` is taken
+//! as a missing value: the functions producing a string propagate it and `starts_with` is false
+//! on it. The numeric fields of a missing record read as 0, so the math functions don't need any
+//! special handling.
+//!
+//! A malformed value can't abort the compiled code either, so the operators which fail on it keep
+//! the error here and read as a default value, and the query returns the error at the end.
+
+use std::{cell::RefCell, io, str::FromStr};
+
+use regex::Regex;
+
+/// The value of a string field which is missing
+pub const MISSING: &str = ".";
+
+fn is_missing(text: &str) -> bool {
+    text == MISSING
+}
+
+/// Resolve a possibly negative index, which counts from the end, to an index in `0..=len`
+fn resolve_index(idx: i64, len: usize) -> usize {
+    let len = len as i64;
+    let idx = if idx < 0 {
+        len.saturating_add(idx)
+    } else {
+        idx
+    };
+    idx.clamp(0, len) as usize
+}
+
+/// The `len` characters from the `start`-th character, or the remaining characters if `len` is
+/// `None`. The range is clipped to the string.
+pub fn substr(text: &str, start: i64, len: Option<i64>) -> String {
+    if is_missing(text) {
+        return MISSING.to_string();
+    }
+    let begin = resolve_index(start, text.chars().count());
+    let len = len.map_or(usize::MAX, |len| len.max(0) as usize);
+    text.chars().skip(begin).take(len).collect()
+}
+
+pub fn concat(parts: &[&str]) -> String {
+    if parts.iter().any(|part| is_missing(part)) {
+        return MISSING.to_string();
+    }
+    parts.concat()
+}
+
+/// The `n`-th field separated by `sep`, which is missing if there isn't such a field. An empty
+/// separator doesn't split the text.
+pub fn split_nth(text: &str, sep: &str, n: i64) -> String {
+    if is_missing(text) {
+        return MISSING.to_string();
+    }
+    let fields: Vec<_> = if sep.is_empty() {
+        vec![text]
+    } else {
+        text.split(sep).collect()
+    };
+    let idx = if n < 0 { n + fields.len() as i64 } else { n };
+    usize::try_from(idx)
+        .ok()
+        .and_then(|idx| fields.get(idx))
+        .map_or(MISSING, |field| field)
+        .to_string()
+}
+
+/// Replace all the occurrences of `from`, an empty pattern doesn't match anything
+pub fn replace(text: &str, from: &str, to: &str) -> String {
+    if is_missing(text) || from.is_empty() {
+        return text.to_string();
+    }
+    text.replace(from, to)
+}
+
+pub fn upper(text: &str) -> String {
+    text.to_uppercase()
+}
+
+pub fn lower(text: &str) -> String {
+    text.to_lowercase()
+}
+
+pub fn starts_with(text: &str, prefix: &str) -> bool {
+    !is_missing(text) && text.starts_with(prefix)
+}
+
+/// Parse an integer, the default value is used if the text is missing or malformed
+pub fn parse_int(text: &str, default: i64) -> i64 {
+    text.trim().parse().unwrap_or(default)
+}
+
+/// Parse a float, the default value is used if the text is missing or malformed
+pub fn parse_float(text: &str, default: f64) -> f64 {
+    text.trim().parse().unwrap_or(default)
+}
+
+thread_local! {
+    static ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Keep the first error raised by the compiled code, just like the error slot of the interpreter
+pub(crate) fn report_error(message: String) {
+    ERROR.with(|error| {
+        error.borrow_mut().get_or_insert(message);
+    });
+}
+
+/// Take the error raised by the compiled code since the last call
+pub fn take_error() -> io::Result<()> {
+    match ERROR.with(|error| error.borrow_mut().take()) {
+        Some(message) => Err(io::Error::new(io::ErrorKind::InvalidData, message)),
+        None => Ok(()),
+    }
+}
+
+/// Cast a string to a number, a malformed number is an error and reads as the default value
+pub fn cast_str<T: FromStr>(text: &str, type_name: &str, default: T) -> T {
+    text.parse().unwrap_or_else(|_| {
+        report_error(format!("Unable to cast {:?} to {}", text, type_name));
+        default
+    })
+}
+
+fn overflow(result: Option<i64>) -> i64 {
+    result.unwrap_or_else(|| {
+        report_error("Integer overflow or division by zero".to_string());
+        0
+    })
+}
+
+/// The integer arithmetic, an overflow is an error and reads as 0
+pub fn add(a: i64, b: i64) -> i64 {
+    overflow(a.checked_add(b))
+}
+
+pub fn sub(a: i64, b: i64) -> i64 {
+    overflow(a.checked_sub(b))
+}
+
+pub fn mul(a: i64, b: i64) -> i64 {
+    overflow(a.checked_mul(b))
+}
+
+pub fn neg(a: i64) -> i64 {
+    overflow(a.checked_neg())
+}
+
+/// The quotient of an integer division, dividing by zero is an error and reads as 0
+pub fn div(a: i64, b: i64) -> i64 {
+    overflow(a.checked_div(b))
+}
+
+/// The remainder of an integer division, dividing by zero is an error and reads as 0
+pub fn rem(a: i64, b: i64) -> i64 {
+    overflow(a.checked_rem(b))
+}
+
+/// Compile a regular expression, a malformed pattern is an error and matches nothing
+pub fn regex(pattern: &str) -> Option<Regex> {
+    Regex::new(pattern)
+        .map_err(|e| report_error(format!("Invalid regular expression {:?}: {}", pattern, e)))
+        .ok()
+}
+
+/// Whether the text matches a regular expression compiled by `regex`
+pub fn regex_match(regex: &Option<Regex>, text: &str) -> bool {
+    matches!(regex, Some(regex) if regex.is_match(text))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_string_functions() {
+        assert_eq!(substr("chr12", 3, None), "12");
+        assert_eq!(substr("chr12", -2, Some(1)), "1");
+        assert_eq!(substr("chr12", 9, Some(2)), "");
+        assert_eq!(substr(MISSING, 0, Some(1)), MISSING);
+        assert_eq!(concat(&["a", "-", "b"]), "a-b");
+        assert_eq!(concat(&["a", MISSING]), MISSING);
+        assert_eq!(split_nth("a|b|c", "|", 1), "b");
+        assert_eq!(split_nth("a|b|c", "|", -1), "c");
+        assert_eq!(split_nth("a|b|c", "|", 3), MISSING);
+        assert_eq!(replace("chr1", "chr", ""), "1");
+        assert_eq!(replace("chr1", "", "x"), "chr1");
+        assert_eq!(upper("chrx"), "CHRX");
+        assert!(starts_with("chr1", "chr"));
+        assert!(!starts_with(MISSING, ""));
+        assert_eq!(parse_int(" 42", 0), 42);
+        assert_eq!(parse_int(MISSING, -1), -1);
+        assert!(parse_float(MISSING, f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(cast_str("42", "i64", 0), 42);
+        assert_eq!(rem(7, 3), 1);
+        assert!(take_error().is_ok());
+
+        assert_eq!(cast_str("4x", "i64", 0), 0);
+        assert!(cast_str("", "f64", f64::NAN).is_nan());
+        assert_eq!(rem(7, 0), 0);
+        // Only the first error is kept
+        assert_eq!(
+            take_error().unwrap_err().to_string(),
+            "Unable to cast \"4x\" to i64"
+        );
+        assert!(take_error().is_ok());
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(add(1, 2), 3);
+        assert_eq!(sub(1, 2), -1);
+        assert_eq!(mul(-3, 2), -6);
+        assert_eq!(neg(5), -5);
+        assert_eq!(div(7, 2), 3);
+        assert!(take_error().is_ok());
+
+        let overflows: [fn() -> i64; 5] = [
+            || add(i64::MAX, 1),
+            || sub(i64::MIN, 1),
+            || mul(i64::MAX, 2),
+            || neg(i64::MIN),
+            || div(7, 0),
+        ];
+        for op in overflows {
+            assert_eq!(op(), 0);
+            assert_eq!(
+                take_error().unwrap_err().to_string(),
+                "Integer overflow or division by zero"
+            );
+        }
+    }
+
+    #[test]
+    fn test_regex() {
+        let digits = regex("^[0-9]+$");
+        assert!(regex_match(&digits, "42"));
+        assert!(!regex_match(&digits, "4x"));
+        assert!(take_error().is_ok());
+
+        let malformed = regex("(");
+        assert!(!regex_match(&malformed, "("));
+        assert!(take_error()
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid regular expression \"(\""));
+    }
+}
@@ -2,38 +2,45 @@ use std::{cell::UnsafeCell, str::FromStr};
 
 use lazy_static::lazy_static;
 
+/// Split the raw constant bag into values, which are separated by `;` and use `\` to escape `;` and
+/// `\` itself. Returns `None` if the constant bag has an invalid escape sequence.
+pub fn split_const_bag(const_bag: &str) -> Option<Vec<String>> {
+    let mut ret = Vec::new();
+    let mut buf = String::new();
+    let mut escape = false;
+    for c in const_bag.chars() {
+        if escape == false {
+            if c == '\\' {
+                escape = true;
+            } else if c == ';' {
+                ret.push(std::mem::take(&mut buf));
+            } else {
+                buf.push(c);
+            }
+        } else {
+            if c == ';' {
+                buf.push(';');
+            } else if c == '\\' {
+                buf.push('\\');
+            } else {
+                return None;
+            }
+            escape = false;
+        }
+    }
+    if escape {
+        return None;
+    }
+    ret.push(buf);
+    Some(ret)
+}
+
 lazy_static! {
     static ref RAW_VALUES: Vec<String> = {
-        let mut ret = Vec::new();
         let const_bag = std::env::var("__GRASS_CONST_BAG")
             .unwrap_or_else(|_| panic!("Unable to read environment variable __GRASS_CONST_BAG"));
-        let mut buf = String::new();
-        let mut escape = false;
-        for c in const_bag.chars() {
-            if escape == false {
-                if c == '\\' {
-                    escape = true;
-                } else if c == ';' {
-                    ret.push(std::mem::take(&mut buf));
-                } else {
-                    buf.push(c);
-                }
-            } else {
-                if c == ';' {
-                    buf.push(';');
-                } else if c == '\\' {
-                    buf.push('\\');
-                } else {
-                    panic!("Invalid constant bag: {}", const_bag);
-                }
-                escape = false;
-            }
-        }
-        if escape {
-            panic!("Invalid constant bag: {}", const_bag);
-        }
-        ret.push(buf);
-        ret
+        split_const_bag(&const_bag)
+            .unwrap_or_else(|| panic!("Invalid constant bag: {}", const_bag))
     };
 }
 
@@ -60,6 +67,13 @@ impl<'a> ConstBagType for &'a ConstBagRef<f64> {
     }
 }
 
+impl ConstBagType for &ConstBagRef<i64> {
+    type ReadOutput = i64;
+    fn value(self) -> i64 {
+        *self.get_ref()
+    }
+}
+
 impl<'a> ConstBagType for &'a ConstBagRef<String> {
     type ReadOutput = &'a str;
     fn value(self) -> &'a str {
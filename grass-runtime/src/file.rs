@@ -1,5 +1,7 @@
 use crate::property::Parsable;
 
+use flate2::read::MultiGzDecoder;
+
 use std::{
     io::{BufRead, BufReader, Read},
     marker::PhantomData, rc::Rc, sync::Mutex,
@@ -57,6 +59,25 @@ fn allocate_string_buffer() -> Buffer {
     Buffer(Some(String::with_capacity(128)))
 }
 
+/// A reader that decompresses gzip input on the fly.
+/// Since BGZF is a series of concatenated gzip members, this handles both plain gzip and BGZF.
+pub struct GzipReader<R: Read> {
+    decoder: MultiGzDecoder<R>,
+}
+
+impl<R: Read> GzipReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            decoder: MultiGzDecoder::new(inner),
+        }
+    }
+}
+
+impl<R: Read> Read for GzipReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.decoder.read(buf)
+    }
+}
 
 pub struct LineRecordStream<R: Read, Rec> {
     reader: BufReader<R>,
@@ -102,3 +123,29 @@ impl<R: Read, T: Parsable> Iterator for LineRecordStream<R, T> {
             .map(|(parsed, _)| parsed)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::{GzipReader, LineRecordStreamExt};
+    use crate::{property::RegionCore, record::Bed3};
+
+    #[test]
+    fn test_multi_member_gzip() {
+        // Each line goes into its own gzip member, just like what BGZF does with its blocks
+        let mut data = Vec::new();
+        for line in ["chr1\t10\t20\n", "chr1\t30\t40\n"] {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(line.as_bytes()).unwrap();
+            data.extend(encoder.finish().unwrap());
+        }
+        let records: Vec<_> = GzipReader::new(data.as_slice())
+            .into_record_iter::<Bed3>()
+            .map(|r| (r.start(), r.end()))
+            .collect();
+        assert_eq!(records, vec![(10, 20), (30, 40)]);
+    }
+}
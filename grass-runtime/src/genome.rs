@@ -156,8 +156,10 @@ impl<'a> ChrRef<'a> {
         if let Some(actual_size) = self.get_chr_size() {
             return size == actual_size;
         }
+        // Assign the id first, otherwise we would dead lock on the genome storage
+        let id = self.get_id_or_update();
         let mut storage = GENOME_STORAGE.write().unwrap();
-        storage.chr_size_list[self.get_id_or_update()] = Some(size);
+        storage.chr_size_list[id] = Some(size);
         true
     }
     pub fn next_chrom(&self) -> Option<ChrRef<'static>> {
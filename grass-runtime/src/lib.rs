@@ -3,11 +3,15 @@ mod genome;
 mod ioutils;
 
 pub mod algorithm;
+pub mod builtin;
 pub mod const_bag;
 pub mod property;
 pub mod record;
 
-pub use file::LineRecordStreamExt;
+#[cfg(test)]
+mod test_utils;
+
+pub use file::{GzipReader, LineRecordStreamExt};
 pub use genome::{ChrRef, Genome};
 
 pub use itertools::Itertools;
@@ -0,0 +1,31 @@
+use super::RegionCore;
+
+/// A record that may consist of several disjoint blocks, for example, the exons of a BED12
+/// record. By default, a record has a single block covering its whole region.
+pub trait Blocked: RegionCore {
+    /// The blocks of the record as absolute 0-based half-open ranges, ordered by start position
+    fn block_ranges(&self) -> Vec<(u32, u32)> {
+        vec![(self.start(), self.end())]
+    }
+
+    /// Check if any block of this record overlaps any block of the other record
+    fn overlaps_on_blocks<T: Blocked>(&self, other: &T) -> bool {
+        if self.chrom() != other.chrom() {
+            return false;
+        }
+        let a = self.block_ranges();
+        let b = other.block_ranges();
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            if a[i].0 < b[j].1 && b[j].0 < a[i].1 {
+                return true;
+            }
+            if a[i].1 <= b[j].1 {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        false
+    }
+}
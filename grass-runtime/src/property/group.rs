@@ -12,6 +12,30 @@ pub trait DumpComponent: RegionCore {
     fn dump_component<W: Write>(&self, idx: usize, fp: W) -> Result<()>;
 }
 
+/// Access the N-th record of a compound record, for example one side of an intersection or one
+/// member of a group
+pub trait RecordAt<const N: usize> {
+    type Output<'a>
+    where
+        Self: 'a;
+    fn record_at(&self) -> Self::Output<'_>;
+}
+
+macro_rules! impl_record_at {
+    ($tuple: tt; $($idx: tt => $out: ident),*) => {
+        $(impl_record_at!(@one $tuple, $idx, $out);)*
+    };
+    (@one ($($t_name: ident),*), $idx: tt, $out: ident) => {
+        impl <$($t_name),*> RecordAt<$idx> for ($($t_name),*) {
+            type Output<'a> = &'a $out where Self: 'a;
+            #[inline(always)]
+            fn record_at(&self) -> &$out {
+                &self.$idx
+            }
+        }
+    };
+}
+
 macro_rules! impl_intersection_trait {
     ($($t_name: ident),* => $($idx: tt),*) => {
         impl <$($t_name: Region + Serializable),*> DumpComponent for ($($t_name),*) {
@@ -83,3 +107,14 @@ impl_with_region_for_tuple!(
     (0, 1, 2, 3, 4, 5, 6, 7, 8, 9),
     10
 );
+
+impl_record_at!((A, B); 0 => A, 1 => B);
+impl_record_at!((A, B, C); 0 => A, 1 => B, 2 => C);
+impl_record_at!((A, B, C, D); 0 => A, 1 => B, 2 => C, 3 => D);
+impl_record_at!((A, B, C, D, E); 0 => A, 1 => B, 2 => C, 3 => D, 4 => E);
+impl_record_at!((A, B, C, D, E, F); 0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F);
+impl_record_at!((A, B, C, D, E, F, G); 0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F, 6 => G);
+impl_record_at!((A, B, C, D, E, F, G, H); 0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F, 6 => G, 7 => H);
+impl_record_at!((A, B, C, D, E, F, G, H, I); 0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F, 6 => G, 7 => H, 8 => I);
+impl_record_at!((A, B, C, D, E, F, G, H, I, J); 0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F, 6 => G, 7 => H, 8 => I, 9 => J);
+impl_record_at!((A, B, C, D, E, F, G, H, I, J, K); 0 => A, 1 => B, 2 => C, 3 => D, 4 => E, 5 => F, 6 => G, 7 => H, 8 => I, 9 => J, 10 => K);
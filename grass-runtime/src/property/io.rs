@@ -8,6 +8,12 @@ pub trait Parsable: Sized {
 
 pub trait Serializable {
     fn dump<W: Write>(&self, fp: W) -> Result<()>;
+
+    /// Write the header of the file the record comes from, which is written before the first
+    /// record of an output file. Most of the record types have no header.
+    fn dump_header<W: Write>(&self, _fp: W) -> Result<()> {
+        Ok(())
+    }
 }
 
 impl<A: Serializable, B: Serializable> Serializable for (A, B) {
@@ -17,3 +23,32 @@ impl<A: Serializable, B: Serializable> Serializable for (A, B) {
         self.1.dump(&mut fp)
     }
 }
+
+/// A missing pair is written as both of its records missing
+impl<A, B> Serializable for Option<(A, B)>
+where
+    A: Serializable,
+    B: Serializable,
+    Option<A>: Serializable,
+    Option<B>: Serializable,
+{
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        match self {
+            Some(inner) => inner.dump(fp),
+            None => (None::<A>, None::<B>).dump(&mut fp),
+        }
+    }
+}
+
+/// A borrowed record that may be missing, which is written the same way as an owned one
+impl<T: Serializable> Serializable for Option<&T>
+where
+    Option<T>: Serializable,
+{
+    fn dump<W: Write>(&self, fp: W) -> Result<()> {
+        match self {
+            Some(inner) => inner.dump(fp),
+            None => None::<T>.dump(fp),
+        }
+    }
+}
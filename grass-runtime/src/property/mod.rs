@@ -1,16 +1,20 @@
+mod block;
 mod group;
 mod io;
 mod name;
+mod pair;
 mod region;
 mod score;
 mod seq;
 mod strand;
 mod tag;
 
-pub use group::{DumpComponent, GroupOps};
+pub use block::Blocked;
+pub use group::{DumpComponent, GroupOps, RecordAt};
 pub use io::{Parsable, Serializable};
 pub use name::Named;
-pub use region::{Region, RegionCore};
+pub use pair::Paired;
+pub use region::{Region, RegionCore, RegionMut};
 pub use score::Scored;
 pub use seq::{Nuclide, WithSequence};
 pub use strand::{Strand, Stranded};
@@ -19,6 +19,15 @@ impl<'a, T: Named<'a>> Named<'a> for Option<T> {
     }
 }
 
+impl<'a, T: Named<'a>> Named<'a> for &T {
+    fn name(&self) -> &str {
+        T::name(*self)
+    }
+    fn rc_name(&self) -> RcStr<'a> {
+        T::rc_name(*self)
+    }
+}
+
 impl<'a, A: Named<'a>, B> Named<'a> for (A, B) {
     fn name(&self) -> &str {
         self.0.name()
@@ -0,0 +1,9 @@
+use super::Region;
+
+/// A record made of two regions, for example, a BEDPE record. The two ends may not be on the
+/// same chromosome.
+pub trait Paired {
+    type End: Region;
+    fn first_end(&self) -> &Self::End;
+    fn second_end(&self) -> &Self::End;
+}
@@ -28,6 +28,11 @@ impl<'a> RegionCore for (ChrRef<'a>, u32, u32) {
     }
 }
 
+/// The records whose region can be changed in place
+pub trait RegionMut: RegionCore {
+    fn set_region(&mut self, start: u32, end: u32);
+}
+
 pub trait Region: RegionCore {
     #[inline(always)]
     fn overlaps(&self, b: &impl Region) -> bool {
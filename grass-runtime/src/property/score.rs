@@ -8,6 +8,12 @@ impl<S, T: Scored<S>> Scored<S> for Option<T> {
     }
 }
 
+impl<S, T: Scored<S>> Scored<S> for &T {
+    fn score(&self) -> Option<S> {
+        T::score(*self)
+    }
+}
+
 impl<S, A: Scored<S>, B> Scored<S> for (A, B) {
     fn score(&self) -> Option<S> {
         self.0.score()
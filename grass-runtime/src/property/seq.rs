@@ -1,3 +1,4 @@
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Nuclide {
     A,
     T,
@@ -7,6 +8,19 @@ pub enum Nuclide {
     N,
 }
 
+impl From<u8> for Nuclide {
+    fn from(base: u8) -> Self {
+        match base {
+            b'A' | b'a' => Nuclide::A,
+            b'T' | b't' => Nuclide::T,
+            b'C' | b'c' => Nuclide::C,
+            b'G' | b'g' => Nuclide::G,
+            b'U' | b'u' => Nuclide::U,
+            _ => Nuclide::N,
+        }
+    }
+}
+
 pub trait WithSequence {
     type RangeType: IntoIterator<Item = Nuclide>;
     fn at(&self, offset: usize) -> Nuclide;
@@ -14,15 +14,18 @@ impl Strand {
     pub fn is_negative(&self) -> bool {
         matches!(self, Strand::Negative)
     }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Positive => "+",
+            Self::Negative => "-",
+            Self::Unknown => ".",
+        }
+    }
 }
 
 impl Display for Strand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Positive => write!(f, "+"),
-            Self::Negative => write!(f, "-"),
-            Self::Unknown => write!(f, "."),
-        }
+        write!(f, "{}", self.as_str())
     }
 }
 
@@ -52,6 +55,12 @@ impl<T: Stranded> Stranded for Option<T> {
     }
 }
 
+impl<T: Stranded> Stranded for &T {
+    fn strand(&self) -> Strand {
+        T::strand(*self)
+    }
+}
+
 impl<A: Stranded, B> Stranded for (A, B) {
     fn strand(&self) -> Strand {
         self.0.strand()
@@ -1,8 +1,8 @@
-use std::{rc::Rc, path::Path, error::Error};
+use std::{rc::Rc, path::Path, error::Error, fs::File, cell::RefCell, io::{Read, Result as IoResult}, thread::JoinHandle};
 
 use d4_hts::{Alignment, BamFile, AlignmentReader, AlignmentIter};
 
-use crate::{ChrRef, Genome, property::{RegionCore, Scored, Stranded, Strand}};
+use crate::{ChrRef, Genome, builtin::report_error, property::{RegionCore, Scored, Stranded, Strand}};
 
 #[derive(Clone)]
 pub struct BamRecord<'a> {
@@ -13,43 +13,106 @@ pub struct BamRecord<'a> {
 pub struct BamReader {
     file:BamFile,
     chroms: Vec<ChrRef<'static>>,
+    // The pipe we read from when the data comes from a Rust reader
+    source: Option<File>,
+    // The thread that copies the data from the Rust reader into the pipe
+    copier: RefCell<Option<JoinHandle<IoResult<u64>>>>,
 }
 
-pub struct BamIter<'a>(AlignmentIter<'a, &'a BamFile>, &'a [ChrRef<'static>]);
+pub struct BamIter<'a>(AlignmentIter<'a, &'a BamFile>, &'a [ChrRef<'static>], &'a RefCell<Option<JoinHandle<IoResult<u64>>>>);
 
 impl <'a> Iterator for BamIter<'a> {
     type Item = BamRecord<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let BamIter(ref mut iter, ref chroms) = self;
+        let BamIter(ref mut iter, ref chroms, copier) = self;
         loop {
-            if let Ok(alignment) = iter.next()? {
-                let chrom_name = chroms[alignment.ref_id() as usize].clone();
-                let record = Rc::new(alignment);
-                return Some(BamRecord{
-                    chrom_name,
-                    record,
-                })
+            match iter.next() {
+                Some(Ok(alignment)) => {
+                    let chrom_name = chroms[alignment.ref_id() as usize].clone();
+                    let record = Rc::new(alignment);
+                    return Some(BamRecord{
+                        chrom_name,
+                        record,
+                    })
+                }
+                Some(Err(_)) => continue,
+                None => {
+                    // A failed copy closes the pipe early, which htslib can't tell from the end
+                    // of the file
+                    if let Some(copier) = copier.borrow_mut().take() {
+                        match copier.join() {
+                            Ok(Ok(_)) => {}
+                            Ok(Err(err)) => report_error(format!("Unable to read the alignments: {}", err)),
+                            Err(_) => report_error("The alignment reader panicked".to_string()),
+                        }
+                    }
+                    return None;
+                }
             }
         }
     }
 }
 
 impl BamReader {
-    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
-        let file = BamFile::open(path.as_ref())?;
+    fn from_bam_file(file: BamFile) -> Self {
         let chroms = file.chroms().iter().map(|(name, size)|{
             let chr = Genome::query_chr(name.as_str()).to_static();
             chr.verify_size_or_update(*size);
             chr
         }).collect();
-        Ok(Self{
+        Self{
             file,
             chroms,
-        })
+            source: None,
+            copier: RefCell::new(None),
+        }
+    }
+    /// Open an alignment file, the path "-" means reading from the standard input
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let file = BamFile::open(path.as_ref())?;
+        Ok(Self::from_bam_file(file))
+    }
+    /// Open a CRAM file, the reference FASTA is required for decoding the alignments
+    pub fn open_cram<P: AsRef<Path>, R: AsRef<Path>>(path: P, reference: R) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::open(path)?.with_reference(reference))
+    }
+    /// Set the reference FASTA file, which is only used by CRAM
+    pub fn with_reference<R: AsRef<Path>>(self, reference: R) -> Self {
+        self.file.reference_path(reference.as_ref());
+        self
+    }
+    /// Read the alignments from a file descriptor, for example, 0 for the standard input.
+    /// Other than the standard input, file descriptors are only supported on unix.
+    pub fn from_fd(fd: u32) -> Result<Self, Box<dyn Error>> {
+        if fd == 0 {
+            return Self::open("-");
+        }
+        if cfg!(unix) {
+            Self::open(format!("/dev/fd/{}", fd))
+        } else {
+            Err(format!("Reading alignments from file descriptor #{} isn't supported on this platform", fd).into())
+        }
+    }
+    /// Read the alignments from any reader.
+    /// Since htslib only reads from file descriptors, the data is pumped into a pipe by a
+    /// background thread. The error of the reader is reported once all the alignments are read.
+    #[cfg(unix)]
+    pub fn from_reader<R: Read + Send + 'static>(mut reader: R) -> Result<Self, Box<dyn Error>> {
+        use std::os::unix::io::{AsRawFd, FromRawFd};
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let (pipe_out, mut pipe_in) = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+        let copier = std::thread::spawn(move || std::io::copy(&mut reader, &mut pipe_in));
+        let mut ret = Self::open(format!("/dev/fd/{}", pipe_out.as_raw_fd()))?;
+        ret.source = Some(pipe_out);
+        ret.copier = RefCell::new(Some(copier));
+        Ok(ret)
     }
     pub fn iter(&self) -> BamIter {
-        BamIter(self.file.into_alignment_iter(), self.chroms.as_slice())
+        BamIter(self.file.into_alignment_iter(), self.chroms.as_slice(), &self.copier)
     }
 }
 
@@ -81,4 +144,76 @@ impl <'a> Stranded for BamRecord<'a> {
             Strand::Positive
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs::File, io::{Cursor, ErrorKind, Read, Result as IoResult, Write}, os::unix::io::FromRawFd};
+
+    use super::BamReader;
+    use crate::{builtin::take_error, property::{RegionCore, Stranded}};
+
+    const SAM: &[u8] = b"@HD\tVN:1.6\tSO:coordinate\n@SQ\tSN:bam1\tLN:1000\n\
+        r1\t0\tbam1\t100\t60\t10M\t*\t0\t0\tACGTACGTAC\t*\n\
+        r2\t16\tbam1\t200\t30\t5M\t*\t0\t0\tACGTA\t*\n";
+
+    fn alignments(reader: &BamReader) -> Vec<(u32, u32, &'static str)> {
+        reader.iter().map(|r| (r.start(), r.end(), r.strand().as_str())).collect()
+    }
+
+    /// Yields the data and then fails, like a truncated input
+    struct Truncated(Cursor<&'static [u8]>);
+
+    impl Read for Truncated {
+        fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+            match self.0.read(buf)? {
+                0 => Err(ErrorKind::UnexpectedEof.into()),
+                size => Ok(size),
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let reader = BamReader::from_reader(Cursor::new(SAM)).unwrap();
+        assert_eq!(alignments(&reader), [(99, 109, "+"), (199, 204, "-")]);
+        assert!(take_error().is_ok());
+    }
+
+    #[test]
+    fn test_from_reader_error() {
+        let reader = BamReader::from_reader(Truncated(Cursor::new(SAM))).unwrap();
+        assert_eq!(alignments(&reader).len(), 2);
+        assert!(take_error().is_err());
+    }
+
+    #[test]
+    fn test_from_fd() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let mut pipe_in = unsafe { File::from_raw_fd(fds[1]) };
+        let writer = std::thread::spawn(move || pipe_in.write_all(SAM));
+        let reader = BamReader::from_fd(fds[0] as u32).unwrap();
+        assert_eq!(alignments(&reader).len(), 2);
+        writer.join().unwrap().unwrap();
+        drop(reader);
+        drop(unsafe { File::from_raw_fd(fds[0]) });
+    }
+
+    #[test]
+    fn test_open_cram_with_reference() {
+        let dir = std::env::temp_dir().join(format!("grass-cram-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let alignments_path = dir.join("input.sam");
+        let reference_path = dir.join("ref.fa");
+        std::fs::write(&alignments_path, SAM).unwrap();
+        std::fs::write(&reference_path, format!(">bam1\n{}\n", "A".repeat(1000))).unwrap();
+
+        // htslib takes the format from the content, so the reference goes through the same path
+        // as a CRAM input, and it's just not needed to decode the SAM text
+        let reader = BamReader::open_cram(&alignments_path, &reference_path).unwrap();
+        assert_eq!(alignments(&reader), [(99, 109, "+"), (199, 204, "-")]);
+        assert!(BamReader::open_cram(dir.join("missing.cram"), &reference_path).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,304 @@
+use std::{
+    fmt::Display,
+    io::{Result, Write},
+    ops::{Deref, DerefMut},
+    rc::Rc,
+    str::FromStr,
+};
+
+use crate::{
+    file::Buffer,
+    property::{
+        Blocked, Named, Parsable, Region, RegionCore, Scored, Serializable, Strand, Stranded,
+        Tagged,
+    },
+    ChrRef,
+};
+
+use super::{Bed3, Bed6, CastTo, RcStr, ToSelfContained};
+
+/// A BED12 record. The blocks are kept as (offset, size) pairs, where the offset is relative to
+/// the start of the record, just like the blockStarts and blockSizes columns.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Bed12<'a, T = f64> {
+    inner: Bed6<'a, T>,
+    pub thick_start: u32,
+    pub thick_end: u32,
+    pub item_rgb: RcStr<'a>,
+    blocks: Rc<[(u32, u32)]>,
+}
+
+impl<'a, T> Deref for Bed12<'a, T> {
+    type Target = Bed6<'a, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'a> DerefMut for Bed12<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<'a, T: Display> Serializable for Bed12<'a, T> {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        self.inner.dump(&mut fp)?;
+        write!(
+            fp,
+            "\t{}\t{}\t{}\t{}\t",
+            self.thick_start,
+            self.thick_end,
+            self.item_rgb.deref(),
+            self.blocks.len()
+        )?;
+        for (_, size) in self.blocks.iter() {
+            write!(fp, "{},", size)?;
+        }
+        fp.write_all(b"\t")?;
+        for (offset, _) in self.blocks.iter() {
+            write!(fp, "{},", offset)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: Display> Serializable for Option<Bed12<'a, T>> {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        if let Some(inner) = self {
+            inner.dump(fp)
+        } else {
+            fp.write_all(b".\t.\t.\t.\t.\t.\t.\t.\t.\t.\t.\t.")
+        }
+    }
+}
+
+fn parse_list(s: &str) -> impl Iterator<Item = u32> + '_ {
+    s.split(',').filter_map(|item| item.trim().parse().ok())
+}
+
+impl<'a, T: FromStr> Parsable for Bed12<'a, T> {
+    fn parse(s: &Rc<Buffer>) -> Option<(Self, usize)> {
+        let (inner, mut pos) = Bed6::parse(s)?;
+        let line_end = s.trim_end().len();
+        let mut next_field = || {
+            if s[pos..].starts_with('\t') {
+                pos += 1;
+            }
+            let begin = pos.min(line_end);
+            let end = memchr::memchr(b'\t', s[begin..line_end].as_bytes())
+                .map_or(line_end, |brk| begin + brk);
+            pos = end;
+            (begin, end)
+        };
+
+        // Missing or malformed optional columns falls back to the values of a single block
+        // record, so that a BED6 line is still a valid BED12 record
+        let (begin, end) = next_field();
+        let thick_start = s[begin..end].parse().unwrap_or_else(|_| inner.start());
+        let (begin, end) = next_field();
+        let thick_end = s[begin..end].parse().unwrap_or_else(|_| inner.end());
+        let (begin, end) = next_field();
+        let item_rgb = if begin < end {
+            RcStr::from_buffer(s, begin, end)
+        } else {
+            RcStr::from_str("0")
+        };
+        let (begin, end) = next_field();
+        let block_count: usize = s[begin..end].parse().unwrap_or(0);
+        let (begin, end) = next_field();
+        let sizes = &s[begin..end];
+        let (begin, end) = next_field();
+        let offsets = &s[begin..end];
+
+        let mut blocks: Vec<_> = parse_list(offsets)
+            .zip(parse_list(sizes))
+            .take(block_count)
+            .collect();
+        if blocks.is_empty() {
+            blocks.push((0, inner.length()));
+        }
+        blocks.sort_unstable();
+
+        Some((
+            Self {
+                inner,
+                thick_start,
+                thick_end,
+                item_rgb,
+                blocks: blocks.into(),
+            },
+            pos,
+        ))
+    }
+}
+
+impl<'a, S> Bed12<'a, S> {
+    pub fn new<T: RegionCore + Named<'a> + Scored<S> + Stranded>(region: &T) -> Self
+    where
+        S: Default,
+    {
+        let inner = Bed6::new(region);
+        Self {
+            thick_start: region.start(),
+            thick_end: region.end(),
+            item_rgb: RcStr::from_str("0"),
+            blocks: vec![(0, region.length())].into(),
+            inner,
+        }
+    }
+
+    #[inline(always)]
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Iterate over the blocks as sub-regions of this record
+    pub fn blocks(&self) -> impl Iterator<Item = Bed3> + '_ {
+        let chrom = self.chrom();
+        let start = self.start();
+        self.blocks.iter().map(move |&(offset, size)| Bed3 {
+            chrom,
+            start: start + offset,
+            end: start + offset + size,
+        })
+    }
+
+    // The gaps between adjacent blocks
+    fn gaps(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let start = self.start();
+        self.blocks.windows(2).filter_map(move |pair| {
+            let gap_start = start + pair[0].0 + pair[0].1;
+            let gap_end = start + pair[1].0;
+            (gap_start < gap_end).then_some((gap_start, gap_end))
+        })
+    }
+}
+
+impl<'a> Bed12<'a> {
+    fn make_part(&self, start: u32, end: u32) -> Bed6<'a> {
+        let mut part = self.inner.clone();
+        part.start = start;
+        part.end = end;
+        part
+    }
+
+    /// Split the record into exons, each of which carries the name, score and strand of this
+    /// record
+    pub fn exons(&self) -> impl Iterator<Item = Bed6<'a>> + '_ {
+        self.blocks()
+            .map(move |block| self.make_part(block.start, block.end))
+    }
+
+    /// Split the record into introns, aka the gaps between exons
+    pub fn introns(&self) -> impl Iterator<Item = Bed6<'a>> + '_ {
+        self.gaps().map(move |(start, end)| self.make_part(start, end))
+    }
+}
+
+impl<'a, S> RegionCore for Bed12<'a, S> {
+    #[inline(always)]
+    fn start(&self) -> u32 {
+        self.inner.start()
+    }
+    #[inline(always)]
+    fn end(&self) -> u32 {
+        self.inner.end()
+    }
+    #[inline(always)]
+    fn chrom(&self) -> ChrRef<'static> {
+        self.inner.chrom()
+    }
+}
+
+impl<'a, T: Clone> Scored<T> for Bed12<'a, T> {
+    #[inline(always)]
+    fn score(&self) -> Option<T> {
+        self.inner.score()
+    }
+}
+
+impl<'a, T> Stranded for Bed12<'a, T> {
+    fn strand(&self) -> Strand {
+        self.inner.strand()
+    }
+}
+
+impl<'a, T> Named<'a> for Bed12<'a, T> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+    fn rc_name(&self) -> RcStr<'a> {
+        self.inner.rc_name()
+    }
+}
+
+impl<'a, T> Blocked for Bed12<'a, T> {
+    fn block_ranges(&self) -> Vec<(u32, u32)> {
+        self.blocks().map(|block| (block.start, block.end)).collect()
+    }
+}
+
+impl<'a> ToSelfContained for Bed12<'a> {
+    type SelfContained = Bed12<'static>;
+    fn to_self_contained(&self) -> Self::SelfContained {
+        Bed12 {
+            inner: self.inner.to_self_contained(),
+            thick_start: self.thick_start,
+            thick_end: self.thick_end,
+            item_rgb: self.item_rgb.to_static(),
+            blocks: self.blocks.clone(),
+        }
+    }
+}
+
+impl<'a, T: Clone> Tagged<T> for Bed12<'a> {}
+
+impl<'a, S: Default, T: Region + Named<'a> + Scored<S> + Stranded> CastTo<Bed12<'a, S>> for T {
+    fn make_record(&self) -> Bed12<'a, S> {
+        Bed12::new(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Bed12;
+    use crate::{
+        property::{Blocked, Named, RegionCore, Serializable},
+        record::Bed3,
+        LineRecordStreamExt,
+    };
+
+    const DATA: &str = "chr1\t100\t1000\ttx1\t0\t+\t150\t900\t255,0,0\t3\t100,200,100,\t0,400,800,\n";
+
+    #[test]
+    fn test_parse_bed12() {
+        let record: Bed12 = DATA.as_bytes().into_record_iter().next().unwrap();
+        assert_eq!(record.name(), "tx1");
+        assert_eq!((record.thick_start, record.thick_end), (150, 900));
+        assert_eq!(record.block_count(), 3);
+
+        let exons: Vec<_> = record.exons().map(|e| (e.start(), e.end())).collect();
+        assert_eq!(exons, vec![(100, 200), (500, 700), (900, 1000)]);
+        let introns: Vec<_> = record.introns().map(|e| (e.start(), e.end())).collect();
+        assert_eq!(introns, vec![(200, 500), (700, 900)]);
+
+        let mut buffer = Vec::new();
+        record.dump(&mut buffer).unwrap();
+        assert_eq!(buffer, DATA.trim_end().as_bytes());
+
+        let in_intron = Bed3 {
+            chrom: record.chrom(),
+            start: 250,
+            end: 450,
+        };
+        let in_exon = Bed3 {
+            start: 650,
+            end: 750,
+            ..in_intron
+        };
+        assert!(!record.overlaps_on_blocks(&in_intron));
+        assert!(record.overlaps_on_blocks(&in_exon));
+    }
+}
@@ -1,7 +1,7 @@
 use std::{io::{Result, Write}, rc::Rc};
 
 use crate::{
-    property::{Named, Parsable, RegionCore, Scored, Serializable, Stranded, Tagged, Region},
+    property::{Blocked, Named, Parsable, RegionCore, Scored, Serializable, Stranded, Tagged, Region, RegionMut},
     ChrRef, file::Buffer,
 };
 
@@ -29,7 +29,7 @@ impl Serializable for Option<Bed3> {
         if let Some(inner) = self {
             inner.dump(fp)
         } else {
-            fp.write_all(b".\t.\t.\t")
+            fp.write_all(b".\t.\t.")
         }
     }
 }
@@ -84,6 +84,14 @@ impl Bed3 {
     }
 }
 
+impl RegionMut for Bed3 {
+    #[inline(always)]
+    fn set_region(&mut self, start: u32, end: u32) {
+        self.start = start;
+        self.end = end;
+    }
+}
+
 impl RegionCore for Bed3 {
     #[inline(always)]
     fn start(&self) -> u32 {
@@ -123,6 +131,8 @@ impl ToSelfContained for Bed3 {
 
 impl<T: Clone> Tagged<T> for Bed3 {}
 
+impl Blocked for Bed3 {}
+
 impl <T: Region> CastTo<Bed3> for T {
     fn make_record(&self) -> Bed3 {
         Bed3::new(self)
@@ -3,7 +3,7 @@ use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
 use crate::file::Buffer;
-use crate::property::{Tagged, Region};
+use crate::property::{Blocked, Tagged, Region, RegionMut};
 use crate::{
     property::{Named, Parsable, RegionCore, Scored, Serializable, Stranded},
     ChrRef,
@@ -143,6 +143,13 @@ impl <'a> Bed4<'a> {
     }
 }
 
+impl<'a> RegionMut for Bed4<'a> {
+    #[inline(always)]
+    fn set_region(&mut self, start: u32, end: u32) {
+        self.inner.set_region(start, end)
+    }
+}
+
 impl<'a> RegionCore for Bed4<'a> {
     #[inline(always)]
     fn start(&self) -> u32 {
@@ -194,6 +201,8 @@ impl<'a> ToSelfContained for Bed4<'a> {
 
 impl<'a, T: Clone> Tagged<T> for Bed4<'a> {}
 
+impl<'a> Blocked for Bed4<'a> {}
+
 impl <'a, T: Region + Named<'a>> CastTo<Bed4<'a>> for T {
     fn make_record(&self) -> Bed4<'a> {
         Bed4::new(self)
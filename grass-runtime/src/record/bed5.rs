@@ -6,7 +6,7 @@ use std::{
 };
 
 use crate::{
-    property::{Named, Parsable, RegionCore, Scored, Serializable, Stranded, Tagged, Region},
+    property::{Blocked, Named, Parsable, RegionCore, Scored, Serializable, Stranded, Tagged, Region, RegionMut},
     ChrRef, file::Buffer,
 };
 
@@ -84,6 +84,13 @@ impl<'a, S> Bed5<'a, S> {
     }
 }
 
+impl<'a, S> RegionMut for Bed5<'a, S> {
+    #[inline(always)]
+    fn set_region(&mut self, start: u32, end: u32) {
+        self.inner.set_region(start, end)
+    }
+}
+
 impl<'a, S> RegionCore for Bed5<'a, S> {
     #[inline(always)]
     fn start(&self) -> u32 {
@@ -129,6 +136,8 @@ impl<'a> ToSelfContained for Bed5<'a> {
 
 impl<'a, T: Clone> Tagged<T> for Bed5<'a> {}
 
+impl<'a, T> Blocked for Bed5<'a, T> {}
+
 impl <'a, S: Default, T: Region + Named<'a> + Scored<S>> CastTo<Bed5<'a, S>> for T {
     fn make_record(&self) -> Bed5<'a, S> {
         Bed5::new(self)
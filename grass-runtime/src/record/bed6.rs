@@ -6,7 +6,7 @@ use std::{
 };
 
 use crate::{
-    property::{Named, Parsable, RegionCore, Scored, Serializable, Strand, Stranded, Tagged, Region},
+    property::{Blocked, Named, Parsable, RegionCore, Scored, Serializable, Strand, Stranded, Tagged, Region, RegionMut},
     ChrRef, file::Buffer,
 };
 
@@ -88,6 +88,13 @@ impl<'a, S> Bed6<'a, S> {
     }
 }
 
+impl<'a, S> RegionMut for Bed6<'a, S> {
+    #[inline(always)]
+    fn set_region(&mut self, start: u32, end: u32) {
+        self.inner.set_region(start, end)
+    }
+}
+
 impl<'a, S> RegionCore for Bed6<'a, S> {
     #[inline(always)]
     fn start(&self) -> u32 {
@@ -137,6 +144,8 @@ impl<'a> ToSelfContained for Bed6<'a> {
 
 impl<'a, T: Clone> Tagged<T> for Bed6<'a> {}
 
+impl<'a, T> Blocked for Bed6<'a, T> {}
+
 impl <'a, S: Default, T: Region + Named<'a> + Scored<S> + Stranded> CastTo<Bed6<'a, S>> for T {
     fn make_record(&self) -> Bed6<'a, S> {
         Bed6::new(self)
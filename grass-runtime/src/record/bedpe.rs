@@ -0,0 +1,240 @@
+use std::{
+    fmt::Display,
+    io::{Result, Write},
+    ops::Deref,
+    rc::Rc,
+    str::FromStr,
+};
+
+use crate::{
+    file::Buffer,
+    property::{
+        Named, Paired, Parsable, RegionCore, Scored, Serializable, Strand, Stranded, Tagged,
+    },
+    ChrRef, Genome,
+};
+
+use super::{Bed3, RcStr, ToSelfContained};
+
+/// A BEDPE record, which describes a pair of regions, for example a Hi-C loop or a structural
+/// variant. The region of the record itself is the first end, so a BEDPE file sorted by the
+/// first end is a sorted stream.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BedPe<'a, T = f64> {
+    pub first: Bed3,
+    pub second: Bed3,
+    pub name: RcStr<'a>,
+    pub score: Option<T>,
+    pub first_strand: Strand,
+    pub second_strand: Strand,
+    /// The user defined columns after the second strand, kept as it is
+    pub rest: RcStr<'a>,
+}
+
+fn dump_end<W: Write>(end: &Bed3, mut fp: W) -> Result<()> {
+    // An unknown end is written as ". -1 -1", as bedtools does
+    if end.chrom == ChrRef::Dummy {
+        fp.write_all(b".\t-1\t-1")
+    } else {
+        end.dump(fp)
+    }
+}
+
+fn parse_end(chrom: &str, start: &str, end: &str) -> Option<Bed3> {
+    if chrom == "." {
+        return Some(Bed3 {
+            chrom: ChrRef::Dummy,
+            start: 0,
+            end: 0,
+        });
+    }
+    Some(Bed3 {
+        chrom: Genome::query_chr(chrom).to_static(),
+        start: start.parse().ok()?,
+        end: end.parse().ok()?,
+    })
+}
+
+fn parse_strand(s: &str) -> Strand {
+    match s {
+        "+" => Strand::Positive,
+        "-" => Strand::Negative,
+        _ => Strand::Unknown,
+    }
+}
+
+impl<'a, T: Display> Serializable for BedPe<'a, T> {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        dump_end(&self.first, &mut fp)?;
+        fp.write_all(b"\t")?;
+        dump_end(&self.second, &mut fp)?;
+        write!(fp, "\t{}\t", self.name.deref())?;
+        if let Some(score) = self.score.as_ref() {
+            write!(fp, "{}", score)?;
+        } else {
+            fp.write_all(b".")?;
+        }
+        write!(fp, "\t{}\t{}", self.first_strand, self.second_strand)?;
+        if !self.rest.is_empty() {
+            write!(fp, "\t{}", self.rest.deref())?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: Display> Serializable for Option<BedPe<'a, T>> {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        if let Some(inner) = self {
+            inner.dump(fp)
+        } else {
+            fp.write_all(b".\t.\t.\t.\t.\t.\t.\t.\t.\t.")
+        }
+    }
+}
+
+impl<'a, T: FromStr> Parsable for BedPe<'a, T> {
+    fn parse(s: &Rc<Buffer>) -> Option<(Self, usize)> {
+        let line_end = s.trim_end().len();
+        let mut fields = Vec::with_capacity(11);
+        let mut begin = 0;
+        for brk in memchr::Memchr::new(b'\t', s[..line_end].as_bytes()) {
+            fields.push((begin, brk));
+            begin = brk + 1;
+            if fields.len() == 10 {
+                break;
+            }
+        }
+        fields.push((begin, line_end));
+        if fields.len() < 6 {
+            return None;
+        }
+        let field = |idx: usize| fields.get(idx).map_or("", |&(b, e)| &s[b..e]);
+
+        let first = parse_end(field(0), field(1), field(2))?;
+        let second = parse_end(field(3), field(4), field(5))?;
+        let name = match fields.get(6) {
+            Some(&(b, e)) => RcStr::from_buffer(s, b, e),
+            None => RcStr::from_str("."),
+        };
+        let rest = match fields.get(10) {
+            Some(&(b, e)) => RcStr::from_buffer(s, b, e),
+            None => RcStr::from_str(""),
+        };
+
+        Some((
+            Self {
+                first,
+                second,
+                name,
+                score: field(7).parse().ok(),
+                first_strand: parse_strand(field(8)),
+                second_strand: parse_strand(field(9)),
+                rest,
+            },
+            line_end,
+        ))
+    }
+}
+
+impl<'a, T> BedPe<'a, T> {
+    /// Check if both ends are on the same chromosome
+    pub fn is_intra_chrom(&self) -> bool {
+        self.first.chrom == self.second.chrom
+    }
+}
+
+impl<'a, T> Paired for BedPe<'a, T> {
+    type End = Bed3;
+    fn first_end(&self) -> &Bed3 {
+        &self.first
+    }
+    fn second_end(&self) -> &Bed3 {
+        &self.second
+    }
+}
+
+impl<'a, T> RegionCore for BedPe<'a, T> {
+    #[inline(always)]
+    fn start(&self) -> u32 {
+        self.first.start()
+    }
+    #[inline(always)]
+    fn end(&self) -> u32 {
+        self.first.end()
+    }
+    #[inline(always)]
+    fn chrom(&self) -> ChrRef<'static> {
+        self.first.chrom()
+    }
+}
+
+impl<'a, T: Clone> Scored<T> for BedPe<'a, T> {
+    #[inline(always)]
+    fn score(&self) -> Option<T> {
+        self.score.clone()
+    }
+}
+
+impl<'a, T> Stranded for BedPe<'a, T> {
+    fn strand(&self) -> Strand {
+        self.first_strand
+    }
+}
+
+impl<'a, T> Named<'a> for BedPe<'a, T> {
+    fn name(&self) -> &str {
+        self.name.deref()
+    }
+    fn rc_name(&self) -> RcStr<'a> {
+        self.name.clone()
+    }
+}
+
+impl<'a> ToSelfContained for BedPe<'a> {
+    type SelfContained = BedPe<'static>;
+    fn to_self_contained(&self) -> Self::SelfContained {
+        BedPe {
+            first: self.first,
+            second: self.second,
+            name: self.name.to_static(),
+            score: self.score,
+            first_strand: self.first_strand,
+            second_strand: self.second_strand,
+            rest: self.rest.to_static(),
+        }
+    }
+}
+
+impl<'a, T: Clone> Tagged<T> for BedPe<'a> {}
+
+#[cfg(test)]
+mod test {
+    use super::BedPe;
+    use crate::{
+        property::{Named, Paired, RegionCore, Scored, Serializable},
+        LineRecordStreamExt,
+    };
+
+    const DATA: &str = "chr1\t100\t200\tchr1\t5000\t5100\tloop1\t30\t+\t-\tx\ty\n\
+        chr1\t300\t400\t.\t-1\t-1\tsv1\t.\t.\t.\n";
+
+    #[test]
+    fn test_parse_bedpe() {
+        let records: Vec<BedPe> = DATA.as_bytes().into_record_iter().collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].start(), records[0].end()), (100, 200));
+        let second = records[0].second_end();
+        assert_eq!((second.start(), second.end()), (5000, 5100));
+        assert_eq!(records[0].name(), "loop1");
+        assert_eq!(records[0].score(), Some(30.0));
+        assert!(records[0].is_intra_chrom());
+        assert!(!records[1].is_intra_chrom());
+
+        let mut buffer = Vec::new();
+        records[0].dump(&mut buffer).unwrap();
+        assert_eq!(buffer, DATA.lines().next().unwrap().as_bytes());
+        let mut buffer = Vec::new();
+        records[1].dump(&mut buffer).unwrap();
+        assert_eq!(buffer, DATA.lines().nth(1).unwrap().as_bytes());
+    }
+}
@@ -0,0 +1,454 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    error::Error,
+    fs::File,
+    io::{
+        BufRead, BufReader, Error as IoError, ErrorKind, Read, Result as IoResult, Seek, SeekFrom,
+        Write,
+    },
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::{
+    builtin::report_error,
+    property::{
+        Blocked, Named, Nuclide, Region, RegionCore, Scored, Serializable, Strand, Stranded, Tagged,
+        WithSequence,
+    },
+    ChrRef, Genome,
+};
+
+use super::{Bed3, RcStr, ToSelfContained};
+
+// A line of the .fai file
+struct FaiEntry {
+    name: String,
+    length: u64,
+    offset: u64,
+    line_bases: u64,
+    line_width: u64,
+}
+
+impl FaiEntry {
+    fn parse(line: &str) -> Option<Self> {
+        let mut tokens = line.split('\t');
+        Some(Self {
+            name: tokens.next()?.to_string(),
+            length: tokens.next()?.parse().ok()?,
+            offset: tokens.next()?.parse().ok()?,
+            line_bases: tokens.next()?.parse().ok()?,
+            line_width: tokens.next()?.parse().ok()?,
+        })
+    }
+
+    fn file_offset(&self, pos: u64) -> u64 {
+        self.offset + pos / self.line_bases * self.line_width + pos % self.line_bases
+    }
+}
+
+/// A reference genome backed by a FASTA file, the sequence is read on demand. The faidx index
+/// next to the FASTA file is used if there's one, otherwise the index is built by scanning the
+/// file.
+pub struct FastaReference {
+    file: RefCell<File>,
+    index: Vec<FaiEntry>,
+    name_map: HashMap<String, usize>,
+}
+
+impl FastaReference {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let mut fai_path = PathBuf::from(path);
+        fai_path.set_extension(match path.extension() {
+            Some(ext) => format!("{}.fai", ext.to_string_lossy()),
+            None => "fai".to_string(),
+        });
+
+        let index = if fai_path.exists() {
+            let mut index = Vec::new();
+            for line in BufReader::new(File::open(fai_path)?).lines() {
+                let line = line?;
+                if line.is_empty() {
+                    continue;
+                }
+                let entry = FaiEntry::parse(&line).ok_or("Invalid faidx index")?;
+                index.push(entry);
+            }
+            index
+        } else {
+            Self::build_index(File::open(path)?)?
+        };
+        let name_map = index
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| (entry.name.clone(), idx))
+            .collect();
+
+        let ret = Self {
+            file: RefCell::new(File::open(path)?),
+            index,
+            name_map,
+        };
+        for (name, size) in ret.chroms() {
+            Genome::query_chr(&name)
+                .to_static()
+                .verify_size_or_update(size as usize);
+        }
+        Ok(ret)
+    }
+
+    /// Build the faidx index of a FASTA file in the same way as `samtools faidx`, all the lines of
+    /// a sequence but the last one should have the same length
+    fn build_index<R: Read>(reader: R) -> IoResult<Vec<FaiEntry>> {
+        let mut reader = BufReader::new(reader);
+        let mut index: Vec<FaiEntry> = Vec::new();
+        let mut offset = 0;
+        let mut line = String::new();
+        // If the last line of the current sequence has been read, which is shorter than others
+        let mut ended = false;
+        loop {
+            line.clear();
+            let size = reader.read_line(&mut line)? as u64;
+            if size == 0 {
+                return Ok(index);
+            }
+            offset += size;
+            if let Some(header) = line.strip_prefix('>') {
+                let name = header.split_whitespace().next().unwrap_or_default();
+                index.push(FaiEntry {
+                    name: name.to_string(),
+                    length: 0,
+                    offset,
+                    line_bases: 0,
+                    line_width: 0,
+                });
+                ended = false;
+                continue;
+            }
+            let entry = match index.last_mut() {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let bases = line.trim_end().len() as u64;
+            if entry.line_bases == 0 {
+                entry.line_bases = bases;
+                entry.line_width = size;
+            } else if bases > 0 && (ended || bases > entry.line_bases) {
+                return Err(IoError::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "The lines of sequence {} have different lengths, which can't be indexed",
+                        entry.name
+                    ),
+                ));
+            }
+            ended |= bases < entry.line_bases;
+            entry.length += bases;
+        }
+    }
+
+    /// List all the sequence names and their sizes in this reference
+    pub fn chroms(&self) -> Vec<(String, u64)> {
+        self.index
+            .iter()
+            .map(|entry| (entry.name.clone(), entry.length))
+            .collect()
+    }
+
+    /// Fetch the sequence of the given 0-based half-open range, the range is clamped to the size
+    /// of the sequence. It's an error if the chromosome isn't in this reference.
+    pub fn fetch(&self, chrom: &str, start: u32, end: u32) -> IoResult<Vec<u8>> {
+        let entry = match self.name_map.get(chrom) {
+            Some(&idx) => &self.index[idx],
+            None => {
+                return Err(IoError::new(
+                    ErrorKind::NotFound,
+                    format!("The chromosome {} isn't in the reference", chrom),
+                ))
+            }
+        };
+        let end = (end as u64).min(entry.length);
+        let start = (start as u64).min(end);
+        if start == end {
+            return Ok(Vec::new());
+        }
+        let begin_ofs = entry.file_offset(start);
+        let end_ofs = entry.file_offset(end - 1) + 1;
+        let mut buffer = vec![0; (end_ofs - begin_ofs) as usize];
+        let mut file = self.file.borrow_mut();
+        file.seek(SeekFrom::Start(begin_ofs))?;
+        file.read_exact(&mut buffer)?;
+        buffer.retain(|b| !b.is_ascii_whitespace());
+        Ok(buffer)
+    }
+
+    /// Attach the sequence of the region to the given record
+    pub fn attach<T: Region>(&self, record: T) -> IoResult<Sequenced<T>> {
+        let chrom = record.chrom();
+        let seq = self.fetch(chrom.get_chr_name(), record.start(), record.end())?;
+        Ok(Sequenced {
+            inner: record,
+            seq: String::from_utf8_lossy(&seq).into(),
+        })
+    }
+
+    /// Attach the sequences to the records of an iterator. The iteration stops at the first
+    /// error, which is reported through `builtin::take_error`.
+    pub fn attach_iter<I>(&self, iter: I) -> AttachIter<'_, I>
+    where
+        I: Iterator,
+        I::Item: Region,
+    {
+        AttachIter {
+            reference: self,
+            iter,
+            failed: false,
+        }
+    }
+
+    /// Iterate over all the sequences in the reference, each of which covers a whole chromosome.
+    /// The sequence of a chromosome is read into memory as a whole, so this is meant for small
+    /// references; use `attach_iter` to read the sequences of the regions instead.
+    pub fn into_record_iter(self) -> impl Iterator<Item = Sequenced<Bed3>> {
+        self.chroms().into_iter().map_while(move |(name, size)| {
+            let region = Bed3 {
+                chrom: Genome::query_chr(&name).to_static(),
+                start: 0,
+                end: size as u32,
+            };
+            self.attach(region).map_err(report_attach_error).ok()
+        })
+    }
+}
+
+fn report_attach_error(err: IoError) {
+    report_error(format!("Unable to read the reference sequence: {}", err));
+}
+
+pub struct AttachIter<'a, I> {
+    reference: &'a FastaReference,
+    iter: I,
+    failed: bool,
+}
+
+impl<I> Iterator for AttachIter<'_, I>
+where
+    I: Iterator,
+    I::Item: Region,
+{
+    type Item = Sequenced<I::Item>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let record = self.iter.next()?;
+        let result = self.reference.attach(record).map_err(report_attach_error).ok();
+        self.failed = result.is_none();
+        result
+    }
+}
+
+/// A record with the reference sequence of its region attached
+#[derive(Clone, PartialEq, PartialOrd, Eq, Ord)]
+pub struct Sequenced<T> {
+    inner: T,
+    seq: Rc<str>,
+}
+
+impl<T> Deref for Sequenced<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for Sequenced<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<T> Sequenced<T> {
+    pub fn seq(&self) -> &str {
+        self.seq.as_ref()
+    }
+
+    /// The fraction of G and C bases in the attached sequence
+    pub fn gc_content(&self) -> f64 {
+        if self.seq.is_empty() {
+            return 0.0;
+        }
+        let gc = self
+            .seq
+            .bytes()
+            .filter(|b| matches!(b, b'G' | b'C' | b'g' | b'c'))
+            .count();
+        gc as f64 / self.seq.len() as f64
+    }
+}
+
+impl<T> WithSequence for Sequenced<T> {
+    type RangeType = Vec<Nuclide>;
+
+    fn at(&self, offset: usize) -> Nuclide {
+        self.seq
+            .as_bytes()
+            .get(offset)
+            .map_or(Nuclide::N, |&b| Nuclide::from(b))
+    }
+
+    fn range(&self, from: usize, to: usize) -> Self::RangeType {
+        let to = to.min(self.seq.len());
+        let from = from.min(to);
+        self.seq.as_bytes()[from..to]
+            .iter()
+            .map(|&b| Nuclide::from(b))
+            .collect()
+    }
+}
+
+impl<T: Serializable> Serializable for Sequenced<T> {
+    fn dump<W: Write>(&self, mut fp: W) -> IoResult<()> {
+        self.inner.dump(&mut fp)?;
+        write!(fp, "\t{}", self.seq.as_ref())
+    }
+}
+
+impl<T: RegionCore> RegionCore for Sequenced<T> {
+    #[inline(always)]
+    fn start(&self) -> u32 {
+        self.inner.start()
+    }
+    #[inline(always)]
+    fn end(&self) -> u32 {
+        self.inner.end()
+    }
+    #[inline(always)]
+    fn chrom(&self) -> ChrRef<'static> {
+        self.inner.chrom()
+    }
+}
+
+impl<S, T: Scored<S>> Scored<S> for Sequenced<T> {
+    #[inline(always)]
+    fn score(&self) -> Option<S> {
+        self.inner.score()
+    }
+}
+
+impl<T: Stranded> Stranded for Sequenced<T> {
+    fn strand(&self) -> Strand {
+        self.inner.strand()
+    }
+}
+
+impl<'a, T: Named<'a>> Named<'a> for Sequenced<T> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+    fn rc_name(&self) -> RcStr<'a> {
+        self.inner.rc_name()
+    }
+}
+
+impl<T: ToSelfContained> ToSelfContained for Sequenced<T> {
+    type SelfContained = Sequenced<T::SelfContained>;
+    fn to_self_contained(&self) -> Self::SelfContained {
+        Sequenced {
+            inner: self.inner.to_self_contained(),
+            seq: self.seq.clone(),
+        }
+    }
+}
+
+impl<T, U: Clone> Tagged<U> for Sequenced<T>
+where
+    T: Tagged<U>,
+{
+    fn tag(&self) -> Option<U> {
+        self.inner.tag()
+    }
+}
+
+impl<T: Blocked> Blocked for Sequenced<T> {
+    fn block_ranges(&self) -> Vec<(u32, u32)> {
+        self.inner.block_ranges()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FastaReference;
+    use crate::{
+        builtin::take_error,
+        property::{Nuclide, WithSequence},
+        record::Bed3,
+        ChrRef,
+    };
+
+    #[test]
+    fn test_fasta_reference() {
+        let dir = std::env::temp_dir().join(format!("grass-fasta-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fasta_path = dir.join("ref.fa");
+        std::fs::write(&fasta_path, ">seqA desc\nACGTA\nCGGCN\nTT\n>seqB\nGGGG\n").unwrap();
+
+        let scanned = FastaReference::open(&fasta_path).unwrap();
+        assert_eq!(scanned.chroms(), [("seqA".to_string(), 12), ("seqB".to_string(), 4)]);
+        assert_eq!(scanned.fetch("seqA", 3, 8).unwrap(), b"TACGG");
+        assert_eq!(scanned.fetch("seqB", 0, 4).unwrap(), b"GGGG");
+
+        std::fs::write(
+            dir.join("ref.fa.fai"),
+            "seqA\t12\t11\t5\t6\nseqB\t4\t32\t4\t5\n",
+        )
+        .unwrap();
+        let indexed = FastaReference::open(&fasta_path).unwrap();
+        assert_eq!(indexed.fetch("seqA", 3, 8).unwrap(), b"TACGG");
+        assert_eq!(indexed.fetch("seqA", 9, 100).unwrap(), b"NTT");
+        assert_eq!(indexed.fetch("seqB", 0, 4).unwrap(), b"GGGG");
+        assert!(indexed.fetch("seqC", 0, 4).is_err());
+
+        let record = Bed3 {
+            chrom: ChrRef::Unassigned("seqA"),
+            start: 5,
+            end: 10,
+        };
+        let record = indexed.attach(record).unwrap();
+        assert_eq!(record.seq(), "CGGCN");
+        assert_eq!(record.at(1), Nuclide::G);
+        assert_eq!(record.range(3, 10), vec![Nuclide::C, Nuclide::N]);
+        assert!((record.gc_content() - 0.8).abs() < 1e-9);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fasta_errors() {
+        let dir = std::env::temp_dir().join(format!("grass-fasta-error-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fasta_path = dir.join("ref.fa");
+        std::fs::write(&fasta_path, ">seqA\nACG\nTTTT\n").unwrap();
+        assert!(FastaReference::open(&fasta_path).is_err());
+
+        std::fs::write(&fasta_path, ">seqA\nACGT\nAC\n").unwrap();
+        let reference = FastaReference::open(&fasta_path).unwrap();
+        let records = ["seqA", "seqC", "seqA"].map(|chrom| Bed3 {
+            chrom: ChrRef::Unassigned(chrom),
+            start: 0,
+            end: 2,
+        });
+        let seqs: Vec<_> = reference
+            .attach_iter(records.into_iter())
+            .map(|record| record.seq().to_string())
+            .collect();
+        assert_eq!(seqs, ["AC"]);
+        assert!(take_error().is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
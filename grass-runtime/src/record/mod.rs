@@ -2,6 +2,10 @@ mod bed3;
 mod bed4;
 mod bed5;
 mod bed6;
+mod bed12;
+mod bedpe;
+mod fasta;
+mod vcf;
 
 #[cfg(feature = "htslib")]
 mod bam;
@@ -15,6 +19,10 @@ pub use bed3::Bed3;
 pub use bed4::{Bed4, RcStr};
 pub use bed5::Bed5;
 pub use bed6::Bed6;
+pub use bed12::Bed12;
+pub use bedpe::BedPe;
+pub use fasta::{FastaReference, Sequenced};
+pub use vcf::{Vcf, VcfHeader, VcfIter, VcfReader};
 
 use crate::algorithm::Sorted;
 
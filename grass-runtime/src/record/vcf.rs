@@ -0,0 +1,287 @@
+use std::io::{BufRead, BufReader, Read, Result, Write};
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+use crate::file::{Buffer, LineRecordStream};
+use crate::property::{Blocked, Named, Parsable, RegionCore, Scored, Serializable, Stranded, Tagged};
+use crate::{ChrRef, LineRecordStreamExt};
+
+use super::{Bed3, RcStr, ToSelfContained};
+
+/// A VCF record. The region of a variant is converted to 0-based half-open coordinate, which starts
+/// at POS - 1 and covers all the bases of the REF allele.
+#[derive(Clone, PartialEq, PartialOrd, Eq, Ord)]
+pub struct Vcf<'a> {
+    inner: Bed3,
+    pub id: RcStr<'a>,
+    pub reference: RcStr<'a>,
+    pub alternative: RcStr<'a>,
+    /// The remaining columns starting from QUAL, kept as it is
+    pub rest: RcStr<'a>,
+    /// The header of the file the record is read from
+    header: Option<Rc<VcfHeader>>,
+}
+
+impl<'a> Deref for Vcf<'a> {
+    type Target = Bed3;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'a> DerefMut for Vcf<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<'a> Serializable for Vcf<'a> {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        fp.write_all(self.chrom().get_chr_name().as_bytes())?;
+        fp.write_all(b"\t")?;
+        crate::ioutils::write_number(&mut fp, self.start() as i32 + 1)?;
+        write!(
+            fp,
+            "\t{}\t{}\t{}",
+            self.id.deref(),
+            self.reference.deref(),
+            self.alternative.deref()
+        )?;
+        if !self.rest.is_empty() {
+            write!(fp, "\t{}", self.rest.deref())?;
+        }
+        Ok(())
+    }
+
+    fn dump_header<W: Write>(&self, fp: W) -> Result<()> {
+        match self.header.as_ref() {
+            Some(header) => header.dump(fp),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> Serializable for Option<Vcf<'a>> {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        if let Some(inner) = self {
+            inner.dump(fp)
+        } else {
+            fp.write_all(b".\t.\t.\t.\t.\t.\t.\t.")
+        }
+    }
+}
+
+impl<'a> Parsable for Vcf<'a> {
+    fn parse(s: &Rc<Buffer>) -> Option<(Self, usize)> {
+        let line = s.trim_end_matches(['\n', '\r']);
+        if line.starts_with('#') {
+            return None;
+        }
+        let mut token_pos_iter = memchr::Memchr::new(b'\t', line.as_bytes());
+        let end_chrom = token_pos_iter.next()?;
+        let end_pos = token_pos_iter.next()?;
+        let end_id = token_pos_iter.next()?;
+        let end_ref = token_pos_iter.next()?;
+        let end_alt = token_pos_iter.next().unwrap_or(line.len());
+
+        let pos: u32 = line[end_chrom + 1..end_pos].parse().ok()?;
+        let start = pos.saturating_sub(1);
+        let ref_len = (end_ref - end_id - 1) as u32;
+        let rest_start = (end_alt + 1).min(line.len());
+
+        let inner = Bed3 {
+            chrom: crate::Genome::query_chr(&line[..end_chrom]).to_static(),
+            start,
+            end: start + ref_len.max(1),
+        };
+        Some((
+            Self {
+                inner,
+                id: RcStr::from_buffer(s, end_pos + 1, end_id),
+                reference: RcStr::from_buffer(s, end_id + 1, end_ref),
+                alternative: RcStr::from_buffer(s, end_ref + 1, end_alt),
+                rest: RcStr::from_buffer(s, rest_start, line.len()),
+                header: None,
+            },
+            line.len(),
+        ))
+    }
+}
+
+impl<'a> RegionCore for Vcf<'a> {
+    #[inline(always)]
+    fn start(&self) -> u32 {
+        self.inner.start()
+    }
+    #[inline(always)]
+    fn end(&self) -> u32 {
+        self.inner.end()
+    }
+    #[inline(always)]
+    fn chrom(&self) -> ChrRef<'static> {
+        self.inner.chrom()
+    }
+}
+
+impl<'a> Scored<f64> for Vcf<'a> {
+    fn score(&self) -> Option<f64> {
+        let qual = self.rest.split('\t').next()?;
+        qual.parse().ok()
+    }
+}
+
+impl<'a> Stranded for Vcf<'a> {}
+
+impl<'a> Named<'a> for Vcf<'a> {
+    fn name(&self) -> &str {
+        self.id.as_ref()
+    }
+    fn rc_name(&self) -> RcStr<'a> {
+        self.id.clone()
+    }
+}
+
+impl<'a> AsRef<Bed3> for Vcf<'a> {
+    fn as_ref(&self) -> &Bed3 {
+        &self.inner
+    }
+}
+
+impl<'a> ToSelfContained for Vcf<'a> {
+    type SelfContained = Vcf<'static>;
+    fn to_self_contained(&self) -> Self::SelfContained {
+        Vcf {
+            inner: self.inner,
+            id: self.id.to_static(),
+            reference: self.reference.to_static(),
+            alternative: self.alternative.to_static(),
+            rest: self.rest.to_static(),
+            header: self.header.clone(),
+        }
+    }
+}
+
+impl<'a, T: Clone> Tagged<T> for Vcf<'a> {}
+
+impl<'a> Blocked for Vcf<'a> {}
+
+/// The meta-information and column header lines of a VCF file
+#[derive(Clone, Default, PartialEq, PartialOrd, Eq, Ord)]
+pub struct VcfHeader {
+    lines: Vec<String>,
+}
+
+impl VcfHeader {
+    pub fn lines(&self) -> &[String] {
+        self.lines.as_slice()
+    }
+}
+
+impl Serializable for VcfHeader {
+    fn dump<W: Write>(&self, mut fp: W) -> Result<()> {
+        for line in self.lines.iter() {
+            writeln!(fp, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads a VCF file, the header lines are consumed eagerly and shared by the records, so that they
+/// can be written back along with the records
+pub struct VcfReader<R: Read> {
+    reader: BufReader<R>,
+    header: Rc<VcfHeader>,
+}
+
+impl<R: Read> VcfReader<R> {
+    pub fn new(inner: R) -> Result<Self> {
+        let mut reader = BufReader::new(inner);
+        let mut header = VcfHeader::default();
+        while reader.fill_buf()?.first() == Some(&b'#') {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            header
+                .lines
+                .push(line.trim_end_matches(['\n', '\r']).to_string());
+        }
+        Ok(Self {
+            reader,
+            header: Rc::new(header),
+        })
+    }
+
+    pub fn header(&self) -> &VcfHeader {
+        self.header.as_ref()
+    }
+
+    pub fn into_record_iter(self) -> VcfIter<R> {
+        VcfIter {
+            header: self.header,
+            inner: self.reader.into_record_iter(),
+        }
+    }
+}
+
+pub struct VcfIter<R: Read> {
+    header: Rc<VcfHeader>,
+    inner: LineRecordStream<BufReader<R>, Vcf<'static>>,
+}
+
+impl<R: Read> VcfIter<R> {
+    pub fn header(&self) -> &VcfHeader {
+        self.header.as_ref()
+    }
+}
+
+impl<R: Read> Iterator for VcfIter<R> {
+    type Item = Vcf<'static>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = self.inner.next()?;
+        record.header = Some(self.header.clone());
+        Some(record)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::VcfReader;
+    use crate::property::{Named, RegionCore, Scored, Serializable};
+
+    const DATA: &str = "##fileformat=VCFv4.2\n\
+        #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n\
+        chr1\t100\trs1\tA\tG\t50\tPASS\t.\n\
+        chr1\t200\t.\tACGT\tA\t.\tPASS\tDP=10\n";
+
+    #[test]
+    fn test_parse_vcf() {
+        let reader = VcfReader::new(DATA.as_bytes()).unwrap();
+        assert_eq!(reader.header().lines().len(), 2);
+        let records: Vec<_> = reader.into_record_iter().collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].start(), records[0].end()), (99, 100));
+        assert_eq!((records[1].start(), records[1].end()), (199, 203));
+        assert_eq!(records[0].name(), "rs1");
+        assert_eq!(records[0].score(), Some(50.0));
+        assert_eq!(records[1].score(), None);
+
+        let mut buffer = Vec::new();
+        records[1].dump(&mut buffer).unwrap();
+        assert_eq!(buffer, b"chr1\t200\t.\tACGT\tA\t.\tPASS\tDP=10");
+    }
+
+    #[test]
+    fn test_write_back() {
+        let reader = VcfReader::new(DATA.as_bytes()).unwrap();
+        let mut buffer = Vec::new();
+        // This is how a record stream is written to a file
+        for (idx, record) in reader.into_record_iter().enumerate() {
+            if idx == 0 {
+                record.dump_header(&mut buffer).unwrap();
+            }
+            record.dump(&mut buffer).unwrap();
+            buffer.push(b'\n');
+        }
+        assert_eq!(String::from_utf8(buffer).unwrap(), DATA);
+    }
+}
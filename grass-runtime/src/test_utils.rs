@@ -0,0 +1,10 @@
+//! The helpers shared by the unit tests
+
+use crate::property::Serializable;
+
+/// The text of a record, which is how it's written to the output
+pub(crate) fn dump<T: Serializable>(record: T) -> String {
+    let mut buffer = Vec::new();
+    record.dump(&mut buffer).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
@@ -0,0 +1,36 @@
+//! Reading the alignments from the standard input. The test runs itself again as a child process
+//! with the SAM text fed into the standard input of the child.
+#![cfg(feature = "htslib")]
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use grass_runtime::{builtin::take_error, property::RegionCore, record::BamReader};
+
+const SAM: &[u8] = b"@HD\tVN:1.6\tSO:coordinate\n@SQ\tSN:stdin1\tLN:1000\n\
+    r1\t0\tstdin1\t100\t60\t10M\t*\t0\t0\tACGTACGTAC\t*\n\
+    r2\t16\tstdin1\t200\t30\t5M\t*\t0\t0\tACGTA\t*\n";
+
+/// Set in the child process, which does the actual reading
+const CHILD_ENV: &str = "GRASS_BAM_STDIN_CHILD";
+
+#[test]
+fn test_read_from_stdin() {
+    if std::env::var_os(CHILD_ENV).is_some() {
+        let reader = BamReader::from_fd(0).unwrap();
+        let starts: Vec<_> = reader.iter().map(|r| r.start()).collect();
+        assert_eq!(starts, [99, 199]);
+        assert!(take_error().is_ok());
+        return;
+    }
+    let mut child = Command::new(std::env::current_exe().unwrap())
+        .args(["test_read_from_stdin", "--exact"])
+        .env(CHILD_ENV, "1")
+        .stdin(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(SAM).unwrap();
+    assert!(child.wait().unwrap().success());
+}
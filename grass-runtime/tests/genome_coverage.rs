@@ -0,0 +1,112 @@
+//! The genome coverage with a loaded genome. The genome is global to the process and can only be
+//! loaded once, so these tests live in their own test binary.
+
+use std::sync::Once;
+
+use grass_runtime::{
+    algorithm::{AssumeSorted, GenomeCoverageExt, GenomeCoverageOptions},
+    builtin::take_error,
+    property::RegionCore,
+    record::Bed3,
+    Genome, LineRecordStreamExt,
+};
+
+const GENOME: &[u8] = b"gc1\t100\ngc2\t50\ngc3\t30\n";
+
+fn load_genome() {
+    static LOADED: Once = Once::new();
+    LOADED.call_once(|| Genome::load_genome_file(GENOME).unwrap());
+}
+
+fn bed_graph(input: &'static [u8]) -> Vec<(String, u32, u32, usize)> {
+    load_genome();
+    let options = GenomeCoverageOptions {
+        include_zero: true,
+        ..Default::default()
+    };
+    input
+        .into_record_iter::<Bed3>()
+        .assume_sorted()
+        .genome_coverage(options)
+        .map(|s| (s.chrom().to_string(), s.start(), s.end(), s.depth()))
+        .collect()
+}
+
+fn histogram(input: &'static [u8]) -> Vec<(String, usize, u64, u64)> {
+    load_genome();
+    input
+        .into_record_iter::<Bed3>()
+        .assume_sorted()
+        .genome_histogram(Default::default())
+        .map(|l| (l.chrom().to_string(), l.depth(), l.covered(), l.size()))
+        .collect()
+}
+
+fn segment(chrom: &str, start: u32, end: u32, depth: usize) -> (String, u32, u32, usize) {
+    (chrom.to_string(), start, end, depth)
+}
+
+#[test]
+fn test_clamp_to_chrom_size() {
+    let segments = bed_graph(b"gc1\t90\t150\n");
+    assert_eq!(
+        segments,
+        [
+            segment("gc1", 0, 90, 0),
+            segment("gc1", 90, 100, 1),
+            segment("gc2", 0, 50, 0),
+            segment("gc3", 0, 30, 0),
+        ]
+    );
+}
+
+#[test]
+fn test_uncovered_genome_chroms() {
+    let segments = bed_graph(b"gc2\t10\t20\n");
+    assert_eq!(
+        segments,
+        [
+            segment("gc1", 0, 100, 0),
+            segment("gc2", 0, 10, 0),
+            segment("gc2", 10, 20, 1),
+            segment("gc2", 20, 50, 0),
+            segment("gc3", 0, 30, 0),
+        ]
+    );
+}
+
+#[test]
+fn test_genome_summary() {
+    let lines = histogram(b"gc1\t0\t50\ngc1\t25\t75\n");
+    let line = |chrom: &str, depth, covered, size| (chrom.to_string(), depth, covered, size);
+    assert_eq!(
+        lines,
+        [
+            line("gc1", 0, 25, 100),
+            line("gc1", 1, 50, 100),
+            line("gc1", 2, 25, 100),
+            line("gc2", 0, 50, 50),
+            line("gc3", 0, 30, 30),
+            line("genome", 0, 105, 180),
+            line("genome", 1, 50, 180),
+            line("genome", 2, 25, 180),
+        ]
+    );
+}
+
+#[test]
+fn test_chrom_out_of_genome_order() {
+    // The uncovered gc1 has been reported before gc2, so its records are skipped
+    let segments = bed_graph(b"gc2\t10\t20\ngc1\t0\t10\n");
+    assert_eq!(
+        segments,
+        [
+            segment("gc1", 0, 100, 0),
+            segment("gc2", 0, 10, 0),
+            segment("gc2", 10, 20, 1),
+            segment("gc2", 20, 50, 0),
+            segment("gc3", 0, 30, 0),
+        ]
+    );
+    assert!(take_error().is_err());
+}
@@ -0,0 +1,9 @@
+#!/usr/bin/env python3
+
+from pygrass import IntervalFile, CmdArg, Bed6, chr, strand, name, score
+
+input = IntervalFile(CmdArg(1), sorted = True)
+
+# Count the intervals and average the scores of each run of intervals on the same strand, similar to
+# `bedtools groupby -g 1,6 -c 4,5 -o count,mean`
+Bed6(input).group_by(chr, strand).aggregate(("count", name), ("mean", score)).print_to_stdout()
@@ -0,0 +1,13 @@
+#!/usr/bin/env python3
+
+from pygrass import IntervalFile, CmdArg, distance, item
+
+genes = IntervalFile(CmdArg(1), sorted = True)
+peaks = IntervalFile(CmdArg(2), sorted = True)
+
+# Report the closest upstream peak within 5kb of each gene along with the distance, similar to
+# `bedtools closest -D a -id -t first`
+genes.closest(peaks, ties = "first", direction = "upstream", k = 1) \
+    .filter(distance >= -5000) \
+    .format("{gene}\t{peak}\t{distance}", gene = item[0].str_repr, peak = item[1].str_repr, distance = distance) \
+    .print_to_stdout()
@@ -0,0 +1,8 @@
+#!/usr/bin/env python3
+
+from pygrass import IntervalFile, CmdArg, chr
+
+input = IntervalFile(CmdArg(1), sorted = True)
+
+# Count the intervals on each chromosome, the input is sorted so each chromosome is a single run
+input.count_by(chr).print_to_stdout()
@@ -0,0 +1,12 @@
+#!/usr/bin/env python3
+
+from pygrass import IntervalFile, CmdArg, fraction
+
+first_file = IntervalFile(CmdArg(1), sorted = True)
+second_file = IntervalFile(CmdArg(2), sorted = True)
+
+# Report the number of overlapping intervals, the covered bases, the length and the covered fraction
+# of each interval (This is similar to `bedtools coverage`), and keep the ones covered by half or more.
+result = first_file.coverage(second_file).filter(fraction >= 0.5)
+
+result.print_to_stdout()
@@ -0,0 +1,11 @@
+#!/usr/bin/env python3
+
+from pygrass import IntervalFile, CmdArg, depth
+
+input_file = IntervalFile(CmdArg(1), sorted = True)
+
+# Report the runs of the same depth as bedGraph intervals (This is similar to `bedtools genomecov -bg`),
+# and keep the ones covered more than once.
+result = input_file.genome_coverage().filter(depth > 1)
+
+result.print_to_stdout()
@@ -0,0 +1,11 @@
+#!/usr/bin/env python3
+
+from pygrass import IntervalFile, CmdArg, chr, start, end, seq, gc_content
+
+# Attach the reference sequence to each interval, this is similar to "bedtools getfasta"
+intervals = IntervalFile(CmdArg(1)).attach_sequence(CmdArg(2).get_value())
+
+# Only keep the GC-rich regions
+gc_rich = intervals.filter(gc_content > 0.6)
+
+gc_rich.format(">{chrom}:{start}-{end}\n{seq}", chrom = chr, start = start, end = end, seq = seq).print_to_stdout()
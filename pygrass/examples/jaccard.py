@@ -0,0 +1,12 @@
+#!/usr/bin/env python3
+
+from pygrass import IntervalFile, CmdArg
+
+first_file = IntervalFile(CmdArg(1), sorted = True)
+second_file = IntervalFile(CmdArg(2), sorted = True)
+
+# Report the number of bases covered by both files, the number of bases covered by either file, the
+# Jaccard statistic and the number of intersections (This is similar to `bedtools jaccard`).
+result = first_file.jaccard(second_file)
+
+result.print_to_stdout()
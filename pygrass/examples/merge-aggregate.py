@@ -0,0 +1,9 @@
+#!/usr/bin/env python3
+
+from pygrass import IntervalFile, CmdArg, Bed5, name, score
+
+input = IntervalFile(CmdArg(1), sorted = True)
+
+# Collapse the names and sum the scores of each cluster of overlapping intervals, similar to
+# `bedtools merge -c 4,5 -o collapse,sum`
+Bed5(input).merge_overlaps().aggregate(("collapse", name), ("sum", score)).print_to_stdout()
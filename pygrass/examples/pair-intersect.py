@@ -0,0 +1,10 @@
+#!/usr/bin/env python3
+
+from pygrass import BedPeFile, IntervalFile, CmdArg
+
+# The Hi-C loops, each record is a pair of anchors
+loops = BedPeFile(CmdArg(1))
+peaks = IntervalFile(CmdArg(2))
+
+# Find the loops with a peak on both anchors, this is similar to "bedtools pairtobed -type both"
+loops.pair_intersect(peaks, mode = "both").print_to_stdout()
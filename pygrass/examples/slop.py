@@ -1,12 +1,10 @@
 #!/usr/bin/env python3
 
-from pygrass import IntervalFile, start, end, length, CmdArg
+from pygrass import IntervalFile, CmdArg
 
 input = IntervalFile(CmdArg(1), sorted = True)
 
-altered = input.alter(
-    start = start - length * 0.1,
-    end = end + length * 0.1,
-)
+# Extend each interval by 10% of its length on both sides (This is similar to `bedtools slop -pct -b 0.1`)
+altered = input.slop(0.1)
 
 altered.print_to_stdout()
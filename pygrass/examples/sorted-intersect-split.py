@@ -0,0 +1,10 @@
+#!/usr/bin/env python3
+
+from pygrass import IntervalFile, BedFile, CmdArg
+
+# A BED12 file, for example the transcript annotation
+transcripts = BedFile(CmdArg(1), sorted = True, num_of_fields = 12)
+second_file = IntervalFile(CmdArg(2), sorted = True)
+
+# Only report the intervals overlapping exons, this is similar to "bedtools intersect -split"
+transcripts.intersect(second_file, blocks_only = True).print_to_stdout()
@@ -0,0 +1,12 @@
+#!/usr/bin/env python3
+
+from pygrass import IntervalFile, CmdArg
+
+first_file = IntervalFile(CmdArg(1), sorted = True)
+second_file = IntervalFile(CmdArg(2), sorted = True)
+
+# Remove the regions covered by the second file from each interval (This is similar to `bedtools subtract`).
+# The remaining fragments keep the name, score and strand of the interval.
+result = first_file.subtract(second_file)
+
+result.print_to_stdout()
@@ -28,9 +28,9 @@ from abc import abstractclassmethod
 from typing import Callable
 
 from pygrass.interval import IntervalBase, IntervalFile, SortedRandomBed3
-from pygrass.interval.formats import BedFile, BamFile, Bed3File, CmdArg
-from pygrass.interval.cast import Bed3, Bed4, Bed5, Bed6
-from pygrass.interval.field_expr import length, start, end, length, name, chr, strand, item, tag, If, score
+from pygrass.interval.formats import BedFile, BedPeFile, BamFile, Bed3File, CmdArg
+from pygrass.interval.cast import Bed3, Bed4, Bed5, Bed6, Bed12
+from pygrass.interval.field_expr import length, start, end, length, name, chr, strand, item, tag, If, score, seq, gc_content, count, distance, covered, fraction, depth, forward_count, reverse_count, size, intersection, union, jaccard, n_intersections, left_tail, right_tail, two_tail, ratio, reldist, total, concat
 from pygrass.backend import DumpIR, BackendBase, RustBackend
 from pygrass.record_base import RustEnv, load_genome_file
 
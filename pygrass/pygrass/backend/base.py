@@ -38,6 +38,21 @@ class BackendBase(object):
             dest = "build_flavor",
             action = "store_const",
             const = "Prof")
+        parser.add_argument( "--interpret",
+            help = "Run the query with the interpreter instead of building the Rust artifact",
+            dest = "execution_mode",
+            action = "store_const",
+            const = "Interpret")
+        parser.add_argument( "--compile",
+            help = "Build the Rust artifact, this is the default",
+            dest = "execution_mode",
+            action = "store_const",
+            const = "Compile")
+        parser.add_argument( "--auto",
+            help = "Run the query with the interpreter if it's supported and its input is small, otherwise build the Rust artifact",
+            dest = "execution_mode",
+            action = "store_const",
+            const = "Auto")
         parser.add_argument("--disable-env-const-bag",
             help = "Do not use environment variable for runtime constant passing, this will generate better optimized but less general binary artifact",
             dest = "no_use_const_bag",
@@ -130,6 +145,8 @@ class BackendBase(object):
             _execute_with_backend("pygrass.backend.BuildBinary", {"GRASS_BIN_OUTPUT": args.bin_path})
         if args.build_flavor != None:
             self.set_build_flavor(args.build_flavor)
+        if args.execution_mode != None:
+            self.set_execution_mode(args.execution_mode)
         self.enable_env_const_bag(not args.no_use_const_bag)
         if args.runtime != None:
             self.set_runtime_source(args.runtime)
@@ -149,6 +166,8 @@ class BackendBase(object):
             self.enable_env_const_bag(False)
     def set_build_flavor(self, flavor : str):
         pass
+    def set_execution_mode(self, mode : str):
+        pass
     def enable_env_const_bag(self, value):
         pass
     def add_dependency(self, crate_name, source: str = None, version = None, features = [], default_features = True):
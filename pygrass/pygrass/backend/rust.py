@@ -20,6 +20,7 @@ class RustBackendBase(BackendBase):
         ret["runtime_source"] = self._runtime_source
         ret["macro_source"] = self._macro_source
         ret["build_flavor"] = self._build_flavor
+        ret["execution_mode"] = self._execution_mode
         ret["cmdline_args"] = self._argv
         ret["const_bag_types"] = []
         if self._const_bag != None:
@@ -65,6 +66,7 @@ class RustBackendBase(BackendBase):
     def load_env_conf(self):
         self.enable_env_const_bag(os.environ.get("ENV_CONST_BAG", "1") == "1")
         self.set_build_flavor(os.environ.get("BUILD_FLAVOR", "Release"))
+        self.set_execution_mode(os.environ.get("EXECUTION_MODE", "Compile"))
         self.set_args(sys.argv[1:])
         runtime_path = os.environ.get("GRASS_RUNTIME_PATH") 
         if runtime_path != None:
@@ -78,6 +80,14 @@ class RustBackendBase(BackendBase):
             self._build_flavor = "ReleaseWithDebugInfo"
         else:
             self._build_flavor = "Release"
+    def set_execution_mode(self, mode : str):
+        mode = mode.upper()
+        if mode == "INTERPRET":
+            self._execution_mode = "Interpret"
+        elif mode == "AUTO":
+            self._execution_mode = "Auto"
+        else:
+            self._execution_mode = "Compile"
     def enable_env_const_bag(self, value):
         if value == True and self._const_bag == None:
             self._const_bag = list()
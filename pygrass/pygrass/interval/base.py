@@ -1,6 +1,6 @@
 from pygrass.interval.field_expr import FieldExpr, make_field_expression
 from pygrass.record_base import RecordCollectionBase
-from pygrass.ir import AssignTag, AssumeSortedIR, Alter, And, Filter as FilterIR, Format, GroupBy as GroupByIR, IRBase, InlineRust, Invert, Limit, MergeOverlap, Intersection as IntersectionIR, SortedRandomInterval, Nop, InternalSort, TwoWayMerge as TwoWayMergeIR
+from pygrass.ir import Aggregate as AggregateIR, AssignTag, AttachSequence, AssumeSortedIR, Alter, And, Closest as ClosestIR, Count as CountIR, Filter as FilterIR, Format, GroupBy as GroupByIR, IRBase, InlineRust, Invert, Limit, MergeOverlap, Intersection as IntersectionIR, PairIntersection as PairIntersectionIR, SortedRandomInterval, Nop, InternalSort, TwoWayMerge as TwoWayMergeIR, Window as WindowIR, Coverage as CoverageIR, Slop as SlopIR, Flank as FlankIR, Resize as ResizeIR, Subtract as SubtractIR, GenomeCoverage as GenomeCoverageIR, Jaccard as JaccardIR, Fisher as FisherIR, RelativeDistance as RelativeDistanceIR
 
 class IntervalBase(RecordCollectionBase):
     """The base class for PyGRASS runtime values which is an iterator of intervals"""
@@ -137,6 +137,22 @@ class IntervalBase(RecordCollectionBase):
         ```
         """
         return TwoWayMerge(self, other)
+    def attach_sequence(self, reference: str):
+        """
+        Attach the reference sequence to each interval in the iterator,
+        after that, the sequence can be accessed through the `seq` field.
+
+        The sequence is read on demand. If there's no faidx index next to the FASTA file, the file is
+        scanned to build the index, in which case all the lines of a sequence but the last one should
+        have the same length. It's an error if the chromosome of an interval isn't in the reference.
+
+        Example:
+        Extract the sequence of each interval (Similar to `bedtools getfasta`):
+        ```
+            intervals.attach_sequence("hg19.fa").format(">{chrom}:{start}-{end}\\n{seq}", chrom = chr, start = start, end = end, seq = seq)
+        ```
+        """
+        return SequenceAttachedInterval(self, reference)
     def limit(self, n: int):
         """
         Return the first n intervals.
@@ -159,11 +175,14 @@ class IntervalBase(RecordCollectionBase):
         ```
         """
         return MergedInterval(self)
-    def intersect(self, other):
+    def intersect(self, other, blocks_only : bool = False):
         """
         Intersect two sorted interval iterators.
         This method is similar to `bedtools intersect`.
         This method performs the inner join of two sorted interval iterators.
+        When `blocks_only` is set, only the pairs overlapping on blocks are reported, for example,
+        a BED12 transcript only matches the intervals overlapping its exons, similar to
+        `bedtools intersect -split`.
 
         Example:
 
@@ -172,7 +191,7 @@ class IntervalBase(RecordCollectionBase):
         ```
 
         """
-        return Intersection(self, other, flavor = "inner")
+        return Intersection(self, other, flavor = "inner", blocks_only = blocks_only)
     def outer_intersect(self, other):
         """
         Perform a full outer join of two sorted interval iterators.
@@ -206,6 +225,240 @@ class IntervalBase(RecordCollectionBase):
         ```
         """
         return Intersection(self, other, flavor = "right-outer")
+    def pair_intersect(self, other, mode : str = "either"):
+        """
+        Intersect paired intervals, for example BEDPE records, with another interval iterator.
+        This method is similar to `bedtools pairtobed`.
+        The mode can be "either", "both" or "neither":
+        - "either" reports a pair with each interval overlapping either end
+        - "both" reports a pair with each interval overlapping its ends, only if both ends have overlaps
+        - "neither" reports the pairs that neither end overlaps any interval
+
+        If both iterators are sorted, the other intervals are streamed rather than loaded into memory,
+        in this case the second end of each pair shouldn't come before its first end.
+
+        Example:
+
+        ```
+            loops.pair_intersect(peaks, mode = "both")
+        ```
+        """
+        return PairIntersection(self, other, mode)
+    def closest(self, other, ties : str = "all", direction : str = "both", ignore_overlaps : bool = False, k : int = 1):
+        """
+        Find the closest intervals in another sorted interval iterator for each interval, both of the
+        iterators should be sorted. This method is similar to `bedtools closest -D a`.
+        - ties can be "all", "first" or "last", which decides the equally distant intervals reported
+        - direction can be "both", "upstream" or "downstream", which is relative to the strand of the interval
+        - ignore_overlaps doesn't report the overlapping intervals
+        - k reports the intervals at the k nearest distances
+
+        The distance can be referred by `distance` in the field expressions. It's 0 for the overlapping
+        intervals, otherwise the gap plus 1, which is negative for the upstream intervals.
+        An interval without any closest interval is reported with dots and the distance is -1.
+
+        Example:
+
+        ```
+            genes.closest(peaks, ties = "first").format("{n}\t{d}", n = name, d = distance)
+        ```
+        """
+        return ClosestInterval(self, other, ties, direction, ignore_overlaps, k)
+    def window(self, other, left : int = 1000, right : int = None, strand_relative : bool = False, count_only : bool = False):
+        """
+        Join each interval with the intervals in another sorted interval iterator within its window,
+        both of the iterators should be sorted. This method is similar to `bedtools window`.
+        - left and right are the number of bases added before the start and after the end, right is
+          the same as left by default
+        - strand_relative takes left as the upstream flank, which is after the end of an interval on
+          the negative strand, similar to `bedtools window -sw`
+        - count_only reports each interval along with the number of intervals within its window,
+          which can be referred by `count` in the field expressions, similar to `bedtools window -c`
+
+        Unlike altering the intervals before intersecting, both of the intervals are kept as they are.
+
+        Example:
+
+        ```
+            file_a.window(file_b, left = 500, right = 1000).format(
+                "{a}\t{b}",
+                a = item[0].str_repr,
+                b = item[1].str_repr
+            )
+        ```
+        """
+        if right is None:
+            right = left
+        return WindowInterval(self, other, left, right, strand_relative, count_only)
+    def coverage(self, other, histogram : bool = False, by_strand : bool = False):
+        """
+        Measure how much each interval is covered by the intervals in another sorted interval iterator,
+        both of the iterators should be sorted. This method is similar to `bedtools coverage`.
+        Each interval is reported along with the number of overlapping intervals, the number of
+        covered bases, the length of the interval and the covered fraction, which can be referred by
+        `count`, `covered`, `length` and `fraction` in the field expressions.
+        - histogram reports the number of bases at each depth instead, one line for each depth of
+          each interval, which can be referred by `depth`, `covered` and `fraction`, similar to
+          `bedtools coverage -hist`
+        - by_strand also counts the overlapping intervals on the positive and the negative strand,
+          which can be referred by `forward_count` and `reverse_count`
+
+        Example:
+
+        ```
+            genes.coverage(reads).filter(fraction > 0.5)
+        ```
+        """
+        if histogram and by_strand:
+            raise RuntimeError("The depth histogram can't be split by strand")
+        return CoverageInterval(self, other, histogram, by_strand)
+    def slop(self, left, right = None, strand_relative : bool = False):
+        """
+        Extend each interval, similar to `bedtools slop`.
+        An integer amount is a number of bases, and a float amount is a fraction of the interval length,
+        for example, 0.1 extends the interval by 10% of its length.
+        - left and right are the amount added before the start and after the end, right is the same
+          as left by default
+        - strand_relative takes left as the upstream side, which is after the end of an interval on
+          the negative strand, similar to `bedtools slop -s`
+
+        The intervals are clamped to the chromosome when the genome file is loaded, and a sorted
+        iterator stays sorted.
+
+        Example:
+
+        ```
+            file.slop(100, 200, strand_relative = True)
+        ```
+        """
+        if right is None:
+            right = left
+        return ResizedInterval(self, lambda base, sorted: SlopIR(base, left, right, strand_relative, sorted))
+    def flank(self, left, right = None, strand_relative : bool = False):
+        """
+        Report the regions next to each interval instead of the interval, similar to `bedtools flank`.
+        The amounts and the strand_relative flag are the same as `slop`, and the empty flanks
+        are not reported.
+
+        Example:
+
+        ```
+            file.flank(1000, 0, strand_relative = True)
+        ```
+        """
+        if right is None:
+            right = left
+        return ResizedInterval(self, lambda base, sorted: FlankIR(base, left, right, strand_relative, sorted))
+    def resize(self, size, anchor : str = "center", strand_relative : bool = False):
+        """
+        Change the length of each interval while keeping the anchor in place.
+        - size is a number of bases for an integer, or a fraction of the interval length for a float
+        - anchor can be "start", "center" or "end"
+        - strand_relative takes the start as the upstream end, which is the end of an interval on the
+          negative strand
+
+        Example:
+
+        ```
+            file.resize(500, anchor = "start", strand_relative = True)
+        ```
+        """
+        return ResizedInterval(self, lambda base, sorted: ResizeIR(base, size, anchor, strand_relative, sorted))
+    def subtract(self, other, remove_overlapping : bool = False, min_fraction : float = 0.0, same_strand : bool = False):
+        """
+        Remove the regions covered by another sorted interval iterator from each interval, both of the
+        iterators should be sorted. This method is similar to `bedtools subtract`.
+        - remove_overlapping drops an interval with any overlap instead, similar to `bedtools subtract -A`
+        - min_fraction is the minimum overlap to subtract, as a fraction of the interval length,
+          similar to `bedtools subtract -f`
+        - same_strand only subtracts the intervals on the same strand, similar to `bedtools subtract -s`
+
+        Each of the remaining fragments keeps the other fields of the interval, such as the name and
+        the strand, and the result is sorted.
+
+        Example:
+
+        ```
+            genes.subtract(repeats, min_fraction = 0.5)
+        ```
+        """
+        return SubtractedInterval(self, other, remove_overlapping, min_fraction, same_strand)
+    def genome_coverage(self, histogram : bool = False, include_zero : bool = False, strand : str = None, scale : float = 1.0):
+        """
+        Report the depth of the sorted intervals along the genome, similar to `bedtools genomecov`.
+        By default, the runs of the same depth are reported as bedGraph intervals, and the depth can
+        be referred by `depth` or `score` in the field expressions, similar to `bedtools genomecov -bg`.
+        - histogram reports the number of bases at each depth for each chromosome and the whole
+          genome instead, which can be referred by `depth`, `covered`, `size` and `fraction`, similar
+          to `bedtools genomecov` without any flags
+        - include_zero also reports the uncovered runs, similar to `bedtools genomecov -bga`
+        - strand only counts the intervals on the given strand, either "+" or "-"
+        - scale multiplies the depth of the bedGraph intervals, similar to `bedtools genomecov -scale`
+
+        The chromosome sizes come from the loaded genome file, so the uncovered chromosomes are only
+        reported when the genome file is loaded.
+
+        Example:
+
+        ```
+            reads.genome_coverage(include_zero = True, strand = "+")
+        ```
+        """
+        if histogram and (include_zero or scale != 1):
+            raise RuntimeError("The histogram can't include zero or be scaled")
+        return GenomeCoverageInterval(self, histogram, include_zero, strand, scale)
+    def jaccard(self, other):
+        """
+        Compute the Jaccard statistic of the bases covered by the intervals and another sorted interval
+        iterator, both of the iterators should be sorted. This method is similar to `bedtools jaccard`.
+        The result is a single line of the number of bases covered by both of them, the number of
+        bases covered by either of them, the Jaccard statistic and the number of intersections,
+        which can be referred by `intersection`, `union`, `jaccard` and `n_intersections`.
+        The overlapping and book-ended intervals of each iterator are merged before comparing.
+
+        Example:
+
+        ```
+            cpg.jaccard(enhancers).print_to_stdout()
+        ```
+        """
+        return OverlapStatsInterval(self, other, JaccardIR)
+    def fisher(self, other, genome_size : int = None):
+        """
+        Run Fisher's exact test on the overlaps between the intervals and another sorted interval
+        iterator, both of the iterators should be sorted. This method is similar to `bedtools fisher`.
+        The result is a single line of the contingency table, the left, right and two-tailed
+        p-values and the odds ratio, which can be referred by `left_tail`, `right_tail`, `two_tail`
+        and `ratio`.
+        - genome_size is the number of bases in the genome, which is the size of the loaded genome
+          file by default
+
+        Example:
+
+        ```
+            load_genome_file("genome.txt")
+            cpg.fisher(enhancers).print_to_stdout()
+        ```
+        """
+        return OverlapStatsInterval(self, other, lambda lhs, rhs: FisherIR(lhs, rhs, genome_size))
+    def relative_distance(self, other):
+        """
+        Report the distribution of the relative distances from the intervals to another sorted
+        interval iterator, both of the iterators should be sorted. This method is similar to
+        `bedtools reldist`.
+        The relative distance of an interval is the distance from its midpoint to the closer one of
+        the midpoints of the intervals right before and after it, divided by the distance between
+        these two midpoints. Each line is a bin of 0.01 wide, along with the number of intervals in
+        the bin, the total number of intervals and the fraction, which can be referred by `reldist`,
+        `count`, `total` and `fraction`.
+
+        Example:
+
+        ```
+            genes.relative_distance(enhancers).print_to_stdout()
+        ```
+        """
+        return OverlapStatsInterval(self, other, RelativeDistanceIR)
     def group_by(self, *args):
         """
         Group the intervals by the given field expression.
@@ -217,6 +470,22 @@ class IntervalBase(RecordCollectionBase):
         ```
         """
         return GroupBy(self, *args)
+    def count_by(self, *args):
+        """
+        Count the intervals for each key, which is described by the given field expressions.
+        The intervals are counted without buffering, so the intervals with the same key should be adjacent,
+        for example, counting a sorted iterator by chromosome.
+        Each result has the key columns followed by the number of intervals, and the number can be
+        referred by `count` in the field expressions.
+
+        Example:
+
+        ```
+            input.count_by(chr).print_to_stdout()
+            input.count_by(chr, strand).format("{count}", count = count)
+        ```
+        """
+        return CountedRecords(self, *args)
 
 class SortedRandomBed3(IntervalBase):
     """
@@ -303,6 +572,21 @@ class GroupBy(IntervalBase):
         super().__init__()
         self._inner = inner
         self._key_func = args
+    def aggregate(self, *columns):
+        """
+        Aggregate the intervals in each group without buffering them, similar to `bedtools groupby -o`.
+        Each column is a pair of the aggregate function and the field expression to aggregate, and
+        the aggregated columns follow the keys of the group.
+        The functions are count, count_distinct, sum, mean, median, min, max, mode, stdev, collapse,
+        distinct, first and last.
+
+        Example:
+
+        ```
+            input.group_by(chr, strand).aggregate(("count", name), ("mean", score))
+        ```
+        """
+        return AggregatedRecords(self._inner, columns, keys = list(self._key_func))
     def emit_eval_code(self) -> IRBase:
         code = self._inner.lower_to_ir()
         return GroupByIR(
@@ -311,6 +595,34 @@ class GroupBy(IntervalBase):
         )
 
 
+class AggregatedRecords(RecordCollectionBase):
+    def __init__(self, inner : IntervalBase, columns, keys = None):
+        super().__init__()
+        self._inner = inner
+        self._columns = [(func, make_field_expression(value)) for func, value in columns]
+        self._keys = keys
+    def emit_eval_code(self) -> IRBase:
+        return AggregateIR(
+            self._inner.lower_to_ir(),
+            columns = [(func, value.lower_to_ir()) for func, value in self._columns],
+            keys = None if self._keys is None else [key_comp.lower_to_ir() for key_comp in self._keys]
+        )
+
+class CountedRecords(RecordCollectionBase):
+    def __init__(self, inner : IntervalBase, *args):
+        super().__init__()
+        self._inner = inner
+        self._key_func = args
+    def format(self, fmt : str, **kwargs):
+        """Format the counts, see `IntervalBase.format` for details"""
+        return FormattedInterval(self, fmt, **kwargs)
+    def emit_eval_code(self) -> IRBase:
+        code = self._inner.lower_to_ir()
+        return CountIR(
+            code,
+            keys = [key_comp.lower_to_ir() for key_comp in self._key_func]
+        )
+
 class InvertedInterval(RecordCollectionBase) :
     def __init__(self, inner: IntervalBase):
         super().__init__()
@@ -356,6 +668,15 @@ class TaggedInterval(IntervalBase):
         base = self._base.lower_to_ir()
         return AssignTag(base, self._tag)
 
+class SequenceAttachedInterval(IntervalBase):
+    def __init__(self, inner: IntervalBase, reference: str):
+        super().__init__()
+        self._inner = inner
+        self._reference = reference
+        self._sorted = inner._sorted
+    def emit_eval_code(self) -> IRBase:
+        return AttachSequence(self._inner.lower_to_ir(), self._reference, self._sorted)
+
 class LimitInterval(IntervalBase):
     def __init__(self, what: IntervalBase, count: int):
         super().__init__()
@@ -425,13 +746,154 @@ class MergedInterval(IntervalBase):
         self._sorted = base._sorted
         get_backend_session().add_dependency("genawaiter", features = ["futures03"])
         get_backend_session().add_dependency("futures")
+    def aggregate(self, *columns):
+        """
+        Aggregate the intervals in each cluster of overlapping intervals, similar to `bedtools merge -c -o`.
+        The aggregated columns follow the merged region, see `GroupBy.aggregate` for the columns.
+
+        Example:
+
+        ```
+            input.merge_overlaps().aggregate(("collapse", name), ("sum", score))
+        ```
+        """
+        return AggregatedRecords(self._base, columns)
     def emit_eval_code(self) -> IRBase:
         return MergeOverlap(inner = self._base.lower_to_ir())
 
+class PairIntersection(IntervalBase):
+    def __init__(self, pairs : IntervalBase, other : IntervalBase, mode : str = "either"):
+        super().__init__()
+        self._mode = mode
+        self._pairs = pairs
+        self._other = other
+        # Only filtering out the pairs keeps the order of the pairs
+        self._sorted = pairs._sorted and mode == "neither"
+    def emit_eval_code(self) -> IRBase:
+        return PairIntersectionIR(
+            lhs = self._pairs.lower_to_ir(),
+            rhs = self._other.lower_to_ir(),
+            mode = self._mode,
+            sorted = self._pairs._sorted and self._other._sorted
+        )
+
+class ClosestInterval(IntervalBase):
+    def __init__(self, left : IntervalBase, right : IntervalBase, ties : str, direction : str, ignore_overlaps : bool, k : int):
+        super().__init__()
+        self._left = left
+        self._right = right
+        self._ties = ties
+        self._direction = direction
+        self._ignore_overlaps = ignore_overlaps
+        self._k = k
+        self._sorted = left._sorted
+    def emit_eval_code(self) -> IRBase:
+        return ClosestIR(
+            lhs = self._left.lower_to_ir(),
+            rhs = self._right.lower_to_ir(),
+            ties = self._ties,
+            direction = self._direction,
+            ignore_overlaps = self._ignore_overlaps,
+            k = self._k
+        )
+
+class ResizedInterval(IntervalBase):
+    def __init__(self, base : IntervalBase, make_ir):
+        super().__init__()
+        self._base = base
+        self._make_ir = make_ir
+        self._sorted = base._sorted
+    def emit_eval_code(self) -> IRBase:
+        return self._make_ir(self._base.lower_to_ir(), self._sorted)
+
+class CoverageInterval(IntervalBase):
+    def __init__(self, left : IntervalBase, right : IntervalBase, histogram : bool, by_strand : bool):
+        super().__init__()
+        self._left = left
+        self._right = right
+        self._histogram = histogram
+        self._by_strand = by_strand
+        self._sorted = left._sorted
+    def emit_eval_code(self) -> IRBase:
+        return CoverageIR(
+            lhs = self._left.lower_to_ir(),
+            rhs = self._right.lower_to_ir(),
+            histogram = self._histogram,
+            by_strand = self._by_strand
+        )
+
+class SubtractedInterval(IntervalBase):
+    def __init__(self, left : IntervalBase, right : IntervalBase, remove_overlapping : bool, min_fraction : float, same_strand : bool):
+        super().__init__()
+        self._left = left
+        self._right = right
+        self._remove_overlapping = remove_overlapping
+        self._min_fraction = min_fraction
+        self._same_strand = same_strand
+        self._sorted = True
+    def emit_eval_code(self) -> IRBase:
+        return SubtractIR(
+            lhs = self._left.lower_to_ir(),
+            rhs = self._right.lower_to_ir(),
+            remove_overlapping = self._remove_overlapping,
+            min_fraction = self._min_fraction,
+            same_strand = self._same_strand
+        )
+
+class GenomeCoverageInterval(IntervalBase):
+    def __init__(self, inner : IntervalBase, histogram : bool, include_zero : bool, strand : str, scale : float):
+        super().__init__()
+        self._inner = inner
+        self._histogram = histogram
+        self._include_zero = include_zero
+        self._strand = strand
+        self._scale = scale
+        self._sorted = not histogram
+    def emit_eval_code(self) -> IRBase:
+        return GenomeCoverageIR(
+            inner = self._inner.lower_to_ir(),
+            histogram = self._histogram,
+            include_zero = self._include_zero,
+            strand = self._strand,
+            scale = self._scale
+        )
+
+class OverlapStatsInterval(IntervalBase):
+    def __init__(self, left : IntervalBase, right : IntervalBase, make_ir):
+        super().__init__()
+        self._left = left
+        self._right = right
+        self._make_ir = make_ir
+        self._sorted = False
+    def emit_eval_code(self) -> IRBase:
+        return self._make_ir(self._left.lower_to_ir(), self._right.lower_to_ir())
+
+class WindowInterval(IntervalBase):
+    def __init__(self, left : IntervalBase, right : IntervalBase, left_flank : int, right_flank : int, strand_relative : bool, count_only : bool):
+        super().__init__()
+        self._left = left
+        self._right = right
+        self._left_flank = left_flank
+        self._right_flank = right_flank
+        self._strand_relative = strand_relative
+        self._count_only = count_only
+        # The region of a pair is where they overlap, so only the counts keep the order
+        self._sorted = left._sorted and count_only
+    def emit_eval_code(self) -> IRBase:
+        return WindowIR(
+            lhs = self._left.lower_to_ir(),
+            rhs = self._right.lower_to_ir(),
+            left = self._left_flank,
+            right = self._right_flank,
+            strand_relative = self._strand_relative,
+            count_only = self._count_only
+        )
+
 class Intersection(IntervalBase):
-    def __init__(self, left : IntervalBase, right : IntervalBase, flavor : str = "inner"):
+    def __init__(self, left : IntervalBase, right : IntervalBase, flavor : str = "inner", blocks_only : bool = False):
         super().__init__()
         self._flavor = flavor
+        self._blocks_only = blocks_only
         self._left = left
         self._right = right
         self._sorted = left._sorted and right._sorted
@@ -442,5 +904,6 @@ class Intersection(IntervalBase):
             lhs = left_ref,
             rhs = right_ref,
             flavor = self._flavor,
-            sorted = self._sorted
+            sorted = self._sorted,
+            blocks_only = self._blocks_only
         )
@@ -37,3 +37,10 @@ class Bed6(BedBase):
     """
     def __init__(self, inner: IntervalBase):
         super().__init__(inner, 6)
+
+class Bed12(BedBase):
+    """
+    Cast an iterator of intervals to BED12 iterator, each interval becomes a single block record.
+    """
+    def __init__(self, inner: IntervalBase):
+        super().__init__(inner, 12)
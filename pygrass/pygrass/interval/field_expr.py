@@ -1,5 +1,5 @@
 from typing import Callable
-from pygrass.ir import Add, And, ComponentFieldRef, Cond, Div, Eq, FieldRef, FullRecordRef, GreaterEqualThan, GreaterThan, IRBase, LeftShift, LessEqualThan, LessThan, Mod, Mul, Ne, Neg, Or, Not as NotIR, RecordRef, RightShift, StringRepr, Sub, Xor as XorIR, NumberOfComponents as NumberOfComponentsIR, ConstValue, RegexMatch
+from pygrass.ir import Add, And, ComponentFieldRef, Cond, Div, Eq, FieldRef, FullRecordRef, GreaterEqualThan, GreaterThan, IRBase, LeftShift, LessEqualThan, LessThan, Mod, Mul, Ne, Neg, Or, Not as NotIR, RecordRef, RightShift, StringRepr, Sub, Xor as XorIR, Cast, Call, NumberOfComponents as NumberOfComponentsIR, ConstValue, RegexMatch
 
 class FieldExpr(object):
     """
@@ -19,16 +19,16 @@ class FieldExpr(object):
         return Operator(And, self, other)
     def logic_or(self, other):
         return Operator(Or, self, other)
-    def logic_not(self, other):
+    def logic_not(self):
         return Operator(NotIR, self)
     def logic_xor(self, other):
-        return Operator(XorIR, self)
+        return Operator(XorIR, self, other)
     def __and__(self, other):
         return self.logic_and(other)
     def __or__(self, other):
-        return self.logic_and(other)
+        return self.logic_or(other)
     def __xor__(self, other):
-        return self.logic_and(other)
+        return self.logic_xor(other)
     def __add__(self, other):
         return Operator(Add, self, other)
     def __sub__(self, other):
@@ -56,9 +56,96 @@ class FieldExpr(object):
     def __lshift__(self, other):
         return Operator(LeftShift, self, other)
     def __invert__(self):
+        return Operator(NotIR, self)
+    def __neg__(self):
         return Operator(Neg, self)
     def matches(self, other):
         return Operator(RegexMatch, self, other)
+    def as_int(self):
+        """
+        Convert the value to an integer, floats are truncated and strings are parsed.
+        """
+        return Operator(lambda value: Cast(value, "Int"), self)
+    def as_float(self):
+        return Operator(lambda value: Cast(value, "Float"), self)
+    def as_str(self):
+        return Operator(lambda value: Cast(value, "Str"), self)
+    def as_bool(self):
+        return Operator(lambda value: Cast(value, "Bool"), self)
+    def substr(self, start, length = None):
+        """
+        The substring starting from the start-th character, negative positions count from the end.
+        All the remaining characters are taken unless the length is given.
+        """
+        if length is None:
+            return call("substr", self, start)
+        return call("substr", self, start, length)
+    def split_nth(self, sep, n):
+        """
+        The n-th field of the string separated by sep, which is "." if there isn't such a field.
+        """
+        return call("split_nth", self, sep, n)
+    def replace(self, pattern, replacement):
+        return call("replace", self, pattern, replacement)
+    def upper(self):
+        return call("upper", self)
+    def lower(self):
+        return call("lower", self)
+    def starts_with(self, prefix):
+        return call("starts_with", self, prefix)
+    def parse_int(self, default = None):
+        """
+        Parse the string as an integer, the default value (0 unless it's given) is used when the
+        string isn't an integer, for example the "." of a missing record.
+        """
+        if default is None:
+            return call("parse_int", self)
+        return call("parse_int", self, default)
+    def parse_float(self, default = None):
+        """
+        Parse the string as a float, the default value is NaN unless it's given.
+        """
+        if default is None:
+            return call("parse_float", self)
+        return call("parse_float", self, default)
+    def min(self, *others):
+        return call("min", self, *others)
+    def max(self, *others):
+        return call("max", self, *others)
+    def floor(self):
+        return call("floor", self)
+    def log(self, base = None):
+        """
+        The natural logarithm unless the base is given.
+        """
+        if base is None:
+            return call("log", self)
+        return call("log", self, base)
+    def exp(self):
+        return call("exp", self)
+    def __abs__(self):
+        return call("abs", self)
+    def __round__(self, ndigits = None):
+        if ndigits is not None:
+            raise ValueError("Only rounding to an integer is supported")
+        return call("round", self)
+    def __floor__(self):
+        return self.floor()
+    def __pow__(self, other):
+        return call("pow", self, other)
+
+
+def call(func : str, *args) -> FieldExpr:
+    """
+    Call a built-in function of the field expressions.
+
+    The string fields of a missing record from an outer intersection are ".", and the string
+    functions keep a "." input as ".".
+    """
+    return Operator(lambda *args: Call(func, list(args)), *args)
+
+def concat(*parts) -> FieldExpr:
+    return call("concat", *parts)
 
 
 def make_field_expression(expr) -> FieldExpr:
@@ -157,6 +244,26 @@ name = FieldReference("name")
 score = FieldReference("score")
 strand = FieldReference("strand")
 tag = FieldReference("tag_str")
+seq = FieldReference("seq")
+gc_content = FieldReference("gc_content")
+count = FieldReference("count")
+distance = FieldReference("distance")
+covered = FieldReference("covered")
+fraction = FieldReference("fraction")
+depth = FieldReference("depth")
+forward_count = FieldReference("forward_count")
+reverse_count = FieldReference("reverse_count")
+size = FieldReference("size")
+intersection = FieldReference("intersection")
+union = FieldReference("union")
+jaccard = FieldReference("jaccard")
+n_intersections = FieldReference("n_intersections")
+left_tail = FieldReference("left_tail")
+right_tail = FieldReference("right_tail")
+two_tail = FieldReference("two_tail")
+ratio = FieldReference("ratio")
+reldist = FieldReference("reldist")
+total = FieldReference("total")
 
 length = end - start
 
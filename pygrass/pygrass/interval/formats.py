@@ -29,21 +29,24 @@ class IntervalFile(IntervalFormatBase):
     Represents any file that encodes intervals supported by GRASS. 
 
     The actual file format is automatically detected. 
+    The ref parameter is the path to the reference FASTA file, which is only used by CRAM files.
     """
-    def __init__(self, path, sorted : bool = True):
+    def __init__(self, path, sorted : bool = True, ref : str = None):
         super().__init__()
         arg_bag = dict()
         actual_path = path if type(path) == str else path.get_value()
         file_type = detect_file_format(actual_path, arg_bag)
         self._sorted = sorted
         if file_type == "cram":
-            self._inner = CramFile(path, sorted)
+            self._inner = CramFile(path, sorted, ref)
         elif file_type == "bam":
             self._inner = BamFile(path, sorted)
         elif file_type == "bed":
             self._inner = BedFile(path, sorted, **arg_bag)
         elif file_type == "vcf":
             self._inner = VcfFile(path, sorted, **arg_bag)
+        elif file_type == "fasta":
+            self._inner = FastaFile(path, sorted)
         else:
             raise RuntimeError("Unsupported file format " + file_type)
     def emit_eval_code(self) -> IRBase:
@@ -54,7 +57,7 @@ class BamFile(IntervalFormatBase):
     Represents a BAM file.
     """
     def from_stdin(**kwargs):
-        ret = BedFile(None, **kwargs)
+        ret = BamFile(None, **kwargs)
         ret._target = { "FileNo": 0 }
         return ret
     def __init__(self, path, sorted : bool = True):
@@ -75,7 +78,7 @@ class CramFile(IntervalFormatBase):
     Represents a CRAM file.
     """
     def from_stdin(**kwargs):
-        ret = BedFile(None, **kwargs)
+        ret = CramFile(None, **kwargs)
         ret._target = { "FileNo": 0 }
         return ret
     def __init__(self, path, sorted : bool = True, ref : str = None):
@@ -84,6 +87,8 @@ class CramFile(IntervalFormatBase):
         self._target = { "CmdArg" : path._nth } if type(path) == CmdArg else { "Path": path }
         self._ref = ref
         self._sorted = sorted
+        from pygrass import get_backend_session
+        get_backend_session().enable_runtime_feature("htslib")
     def emit_eval_code(self) -> IRBase:
         return OpenFile(
             target = self._target,
@@ -115,6 +120,29 @@ class BedFile(IntervalFormatBase):
             num_of_fields = self._nof
         )
 
+class BedPeFile(IntervalFormatBase):
+    """
+    Represents a BEDPE file, each record is a pair of intervals.
+    The records are considered sorted if they are sorted by the first interval.
+    """
+    def from_stdin(**kwargs):
+        ret = BedPeFile(None, **kwargs)
+        ret._target = { "FileNo": 0 }
+        return ret
+    def __init__(self, path, sorted : bool = True, compressed : bool = False):
+        super().__init__()
+        self._sorted = sorted
+        self._target = { "CmdArg" : path._nth } if type(path) == CmdArg else { "Path": path }
+        self._compressed = compressed
+    def emit_eval_code(self) -> IRBase:
+        return OpenFile(
+            target = self._target,
+            format = "BedPe",
+            sorted = self._sorted,
+            compression = self._compressed,
+            num_of_fields = 10
+        )
+
 class VcfFile(IntervalFormatBase):
     """
     Represents a VCF file.
@@ -136,6 +164,21 @@ class VcfFile(IntervalFormatBase):
             compression = self._compressed,
         )
 
+class FastaFile(IntervalFormatBase):
+    """
+    Represents a FASTA file, each sequence in the file is an interval covering the whole sequence.
+    """
+    def __init__(self, path, sorted : bool = True):
+        super().__init__()
+        self._sorted = sorted
+        self._target = { "CmdArg" : path._nth } if type(path) == CmdArg else { "Path": path }
+    def emit_eval_code(self) -> IRBase:
+        return OpenFile(
+            target = self._target,
+            format = "Fasta",
+            sorted = self._sorted,
+        )
+
 class Bed3File(BedFile):
     """
     Represents a BED file with 3 fields.
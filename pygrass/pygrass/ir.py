@@ -185,6 +185,8 @@ class OpenFile(BatchOperationBase):
         ret["num_of_fields"] = self._num_of_fields
         ret["compression"] = self._compression
         ret["sorted"] = self._sorted
+        if self._ref != None:
+            ret["reference"] = make_const_bag_ref(self._ref, bag) if bag != None else self._ref
         return ret
 
 ## Record type casting
@@ -201,6 +203,22 @@ class CastToBed(BatchOperationBase):
         ret["sorted"] = self._sorted
         return ret
 
+class AttachSequence(BatchOperationBase):
+    def __init__(self, inner : IRBase, reference : str, sorted: bool):
+        super().__init__("AttachSequence")
+        self._inner = inner
+        self._reference = reference
+        self._sorted = sorted
+    def to_dict(self, bag = None) -> dict[str]:
+        ret = super().to_dict(bag)
+        ret["inner"] = self._inner.to_dict(bag)
+        if bag != None:
+            ret["reference"] = make_const_bag_ref(self._reference, bag)
+        else:
+            ret["reference"] = self._reference
+        ret["sorted"] = self._sorted
+        return ret
+
 ## Record collection operators
 class GroupBy(BatchOperationBase):
     def __init__(self, inner :IRBase, key_func : list[IRBase]):
@@ -323,7 +341,7 @@ class MergeOverlap(BatchOperationBase):
         return ret
 
 class Intersection(BatchOperationBase):
-    def __init__(self, lhs : IRBase, rhs : IRBase, flavor : str, sorted : bool):
+    def __init__(self, lhs : IRBase, rhs : IRBase, flavor : str, sorted : bool, blocks_only : bool = False):
         super().__init__("Intersection")
         if flavor not in ["inner", "outer", "left-outer", "right-outer"]:
             raise RuntimeError("Unexpected intersection flavor")
@@ -331,14 +349,231 @@ class Intersection(BatchOperationBase):
         self._lhs = lhs
         self._rhs = rhs
         self._sorted = sorted
+        self._blocks_only = blocks_only
     def to_dict(self, bag = None) -> dict[str]:
         ret = super().to_dict(bag)
         ret["flavor"] = self._flavor
         ret["lhs"] = self._lhs.to_dict(bag)
         ret["rhs"] = self._rhs.to_dict(bag)
         ret["sorted"] = self._sorted
+        if self._blocks_only:
+            ret["blocks_only"] = True
+        return ret
+
+class PairIntersection(BatchOperationBase):
+    def __init__(self, lhs : IRBase, rhs : IRBase, mode : str, sorted : bool = False):
+        super().__init__("PairIntersection")
+        if mode not in ["either", "both", "neither"]:
+            raise RuntimeError("Unexpected pair intersection mode")
+        self._mode = mode
+        self._lhs = lhs
+        self._rhs = rhs
+        self._sorted = sorted
+    def to_dict(self, bag = None) -> dict[str]:
+        ret = super().to_dict(bag)
+        ret["mode"] = self._mode
+        ret["lhs"] = self._lhs.to_dict(bag)
+        ret["rhs"] = self._rhs.to_dict(bag)
+        ret["sorted"] = self._sorted
+        return ret
+
+class Closest(BatchOperationBase):
+    def __init__(self, lhs : IRBase, rhs : IRBase, ties : str, direction : str, ignore_overlaps : bool, k : int):
+        super().__init__("Closest")
+        if ties not in ["all", "first", "last"]:
+            raise RuntimeError("Unexpected tie handling mode")
+        if direction not in ["both", "upstream", "downstream"]:
+            raise RuntimeError("Unexpected closest direction")
+        self._lhs = lhs
+        self._rhs = rhs
+        self._ties = ties
+        self._direction = direction
+        self._ignore_overlaps = ignore_overlaps
+        self._k = k
+    def to_dict(self, bag = None) -> dict[str]:
+        ret = super().to_dict(bag)
+        ret["lhs"] = self._lhs.to_dict(bag)
+        ret["rhs"] = self._rhs.to_dict(bag)
+        ret["ties"] = self._ties
+        ret["direction"] = self._direction
+        if self._ignore_overlaps:
+            ret["ignore_overlaps"] = True
+        ret["k"] = self._k
+        return ret
+
+class Window(BatchOperationBase):
+    def __init__(self, lhs : IRBase, rhs : IRBase, left : int, right : int, strand_relative : bool, count_only : bool):
+        super().__init__("Window")
+        if left < 0 or right < 0:
+            raise RuntimeError("The window flanks should not be negative")
+        self._lhs = lhs
+        self._rhs = rhs
+        self._left = left
+        self._right = right
+        self._strand_relative = strand_relative
+        self._count_only = count_only
+    def to_dict(self, bag = None) -> dict[str]:
+        ret = super().to_dict(bag)
+        ret["lhs"] = self._lhs.to_dict(bag)
+        ret["rhs"] = self._rhs.to_dict(bag)
+        ret["left"] = self._left
+        ret["right"] = self._right
+        if self._strand_relative:
+            ret["strand_relative"] = True
+        if self._count_only:
+            ret["count_only"] = True
+        return ret
+
+def resize_amount(amount) -> dict[str]:
+    """An integer is a number of bases, and a float is a fraction of the interval length"""
+    if amount < 0:
+        raise RuntimeError("The resize amount should not be negative")
+    if type(amount) == float:
+        return {"fraction": amount}
+    return {"bases": int(amount)}
+
+class Slop(BatchOperationBase):
+    def __init__(self, base : IRBase, left, right, strand_relative : bool, sorted : bool, opcode : str = "Slop"):
+        super().__init__(opcode)
+        self._inner = base
+        self._left = resize_amount(left)
+        self._right = resize_amount(right)
+        self._strand_relative = strand_relative
+        self._sorted = sorted
+    def to_dict(self, bag = None) -> dict[str]:
+        ret = super().to_dict(bag)
+        ret["inner"] = self._inner.to_dict(bag)
+        ret["left"] = self._left
+        ret["right"] = self._right
+        if self._strand_relative:
+            ret["strand_relative"] = True
+        ret["sorted"] = self._sorted
+        return ret
+
+class Flank(Slop):
+    def __init__(self, base : IRBase, left, right, strand_relative : bool, sorted : bool):
+        super().__init__(base, left, right, strand_relative, sorted, opcode = "Flank")
+
+class Resize(BatchOperationBase):
+    def __init__(self, base : IRBase, size, anchor : str, strand_relative : bool, sorted : bool):
+        super().__init__("Resize")
+        if anchor not in ["start", "center", "end"]:
+            raise RuntimeError("Unexpected resize anchor")
+        self._inner = base
+        self._size = resize_amount(size)
+        self._anchor = anchor
+        self._strand_relative = strand_relative
+        self._sorted = sorted
+    def to_dict(self, bag = None) -> dict[str]:
+        ret = super().to_dict(bag)
+        ret["inner"] = self._inner.to_dict(bag)
+        ret["size"] = self._size
+        ret["anchor"] = self._anchor
+        if self._strand_relative:
+            ret["strand_relative"] = True
+        ret["sorted"] = self._sorted
+        return ret
+
+class Coverage(BatchOperationBase):
+    def __init__(self, lhs : IRBase, rhs : IRBase, histogram : bool, by_strand : bool):
+        super().__init__("Coverage")
+        self._lhs = lhs
+        self._rhs = rhs
+        self._histogram = histogram
+        self._by_strand = by_strand
+    def to_dict(self, bag = None) -> dict[str]:
+        ret = super().to_dict(bag)
+        ret["lhs"] = self._lhs.to_dict(bag)
+        ret["rhs"] = self._rhs.to_dict(bag)
+        if self._histogram:
+            ret["histogram"] = True
+        if self._by_strand:
+            ret["by_strand"] = True
+        return ret
+
+class Subtract(BatchOperationBase):
+    def __init__(self, lhs : IRBase, rhs : IRBase, remove_overlapping : bool, min_fraction : float, same_strand : bool):
+        super().__init__("Subtract")
+        if not 0 <= min_fraction <= 1:
+            raise RuntimeError("The minimum fraction should be between 0 and 1")
+        self._lhs = lhs
+        self._rhs = rhs
+        self._mode = "remove_overlapping" if remove_overlapping else "fragments"
+        self._min_fraction = float(min_fraction)
+        self._same_strand = same_strand
+    def to_dict(self, bag = None) -> dict[str]:
+        ret = super().to_dict(bag)
+        ret["lhs"] = self._lhs.to_dict(bag)
+        ret["rhs"] = self._rhs.to_dict(bag)
+        ret["mode"] = self._mode
+        if self._min_fraction > 0:
+            ret["min_fraction"] = self._min_fraction
+        if self._same_strand:
+            ret["same_strand"] = True
+        return ret
+
+class GenomeCoverage(BatchOperationBase):
+    def __init__(self, inner : IRBase, histogram : bool, include_zero : bool, strand : str, scale : float):
+        super().__init__("GenomeCoverage")
+        if strand not in [None, "+", "-"]:
+            raise RuntimeError("The strand should be None, \"+\" or \"-\"")
+        if scale <= 0:
+            raise RuntimeError("The scale should be positive")
+        self._inner = inner
+        self._report = "histogram" if histogram else "bed_graph"
+        self._include_zero = include_zero
+        self._strand = strand
+        self._scale = float(scale)
+    def to_dict(self, bag = None) -> dict[str]:
+        ret = super().to_dict(bag)
+        ret["inner"] = self._inner.to_dict(bag)
+        ret["report"] = self._report
+        if self._include_zero:
+            ret["include_zero"] = True
+        if self._strand is not None:
+            ret["strand"] = "positive" if self._strand == "+" else "negative"
+        if self._scale != 1:
+            ret["scale"] = self._scale
+        return ret
+
+
+class Jaccard(BatchOperationBase):
+    def __init__(self, lhs : IRBase, rhs : IRBase):
+        super().__init__("Jaccard")
+        self._lhs = lhs
+        self._rhs = rhs
+    def to_dict(self, bag = None) -> dict[str]:
+        ret = super().to_dict(bag)
+        ret["lhs"] = self._lhs.to_dict(bag)
+        ret["rhs"] = self._rhs.to_dict(bag)
+        return ret
+
+class Fisher(BatchOperationBase):
+    def __init__(self, lhs : IRBase, rhs : IRBase, genome_size : int = None):
+        super().__init__("Fisher")
+        if genome_size is not None and genome_size <= 0:
+            raise RuntimeError("The genome size should be positive")
+        self._lhs = lhs
+        self._rhs = rhs
+        self._genome_size = genome_size
+    def to_dict(self, bag = None) -> dict[str]:
+        ret = super().to_dict(bag)
+        ret["lhs"] = self._lhs.to_dict(bag)
+        ret["rhs"] = self._rhs.to_dict(bag)
+        if self._genome_size is not None:
+            ret["genome_size"] = int(self._genome_size)
         return ret
 
+class RelativeDistance(BatchOperationBase):
+    def __init__(self, lhs : IRBase, rhs : IRBase):
+        super().__init__("RelativeDistance")
+        self._lhs = lhs
+        self._rhs = rhs
+    def to_dict(self, bag = None) -> dict[str]:
+        ret = super().to_dict(bag)
+        ret["lhs"] = self._lhs.to_dict(bag)
+        ret["rhs"] = self._rhs.to_dict(bag)
+        return ret
 
 ## Drain Functions
 class WriteFile(BatchOperationBase):
@@ -356,12 +591,36 @@ class WriteFile(BatchOperationBase):
         return ret
 
 class Count(BatchOperationBase):
-    def __init__(self, what : IRBase):
+    def __init__(self, what : IRBase, keys : list[IRBase] = []):
         super().__init__("Count")
         self._what = what
+        self._keys = keys
     def to_dict(self, bag = None) -> dict[str]:
         ret = super().to_dict(bag)
         ret["what"] = self._what.to_dict(bag)
+        if self._keys:
+            ret["keys"] = [key.to_dict(bag) for key in self._keys]
+        return ret
+
+class Aggregate(BatchOperationBase):
+    AGGREGATE_FUNCTIONS = ["count", "count_distinct", "sum", "mean", "median", "min", "max",
+        "mode", "stdev", "collapse", "distinct", "first", "last"]
+    def __init__(self, inner : IRBase, columns : list[tuple[str, IRBase]], keys : list[IRBase] = None):
+        super().__init__("Aggregate")
+        for func, _ in columns:
+            if func not in self.AGGREGATE_FUNCTIONS:
+                raise RuntimeError("Unknown aggregate function {}".format(func))
+        self._inner = inner
+        self._columns = columns
+        self._keys = keys
+    def to_dict(self, bag = None) -> dict[str]:
+        ret = super().to_dict(bag)
+        ret["inner"] = self._inner.to_dict(bag)
+        if self._keys is None:
+            ret["group"] = "overlap"
+        else:
+            ret["group"] = {"keys": [key.to_dict(bag) for key in self._keys]}
+        ret["columns"] = [{"func": func, "value": value.to_dict(bag)} for func, value in self._columns]
         return ret
 
 class Limit(BatchOperationBase):
@@ -456,13 +715,16 @@ class ComponentFieldRef(FieldExpressionBase):
         return ret
 
 class ConstValue(UnaryBase):
+    _value_types = {int: "Int", float: "Float", str: "Str"}
     def __init__(self, value : Any):
         super().__init__("ConstValue", "value", value)
     def to_dict(self, bag) -> dict[str]:
         ret = super().to_dict(bag)
         type_of_value = type(self._dict["value"])
-        if bag != None and (type_of_value in [int, float, str]):
+        if bag != None and (type_of_value in self._value_types):
             ret["value"] = make_const_bag_ref(self._dict["value"], bag)
+            # The constant bag stores all the numbers as floats
+            ret["value_type"] = self._value_types[type_of_value]
         return ret
 
 class FullRecordRef(RuntimeValueBase):
@@ -482,6 +744,26 @@ class StringRepr(UnaryBase):
     def __init__(self, operand: IRBase):
         super().__init__("StringRepr", "value", operand)
 
+class Cast(UnaryBase):
+    def __init__(self, operand: IRBase, to: str):
+        super().__init__("Cast", "value", operand)
+        self._to = to
+    def to_dict(self, bag = None) -> dict[str]:
+        ret = super().to_dict(bag)
+        ret["to"] = self._to
+        return ret
+
+class Call(FieldExpressionBase):
+    def __init__(self, func : str, args : list[IRBase]):
+        super().__init__("Call")
+        self._func = func
+        self._args = args
+    def to_dict(self, bag = None) -> dict[str]:
+        ret = super().to_dict(bag)
+        ret["func"] = self._func
+        ret["args"] = [arg.to_dict(bag) for arg in self._args]
+        return ret
+
 class And(BinaryBase):
     def __init__(self, lhs : IRBase, rhs : IRBase):
         super().__init__("And", lhs, rhs)
@@ -492,7 +774,7 @@ class Or(BinaryBase):
 
 class Xor(BinaryBase):
     def __init__(self, lhs : IRBase, rhs: IRBase):
-        super().__init__("Or", lhs, rhs)
+        super().__init__("Xor", lhs, rhs)
 
 class Not(UnaryBase):
     def __init__(self, operand : IRBase):
@@ -555,10 +837,6 @@ class LeftShift(BinaryBase):
     def __init__(self, lhs : IRBase, rhs: IRBase):
         super().__init__("LeftShift", lhs, rhs)
 
-class Neg(BinaryBase):
-    def __init__(self, lhs : IRBase, rhs: IRBase):
-        super().__init__("Neg", lhs, rhs)
-
 class RegexMatch(BinaryBase):
     def __init__(self, lhs: IRBase, rhs: IRBase):
         super().__init__("RegexMatch", lhs, rhs)
\ No newline at end of file
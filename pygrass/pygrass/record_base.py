@@ -123,11 +123,11 @@ class RecordCollectionBase(IteratorBase):
         return WriteFile(path, self.lower_to_ir())
     @_drain_method
     def count(self) -> IRBase:
-        """Count and report the number of record has been seen in the report file.
+        """Count the records and print the number to standard output.
 
         NOTE: This is a drain method, which will post the captured IR to backend session.
         """
-        return Count(self.lower_to_ir())
+        return WriteFile(1, Count(self.lower_to_ir()))
     @abstractclassmethod
     def emit_eval_code(self) -> IRBase:
         """The abstract method that emits the actual IR to compute the value described by current object."""